@@ -0,0 +1,92 @@
+// ==========================================
+// 変更の番号 (get-change-versions) の進め方
+// ==========================================
+//
+// 番号は「実際に中身が変わったとき」だけ進める。
+// 範囲外の番号を指した・同じ値を入れ直した・エラーで弾いた操作では進めない
+// (進めるとUIが変わっていない所まで描き直すので)
+
+/// どの部分が変わったか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeArea {
+    Staff,    // スタッフグループ・スロット・出勤日数の上限
+    Rules,    // weekly rule
+    Calendar, // 生成したシフト・スキップ・固定
+}
+
+/// 部分ごとの変更の番号
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VersionCounter {
+    pub staff: u64,
+    pub rules: u64,
+    pub calendar: u64,
+}
+
+impl VersionCounter {
+    /// `changed` のときだけ `area` の番号を1進める
+    pub fn record(&mut self, area: ChangeArea, changed: bool) {
+        if !changed {
+            return;
+        }
+        match area {
+            ChangeArea::Staff => self.staff += 1,
+            ChangeArea::Rules => self.rules += 1,
+            ChangeArea::Calendar => self.calendar += 1,
+        }
+    }
+}
+
+/// 範囲内なら取り除いて true (範囲外なら何もしない)
+pub fn remove_at<T>(items: &mut Vec<T>, index: usize) -> bool {
+    if index < items.len() {
+        items.remove(index);
+        true
+    } else {
+        false
+    }
+}
+
+/// 値が違うときだけ置き換えて true
+pub fn set_if_changed<T: PartialEq>(slot: &mut T, value: T) -> bool {
+    if *slot == value {
+        false
+    } else {
+        *slot = value;
+        true
+    }
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod change_versions_test {
+    use super::{ChangeArea, VersionCounter, remove_at, set_if_changed};
+
+    /// 弾いた操作・同じ値の入れ直しでは番号が変わらない
+    #[test]
+    fn test00() {
+        let mut versions = VersionCounter::default();
+        let mut groups = vec![String::from("Group0")];
+
+        versions.record(ChangeArea::Staff, remove_at(&mut groups, 3));
+        versions.record(ChangeArea::Staff, set_if_changed(&mut groups[0], String::from("Group0")));
+        let mut max_days = Some(5);
+        versions.record(ChangeArea::Staff, set_if_changed(&mut max_days, Some(5)));
+
+        assert_eq!(versions, VersionCounter::default());
+        assert_eq!(groups, vec![String::from("Group0")]);
+    }
+
+    /// 変えた部分の番号だけ進む
+    #[test]
+    fn test01() {
+        let mut versions = VersionCounter::default();
+        let mut groups = vec![String::from("Group0"), String::from("Group1")];
+
+        versions.record(ChangeArea::Staff, set_if_changed(&mut groups[1], String::from("夜勤")));
+        versions.record(ChangeArea::Staff, remove_at(&mut groups, 0));
+        versions.record(ChangeArea::Calendar, true);
+
+        assert_eq!(groups, vec![String::from("夜勤")]);
+        assert_eq!(versions, VersionCounter { staff: 2, rules: 0, calendar: 1 });
+    }
+}
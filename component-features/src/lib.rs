@@ -9,4 +9,8 @@ pub mod async_example;
 
 pub mod shift_calendar_manager;
 
-mod load_rules;
+pub mod load_rules;
+
+pub mod project;
+
+pub mod change_versions;
@@ -1,38 +1,48 @@
 use serde::{Deserialize, Serialize};
 
+use shift_calendar::shift_gen::{
+    DayRule, Incomplete, ShiftHoll, StaffGroup, StaffGroupList, WeekRule, WeekRuleTable,
+};
+use shift_timeline::schema::{DocumentSchema, ValidationError, unchanged};
+use shift_timeline::{CoverageLimits, GroupCoverage};
+
 // ==========================================
 // 1. スタッフグループ定義
 // ==========================================
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct JsonSlot {
     pub name: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct JsonStaffGroup {
     pub name: String,
     pub slots: Vec<JsonSlot>,
+    /// 1コマあたりの最低人数 (人員チェック用。無ければ指定なし)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_per_period: Option<u32>,
 }
 
 // ==========================================
 // 2. ルール・スケジュール定義
 // ==========================================
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")] // JSONの camelCase を Rustの snake_case に対応させる
 pub struct JsonAssignment {
     pub staff_group_id: u32,
     pub shift_staff_index: u32,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct JsonDailySchedule {
     pub m: Vec<JsonAssignment>, // 午前 (Morning)
     pub a: Vec<JsonAssignment>, // 午後 (Afternoon)
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct JsonWeeklySchedule {
     pub mon: JsonDailySchedule,
     pub tue: JsonDailySchedule,
@@ -43,7 +53,7 @@ pub struct JsonWeeklySchedule {
     pub sun: JsonDailySchedule,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct JsonRule {
     pub name: String,
     pub schedule: JsonWeeklySchedule,
@@ -53,11 +63,213 @@ pub struct JsonRule {
 // 3. ルート定義 (全体)
 // ==========================================
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonConfig {
     pub staff_groups: Vec<JsonStaffGroup>,
     pub rules: Vec<JsonRule>,
+    /// 1人あたりの週の出勤日数の上限 (人員チェック用。無ければ指定なし)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_days_per_week: Option<u32>,
+}
+
+/// 保存するルール設定JSONの形式
+/// - v0: `JsonConfig` そのもの (エンベロープなし)
+/// - v1: エンベロープを導入 (本体は v0 と同じ)
+/// - v2: `staffGroups[].minPerPeriod`・`maxDaysPerWeek` (人員チェックの条件) を追加 (無ければ指定なし)
+///   古いアプリで読んで保存し直すと条件が消えるので、バージョンを上げる
+pub const CONFIG_SCHEMA: DocumentSchema = DocumentSchema {
+    format: "shift-config",
+    migrations: &[unchanged, unchanged],
+};
+
+impl JsonConfig {
+    /// 存在しないグループ・スロットを指しているアサインを探す
+    /// (`path` はJSON上の位置。例: `rules[0].schedule.mon.m[1]`)
+    pub fn validation_errors(&self) -> Vec<ValidationError> {
+        const DAY_KEYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+        let mut errors = Vec::new();
+        for (rule_index, rule) in self.rules.iter().enumerate() {
+            for (day_key, day) in DAY_KEYS.iter().zip(rule.schedule.days()) {
+                for (time_key, holls) in [("m", &day.m), ("a", &day.a)] {
+                    for (holl_index, holl) in holls.iter().enumerate() {
+                        let problem = match self.staff_groups.get(holl.staff_group_id as usize) {
+                            None => format!("グループ {} が存在しません", holl.staff_group_id),
+                            Some(group) if holl.shift_staff_index as usize >= group.slots.len() => format!(
+                                "グループ {} ({}) にスロット {} がありません",
+                                holl.staff_group_id, group.name, holl.shift_staff_index
+                            ),
+                            _ => continue,
+                        };
+                        errors.push(ValidationError::new(
+                            format!("rules[{}].schedule.{}.{}[{}]", rule_index, day_key, time_key, holl_index),
+                            problem
+                        ));
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// エンベロープの無い古い形式も読める
+    pub fn from_json(json_str: &str) -> Result<Self, String> {
+        CONFIG_SCHEMA.from_json(json_str)
+    }
+
+    /// パースと検査をまとめて行う (wasm版の読み込み用)
+    pub fn load_json(json_str: &str) -> Result<Self, Vec<ValidationError>> {
+        CONFIG_SCHEMA.load_json(json_str, Self::validation_errors)
+    }
+
+    /// 最新バージョンのエンベロープで包んだJSON
+    pub fn to_json(&self) -> Result<String, String> {
+        CONFIG_SCHEMA.to_json(self)
+    }
+
+    /// JSONと同じエンベロープを、TOMLのトップレベルのキーとして持つ
+    /// ```toml
+    /// format = "shift-config"
+    /// version = 2
+    ///
+    /// [[data.staffGroups]]
+    /// name = "Kitchen"
+    /// ```
+    pub fn from_toml(toml_str: &str) -> Result<Self, String> {
+        CONFIG_SCHEMA.from_value(parse_toml(toml_str)?)
+    }
+
+    /// `load_json` のTOML版
+    pub fn load_toml(toml_str: &str) -> Result<Self, Vec<ValidationError>> {
+        let value = parse_toml(toml_str)
+            .map_err(|e| vec![ValidationError::document(e)])?;
+        CONFIG_SCHEMA.load_value(value, Self::validation_errors)
+    }
+
+    /// 人が編集する用のTOML
+    /// グループ・ルールは `[[data.staffGroups]]` / `[[data.rules]]` に分け、
+    /// スロットと曜日ごとのアサインは1行に収める
+    pub fn to_toml(&self) -> Result<String, String> {
+        let mut doc = toml_edit::ser::to_document(&CONFIG_SCHEMA.wrap(self))
+            .map_err(|_e| String::from("FailedToStringify"))?;
+
+        let Some(toml_edit::Item::Value(toml_edit::Value::InlineTable(data))) = doc.remove("data") else {
+            return Err(String::from("FailedToStringify"));
+        };
+        let mut data = data.into_table();
+        data.set_implicit(true);
+
+        expand_array_of_tables(&mut data, "staffGroups", |_| {});
+        expand_array_of_tables(&mut data, "rules", |rule| {
+            if let Some(toml_edit::Item::Value(toml_edit::Value::InlineTable(schedule))) = rule.remove("schedule") {
+                rule.insert("schedule", toml_edit::Item::Table(schedule.into_table()));
+            }
+        });
+
+        doc.insert("data", toml_edit::Item::Table(data));
+        Ok(doc.to_string())
+    }
+}
+
+fn parse_toml(toml_str: &str) -> Result<serde_json::Value, String> {
+    toml::from_str(toml_str)
+        .map_err(|e| format!("TOML parse error: {}", e))
+}
+
+/// `key = [{...}, {...}]` を `[[key]]` の並びにする
+/// (空の配列は `key = []` のまま残す。消すと読み込めなくなるため)
+fn expand_array_of_tables(table: &mut toml_edit::Table, key: &str, each: impl Fn(&mut toml_edit::Table)) {
+    let Some(toml_edit::Item::Value(toml_edit::Value::Array(array))) = table.get(key) else {
+        return;
+    };
+    if array.is_empty() || !array.iter().all(|v| v.is_inline_table()) {
+        return;
+    }
+
+    let Some(toml_edit::Item::Value(toml_edit::Value::Array(array))) = table.remove(key) else {
+        return;
+    };
+    let mut tables = toml_edit::ArrayOfTables::new();
+    for value in array {
+        if let toml_edit::Value::InlineTable(inline) = value {
+            let mut sub = inline.into_table();
+            each(&mut sub);
+            tables.push(sub);
+        }
+    }
+    table.insert(key, toml_edit::Item::ArrayOfTables(tables));
+}
+
+// ==========================================
+// 4. shift_calendar への変換 (shift_tools など wasm 以外から使う)
+// ==========================================
+
+impl JsonDailySchedule {
+    fn to_day_rule<'a>(&self) -> DayRule<'a, Incomplete> {
+        let holls = |list: &[JsonAssignment]| {
+            list.iter()
+                .map(|h| ShiftHoll::new(h.staff_group_id as usize, h.shift_staff_index as usize))
+                .collect()
+        };
+        DayRule {
+            shift_morning: holls(&self.m),
+            shift_afternoon: holls(&self.a),
+        }
+    }
+}
+
+impl JsonWeeklySchedule {
+    /// 月曜から日曜の順に並べる
+    pub fn days(&self) -> [&JsonDailySchedule; 7] {
+        [&self.mon, &self.tue, &self.wed, &self.thu, &self.fri, &self.sat, &self.sun]
+    }
+
+    pub fn days_mut(&mut self) -> [&mut JsonDailySchedule; 7] {
+        [
+            &mut self.mon, &mut self.tue, &mut self.wed, &mut self.thu,
+            &mut self.fri, &mut self.sat, &mut self.sun,
+        ]
+    }
+}
+
+impl JsonConfig {
+    pub fn to_staff_group_list(&self) -> StaffGroupList {
+        let mut staff_group_list = StaffGroupList::new();
+        for group in &self.staff_groups {
+            let mut staff_group = StaffGroup::new(&group.name);
+            for slot in &group.slots {
+                staff_group.add_staff(&slot.name);
+            }
+            staff_group_list.add_staff_group(staff_group);
+        }
+        staff_group_list
+    }
+
+    /// wasm版と同じく、全ルールを1つのテーブルにまとめる
+    pub fn to_week_rule_table<'a>(&self) -> WeekRuleTable<'a, Incomplete> {
+        let mut week_rule_table = WeekRuleTable::new();
+        for rule in &self.rules {
+            week_rule_table.add_week_rule(WeekRule(
+                rule.schedule.days().map(|day| day.to_day_rule())
+            ));
+        }
+        week_rule_table
+    }
+
+    /// 人員チェック (`shift_timeline::check_week`) の条件
+    pub fn coverage_limits(&self) -> CoverageLimits {
+        CoverageLimits {
+            groups: self.staff_groups
+                .iter()
+                .map(|group| GroupCoverage {
+                    name: group.name.clone(),
+                    min_per_period: group.min_per_period.map(|n| n as usize),
+                })
+                .collect(),
+            max_days_per_week: self.max_days_per_week.map(|n| n as usize),
+        }
+    }
 }
 
 /*
@@ -89,3 +301,90 @@ pub fn load_config_from_json(, json_str: &str) -> Result<(), String> {
     Ok(())
 }
 */
+
+#[cfg(test)]
+mod load_rules_test {
+    use super::JsonConfig;
+
+    /// リポジトリ同梱のサンプル (v1) とエンベロープの無い古い形式が読める
+    #[test]
+    fn test00() {
+        let config = JsonConfig::from_json(include_str!("../../test_shift_config.json")).unwrap();
+        assert_eq!(config.staff_groups[0].name, "Group0");
+
+        let legacy = serde_json::to_string(&config).unwrap();
+        let loaded = JsonConfig::from_json(&legacy).unwrap();
+        assert_eq!(loaded.to_json().unwrap(), config.to_json().unwrap());
+    }
+
+    /// 新しいバージョン・カレンダーのJSONは読まない
+    #[test]
+    fn test01() {
+        let err = JsonConfig::from_json(r#"{"format":"shift-config","version":3,"data":{}}"#).unwrap_err();
+        assert!(err.contains("バージョン 3"));
+
+        let err = JsonConfig::from_json(r#"{"format":"shift-calendar","version":1,"data":{}}"#).unwrap_err();
+        assert!(err.contains("shift-config のデータではありません"));
+    }
+
+    /// JSON・TOMLのどちらを経由しても内容が変わらない
+    #[test]
+    fn test02() {
+        let mut config = JsonConfig::from_json(include_str!("../../test_shift_config.json")).unwrap();
+        // 空のグループ・空の名前も残る
+        config.staff_groups.push(super::JsonStaffGroup { name: String::from(""), slots: vec![], min_per_period: None });
+        // 人員チェックの条件も残る
+        config.staff_groups[0].min_per_period = Some(2);
+        config.max_days_per_week = Some(5);
+        let json = config.to_json().unwrap();
+
+        let toml_str = config.to_toml().unwrap();
+        assert!(toml_str.starts_with("format = \"shift-config\"\nversion = 2\n"));
+
+        let from_toml = JsonConfig::from_toml(&toml_str).unwrap();
+        assert_eq!(from_toml.to_json().unwrap(), json);
+        assert_eq!(from_toml.to_toml().unwrap(), toml_str);
+        assert_eq!(JsonConfig::from_json(&json).unwrap().to_toml().unwrap(), toml_str);
+
+        // 何も無い設定も読み戻せる
+        let empty = JsonConfig { staff_groups: vec![], rules: vec![], max_days_per_week: None };
+        let from_toml = JsonConfig::from_toml(&empty.to_toml().unwrap()).unwrap();
+        assert_eq!(from_toml.to_json().unwrap(), empty.to_json().unwrap());
+    }
+
+    /// 存在しないグループ・スロットを指すアサインは、位置付きでまとめて返る
+    #[test]
+    fn test03() {
+        let mut config = JsonConfig::from_json(include_str!("../../test_shift_config.json")).unwrap();
+        config.rules[0].schedule.mon.m[0].staff_group_id = 9;
+        config.rules[0].schedule.tue.a[0].shift_staff_index = 7;
+
+        let errors = JsonConfig::load_json(&config.to_json().unwrap()).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].path, "rules[0].schedule.mon.m[0]");
+        assert_eq!(errors[0].message, "グループ 9 が存在しません");
+        assert_eq!(errors[1].to_string(), "rules[0].schedule.tue.a[0]: グループ 0 (Group0) にスロット 7 がありません");
+
+        // TOMLでも同じ
+        let errors = JsonConfig::load_toml(&config.to_toml().unwrap()).unwrap_err();
+        assert_eq!(errors.len(), 2);
+
+        // パースできないもの
+        let errors = JsonConfig::load_toml("staffGroups = [").unwrap_err();
+        assert_eq!(errors[0].path, "");
+        assert!(errors[0].message.starts_with("TOML parse error"));
+    }
+
+    /// v1 (人員チェックの条件が無い) も読め、保存すると v2 になる
+    #[test]
+    fn test04() {
+        let v1 = r#"{"format":"shift-config","version":1,"data":{"staffGroups":[{"name":"A","slots":[{"name":"a0"}]}],"rules":[]}}"#;
+        let config = JsonConfig::from_json(v1).unwrap();
+        assert_eq!(config.staff_groups[0].slots[0].name, "a0");
+        assert_eq!(config.staff_groups[0].min_per_period, None);
+        assert_eq!(config.max_days_per_week, None);
+
+        let json = config.to_json().unwrap();
+        assert!(json.contains(r#""version":2"#), "{}", json);
+    }
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+use shift_timeline::ShiftOverride;
+use shift_timeline::schema::{DocumentSchema, ValidationError, unchanged};
+
+use crate::load_rules::JsonConfig;
+use crate::shift_calendar_manager::ShiftCalendarManager;
+
+// ==========================================
+// ルール設定とカレンダーをまとめた1つのファイル (export-project / import-project)
+// ==========================================
+
+/// 保存するプロジェクトJSONの形式
+/// - v1: 最初のバージョン
+/// - v2: `calendar.lockedWeeks` (固定した週) を追加 (無ければ空)
+/// - v3: `overrides` を `ShiftOverride` の形にした
+///   (v2 までは中身を見ていなかった。形の違うものはパースのエラーになる)
+pub const PROJECT_SCHEMA: DocumentSchema = DocumentSchema {
+    format: "shift-project",
+    migrations: &[unchanged, unchanged, unchanged],
+};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonProjectMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// 書き出したプログラムとバージョン (例: `component-features 0.1.0`)
+    #[serde(default)]
+    pub generator: String,
+
+    /// 書き出したときに表示していた年月 (month: 0-11)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub month: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonProject {
+    #[serde(default)]
+    pub metadata: JsonProjectMetadata,
+
+    /// staffGroups, rules はトップレベルに展開される
+    #[serde(flatten)]
+    pub config: JsonConfig,
+
+    pub calendar: ShiftCalendarManager,
+
+    /// 週・日単位の手動調整 (導出した結果に、並びの順に当てる)
+    #[serde(default)]
+    pub overrides: Vec<ShiftOverride>,
+}
+
+impl JsonProject {
+    /// パースと検証をまとめて行う
+    /// ここで Ok になったものだけを状態に反映すれば、途中まで読み込まれることはない
+    pub fn from_json(json_str: &str) -> Result<Self, Vec<ValidationError>> {
+        PROJECT_SCHEMA.load_json(json_str, Self::validation_errors)
+    }
+
+    /// 最新バージョンのエンベロープで包んだJSON
+    pub fn to_json(&self) -> Result<String, String> {
+        PROJECT_SCHEMA.to_json(self)
+    }
+
+    /// 読み込む前に見つけられる不整合
+    pub fn validation_errors(&self) -> Vec<ValidationError> {
+        // staffGroups, rules はトップレベルにあるので位置はそのまま
+        let mut errors = self.config.validation_errors();
+
+        errors.extend(
+            self.calendar
+                .validation_errors()
+                .into_iter()
+                .map(|e| e.within("calendar"))
+        );
+
+        // 手動調整は staffGroups のグループ・スロットを指す
+        let group_sizes: Vec<usize> = self.config.staff_groups.iter().map(|g| g.slots.len()).collect();
+        errors.extend(self.overrides.iter().enumerate().filter_map(|(index, o)| {
+            o.problem(&group_sizes)
+                .map(|problem| ValidationError::new(format!("overrides[{}]", index), problem))
+        }));
+
+        if let Some(month) = self.metadata.month.filter(|m| *m > 11) {
+            errors.push(ValidationError::new(
+                "metadata.month",
+                format!("{} は 0-11 の範囲外です", month)
+            ));
+        }
+        errors
+    }
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod project_test {
+    use shift_timeline::{LockedStaff, LockedWeek, OverrideAction, ShiftOverride, ShiftPeriod};
+
+    use super::{JsonProject, JsonProjectMetadata};
+    use crate::load_rules::JsonConfig;
+    use crate::shift_calendar_manager::ShiftCalendarManager;
+
+    fn sample() -> JsonProject {
+        let mut calendar = ShiftCalendarManager::new(2944, 0);
+        calendar.apply_weeks(2944, &[Some(()), None, Some(())]).unwrap();
+        let mut locked = LockedWeek { abs_week: 2946, days: Default::default() };
+        locked.days[0].morning.push(LockedStaff { name: String::from("田中"), group_index: 0, staff_index: 1, member_id: None });
+        calendar.lock_week(locked).unwrap();
+
+        JsonProject {
+            metadata: JsonProjectMetadata {
+                title: Some(String::from("sample")),
+                generator: String::from("test"),
+                year: Some(2026),
+                month: Some(5),
+            },
+            config: JsonConfig::from_json(include_str!("../../test_shift_config.json")).unwrap(),
+            calendar,
+            overrides: vec![ShiftOverride {
+                abs_week: 2944,
+                weekday: 1,
+                period: ShiftPeriod::Afternoon,
+                group_index: 0,
+                staff_index: 2,
+                action: OverrideAction::Add,
+            }],
+        }
+    }
+
+    /// 書き出したものがそのまま読める
+    #[test]
+    fn test00() {
+        let project = sample();
+        let json = project.to_json().unwrap();
+        assert!(json.starts_with(r#"{"format":"shift-project","version":3,"data":{"metadata":"#));
+
+        let loaded = JsonProject::from_json(&json).unwrap();
+        assert_eq!(loaded.to_json().unwrap(), json);
+        assert_eq!(loaded.overrides, project.overrides);
+        assert_eq!(loaded.calendar.locked_weeks, project.calendar.locked_weeks);
+    }
+
+    /// 不整合のあるファイルはまとめてエラーになる
+    #[test]
+    fn test01() {
+        let mut project = sample();
+        project.config.rules[0].schedule.mon.m[0].staff_group_id = 9;
+        project.calendar.timeline[2] = shift_timeline::WeekStatus::Active { logical_delta: 5, payload: () };
+        project.overrides[0].staff_index = 3;
+        project.metadata.month = Some(12);
+
+        let errors = JsonProject::from_json(&project.to_json().unwrap()).unwrap_err();
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["rules[0].schedule.mon.m[0]", "calendar.timeline[2]", "overrides[0]", "metadata.month"]);
+        assert_eq!(errors[0].message, "グループ 9 が存在しません");
+        assert_eq!(errors[2].message, "グループ 0 にスタッフ 3 がいません");
+    }
+
+    /// v2 の overrides は形を見ていなかったので、手動調整として読めないものはエラー
+    #[test]
+    fn test02() {
+        let mut value: serde_json::Value = serde_json::from_str(&sample().to_json().unwrap()).unwrap();
+        value["version"] = serde_json::json!(2);
+        let v2 = value.to_string();
+        assert_eq!(JsonProject::from_json(&v2).unwrap().overrides, sample().overrides);
+
+        value["data"]["overrides"] = serde_json::json!([{ "absWeek": 2944, "note": "kept as is" }]);
+        assert!(JsonProject::from_json(&value.to_string()).is_err());
+    }
+}
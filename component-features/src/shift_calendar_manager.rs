@@ -1,403 +1,29 @@
-// --- カレンダー管理層の実装 ---
-use shift_calendar::{
-    self,
-    shift_gen::{
-        Incomplete, 
-        StaffGroupList,
-        WeekRuleTable,
-        WeekDecidedShift,
-        gen_one_week_shift,
-    }
+// --- カレンダー管理層 ---
+// 実装は shift_timeline クレートにあり、WASM版ではルールIDを持たない (`()`) 形で使う
+pub use shift_timeline::{
+    AbsWeek,
+    AppendWeekErrorKind,
+    LogicalDelta,
 };
 
-// use crate::shift_gen::dummy::logger::logger::log;
-
-use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Deserialize, Serialize)]
-pub enum WeekStatus {
-    Active { 
-        logical_delta: LogicalDelta,
-    },
-    Skipped,
-}
-
-// 1970年以前のシフトには対応しません（必要ない故）
-// 絶対週番号を型エイリアスとして定義（わかりやすくするため）
-pub type AbsWeek = usize;
-pub type LogicalDelta = usize;
+pub type WeekStatus = shift_timeline::WeekStatus<()>;
 
 /// 確定した予定を入れます
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ShiftCalendarManager {
-    pub base_abs_week: AbsWeek,
-    pub initial_delta: LogicalDelta,
-
-    pub timeline: Vec<WeekStatus>, // 実週番号 -> 状態
-}
-
-#[derive(Debug)]
-pub enum AppendWeekErrorKind {
-    /// 予定の上書きエラー
-    AttemptedToOverwrite,
-    /// 連続しない予定エラー
-    NotConsecutiveShifts,
-    /// 加減突破
-    UnderFlow,
-}
-
-impl ShiftCalendarManager {
-
-    pub fn new(
-        base_abs_week: AbsWeek, 
-        initial_delta: LogicalDelta
-    ) -> Self {
-        // コンストラクターがエラーを判定
-        Self { base_abs_week, initial_delta, timeline: Vec::new() }
-    }
-
-    fn abs_to_index(
-        &self, 
-        abs_week: AbsWeek
-    ) -> Result<usize, AppendWeekErrorKind> {
-        // self.delta_to_index(self.abs_to_delta(abs_week)?)
-        if abs_week < self.base_abs_week {
-            Err(AppendWeekErrorKind::UnderFlow)
-        } else {
-            Ok(abs_week - self.base_abs_week)
-        }
-    }
-
-    /// すでに作成したリストのなかにtarget_abs_weekを含み
-    /// かつそれが変更される場合はエラーを返却する.
-    ///
-    /// [A | S]
-    ///
-    /// Ok
-    /// ```
-    ///  0  1  2  3  4  5
-    /// [A, S, A, A]       self
-    ///     I  I  I    
-    ///    [S, A, A, N, N] shift you want to append
-    /// target_abs_week: 1
-    /// ```
-    ///
-    /// Err(AttemptedToOverwrite)
-    /// ```
-    ///  0  1  2  3  4  5
-    /// [A, S, A, A] E  E  self
-    ///     I  I  N      
-    ///    [S, A, S, N, N] shift you want to append
-    /// target_abs_week: 1
-    /// ```
-    ///
-    /// if self.timeline[delta_to_abs(initial_delta)..], [0..self.timeline.len() - delta_to_abs(initial_delta)]
-    ///
-    /// Ok
-    /// ```
-    ///  0  1  2  3  4
-    /// [A, S, A, A] E              :self
-    ///              I
-    ///             [S, A, S, N, N] :shift you want to append
-    /// target_abs_week: 4
-    /// ```
-    ///
-    /// Err(NotConsecutiveShifts)
-    /// ```
-    ///  0  1  2  3  4  5
-    /// [A, S, A, A] E  E              :self
-    ///              E [S, A, S, N, N] :shift you want to append
-    /// target_abs_week: 5
-    /// ```
-    /// if self.timeline.len() < target_abs_week -> Error
-    pub fn append_check(
-        &self,
-        target_abs_week: AbsWeek,
-        skip_flags: &[bool]
-    ) -> Result<(), AppendWeekErrorKind> {
-        if self.timeline.len() + self.base_abs_week < target_abs_week {
-            return Err(AppendWeekErrorKind::NotConsecutiveShifts);
-        }
-
-        if self
-            .timeline[self.abs_to_index(target_abs_week)?..]
-            .iter()
-            .zip(
-                skip_flags
-            ).all(|(week_status, is_skipped)|
-                matches!(week_status, WeekStatus::Skipped) == *is_skipped
-            )
-        {
-            // Ok
-        } else {
-            return Err(AppendWeekErrorKind::AttemptedToOverwrite);
-        }
-        Ok(())
-    }
-
-    /// checkをしてOkであればtimelineに適用する
-    /// self.append_check関数のチェックが入る
-    pub fn apply_weeks(
-        &mut self,
-        target_abs_week: AbsWeek,
-        skip_flags: &[bool]
-    ) -> Result<(), AppendWeekErrorKind> {
-
-        self.append_check(target_abs_week, skip_flags)?; // チェックをする
-        // target_abs_week: 2,
-        //  0, 1, 2  3  4  5  <- index
-        // [T, F, F]          <- timeline     .len() -> 3
-        //       [F, T, F, F] <- skip_flags
-        //         [ T, F, F] <- append_skip_flags
-        // 
-        //  let after_timeline_len = target_abs_week (2) + skip_flags.len() (4);
-        //  let append_len = after_timeline_len (6) - self.timeline.len() (3); (3)
-        //  let append_start_index = skip_flags.len() (4) - append_len (3); (1)     // append start index
-        //  let append_skip_flags = [append_start_index..]
-        //  [T, T, T, T]
-        //  [T, T]      
-        //              []
-        //
-        if self.timeline.len() < self.abs_to_index(target_abs_week)? {
-            return Err(AppendWeekErrorKind::UnderFlow);
-        }
-
-        let append_start_index = self.timeline.len() - self.abs_to_index(target_abs_week)?;
-
-        // 重要: indexを超えている場合
-        // 何もしない
-        if skip_flags.len() <= append_start_index {
-            return Ok(());
-        }
-
-        for is_skipped in &skip_flags[append_start_index..] {
-            self.append_week(*is_skipped);
-        }
-        Ok(())
-    }
-
-    /// 
-    pub fn append_week(
-        &mut self,
-        is_skipped: bool,
-    ) {
-        // 1. 次のdeltaを計算
-        let next_delta = match self.timeline.last() {
-            Some(WeekStatus::Active { logical_delta}) => logical_delta + 1,
-            Some(WeekStatus::Skipped) => {
-                self.find_last_active_delta().map(|d| d + 1).unwrap_or(self.initial_delta)
-            },
-            None => self.initial_delta, // 初回
-        };
-
-        // 2. スロット作成
-        let slot = if is_skipped {
-            WeekStatus::Skipped
-        } else {
-            WeekStatus::Active { 
-                logical_delta: next_delta,
-            }
-        };
-
-        self.timeline.push(slot);
-    }
-
-    /// シフトの導出
-    /// base_abs_weekを下回る場合でも返せる場合があるが
-    /// skipを使うことで週の途中からルールを開始することは可能なので対応しない
-    pub fn derive_shift<'a>(
-        &self,
-        week_rule_table: & WeekRuleTable<'a, Incomplete>,
-        staff_group_list: &'a StaffGroupList,
-        gen_week_abs: AbsWeek,  // 生成の始点となる絶対週
-        gen_range: usize,       // 何週間分のシフトを作成するか
-    ) -> Vec<Option<WeekDecidedShift<'a>>>{
-        if let Ok(index) = self.abs_to_index(gen_week_abs) {
-            if index + gen_range < self.timeline.len() {
-                self.timeline[
-                    index..index + gen_range
-                ].iter().map(|i|{
-                    if let WeekStatus::Active { logical_delta } = i {
-                        Some(gen_one_week_shift(week_rule_table, staff_group_list, *logical_delta))
-                    } else {
-                        None
-                    }
-                }).collect()
-            } else if index < self.timeline.len() {
-                self.timeline[
-                    index..
-                ].iter().map(|i|{
-                    if let WeekStatus::Active { logical_delta } = i {
-                        Some(gen_one_week_shift(week_rule_table, staff_group_list, *logical_delta))
-                    } else {
-                        None
-                    }
-                }).collect()
-            } else {
-                Vec::new()
-            }
-        } else {
-            Vec::new()
-        }
-    }
-
-    /// 直近の有効なDeltaを探すヘルパー
-    fn find_last_active_delta(&self) -> Option<LogicalDelta> {
-        self.timeline.iter().rev().find_map(|slot| match slot {
-            WeekStatus::Active {
-                logical_delta, 
-            } => Some(*logical_delta),
-            WeekStatus::Skipped => None,
-        })
-    }
-
-    /// 【重要】指定した絶対週以降をすべて削除する（Truncate）
-    /// 配列を短くするだけなので極めて高速かつ安全
-    pub fn truncate_from(&mut self, target_abs_week: AbsWeek) {
-        if target_abs_week < self.base_abs_week {
-            // 開始地点より前を指定されたら全消し
-            self.timeline.clear();
-            // 必要なら start_abs_week 自体を書き換えるロジックも検討
-            return;
-        }
-
-        let keep_len = (target_abs_week - self.base_abs_week) as usize;
-        if keep_len < self.timeline.len() {
-            self.timeline.truncate(keep_len);
-        }
-    }
-
-    pub fn get_skip_list_by_abs(
-        &self, 
-        abs_week: AbsWeek, 
-        range: usize
-    ) -> Vec<bool> {
-        if let Ok(index) = self.abs_to_index(abs_week) {
-            if index < self.timeline.len() {
-                if index + range < self.timeline.len() {
-                    self
-                        .timeline[index..index + range]
-                        .iter()
-                        .map(|a| matches!(a, WeekStatus::Skipped))
-                        .collect()
-                } else {
-                    self
-                        .timeline[index..]
-                        .iter()
-                        .map(|a| matches!(a, WeekStatus::Skipped))
-                        .collect()
-                }
-            } else {
-                Vec::new()
-            }
-        } else {
-            Vec::new()
-        }
-       
-    }
-
-    pub fn get_skip_list(&self) -> Vec<bool> {
-        self
-            .timeline
-            .iter()
-            .map(|a| matches!(a, WeekStatus::Skipped))
-            .collect()
-    }
-
-    pub fn get_timeline(&self) -> &Vec<WeekStatus> {
-        &self.timeline
-    }
-
-    /// json化した内部状態を返却します
-    pub fn output_inner_data(&self) -> Result<String, String>{
-        match serde_json::to_string(&self) {
-            Ok(v) => {
-                Ok(v)
-            }
-            Err(_e) => {
-                Err(String::from("FailedToStringify"))
-            }
-        }
-    }
-
-    pub fn load_calendar_from_json(&mut self, json_str: String) -> Result<(), String>{
-        let mut config: ShiftCalendarManager = serde_json::from_str(&json_str)
-            .map_err(|e| format!("JSON parse error: {}", e))?;
-
-        self.base_abs_week = config.base_abs_week;
-        self.initial_delta = config.initial_delta;
-        self.timeline = config.timeline;
-        Ok(())
-    }
+pub type ShiftCalendarManager = shift_timeline::ShiftCalendarManager<()>;
+
+/// skip_flags (`true` = skip) をタイムラインに追加できる形に変換する
+pub fn weeks_from_skip_flags(skip_flags: &[bool]) -> Vec<Option<()>> {
+    skip_flags
+        .iter()
+        .map(|is_skipped| if *is_skipped { None } else { Some(()) })
+        .collect()
 }
 
 
 // ==================================== test ==================================== 
 #[cfg(test)]
 mod shift_calendar_manager {
-    use crate::shift_calendar_manager::{AppendWeekErrorKind, ShiftCalendarManager};
-
-    /// 正しくシフトカレンダーに設定できるか？
-    #[test] 
-    fn test00() {
-        let mut shift_calendar_manager 
-            = ShiftCalendarManager::new(2000, 0);
-
-        let mut r = shift_calendar_manager
-            .append_check(2000, &[
-                false, // 2000
-                false, // 2001
-                true,  // 2002 skip!
-                false  // 2003
-            ]);
-
-        // 何もないリストへ追加可能をチェック
-        // ここではOkと変えるはず
-        assert!(matches!(r, Ok(())));
-
-        shift_calendar_manager.append_week(false);
-        shift_calendar_manager.append_week(false);
-        shift_calendar_manager.append_week(true);
-        shift_calendar_manager.append_week(false);  // 2003
-
-        let r = shift_calendar_manager
-            .append_check(2001, &[
-                false, // 2001
-                true,  // 2002
-                false, // 2003
-                false  // 2004 (new)
-            ]);
-
-        shift_calendar_manager.append_week(false);  // 2004
-
-        assert!(matches!(r, Ok(())));
-
-        let r = shift_calendar_manager
-            .append_check(2005, &[
-                false, // 2005
-            ]);
-
-        shift_calendar_manager.append_week(false);  // 2005
-        assert!(matches!(r, Ok(())));
-
-        let r = shift_calendar_manager
-            .append_check(2007, &[
-                false, // 2005
-            ]);
-
-        assert!(matches!(r, Err(AppendWeekErrorKind::NotConsecutiveShifts)));
-
-        let r = shift_calendar_manager
-            .append_check(2001, &[
-                true, // 2001
-                true,  // 2002
-                false, // 2003
-                false  // 2004 (new)
-            ]);
-
-        assert!(matches!(r, Err(AppendWeekErrorKind::AttemptedToOverwrite)));
-    }
+    use crate::shift_calendar_manager::{ShiftCalendarManager, weeks_from_skip_flags};
 
     use shift_calendar::{
         self,
@@ -527,18 +153,18 @@ mod shift_calendar_manager {
         staff_group_list.add_staff_group(staff_group_b);
 
         let r = shift_calendar_manager
-            .append_check(2000, &[
+            .append_check(2000, &weeks_from_skip_flags(&[
                 false, // 2001
                 false,  // 2002
                 true, // 2003
                 false  // 2004 (new)
-            ]);
+            ]));
 
         // then 
-        shift_calendar_manager.append_week(false);
-        shift_calendar_manager.append_week(false);
-        shift_calendar_manager.append_week(true);
-        shift_calendar_manager.append_week(false); // ここで設定されたら決定
+        shift_calendar_manager.append_week(Some(()));
+        shift_calendar_manager.append_week(Some(()));
+        shift_calendar_manager.append_week(None);
+        shift_calendar_manager.append_week(Some(())); // ここで設定されたら決定
 
         assert!(matches!(r, Ok(())));
 
@@ -547,7 +173,7 @@ mod shift_calendar_manager {
         // 内部的にはシフトは決定済み
         let a = shift_calendar_manager
             .derive_shift(
-                &week_rule_table,
+                |_| Some(&week_rule_table),
                 &staff_group_list, 
                 gen_week_abs, 
                 gen_range
@@ -612,12 +238,12 @@ mod shift_calendar_manager {
         staff_group_list.add_staff_group(staff_group_a);
         staff_group_list.add_staff_group(staff_group_b);
 
-        let r = shift_calendar_manager.apply_weeks(2000, &[
+        let r = shift_calendar_manager.apply_weeks(2000, &weeks_from_skip_flags(&[
             false,
             false,
             true,
             false
-        ]);
+        ]));
 
         assert!(matches!(r, Ok(())));
 
@@ -626,7 +252,7 @@ mod shift_calendar_manager {
         // 内部的にはシフトは決定済み
         let a = shift_calendar_manager
             .derive_shift(
-                &week_rule_table,
+                |_| Some(&week_rule_table),
                 &staff_group_list, 
                 gen_week_abs, 
                 gen_range
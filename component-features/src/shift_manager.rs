@@ -18,14 +18,26 @@ use exports::component::component_features::shift_manager::{
     WeekSchedule,
     DayShiftIds,
     Holl,
+    // ==== load ====
+    LoadError,
     // ==== out ====
-    WeeklyShiftOut
+    WeeklyShiftOut,
+    StaffShiftOut,
+    ChangeVersions,
+    CoverageWarningOut,
+    CoverageWarningKind,
+};
+
+use shift_timeline::{
+    CoverageLimits, CoverageWarning, LockedOverlay, LockedWeek, ShiftOverride, ShiftPeriod, StaffRemoval, apply_overrides,
+    check_week, remove_from_overrides,
+    schema::ValidationError,
 };
 
 use shift_calendar::{
     self,
     shift_gen::{
-        DayDecidedShift, DayRule, Incomplete, ShiftHoll, Staff, WeekDecidedShift, WeekRule 
+        DayDecidedShift, DayRule, Incomplete, ShiftHoll, Staff, StaffGroupList, WeekDecidedShift, WeekRule, WeekRuleTable
     }
 };
 
@@ -34,9 +46,13 @@ use std::{
 };
 
 use crate::{
-    load_rules::{JsonAssignment, JsonConfig, JsonDailySchedule, JsonRule, JsonStaffGroup, JsonWeeklySchedule}, shift_calendar_manager::{
+    change_versions::{ChangeArea, VersionCounter, remove_at, set_if_changed},
+    load_rules::{JsonAssignment, JsonConfig, JsonDailySchedule, JsonRule, JsonSlot, JsonStaffGroup, JsonWeeklySchedule},
+    project::{JsonProject, JsonProjectMetadata},
+    shift_calendar_manager::{
         AbsWeek, 
-        ShiftCalendarManager
+        ShiftCalendarManager,
+        weeks_from_skip_flags,
     }, shift_manager::exports::component::component_features::shift_manager::{
         DailyShiftOut, 
         StaffPillOut
@@ -63,6 +79,7 @@ impl StaffGroup {
     fn from_json(json_group: JsonStaffGroup) -> Self {
         Self { 
             name: json_group.name, 
+            min_per_period: json_group.min_per_period,
             slots: json_group
                 .slots
                 .iter()
@@ -75,18 +92,32 @@ impl StaffGroup {
         }
     }
 
+    fn to_json(&self) -> JsonStaffGroup {
+        JsonStaffGroup {
+            name: self.name.clone(),
+            slots: self
+                .slots
+                .iter()
+                .map(|i| JsonSlot { name: i.name.clone() })
+                .collect(),
+            min_per_period: self.min_per_period,
+        }
+    }
+
     fn add_slot(&mut self) {
         self.slots.push(StaffInfo{name: String::from("")});
     }
 
-    fn remove_slot(&mut self, slot_idx: u32) {
-        self.slots.remove(slot_idx as usize);
+    /// 取り除いたら true (範囲外なら何もしない)
+    fn remove_slot(&mut self, slot_idx: u32) -> bool {
+        remove_at(&mut self.slots, slot_idx as usize)
     }
 
-    fn update_memo(&mut self, staff_slot_index: u32, name:String) {
-        if let Some(a) = self.slots.get_mut(staff_slot_index as usize) {
-            a.name = name;
-        }
+    /// 名前が変わったら true
+    fn update_memo(&mut self, staff_slot_index: u32, name:String) -> bool {
+        self.slots
+            .get_mut(staff_slot_index as usize)
+            .is_some_and(|a| set_if_changed(&mut a.name, name))
     }
 }
 
@@ -136,6 +167,18 @@ impl WeekSchedule {
         }
     }
 
+    fn to_json(&self) -> JsonWeeklySchedule {
+        JsonWeeklySchedule {
+            mon: self.mon.to_json(),
+            tue: self.tue.to_json(),
+            wed: self.wed.to_json(),
+            thu: self.thu.to_json(),
+            fri: self.fri.to_json(),
+            sat: self.sat.to_json(),
+            sun: self.sun.to_json(),
+        }
+    }
+
     // WeeklyRuleに新しいhollを追加
     fn add_week_rule_assignment (
         &mut self,
@@ -160,15 +203,15 @@ impl WeekSchedule {
         day: ShiftWeekday,
         shift_time: ShiftTime,
         index: u32
-    ) {
+    ) -> bool {
         let day = day.extract_mut_day_shift_ids(self);
 
         match shift_time {
             ShiftTime::Morning => {
-                day.m.remove(index as usize);
+                remove_at(&mut day.m, index as usize)
             }
             ShiftTime::Afternoon => {
-                day.a.remove(index as usize);
+                remove_at(&mut day.a, index as usize)
             } 
         }
     }
@@ -233,8 +276,16 @@ impl WeeklyRule {
         }
     }
 
-    fn change_name(&mut self, name: String) {
-        self.name = name;
+    fn to_json(&self) -> JsonRule {
+        JsonRule {
+            name: self.name.clone(),
+            schedule: self.schedule.to_json()
+        }
+    }
+
+    /// 名前が変わったら true
+    fn change_name(&mut self, name: String) -> bool {
+        set_if_changed(&mut self.name, name)
     }
 }
 
@@ -256,6 +307,13 @@ impl DayShiftIds {
         }
     }
 
+    fn to_json(&self) -> JsonDailySchedule {
+        JsonDailySchedule {
+            m: self.m.iter().map(|i| i.to_json()).collect(),
+            a: self.a.iter().map(|i| i.to_json()).collect()
+        }
+    }
+
     /// shift_calendarが処理できる型に変換する
     fn day_shift_ids_into_day_rule<'a>(&self) -> DayRule<'a, Incomplete> {
         DayRule {
@@ -285,6 +343,13 @@ impl Holl {
             shift_staff_index: json_assignment.shift_staff_index
         }
     } 
+
+    fn to_json(&self) -> JsonAssignment {
+        JsonAssignment {
+            staff_group_id: self.staff_group_id,
+            shift_staff_index: self.shift_staff_index
+        }
+    }
 }
 
 // --------------------------------------------------------
@@ -304,6 +369,17 @@ struct AppState {
     // Key: "YYYY-MM-DD"
     // 実際に生成されたカレンダー
     schedule_data: RefCell<ShiftCalendarManager>,
+
+    // プロジェクトのjsonから読み込み、そのまま書き出すもの
+    project_title: RefCell<Option<String>>,
+    // 週・日単位の手動調整 (プロジェクトのjsonから読み込む。導出した結果に当てる)
+    overrides: RefCell<Vec<ShiftOverride>>,
+
+    // 人員チェック用: 1人あたりの週の出勤日数の上限
+    max_days_per_week: RefCell<Option<u32>>,
+
+    // 変更の番号 (UIが変わった所だけ描き直す用)
+    versions: RefCell<VersionCounter>,
 }
 
 impl GuestShiftManager for AppState {
@@ -333,7 +409,11 @@ impl GuestShiftManager for AppState {
                     gen_week_abs, //base_abs_week, 
                     0 // initial_delta
                 )
-            )
+            ),
+            project_title: RefCell::new(None),
+            overrides: RefCell::new(vec![]),
+            max_days_per_week: RefCell::new(None),
+            versions: RefCell::new(VersionCounter::default()),
         }
     }
 
@@ -342,51 +422,81 @@ impl GuestShiftManager for AppState {
         self.staff_groups.borrow_mut().push(
             StaffGroup { 
                 name: format!("Group{}", staff_group_length),
-                slots: vec![]
+                slots: vec![],
+                min_per_period: None,
             }
         );
+        self.touch(ChangeArea::Staff, true);
     }
 
     fn remove_group(&self, index: u32) {
-        self.staff_groups.borrow_mut().remove(index as usize);
+        let changed = remove_at(&mut self.staff_groups.borrow_mut(), index as usize);
+        if changed {
+            self.remove_staff(StaffRemoval::Group { group_index: index as usize });
+        }
+        self.touch(ChangeArea::Staff, changed);
     }
 
     fn update_group_name(&self, index: u32, name: String) {
-        if let Some(a) = self
+        let changed = self
             .staff_groups
             .borrow_mut()
-            .get_mut(index as usize) {
-                a.name = name;
-        }
+            .get_mut(index as usize)
+            .is_some_and(|a| set_if_changed(&mut a.name, name));
+        self.touch(ChangeArea::Staff, changed);
+    }
+
+    fn update_group_min_staff(&self, index: u32, min_per_period: Option<u32>) {
+        let changed = self
+            .staff_groups
+            .borrow_mut()
+            .get_mut(index as usize)
+            .is_some_and(|a| set_if_changed(&mut a.min_per_period, min_per_period));
+        self.touch(ChangeArea::Staff, changed);
+    }
+
+    fn set_max_days_per_week(&self, max_days: Option<u32>) {
+        let changed = set_if_changed(&mut *self.max_days_per_week.borrow_mut(), max_days);
+        self.touch(ChangeArea::Staff, changed);
+    }
+
+    fn get_max_days_per_week(&self) -> Option<u32> {
+        *self.max_days_per_week.borrow()
     }
 
     fn add_slot(&self, group_idx: u32) {
-        if let Some(a) = self
+        let changed = if let Some(a) = self
             .staff_groups
             .borrow_mut()
             .get_mut(group_idx as usize)
         {
             a.add_slot();
-        }
+            true
+        } else {
+            false
+        };
+        self.touch(ChangeArea::Staff, changed);
     }
 
     fn remove_slot(&self,group_idx:u32,slot_idx:u32,){
-        if let Some(a) =self
+        let changed = self
             .staff_groups
             .borrow_mut()
-            .get_mut(group_idx as usize) {
-                a.remove_slot(slot_idx);
+            .get_mut(group_idx as usize)
+            .is_some_and(|a| a.remove_slot(slot_idx));
+        if changed {
+            self.remove_staff(StaffRemoval::Slot { group_index: group_idx as usize, staff_index: slot_idx as usize });
         }
+        self.touch(ChangeArea::Staff, changed);
     }
 
     fn update_slot_memo(&self, group_idx:u32, slot_idx:u32, memo: String) {
-        if let Some(a) = self
+        let changed = self
             .staff_groups
             .borrow_mut()
             .get_mut(group_idx as usize)
-        {
-            a.update_memo(slot_idx, memo);
-        }
+            .is_some_and(|a| a.update_memo(slot_idx, memo));
+        self.touch(ChangeArea::Staff, changed);
     }
 
     fn add_week(&self) {
@@ -394,19 +504,21 @@ impl GuestShiftManager for AppState {
             .rules
             .borrow_mut()
             .push(WeeklyRule::new());
+        self.touch(ChangeArea::Rules, true);
     }
 
     fn remove_rule(&self, index: u32) {
-        self.rules.borrow_mut().remove(index as usize);
+        let changed = remove_at(&mut self.rules.borrow_mut(), index as usize);
+        self.touch(ChangeArea::Rules, changed);
     }
 
     fn update_rule_name(&self, index: u32, name: String) {
-        if let Some(a) =self
+        let changed = self
             .rules
             .borrow_mut()
-            .get_mut(index as usize) {
-            a.change_name(name);
-        }
+            .get_mut(index as usize)
+            .is_some_and(|a| a.change_name(name));
+        self.touch(ChangeArea::Rules, changed);
     }
 
     fn add_rule_assignment(
@@ -418,7 +530,7 @@ impl GuestShiftManager for AppState {
         shift_staff_index:u32,
     )
     {
-        if let Some(weekly_rule) = self
+        let changed = if let Some(weekly_rule) = self
             .rules
             .borrow_mut()
             .get_mut(
@@ -431,7 +543,11 @@ impl GuestShiftManager for AppState {
                     shift_time, 
                     Holl { staff_group_id, shift_staff_index }
                 );
-        }
+            true
+        } else {
+            false
+        };
+        self.touch(ChangeArea::Rules, changed);
     }
 
     fn remove_rule_assignment(
@@ -440,20 +556,22 @@ impl GuestShiftManager for AppState {
         day: ShiftWeekday, 
         shift_time: ShiftTime, 
         index: u32) {
-        if let Some(weekly_rule) = self
+        let changed = self
             .rules
             .borrow_mut()
             .get_mut(
             rule_idx as usize
-        ){ 
-            weekly_rule
-                .schedule
-                .remove_week_rule_assignment(
-                    day, 
-                    shift_time,
-                    index
-                );
-        }
+        )
+            .is_some_and(|weekly_rule| {
+                weekly_rule
+                    .schedule
+                    .remove_week_rule_assignment(
+                        day, 
+                        shift_time,
+                        index
+                    )
+            });
+        self.touch(ChangeArea::Rules, changed);
     }
 
     fn get_rule_assignment(
@@ -515,6 +633,11 @@ impl GuestShiftManager for AppState {
         self.staff_groups.borrow().clone()
     }
 
+    fn get_change_versions(&self) -> ChangeVersions {
+        let versions = self.versions.borrow();
+        ChangeVersions { staff: versions.staff, rules: versions.rules, calendar: versions.calendar }
+    }
+
     fn get_year(&self) -> u32 {
         *self.year.borrow()
     }
@@ -524,30 +647,9 @@ impl GuestShiftManager for AppState {
     }
 
     fn get_monthly_shift(&self) -> Vec<Option<WeeklyShiftOut>> {
-        let mut week_rule_table = 
-            shift_calendar::shift_gen::WeekRuleTable::new();
-        let mut staff_group_list = 
-            shift_calendar::shift_gen::StaffGroupList::new();
-        for i in self.get_weekly_rules() {
-            week_rule_table.add_week_rule(WeekRule([
-                i.schedule.mon.day_shift_ids_into_day_rule(),
-                i.schedule.tue.day_shift_ids_into_day_rule(),
-                i.schedule.wed.day_shift_ids_into_day_rule(),
-                i.schedule.thu.day_shift_ids_into_day_rule(),
-                i.schedule.fri.day_shift_ids_into_day_rule(),
-                i.schedule.sat.day_shift_ids_into_day_rule(),
-                i.schedule.sun.day_shift_ids_into_day_rule(),
-            ]));
-        }
-
-        for i in self.get_staff_groups() {
-            let mut staff_group = 
-                shift_calendar::shift_gen::StaffGroup::new(&i.name);
-            for j in &i.slots {
-                staff_group.add_staff(&j.name);
-            }
-            staff_group_list.add_staff_group(staff_group);
-        }
+        let week_rule_table = self.week_rule_table();
+        let staff_group_list = self.staff_group_list();
+        let limits = self.current_config().coverage_limits();
 
         let gen_week_abs = if let Some (a) = calculate_weeks_delta_from_base(
             self.get_year() as i32,
@@ -559,27 +661,96 @@ impl GuestShiftManager for AppState {
             return Vec::new();
         };
 
-        self.schedule_data
-            .borrow()
-            .derive_shift(
-                &week_rule_table,
-                &staff_group_list,
-                gen_week_abs,
-                calculate_weeks_in_month(
-                    self.get_year() as i32,
-                    self.get_month()) as usize
-            )
+        let gen_range = calculate_weeks_in_month(self.get_year() as i32, self.get_month()) as usize;
+        let schedule_data = self.schedule_data.borrow();
+
+        // 手動調整を当ててから、固定した週は固定した内容に置き換える
+        let staff = self.staff();
+        let overlay = LockedOverlay::new(&schedule_data.locked_weeks, gen_week_abs, gen_range);
+        let mut decided = schedule_data.derive_shift(
+            |_| Some(&week_rule_table),
+            &staff_group_list,
+            gen_week_abs,
+            gen_range
+        );
+        apply_overrides(&mut decided, gen_week_abs, &self.overrides.borrow(), &staff);
+        overlay.apply(&mut decided);
+
+        decided
             .iter()
-            .map(|a| {
+            .enumerate()
+            .map(|(offset, a)| {
                 a.as_ref().map(|b| 
-                    week_decided_shift_into_weekly_shift_out(&b)
+                    week_decided_shift_into_weekly_shift_out(b, &limits, overlay.is_locked(offset))
                 )
             }
             )
             .collect()
     }
 
+    fn get_staff_schedule(
+        &self,
+        staff_group_id: u32,
+        staff_index: u32,
+        start_date: String,
+        end_date: String
+    ) -> Result<Vec<StaffShiftOut>, String> {
+        let parse_date = |text: &str| NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .map_err(|_| format!("日付は YYYY-MM-DD で指定してください: {}", text));
+        let start = parse_date(&start_date)?;
+        let end = parse_date(&end_date)?;
+        if end < start {
+            return Err(format!("終了日 {} が開始日 {} より前です", end_date, start_date));
+        }
+
+        let group_name = self.staff_groups
+            .borrow()
+            .get(staff_group_id as usize)
+            .map(|g| g.name.clone())
+            .ok_or_else(|| format!("グループ {} が存在しません", staff_group_id))?;
+
+        let abs_week_of = |date: NaiveDate| calculate_weeks_delta_from_base(date.year(), date.month0(), date.day())
+            .ok_or_else(|| format!("{} は1970年より前です", date));
+        let start_abs = abs_week_of(start)?;
+        let end_abs = abs_week_of(end)?;
+
+        // 全ルールを1つのテーブルにまとめて使うので、週に使うルールは logical_delta で決まる (導出と同じ)
+        let rules = self.rules.borrow();
+        let rule_name = |logical_delta: usize| (!rules.is_empty()).then(|| rules[logical_delta % rules.len()].name.clone());
+
+        let week_rule_table = self.week_rule_table();
+        let staff_group_list = self.staff_group_list();
+
+        Ok(self.schedule_data
+            .borrow()
+            .staff_shifts(
+                |_| Some(&week_rule_table),
+                &staff_group_list,
+                (staff_group_id as usize, staff_index as usize),
+                &self.overrides.borrow(),
+                start_abs,
+                end_abs - start_abs + 1
+            )
+            .into_iter()
+            .filter_map(|shift| {
+                // 週の途中から・途中までの指定なので、範囲外の曜日を落とす
+                let date = date_of_abs_week(shift.abs_week, shift.weekday);
+                (start <= date && date <= end).then(|| StaffShiftOut {
+                    date: date.format("%Y-%m-%d").to_string(),
+                    shift_time: match shift.period {
+                        ShiftPeriod::Morning => ShiftTime::Morning,
+                        ShiftPeriod::Afternoon => ShiftTime::Afternoon,
+                    },
+                    group_name: group_name.clone(),
+                    rule_name: rule_name(shift.logical_delta),
+                })
+            })
+            .collect())
+    }
+
     fn apply_month_shift(&self, skip_flags: Vec<bool>) {
+        // apply_weeks は末尾に足すだけなので、長さが変わったら変更あり
+        let timeline_len = self.schedule_data.borrow().get_timeline().len();
         if let Some (gen_week_abs) =
             calculate_weeks_delta_from_base(
             self.get_year() as i32,
@@ -589,11 +760,13 @@ impl GuestShiftManager for AppState {
             // TODO: Err処理をするapi設計に変える
             if let Err(e) = self.schedule_data.borrow_mut().apply_weeks(
                 gen_week_abs, 
-                &skip_flags
+                &weeks_from_skip_flags(&skip_flags)
             ) {
                 log(&format!("error occured {:?}", e));
             };
         }
+        let changed = self.schedule_data.borrow().get_timeline().len() != timeline_len;
+        self.touch(ChangeArea::Calendar, changed);
     }
 
     fn get_skip_flags(
@@ -624,53 +797,88 @@ impl GuestShiftManager for AppState {
     }
 
     fn reset_from_this_month(&self) {
-        if let Some (a) = calculate_weeks_delta_from_base(
+        let changed = calculate_weeks_delta_from_base(
             self.get_year() as i32,
             self.get_month(),
             1
-        ) {
+        ).is_some_and(|a| {
             self
                 .schedule_data
                 .borrow_mut()
-                .truncate_from(a);
-        }
+                .truncate_from(a)
+        });
+        self.touch(ChangeArea::Calendar, changed);
     }
 
-    /// この関数では、与えられたjson文字列をパースし、app_stateにセットする
-    fn load_config_from_json(&self, json_str: String) -> Result<(), String> {
-        // 1. JSON文字列を Rustの構造体にパース
-        let config: JsonConfig = serde_json::from_str(&json_str)
-            .map_err(|e| format!("JSON parse error: {}", e))?;
-
-        // 2. 内部状態をクリア (必要に応じて)
-
-        let mut groups_guard = self.staff_groups.borrow_mut();
-        let mut rules_guard = self.rules.borrow_mut();
+    fn lock_this_month(&self) -> Result<(), String> {
+        let gen_week_abs = calculate_weeks_delta_from_base(self.get_year() as i32, self.get_month(), 1)
+            .ok_or_else(|| String::from("1970年より前の月は固定できません"))?;
+        let gen_range = calculate_weeks_in_month(self.get_year() as i32, self.get_month()) as usize;
+
+        let week_rule_table = self.week_rule_table();
+        let staff_group_list = self.staff_group_list();
+        let mut schedule_data = self.schedule_data.borrow_mut();
+
+        // 今表示している内容 (手動調整を当てた結果) をそのまま固定する
+        // 固定済みの週は固定し直さない (消した人のコマが位置から外れているので)
+        let staff = self.staff();
+        let mut decided = schedule_data.derive_shift(
+            |_| Some(&week_rule_table),
+            &staff_group_list,
+            gen_week_abs,
+            gen_range
+        );
+        apply_overrides(&mut decided, gen_week_abs, &self.overrides.borrow(), &staff);
+        let weeks: Vec<LockedWeek> = decided
+            .iter()
+            .enumerate()
+            .filter(|(offset, _)| !schedule_data.is_locked(gen_week_abs + offset))
+            .filter_map(|(offset, week)| week.as_ref().map(|week| LockedWeek::from_decided(gen_week_abs + offset, week)))
+            .collect();
+
+        // 導出できた週はActiveなので失敗しない
+        let locked_before = schedule_data.locked_weeks.clone();
+        for week in weeks {
+            schedule_data.lock_week(week).map_err(|e| e.to_string())?;
+        }
+        // 全部固定済みなら変更なし
+        let changed = schedule_data.locked_weeks != locked_before;
+        drop(schedule_data);
+        self.touch(ChangeArea::Calendar, changed);
+        Ok(())
+    }
 
-        groups_guard.clear();
-        rules_guard.clear();
+    fn unlock_this_month(&self) {
+        let changed = calculate_weeks_delta_from_base(self.get_year() as i32, self.get_month(), 1).is_some_and(|a| {
+            self.schedule_data
+                .borrow_mut()
+                .unlock_weeks(a, calculate_weeks_in_month(self.get_year() as i32, self.get_month()) as usize)
+        });
+        self.touch(ChangeArea::Calendar, changed);
+    }
 
-        // 3. データを内部構造に移し替える
-        // (JsonConfigの構造と内部構造が完全に一致しているならそのまま代入でOKですが、
-        //  型が違う場合はここで変換します)
+    /// この関数では、与えられたjson文字列をパース・検査し、問題が無ければapp_stateにセットする
+    fn load_config_from_json(&self, json_str: String) -> Result<(), Vec<LoadError>> {
+        // JSON文字列を Rustの構造体にパース (古いバージョンはここで最新の形になる)
+        let config = JsonConfig::load_json(&json_str)
+            .map_err(validation_errors_into_load_errors)?;
+        self.replace_config(config);
+        Ok(())
+    }
 
-        // --- Staff Groups のロード ---
-        for json_group in config.staff_groups {
-            // 内部用のStaffGroup型に変換して追加
-            groups_guard.push(
-                StaffGroup::from_json(json_group)
-            );
-        }
+    fn load_config_from_toml(&self, toml_str: String) -> Result<(), Vec<LoadError>> {
+        let config = JsonConfig::load_toml(&toml_str)
+            .map_err(validation_errors_into_load_errors)?;
+        self.replace_config(config);
+        Ok(())
+    }
 
-        // --- Rules のロード ---
-        for json_rule in config.rules {
-            // 内部用のRule型に変換して追加
-            rules_guard.push(
-                WeeklyRule::from_json(&json_rule)
-            );
-        }
+    fn output_config_json(&self) -> Result<String, String> {
+        self.current_config().to_json()
+    }
 
-        Ok(())
+    fn output_config_toml(&self) -> Result<String, String> {
+        self.current_config().to_toml()
     }
 
     fn output_calendar_manager_data(&self,) -> Result<String, String> {
@@ -679,13 +887,165 @@ impl GuestShiftManager for AppState {
             .output_inner_data()
     }
 
-    fn load_calendar_from_json(&self, json_setting:String) -> Result<(), String> {
+    fn load_calendar_from_json(&self, json_setting:String) -> Result<(), Vec<LoadError>> {
         self.schedule_data
             .borrow_mut()
             .load_calendar_from_json(json_setting)
+            .map_err(validation_errors_into_load_errors)?;
+        self.touch(ChangeArea::Calendar, true);
+        Ok(())
+    }
+
+    fn export_project(&self) -> Result<String, String> {
+        // グループ・スロットを消したときに調整も付け替えているので、ここで合わないものは無いはず
+        // 合わないものがあれば、読み込めないファイルを書き出さないように黙って落とさずエラーにする
+        let config = self.current_config();
+        let overrides = self.overrides.borrow().clone();
+        let problems = override_problems(&overrides, &config);
+        if !problems.is_empty() {
+            return Err(format!("今の設定と合わない手動調整があります: {}", problems.join(", ")));
+        }
+        JsonProject {
+            metadata: JsonProjectMetadata {
+                title: self.project_title.borrow().clone(),
+                generator: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+                year: Some(self.get_year()),
+                month: Some(self.get_month()),
+            },
+            config,
+            calendar: self.schedule_data.borrow().clone(),
+            overrides,
+        }
+        .to_json()
+    }
+
+    fn import_project(&self, json_setting: String) -> Result<(), Vec<LoadError>> {
+        // 1. パースと検証 (失敗した場合はここで戻るので、状態は何も変わらない)
+        let project = JsonProject::from_json(&json_setting)
+            .map_err(validation_errors_into_load_errors)?;
+
+        // 2. まとめて差し替える (ここから先は失敗しない)
+        self.replace_config(project.config);
+        *self.schedule_data.borrow_mut() = project.calendar;
+        *self.project_title.borrow_mut() = project.metadata.title;
+        *self.overrides.borrow_mut() = project.overrides;
+        if let (Some(year), Some(month)) = (project.metadata.year, project.metadata.month) {
+            *self.year.borrow_mut() = year;
+            *self.month.borrow_mut() = month;
+        }
+        self.touch(ChangeArea::Calendar, true);
+        Ok(())
+    }
+}
+
+impl AppState {
+    /// 現在のルール設定 (保存用の形)
+    fn current_config(&self) -> JsonConfig {
+        JsonConfig {
+            staff_groups: self.staff_groups.borrow().iter().map(|g| g.to_json()).collect(),
+            rules: self.rules.borrow().iter().map(|r| r.to_json()).collect(),
+            max_days_per_week: *self.max_days_per_week.borrow(),
+        }
+    }
+
+    /// 全ルールを1つにまとめたテーブル (シフト導出用)
+    fn week_rule_table<'a>(&self) -> WeekRuleTable<'a, Incomplete> {
+        let mut week_rule_table = WeekRuleTable::new();
+        for i in self.rules.borrow().iter() {
+            week_rule_table.add_week_rule(WeekRule([
+                i.schedule.mon.day_shift_ids_into_day_rule(),
+                i.schedule.tue.day_shift_ids_into_day_rule(),
+                i.schedule.wed.day_shift_ids_into_day_rule(),
+                i.schedule.thu.day_shift_ids_into_day_rule(),
+                i.schedule.fri.day_shift_ids_into_day_rule(),
+                i.schedule.sat.day_shift_ids_into_day_rule(),
+                i.schedule.sun.day_shift_ids_into_day_rule(),
+            ]));
+        }
+        week_rule_table
+    }
+
+    /// シフト導出用のスタッフリスト
+    fn staff_group_list(&self) -> StaffGroupList {
+        let mut staff_group_list = StaffGroupList::new();
+        for i in self.staff_groups.borrow().iter() {
+            let mut staff_group =
+                shift_calendar::shift_gen::StaffGroup::new(&i.name);
+            for j in &i.slots {
+                staff_group.add_staff(&j.name);
+            }
+            staff_group_list.add_staff_group(staff_group);
+        }
+        staff_group_list
+    }
+
+    /// 手動調整で足す人を探す用の全スタッフ (`Staff::group_id` / `id` は staff_group_list と同じ位置)
+    fn staff(&self) -> Vec<Staff> {
+        self.staff_groups
+            .borrow()
+            .iter()
+            .enumerate()
+            .flat_map(|(group_id, group)| {
+                group.slots.iter().enumerate().map(move |(id, slot)| Staff {
+                    name: slot.name.clone(),
+                    group_id,
+                    id,
+                })
+            })
+            .collect()
+    }
+
+    /// ルール設定をまるごと差し替える
+    /// 手動調整は位置でスタッフを指すので、新しい設定にいない人を指すものは取り除く
+    fn replace_config(&self, config: JsonConfig) {
+        let group_sizes: Vec<usize> = config.staff_groups.iter().map(|g| g.slots.len()).collect();
+        let override_count = self.overrides.borrow().len();
+        self.overrides.borrow_mut().retain(|o| o.problem(&group_sizes).is_none());
+        self.touch(ChangeArea::Calendar, self.overrides.borrow().len() != override_count);
+        *self.max_days_per_week.borrow_mut() = config.max_days_per_week;
+        *self.staff_groups.borrow_mut() = config.staff_groups
+            .into_iter()
+            .map(StaffGroup::from_json)
+            .collect();
+        *self.rules.borrow_mut() = config.rules
+            .iter()
+            .map(WeeklyRule::from_json)
+            .collect();
+        self.touch(ChangeArea::Staff, true);
+        self.touch(ChangeArea::Rules, true);
+    }
+
+    /// グループ・スロットを消した後ろの位置を詰める (手動調整や固定した週が別の人を指さないように)
+    /// 固定した週の消した人は、どのグループにも入らない人として名前だけ残す
+    fn remove_staff(&self, removal: StaffRemoval) {
+        let overrides_changed = remove_from_overrides(&mut self.overrides.borrow_mut(), removal);
+        let locked_changed = self.schedule_data.borrow_mut().remove_locked_staff(removal);
+        self.touch(ChangeArea::Calendar, overrides_changed || locked_changed);
+    }
+
+    // 変更の番号を進める (get-change-versions)。変わらなかった操作では進めない
+    fn touch(&self, area: ChangeArea, changed: bool) {
+        self.versions.borrow_mut().record(area, changed);
     }
 }
 
+/// `config` のグループ・スロットを指していない手動調整 (`overrides[i]: 理由`)
+fn override_problems(overrides: &[ShiftOverride], config: &JsonConfig) -> Vec<String> {
+    let group_sizes: Vec<usize> = config.staff_groups.iter().map(|g| g.slots.len()).collect();
+    overrides
+        .iter()
+        .enumerate()
+        .filter_map(|(index, o)| o.problem(&group_sizes).map(|problem| format!("overrides[{}]: {}", index, problem)))
+        .collect()
+}
+
+fn validation_errors_into_load_errors(errors: Vec<ValidationError>) -> Vec<LoadError> {
+    errors
+        .into_iter()
+        .map(|e| LoadError { path: e.path, message: e.message })
+        .collect()
+}
+
 fn staff_into_staff_pill_out (staff: &Staff) -> StaffPillOut {
     StaffPillOut { 
         name: staff.name.clone(),
@@ -701,7 +1061,28 @@ fn day_decided_shift_into_daily_shift_out (day_decided_shift: &DayDecidedShift)
     }
 }
 
-fn week_decided_shift_into_weekly_shift_out<'a>(week_decided_shift: &WeekDecidedShift<'a>) -> WeeklyShiftOut {
+fn coverage_warning_into_coverage_warning_out(warning: &CoverageWarning) -> CoverageWarningOut {
+    const WEEKDAYS: [ShiftWeekday; 7] = [
+        ShiftWeekday::Mon, ShiftWeekday::Tue, ShiftWeekday::Wed, ShiftWeekday::Thu,
+        ShiftWeekday::Fri, ShiftWeekday::Sat, ShiftWeekday::Sun,
+    ];
+    CoverageWarningOut {
+        kind: match warning {
+            CoverageWarning::EmptyPeriod { .. } => CoverageWarningKind::EmptyPeriod,
+            CoverageWarning::BelowMinimum { .. } => CoverageWarningKind::BelowMinimum,
+            CoverageWarning::DoubleBooked { .. } => CoverageWarningKind::DoubleBooked,
+            CoverageWarning::TooManyDays { .. } => CoverageWarningKind::TooManyDays,
+        },
+        weekday: warning.weekday().map(|d| WEEKDAYS[d]),
+        shift_time: warning.period().map(|p| match p {
+            ShiftPeriod::Morning => ShiftTime::Morning,
+            ShiftPeriod::Afternoon => ShiftTime::Afternoon,
+        }),
+        message: warning.to_string(),
+    }
+}
+
+fn week_decided_shift_into_weekly_shift_out<'a>(week_decided_shift: &WeekDecidedShift<'a>, limits: &CoverageLimits, locked: bool) -> WeeklyShiftOut {
     WeeklyShiftOut { 
         mon:day_decided_shift_into_daily_shift_out(&week_decided_shift.0[0]),
         tue:day_decided_shift_into_daily_shift_out(&week_decided_shift.0[1]), 
@@ -709,7 +1090,12 @@ fn week_decided_shift_into_weekly_shift_out<'a>(week_decided_shift: &WeekDecided
         thu:day_decided_shift_into_daily_shift_out(&week_decided_shift.0[3]), 
         fri:day_decided_shift_into_daily_shift_out(&week_decided_shift.0[4]), 
         sat:day_decided_shift_into_daily_shift_out(&week_decided_shift.0[5]), 
-        sun:day_decided_shift_into_daily_shift_out(&week_decided_shift.0[6]) 
+        sun:day_decided_shift_into_daily_shift_out(&week_decided_shift.0[6]),
+        warnings: check_week(week_decided_shift, limits)
+            .iter()
+            .map(coverage_warning_into_coverage_warning_out)
+            .collect(),
+        locked,
     }
 }
 
@@ -754,6 +1140,13 @@ fn calculate_weeks_delta_from_base(year: i32, month: u32, day: u32) -> Option<Ab
     }
 }
 
+/// calculate_weeks_delta_from_base の逆: 絶対週と曜日 (0=月 〜 6=日) から日付を求める
+fn date_of_abs_week(abs_week: AbsWeek, weekday: usize) -> NaiveDate {
+    let week_base = NaiveDate::from_ymd_opt(1969, 12, 29)
+        .unwrap() /* safe unwrap */;
+    week_base + Duration::days((abs_week * 7 + weekday) as i64)
+}
+
 /// 指定された年・月が、カレンダー上で何週（何行）になるかを計算する
 /// ※ month: 0 (1月) 〜 11 (12月)
 /// ※ 月曜始まり (Monday start) 前提
@@ -4,14 +4,15 @@
 mod shift_calendar_manager_test {
     use component_features::shift_calendar_manager::{
         ShiftCalendarManager, 
-        AppendWeekErrorKind
+        AppendWeekErrorKind,
+        weeks_from_skip_flags,
     };
 
 
     /// timelineがからのときの追加
     #[test]
     fn test00() {
-        let mut shift_calendar_manager 
+        let shift_calendar_manager 
             = ShiftCalendarManager::new(2000, 0);
         // shift_calendar_manager.append_week(false); // 2000
         // shift_calendar_manager.append_week(false); // 2001
@@ -20,11 +21,11 @@ mod shift_calendar_manager_test {
         let r = shift_calendar_manager
             .append_check(
             2003,
-            &[
+            &weeks_from_skip_flags(&[
                 false, // 2001
                 false, // 2002
                 true   // 2003
-            ]);
+            ]));
         assert!(matches!(r, Err(AppendWeekErrorKind::NotConsecutiveShifts)));
         // println!("{:?}", r);
 
@@ -32,7 +33,7 @@ mod shift_calendar_manager_test {
 
     #[test]
     fn test01() {
-        let mut shift_calendar_manager 
+        let shift_calendar_manager 
             = ShiftCalendarManager::new(2000, 0);
         // shift_calendar_manager.append_week(false); // 2000
         // shift_calendar_manager.append_week(false); // 2001
@@ -41,11 +42,11 @@ mod shift_calendar_manager_test {
         let r = shift_calendar_manager
             .append_check(
             2003,
-            &[
+            &weeks_from_skip_flags(&[
                 false, // 2001
                 false, // 2002
                 true   // 2003
-            ]);
+            ]));
         println!("{:?}", r);
     }
 }
@@ -13,7 +13,8 @@ import type {
         ShiftTime,
         ShiftWeekday,
         DailyShiftOut,
-        WeeklyShiftOut
+        WeeklyShiftOut,
+        ChangeVersions
 } from "./target/jco/interfaces/component-component-features-shift-manager.d";
 
 /* ==========================================================================
@@ -44,6 +45,16 @@ function getGroupColor(index: number) {
     return index < palette.length ? palette[index] : `hsl(${(index * 137.5) % 360}, 65%, 45%)`;
 }
 
+// 読み込み系 (load-config-from-json など) の result<_, list<load-error>> のErrを表示用の文字列にする
+// jcoのバインディングでは、Errの値は例外の payload に入る
+function formatLoadErrors(err: any): string {
+    const errors = err?.payload;
+    if (!Array.isArray(errors)) return String(err);
+    return errors
+        .map((e: { path: string, message: string }) => e.path ? `${e.path}: ${e.message}` : e.message)
+        .join('\n');
+}
+
 const getGroupPrefix = (idx: number) => String.fromCharCode(97 + idx); 
 
 const days: ShiftWeekday[] = ['mon', 'tue', 'wed', 'thu', 'fri', 'sat', 'sun'];
@@ -76,6 +87,8 @@ function switchView(manager: shiftManager.ShiftManager, viewName:string) {
     
     if (viewName === 'calendar') {
         updateRuleSelect(manager);
+        // 設定画面でスタッフ・ルールを変えていたら、カレンダーを描き直す
+        if (hasChangedSinceCalendarRender(manager)) renderCalendar(manager);
     }
 }
 
@@ -110,6 +123,22 @@ function updateGroupName(manager: shiftManager.ShiftManager, i:number, v:string)
     renderConfig(manager);
 }
 
+// 人員チェックの条件 (空欄で解除)
+function parseLimit(v: string): number | undefined {
+    const n = parseInt(v);
+    return isNaN(n) || n < 0 ? undefined : n;
+}
+
+function updateGroupMinStaff(manager: shiftManager.ShiftManager, i: number, v: string) {
+    manager.updateGroupMinStaff(i, parseLimit(v));
+    renderJSON(manager);
+}
+
+function updateMaxDaysPerWeek(manager: shiftManager.ShiftManager, v: string) {
+    manager.setMaxDaysPerWeek(parseLimit(v));
+    renderJSON(manager);
+}
+
 function addSlot(manager:shiftManager.ShiftManager, i: number) {
     manager.addSlot(i)
     renderConfig(manager);
@@ -216,7 +245,18 @@ function closeModal() {
 type skip_states = "fixed_skipped" | "fixed_active" | "pending_active"| "pending_skipped";
 let pendingSkipFlags: skip_states[] = [];
 
+// 最後にカレンダーを描いたときの変更の番号
+let calendarRenderedVersions: ChangeVersions | null = null;
+
+function hasChangedSinceCalendarRender(manager: shiftManager.ShiftManager): boolean {
+    const v = manager.getChangeVersions();
+    const last = calendarRenderedVersions;
+    return last === null || v.staff !== last.staff || v.rules !== last.rules || v.calendar !== last.calendar;
+}
+
 function renderCalendar(manager: shiftManager.ShiftManager) {
+    calendarRenderedVersions = manager.getChangeVersions();
+
     // 1. ラベル更新
     const label = document.getElementById('current-month-label');
     if (label) {
@@ -355,6 +395,24 @@ function renderCalendar(manager: shiftManager.ShiftManager) {
         statusText.textContent = statusLabel;
         
         controlCell.appendChild(statusText);
+
+        // 固定した週 (ルールを変えても変わらない)
+        if (weekShiftData && weekShiftData.locked) {
+            const lockText = document.createElement('span');
+            lockText.className = 'week-locked';
+            lockText.textContent = '🔒';
+            lockText.title = '固定した週です (ルールやスタッフを変えても変わりません)';
+            controlCell.appendChild(lockText);
+        }
+
+        // 人員チェックの警告 (件数とホバーで内容)
+        if (weekShiftData && weekShiftData.warnings.length > 0) {
+            const warnText = document.createElement('span');
+            warnText.className = 'coverage-warning';
+            warnText.textContent = `⚠ ${weekShiftData.warnings.length}`;
+            warnText.title = weekShiftData.warnings.map(w => w.message).join('\n');
+            controlCell.appendChild(warnText);
+        }
         row.appendChild(controlCell);
 
         // --- [右7列] 日付セル ---
@@ -481,6 +539,8 @@ function calculateCalendarDates(year: number, month: number) {
 // calendar ==========================
 
 function renderConfig(manager: shiftManager.ShiftManager) { 
+    (document.getElementById('max-days-input') as HTMLInputElement).value =
+        manager.getMaxDaysPerWeek()?.toString() ?? '';
     renderGroups(manager); 
     renderRules(manager); 
     renderJSON(manager); 
@@ -532,6 +592,20 @@ function renderGroups(manager: shiftManager.ShiftManager) {
                             updateGroupName(manager, gIdx, target.value) 
                     }
             }),
+            el('div', { className: 'slot-item' },
+                el('span', { className: 'slot-idx' }, 'Min:'),
+                el('input', {
+                        type: 'number',
+                        className: 'slot-input',
+                        min: '0',
+                        value: group.minPerPeriod?.toString() ?? '',
+                        placeholder: 'Min staff / period',
+                        onchange: (e: Event) => {
+                                const target = e.target as HTMLInputElement;
+                                updateGroupMinStaff(manager, gIdx, target.value);
+                        }
+                })
+            ),
             slotListContainer,
             el('button', {
                     className: 'btn btn-outline',
@@ -601,7 +675,7 @@ function renderRules(manager: shiftManager.ShiftManager) {
 }
 
 function renderJSON(manager: shiftManager.ShiftManager) { 
-    document.getElementById('json-output')!.textContent = JSON.stringify({staffGroups: manager.getStaffGroups(), rules: manager.getWeeklyRules()}, null, 2); 
+    document.getElementById('json-output')!.textContent = JSON.stringify({staffGroups: manager.getStaffGroups(), rules: manager.getWeeklyRules(), maxDaysPerWeek: manager.getMaxDaysPerWeek()}, null, 2); 
 }
 
 /* ==========================================================================
@@ -683,6 +757,25 @@ function initApp(manager: shiftManager.ShiftManager) {
         }
     }
 
+    // 週の固定 (公開)
+    document.getElementById('lock-btn')!.onclick = () => {
+        try {
+            manager.lockThisMonth();
+        } catch (e) {
+            console.error("Lock failed:", e);
+            alert(`固定に失敗しました: ${e}`);
+        }
+        renderCalendar(manager);
+    };
+
+    document.getElementById('unlock-btn')!.onclick = () => {
+        if (!confirm('この月の固定を外すと、今のルールから導出し直します。続けますか？')) {
+            return;
+        }
+        manager.unlockThisMonth();
+        renderCalendar(manager);
+    };
+
 // ==========================================
     // ★ Calendar Data Import / Export Logic
     // ==========================================
@@ -708,7 +801,7 @@ function initApp(manager: shiftManager.ShiftManager) {
                 const jsonText = await file.text();
 
                 // Wasm API呼び出し
-                // load-calendar-from-json: func(json-setting: string) -> result<_, string>;
+                // load-calendar-from-json: func(json-setting: string) -> result<_, list<load-error>>;
                 manager.loadCalendarFromJson(jsonText);
 
                 // ★重要: ロード後はローカルの編集状態をクリアし、ロードしたデータを正とする
@@ -720,7 +813,7 @@ function initApp(manager: shiftManager.ShiftManager) {
 
             } catch (err: any) {
                 console.error("Calendar Load Error:", err);
-                alert(`読み込みに失敗しました:\n${err}`);
+                alert(`読み込みに失敗しました:\n${formatLoadErrors(err)}`);
             } finally {
                 calFileInput.value = ''; // リセット
             }
@@ -782,9 +875,13 @@ function initApp(manager: shiftManager.ShiftManager) {
                 const jsonText = await file.text();
 
                 // WASMのメソッドを呼び出してロード
-                // WIT定義: load-config-from-json: func(json-setting: string) -> result<_, string>;
+                // WIT定義: load-config-from-json / load-config-from-toml: func(json-setting: string) -> result<_, list<load-error>>;
                 // jcoのバインディングでは、ResultのErrは例外としてスローされます
-                manager.loadConfigFromJson(jsonText);
+                if (file.name.endsWith('.toml')) {
+                    manager.loadConfigFromToml(jsonText);
+                } else {
+                    manager.loadConfigFromJson(jsonText);
+                }
 
                 // 成功したらUIを更新
                 renderConfig(manager);
@@ -793,7 +890,7 @@ function initApp(manager: shiftManager.ShiftManager) {
             } catch (err: any) {
                 console.error("Config Load Error:", err);
                 // Rust側から返されたエラーメッセージを表示
-                alert(`設定の読み込みに失敗しました:\n${err}`);
+                alert(`設定の読み込みに失敗しました:\n${formatLoadErrors(err)}`);
             } finally {
                 // 同じファイルを再度選択できるように値をリセット
                 fileInput.value = '';
@@ -802,29 +899,110 @@ function initApp(manager: shiftManager.ShiftManager) {
     }
 
     // 3. Save (Download) ボタンの処理
+    // output-config-json / output-config-toml: func() -> result<string, string>;
+    const downloadConfig = (dataStr: string, type: string, ext: string) => {
+        // Blobを作成してダウンロードリンクを生成
+        const blob = new Blob([dataStr], { type });
+        const url = URL.createObjectURL(blob);
+        const a = document.createElement('a');
+        a.href = url;
+        a.download = `シフト設定データ${new Date().toISOString().slice(0, 10)}.${ext}`;
+        document.body.appendChild(a);
+        a.click();
+        document.body.removeChild(a);
+        URL.revokeObjectURL(url);
+    };
+
     if (downloadBtn) {
         downloadBtn.onclick = () => {
-            // 現在の設定を取得
-            const dataStr = JSON.stringify({
-                staffGroups: manager.getStaffGroups(),
-                rules: manager.getWeeklyRules()
-            }, null, 2);
-
-            // Blobを作成してダウンロードリンクを生成
-            const blob = new Blob([dataStr], { type: "application/json" });
-            const url = URL.createObjectURL(blob);
-            const a = document.createElement('a');
-            a.href = url;
-            a.download = `シフト設定データ${new Date().toISOString().slice(0, 10)}.json`;
-            document.body.appendChild(a);
-            a.click();
-            document.body.removeChild(a);
-            URL.revokeObjectURL(url);
+            try {
+                downloadConfig(manager.outputConfigJson(), "application/json", "json");
+            } catch (err: any) {
+                console.error("Config Export Error:", err);
+                alert(`設定の保存に失敗しました:\n${err}`);
+            }
+        };
+    }
+
+    const downloadTomlBtn = document.getElementById('download-toml-btn');
+    if (downloadTomlBtn) {
+        downloadTomlBtn.onclick = () => {
+            try {
+                downloadConfig(manager.outputConfigToml(), "application/toml", "toml");
+            } catch (err: any) {
+                console.error("Config Export Error:", err);
+                alert(`設定の保存に失敗しました:\n${err}`);
+            }
+        };
+    }
+
+    // --- Project Load/Save Controls (ルール設定 + カレンダー) ---
+
+    const projectFileInput = document.getElementById('project-file-input') as HTMLInputElement;
+    const projectImportBtn = document.getElementById('import-project-btn');
+    const projectExportBtn = document.getElementById('export-project-btn');
+
+    if (projectImportBtn) {
+        projectImportBtn.onclick = () => {
+            projectFileInput.click();
+        };
+    }
+
+    if (projectFileInput) {
+        projectFileInput.onchange = async (e) => {
+            const target = e.target as HTMLInputElement;
+            const file = target.files?.[0];
+            if (!file) return;
+
+            try {
+                const jsonText = await file.text();
+
+                // import-project: func(json-setting: string) -> result<_, list<load-error>>;
+                // エラーの場合、Rust側の状態は何も変わらない
+                manager.importProject(jsonText);
+
+                pendingSkipFlags = [];
+
+                renderConfig(manager);
+                renderCalendar(manager);
+                alert(`プロジェクトを読み込みました: ${file.name}`);
+
+            } catch (err: any) {
+                console.error("Project Load Error:", err);
+                alert(`プロジェクトの読み込みに失敗しました:\n${formatLoadErrors(err)}`);
+            } finally {
+                projectFileInput.value = '';
+            }
+        };
+    }
+
+    if (projectExportBtn) {
+        projectExportBtn.onclick = () => {
+            try {
+                // export-project: func() -> result<string, string>;
+                const jsonStr = manager.exportProject();
+
+                const blob = new Blob([jsonStr], { type: "application/json" });
+                const url = URL.createObjectURL(blob);
+                const a = document.createElement('a');
+                a.href = url;
+                a.download = `シフトプロジェクト${new Date().toISOString().slice(0, 10)}.json`;
+                document.body.appendChild(a);
+                a.click();
+                document.body.removeChild(a);
+                URL.revokeObjectURL(url);
+
+            } catch (err: any) {
+                console.error("Project Export Error:", err);
+                alert(`プロジェクトの保存に失敗しました:\n${err}`);
+            }
         };
     }
 
     // Config Controls
     document.getElementById('add-group-btn')!.onclick = () => addNewGroup(manager);
+    document.getElementById('max-days-input')!.onchange = (e: Event) =>
+        updateMaxDaysPerWeek(manager, (e.target as HTMLInputElement).value);
     document.getElementById('add-rule-btn')!.onclick = () => addNewRule(manager);
 
     // Modal Controls
@@ -1,3 +1,5 @@
 fn main() {
+    // Tauri のウィンドウを作るときだけ (desktop フィーチャー)
+    #[cfg(feature = "desktop")]
     tauri_build::build()
 }
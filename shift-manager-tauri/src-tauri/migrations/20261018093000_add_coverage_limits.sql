@@ -0,0 +1,9 @@
+-- Add migration script here
+-- 導出したシフトの人員チェック (shift_timeline::coverage) の条件
+-- NULL = 指定なし
+
+-- グループごとの、1コマあたりの最低人数
+ALTER TABLE staff_groups ADD COLUMN min_per_period INTEGER;
+
+-- 1人あたりの週の出勤日数の上限
+ALTER TABLE plans ADD COLUMN max_days_per_week INTEGER;
@@ -0,0 +1,28 @@
+-- Add migration script here
+-- メンバーごとの制約 (shift_timeline::staff_constraints)
+-- 行が無いメンバーは制約なし
+
+CREATE TABLE staff_member_constraints (
+    member_id INTEGER PRIMARY KEY,
+
+    -- コマ数の上限 (午前・午後をそれぞれ1コマと数える)。NULL = 指定なし
+    max_shifts_per_week INTEGER,
+    max_shifts_per_month INTEGER,
+
+    -- 1 = 午後に入った翌日の午前には入らない
+    no_morning_after_afternoon INTEGER NOT NULL DEFAULT 0,
+
+    -- 雇用期間 (YYYY-MM-DD, 両端を含む)。NULL = 指定なし
+    employment_start TEXT,
+    employment_end TEXT,
+
+    FOREIGN KEY (member_id) REFERENCES staff_members(id) ON DELETE CASCADE
+);
+
+-- 固定の休みの曜日 (0:Mon ... 6:Sun)
+CREATE TABLE staff_member_days_off (
+    member_id INTEGER NOT NULL,
+    weekday INTEGER NOT NULL,
+    PRIMARY KEY (member_id, weekday),
+    FOREIGN KEY (member_id) REFERENCES staff_members(id) ON DELETE CASCADE
+);
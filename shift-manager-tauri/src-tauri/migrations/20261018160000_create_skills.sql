@@ -0,0 +1,21 @@
+-- Add migration script here
+-- スキル (資格) と、ルールの穴ごとの必要スキル (shift_timeline::skills)
+
+-- メンバーが持っているスキル
+CREATE TABLE staff_member_skills (
+    member_id INTEGER NOT NULL,
+    skill TEXT NOT NULL,
+    PRIMARY KEY (member_id, skill),
+    FOREIGN KEY (member_id) REFERENCES staff_members(id) ON DELETE CASCADE
+);
+
+-- 穴に入る人が持っている必要があるスキル (すべて必要)
+CREATE TABLE rule_assignment_skills (
+    assignment_id INTEGER NOT NULL,
+    skill TEXT NOT NULL,
+    PRIMARY KEY (assignment_id, skill),
+    FOREIGN KEY (assignment_id) REFERENCES rule_assignments(id) ON DELETE CASCADE
+);
+
+-- 1 = スキルを持っていない人が入ったら、同じグループの中で入れ替える
+ALTER TABLE plans ADD COLUMN auto_swap_skills INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+-- Add migration script here
+-- グループごとのローテーションの方式 (shift_timeline::rotation)
+-- NULL = shift_gen の並べ方のまま
+
+-- fixed / byWeek / byDay / everyNWeeks / shuffle
+ALTER TABLE staff_groups ADD COLUMN rotation TEXT;
+
+-- everyNWeeks の週数、shuffle の seed
+ALTER TABLE staff_groups ADD COLUMN rotation_param INTEGER;
@@ -0,0 +1,8 @@
+-- Add migration script here
+-- メンバーの在籍期間 (YYYY-MM-DD, 両端を含む)。NULL = 指定なし
+-- 週の月曜に在籍しているメンバーだけで、その週のシフトを導出する
+-- (辞めた人を削除せずに left_on を入れれば、過去の週のシフトは変わらない)
+
+ALTER TABLE staff_members ADD COLUMN joined_on TEXT;
+
+ALTER TABLE staff_members ADD COLUMN left_on TEXT;
@@ -0,0 +1,25 @@
+-- Add migration script here
+-- 確定 (公開) して固定した週 (shift_timeline::LockedWeek)
+-- 固定した週はルールから導出し直さず、ここに保存した人をそのまま使う
+
+CREATE TABLE locked_weeks (
+    calendar_id INTEGER NOT NULL,
+    abs_week INTEGER NOT NULL,
+    PRIMARY KEY (calendar_id, abs_week),
+    FOREIGN KEY (calendar_id) REFERENCES shift_calendars(id) ON DELETE CASCADE
+);
+
+-- 固定した週のコマに入っている人 (position はコマの中での並び順)
+-- 後でメンバーを変えても変わらないよう、名前とグループ内の位置をそのまま持つ
+CREATE TABLE locked_week_slots (
+    calendar_id INTEGER NOT NULL,
+    abs_week INTEGER NOT NULL,
+    weekday INTEGER NOT NULL,
+    shift_time_type INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    group_index INTEGER NOT NULL,
+    staff_index INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (calendar_id, abs_week, weekday, shift_time_type, position),
+    FOREIGN KEY (calendar_id, abs_week) REFERENCES locked_weeks(calendar_id, abs_week) ON DELETE CASCADE
+);
@@ -0,0 +1,29 @@
+-- Add migration script here
+-- ルールの並び方 (パターン)。タイムラインを N 週延ばすときに、週ごとのルールをここから決める
+-- sequence: steps を順に繰り返す (anchor_abs_week が steps の先頭)
+-- isoWeekParity: ISO週番号が奇数の週と偶数の週で分ける
+
+CREATE TABLE rule_patterns (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    plan_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('sequence', 'isoWeekParity')),
+    anchor_abs_week INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (plan_id) REFERENCES plans(id) ON DELETE CASCADE
+);
+
+-- sequence: position の順に rule_id を weeks 週ずつ
+-- isoWeekParity: position 0 が奇数の週、1 が偶数の週 (weeks は 1)
+-- rule_id が NULL の週はSkipped (ルールを消した場合もSkippedになる)
+CREATE TABLE rule_pattern_steps (
+    pattern_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    rule_id INTEGER,
+    weeks INTEGER NOT NULL CHECK (weeks >= 1),
+    PRIMARY KEY (pattern_id, position),
+    FOREIGN KEY (pattern_id) REFERENCES rule_patterns(id) ON DELETE CASCADE,
+    FOREIGN KEY (rule_id) REFERENCES weekly_rules(id) ON DELETE SET NULL
+);
+
+-- その週を追加したパターン (パターンを消しても週は残る)
+ALTER TABLE weekly_statuses ADD COLUMN pattern_id INTEGER REFERENCES rule_patterns(id) ON DELETE SET NULL;
@@ -0,0 +1,21 @@
+-- Add migration script here
+-- 休業ルール。タイムラインに週を追加するときに、当たる週を自動でSkippedにする
+-- annualClosure: from_month_day 〜 to_month_day ("MM-DD"、年をまたいでもよい)
+-- lastWeekOfMonth: 毎月の最後の週
+-- dates: skip_rule_dates の日付の入っている週
+
+CREATE TABLE skip_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    plan_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('annualClosure', 'lastWeekOfMonth', 'dates')),
+    from_month_day TEXT,
+    to_month_day TEXT,
+    FOREIGN KEY (plan_id) REFERENCES plans(id) ON DELETE CASCADE
+);
+
+CREATE TABLE skip_rule_dates (
+    skip_rule_id INTEGER NOT NULL,
+    date TEXT NOT NULL, -- "YYYY-MM-DD"
+    PRIMARY KEY (skip_rule_id, date),
+    FOREIGN KEY (skip_rule_id) REFERENCES skip_rules(id) ON DELETE CASCADE
+);
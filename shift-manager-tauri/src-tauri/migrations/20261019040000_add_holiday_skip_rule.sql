@@ -0,0 +1,33 @@
+-- Add migration script here
+-- 祝日・休日の入っている週をSkippedにする休業ルール (nationalHolidays) を追加する
+-- min_days: 週 (月〜日) に祝日・休日がこの日数以上あれば当たる
+-- kind の CHECK を変えるため、skip_rules と skip_rule_dates を作り直す
+
+CREATE TABLE skip_rules_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    plan_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('annualClosure', 'lastWeekOfMonth', 'dates', 'nationalHolidays')),
+    from_month_day TEXT,
+    to_month_day TEXT,
+    min_days INTEGER CHECK (min_days >= 1),
+    FOREIGN KEY (plan_id) REFERENCES plans(id) ON DELETE CASCADE
+);
+
+CREATE TABLE skip_rule_dates_new (
+    skip_rule_id INTEGER NOT NULL,
+    date TEXT NOT NULL, -- "YYYY-MM-DD"
+    PRIMARY KEY (skip_rule_id, date),
+    FOREIGN KEY (skip_rule_id) REFERENCES skip_rules_new(id) ON DELETE CASCADE
+);
+
+INSERT INTO skip_rules_new (id, plan_id, kind, from_month_day, to_month_day)
+    SELECT id, plan_id, kind, from_month_day, to_month_day FROM skip_rules;
+INSERT INTO skip_rule_dates_new (skip_rule_id, date)
+    SELECT skip_rule_id, date FROM skip_rule_dates;
+
+DROP TABLE skip_rule_dates;
+DROP TABLE skip_rules;
+
+-- skip_rule_dates_new の外部キーも skip_rules を指すようになる
+ALTER TABLE skip_rules_new RENAME TO skip_rules;
+ALTER TABLE skip_rule_dates_new RENAME TO skip_rule_dates;
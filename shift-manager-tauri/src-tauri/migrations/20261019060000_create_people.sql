@@ -0,0 +1,11 @@
+-- Add migration script here
+-- プランをまたいだ同じ人 (例: 2つの店に入っている人)
+-- 各プランのメンバーを person_id で1人にまとめ、同じ日・同じ時間帯に複数のプランに入っていないかを調べる
+
+CREATE TABLE people (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL
+);
+
+-- 人を消してもメンバーは残り、まとめだけが外れる
+ALTER TABLE staff_members ADD COLUMN person_id INTEGER REFERENCES people(id) ON DELETE SET NULL;
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- 楽観的排他用の番号。プランの中身を書き換えるコマンドが、読み込んだときの番号と同じか確かめて1つ進める
+-- (2つのウィンドウが同じプランを同時に書き換えたとき、古い内容を元にした方を断る)
+
+ALTER TABLE plans ADD COLUMN revision INTEGER NOT NULL DEFAULT 0;
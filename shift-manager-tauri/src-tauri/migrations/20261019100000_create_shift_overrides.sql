@@ -0,0 +1,16 @@
+-- Add migration script here
+-- 週・日単位の調整。導出した結果に、1コマずつメンバーを足す・外す (固定した週には当てない)
+-- メンバーはIDで持つので、並び替え・削除で別の人にずれない (メンバーを消すと調整も消える)
+-- weekday: 0 (月) 〜 6 (日)、shift_time_type: 0 (午前) / 1 (午後)
+
+CREATE TABLE shift_overrides (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    plan_id INTEGER NOT NULL,
+    abs_week INTEGER NOT NULL,
+    weekday INTEGER NOT NULL CHECK (weekday BETWEEN 0 AND 6),
+    shift_time_type INTEGER NOT NULL CHECK (shift_time_type IN (0, 1)),
+    member_id INTEGER NOT NULL,
+    action TEXT NOT NULL CHECK (action IN ('add', 'remove')),
+    FOREIGN KEY (plan_id) REFERENCES plans(id) ON DELETE CASCADE,
+    FOREIGN KEY (member_id) REFERENCES staff_members(id) ON DELETE CASCADE
+);
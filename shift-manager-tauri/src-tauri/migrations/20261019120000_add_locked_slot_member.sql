@@ -0,0 +1,7 @@
+-- Add migration script here
+-- 固定した週のコマに入っている人のメンバーID
+-- グループ内の位置はメンバーを削除・並び替えするとずれるので、導出に使うときはIDから今の位置に付け替える
+-- (削除したメンバーはどの位置にも当てず、名前だけ表示する)
+-- メンバーを削除しても固定した内容は残すので、外部キーは付けない。この列を追加する前に固定した週は NULL (位置のまま使う)
+
+ALTER TABLE locked_week_slots ADD COLUMN member_id INTEGER;
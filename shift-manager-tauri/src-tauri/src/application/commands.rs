@@ -1,124 +1,432 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "desktop")]
+use std::{fs, path::Path};
 
+// Tauri のコマンドは desktop フィーチャーのときだけ (compute_* はGUI無しでも使える)
+#[cfg(feature = "desktop")]
 use tauri::State;
-use crate::application::time::{calculate_abs_week, calculate_weeks_in_month};
-use crate::domain::calendar_logic::calculate_partial_shift;
+#[cfg(feature = "desktop")]
+use crate::application::events::{ChangeAction, ChangeEvent, ChangeTarget, TimelineChange};
+#[cfg(feature = "desktop")]
+use crate::application::schedule_print::{check_output_path, render_html};
+use crate::application::schedule_print::{
+    PrintDay, PrintEntry, PrintGroup, PrintWeek, PrintableMonth, group_color,
+};
+use crate::application::time::{abs_day_of_date, calculate_abs_week, calculate_weeks_in_month, date_of_abs_week};
+use crate::domain::rule_logic::{apply_rule_edit as apply_edit_to_config, edited_rule_id, is_enrolled_on, parse_employment_date};
+use crate::domain::calendar_logic::calculate_partial_shift_by_week;
+use crate::domain::holiday::holiday_table;
+use crate::domain::repository::{not_found, CalendarRepository, PlanRepository, RuleRepository};
 use crate::domain::{rule_model::*, shift_calendar_model::*};
 use crate::AppServices;
 
+// プランの中身を書き換えるコマンドは、expected_revision (画面が読み込んだときの Plan.revision) を受け取る
+// with_revision で確かめて revision を1つ進め、違っていれば何も書き換えずに競合のエラーを返す (None なら確かめずに進める)
+// 書き換えは同じトランザクションで行うので、書き換えに失敗したら revision も進まない
+
 // --- Plan Commands ---
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn create_new_plan(name: String, repo: State<'_, AppServices>) -> Result<i64, String> {
-    repo.rule.create_plan(&name).await
+    let id = repo.rule.create_plan(&name).await?;
+    repo.events.emit(ChangeEvent::PlanChanged { plan_id: id, action: ChangeAction::Created });
+    Ok(id)
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn list_all_plans(repo: State<'_, AppServices>) -> Result<Vec<Plan>, String> {
     repo.rule.list_plans().await
 }
 
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn delete_plan(id: i64, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    repo.with_revision(PlanItem::Plan(id), expected_revision, async |tx| tx.rule.delete_plan(id).await).await?;
+    repo.events.emit(ChangeEvent::PlanChanged { plan_id: id, action: ChangeAction::Deleted });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn delete_plan(id: i64, repo: State<'_, AppServices>) -> Result<(), String> {
-    repo.rule.delete_plan(id).await
+pub async fn update_plan_max_days(plan_id: i64, max_days_per_week: Option<u32>, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.update_plan_max_days(plan_id, max_days_per_week).await).await?;
+    repo.events.emit(ChangeEvent::PlanChanged { plan_id, action: ChangeAction::Updated });
+    Ok(())
 }
 
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn update_plan_auto_swap(plan_id: i64, auto_swap_skills: bool, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.update_plan_auto_swap(plan_id, auto_swap_skills).await).await?;
+    repo.events.emit(ChangeEvent::PlanChanged { plan_id, action: ChangeAction::Updated });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn get_plan_config(plan_id: i64, repo: State<'_, AppServices>) -> Result<PlanConfig, String> {
     repo.rule.get_plan_config(plan_id).await
 }
 
 // --- Group / Member ---
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn add_staff_group(plan_id: i64, name: String, repo: State<'_, AppServices>) -> Result<i64, String> {
-    repo.rule.add_staff_group(plan_id, &name).await
+pub async fn add_staff_group(plan_id: i64, name: String, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<i64, String> {
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.add_staff_group(plan_id, &name).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Group(id), action: ChangeAction::Created });
+    Ok(id)
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn delete_staff_group(group_id: i64, repo: State<'_, AppServices>) -> Result<(), String> {
-    repo.rule.delete_staff_group(group_id).await
+pub async fn delete_staff_group(group_id: i64, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Group(group_id), expected_revision, async |tx| tx.rule.delete_staff_group(group_id).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Group(group_id), action: ChangeAction::Deleted });
+    Ok(())
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn update_group_name(group_id: i64, name: String, repo: State<'_, AppServices>) -> Result<(), String> {
-    repo.rule.update_group_name(group_id, &name).await
+pub async fn update_group_name(group_id: i64, name: String, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Group(group_id), expected_revision, async |tx| tx.rule.update_group_name(group_id, &name).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Group(group_id), action: ChangeAction::Updated });
+    Ok(())
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn add_staff_member(group_id: i64, name: String, repo: State<'_, AppServices>) -> Result<i64, String> {
-    repo.rule.add_staff_member(group_id, &name).await
+pub async fn update_group_min_staff(group_id: i64, min_per_period: Option<u32>, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Group(group_id), expected_revision, async |tx| tx.rule.update_group_min_staff(group_id, min_per_period).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Group(group_id), action: ChangeAction::Updated });
+    Ok(())
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn delete_staff_member(member_id: i64, repo: State<'_, AppServices>) -> Result<(), String> {
-    repo.rule.delete_staff_member(member_id).await
+pub async fn update_group_rotation(group_id: i64, rotation: Option<Rotation>, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Group(group_id), expected_revision, async |tx| tx.rule.update_group_rotation(group_id, rotation).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Group(group_id), action: ChangeAction::Updated });
+    Ok(())
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn update_member_name(member_id: i64, name: String, repo: State<'_, AppServices>) -> Result<(), String> {
-    repo.rule.update_member_name(member_id, &name).await
+pub async fn add_staff_member(group_id: i64, name: String, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<i64, String> {
+    let (plan_id, id) = repo.with_revision(PlanItem::Group(group_id), expected_revision, async |tx| tx.rule.add_staff_member(group_id, &name).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Member(id), action: ChangeAction::Created });
+    Ok(id)
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn delete_staff_member(member_id: i64, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.delete_staff_member(member_id).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Member(member_id), action: ChangeAction::Deleted });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn update_member_name(member_id: i64, name: String, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.update_member_name(member_id, &name).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Member(member_id), action: ChangeAction::Updated });
+    Ok(())
+}
+
+/// joined_on / left_on は "YYYY-MM-DD" (None で指定なし)
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn update_member_period(member_id: i64, joined_on: Option<String>, left_on: Option<String>, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.update_member_period(member_id, joined_on.as_deref(), left_on.as_deref()).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Member(member_id), action: ChangeAction::Updated });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn set_member_constraints(member_id: i64, constraints: MemberConstraints, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.set_member_constraints(member_id, &constraints).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Member(member_id), action: ChangeAction::Updated });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn clear_member_constraints(member_id: i64, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.clear_member_constraints(member_id).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Member(member_id), action: ChangeAction::Updated });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn set_member_skills(member_id: i64, skills: Vec<String>, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.set_member_skills(member_id, &skills).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Member(member_id), action: ChangeAction::Updated });
+    Ok(())
+}
+
+/// person_id を None にするとまとめを外す
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn link_member_person(member_id: i64, person_id: Option<i64>, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.link_member_person(member_id, person_id).await).await?;
+    repo.events.emit(ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Member(member_id), action: ChangeAction::Updated });
+    Ok(())
+}
+
+// --- Person (プランをまたいだ同じ人) ---
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn add_person(name: String, repo: State<'_, AppServices>) -> Result<i64, String> {
+    let id = repo.rule.add_person(&name).await?;
+    repo.events.emit(ChangeEvent::PeopleChanged { target: ChangeTarget::Person(id), action: ChangeAction::Created });
+    Ok(id)
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn update_person_name(person_id: i64, name: String, repo: State<'_, AppServices>) -> Result<(), String> {
+    repo.rule.update_person_name(person_id, &name).await?;
+    repo.events.emit(ChangeEvent::PeopleChanged { target: ChangeTarget::Person(person_id), action: ChangeAction::Updated });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn delete_person(person_id: i64, repo: State<'_, AppServices>) -> Result<(), String> {
+    repo.rule.delete_person(person_id).await?;
+    repo.events.emit(ChangeEvent::PeopleChanged { target: ChangeTarget::Person(person_id), action: ChangeAction::Deleted });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn list_people(repo: State<'_, AppServices>) -> Result<Vec<Person>, String> {
+    repo.rule.list_people().await
 }
 
 // --- Rules ---
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn add_weekly_rule(plan_id: i64, name: String, repo: State<'_, AppServices>) -> Result<i64, String> {
-    repo.rule.add_weekly_rule(plan_id, &name).await
+pub async fn add_weekly_rule(plan_id: i64, name: String, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<i64, String> {
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.add_weekly_rule(plan_id, &name).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Rule(id), action: ChangeAction::Created });
+    Ok(id)
 }
 
+/// タイムラインで使われているルールは消さない (delete_weekly_rule_with の refuse と同じ)
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn delete_weekly_rule(rule_id: i64, repo: State<'_, AppServices>) -> Result<(), String> {
-    repo.rule.delete_weekly_rule(rule_id).await
+pub async fn delete_weekly_rule(rule_id: i64, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, _) = repo
+        .with_plan_revision(PlanItem::Rule(rule_id), expected_revision, async |tx, plan_id| {
+            compute_delete_weekly_rule_with(tx, plan_id, rule_id, &RuleDeletion::Refuse).await
+        })
+        .await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Rule(rule_id), action: ChangeAction::Deleted });
+    Ok(())
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn update_rule_name(rule_id: i64, name: String, repo: State<'_, AppServices>) -> Result<(), String> {
-    repo.rule.update_rule_name(rule_id, &name).await
+pub async fn update_rule_name(rule_id: i64, name: String, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Rule(rule_id), expected_revision, async |tx| tx.rule.update_rule_name(rule_id, &name).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Rule(rule_id), action: ChangeAction::Updated });
+    Ok(())
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn add_rule_assignment(rule_id: i64, weekday: i64, shift_time: i64, group_id: i64, member_index: i64, repo: State<'_, AppServices>) -> Result<i64, String> {
-    repo.rule.add_rule_assignment(rule_id, weekday, shift_time, group_id, member_index).await
+pub async fn add_rule_assignment(rule_id: i64, weekday: i64, shift_time: i64, group_id: i64, member_index: i64, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<i64, String> {
+    let edit = RuleEdit::AddAssignment { rule_id, weekday, shift_time, group_id, member_index };
+    let (plan_id, id) = repo.with_plan_revision(PlanItem::Rule(rule_id), expected_revision, async |tx, plan_id| compute_add_rule_assignment(tx, plan_id, edit).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Assignment(id), action: ChangeAction::Created });
+    Ok(id)
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn delete_assignment(assignment_id: i64, repo: State<'_, AppServices>) -> Result<(), String> {
-    repo.rule.delete_assignment(assignment_id).await
+pub async fn delete_assignment(assignment_id: i64, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let edit = RuleEdit::DeleteAssignment { assignment_id };
+    let (plan_id, _) = repo.with_plan_revision(PlanItem::Assignment(assignment_id), expected_revision, async |tx, plan_id| compute_apply_rule_edit(tx, plan_id, edit, false).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Assignment(assignment_id), action: ChangeAction::Deleted });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn set_assignment_skills(assignment_id: i64, skills: Vec<String>, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Assignment(assignment_id), expected_revision, async |tx| tx.rule.set_assignment_skills(assignment_id, &skills).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Assignment(assignment_id), action: ChangeAction::Updated });
+    Ok(())
+}
+
+// --- Rule Pattern ---
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn add_rule_pattern(plan_id: i64, name: String, kind: RulePatternKind, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<i64, String> {
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.add_rule_pattern(plan_id, &name, &kind).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Pattern(id), action: ChangeAction::Created });
+    Ok(id)
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn delete_rule_pattern(pattern_id: i64, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Pattern(pattern_id), expected_revision, async |tx| tx.rule.delete_rule_pattern(pattern_id).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Pattern(pattern_id), action: ChangeAction::Deleted });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn list_rule_patterns(plan_id: i64, repo: State<'_, AppServices>) -> Result<Vec<RulePattern>, String> {
+    repo.rule.list_rule_patterns(plan_id).await
+}
+
+// --- Shift Override ---
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn add_shift_override(plan_id: i64, entry: OverrideEntry, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<i64, String> {
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.add_shift_override(plan_id, &entry).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Override(id), action: ChangeAction::Created });
+    Ok(id)
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn delete_shift_override(override_id: i64, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::Override(override_id), expected_revision, async |tx| tx.rule.delete_shift_override(override_id).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Override(override_id), action: ChangeAction::Deleted });
+    Ok(())
 }
 
 // --- Calendar ---
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn get_calendar_state(plan_id: i64, repo: State<'_, AppServices>) -> Result<Option<ShiftCalendarManager>, String> {
+pub async fn get_calendar_state(plan_id: i64, repo: State<'_, AppServices>) -> Result<Option<PlanCalendar>, String> {
     repo.calendar.find_by_plan_id(plan_id).await
 }
 
 // --- Calendar ---
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn create_calendar(plan_id: i64, base_abs_week: usize, initial_delta: usize, repo: State<'_, AppServices>) -> Result<i64, String> {
+pub async fn create_calendar(plan_id: i64, base_abs_week: usize, initial_delta: usize, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<i64, String> {
     // Repository側の create_calendar を呼び出す
-    repo.calendar.create_calendar(plan_id, base_abs_week, initial_delta).await
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.calendar.create_calendar(plan_id, base_abs_week, initial_delta).await).await?;
+    repo.events.emit(ChangeEvent::TimelineChanged { plan_id, change: TimelineChange::Created, from_abs_week: base_abs_week, count: None });
+    Ok(id)
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn append_timeline(plan_id: i64, start_abs_week: usize, statuses: Vec<Option<i64>>, repo: State<'_, AppServices>) -> Result<(), String> {
+pub async fn append_timeline(plan_id: i64, start_abs_week: usize, statuses: Vec<Option<i64>>, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
     // Repository側の try_to_append_timeline を呼び出す
-    repo.calendar.try_to_append_timeline(plan_id, start_abs_week, statuses).await
+    let count = statuses.len();
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.calendar.try_to_append_timeline(plan_id, start_abs_week, statuses).await).await?;
+    repo.events.emit(ChangeEvent::TimelineChanged { plan_id, change: TimelineChange::Extended, from_abs_week: start_abs_week, count: Some(count) });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn truncate_timeline(plan_id: i64, from_abs_week: usize, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.calendar.truncate_timeline(plan_id, from_abs_week).await).await?;
+    repo.events.emit(ChangeEvent::TimelineChanged { plan_id, change: TimelineChange::Truncated, from_abs_week, count: None });
+    Ok(())
+}
+
+/// タイムラインの末尾からパターンで weeks 週延ばし、最初に追加した絶対週を返す
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn extend_timeline_by_pattern(plan_id: i64, pattern_id: i64, weeks: usize, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<usize, String> {
+    let (_, first) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.calendar.extend_timeline_by_pattern(plan_id, pattern_id, weeks).await).await?;
+    repo.events.emit(ChangeEvent::TimelineChanged { plan_id, change: TimelineChange::Extended, from_abs_week: first, count: Some(weeks) });
+    Ok(first)
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn get_pattern_weeks(plan_id: i64, repo: State<'_, AppServices>) -> Result<Vec<PatternWeek>, String> {
+    repo.calendar.find_pattern_weeks(plan_id).await
+}
+
+// --- Skip Rule ---
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn add_skip_rule(plan_id: i64, kind: SkipRuleKind, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<i64, String> {
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.calendar.add_skip_rule(plan_id, &kind).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::SkipRule(id), action: ChangeAction::Created });
+    Ok(id)
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn update_skip_rule(skip_rule_id: i64, kind: SkipRuleKind, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::SkipRule(skip_rule_id), expected_revision, async |tx| tx.calendar.update_skip_rule(skip_rule_id, &kind).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::SkipRule(skip_rule_id), action: ChangeAction::Updated });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn delete_skip_rule(skip_rule_id: i64, expected_revision: Option<i64>, repo: State<'_, AppServices>) -> Result<(), String> {
+    let (plan_id, ()) = repo.with_revision(PlanItem::SkipRule(skip_rule_id), expected_revision, async |tx| tx.calendar.delete_skip_rule(skip_rule_id).await).await?;
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::SkipRule(skip_rule_id), action: ChangeAction::Deleted });
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn list_skip_rules(plan_id: i64, repo: State<'_, AppServices>) -> Result<Vec<SkipRule>, String> {
+    repo.calendar.list_skip_rules(plan_id).await
+}
+
+// --- Change Events ---
+
+/// 今の変更の番号 (変更のイベントの version と同じ)
+/// 隠れていたウィンドウが、見ていない間に変更があったかを調べる用
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn get_change_version(repo: State<'_, AppServices>) -> Result<u64, String> {
+    Ok(repo.events.version())
 }
 
-use crate::application::dto::{MonthlyShiftResult, WeeklyShiftDto, DailyShiftDto};
+use crate::application::dto::{
+    CellChangeDto, CoverageWarningDto, CrossPlanConflictDto, HolidayDto, MonthlyShiftResult, RuleEditImpactDto,
+    WeeklyShiftDto, DailyShiftDto, SkillIssueDto, StaffShiftDto, StaffViolationDto, TeamShiftDto,
+};
 
-use shift_calendar::shift_gen::{DayRule, Incomplete, ShiftHoll, StaffGroup, StaffGroupList, WeekRule, WeekRuleTable};
+use chrono::{Datelike, NaiveDate};
+use shift_timeline::{
+    ConstrainedStaff, CoverageLimits, DaySkillRule, DaySlots, GroupCoverage, LockedOverlay, Rotation, RuleSlot,
+    ShiftOverride, ShiftPeriod, SkillIssue, SkilledStaff, SlotRequirement, StaffConstraints, WeekSkillRule, WeekSlots,
+    apply_overrides, apply_rotation, check_skills, check_staff_constraints, check_week, diff_weeks, rebind_members,
+    staff_shifts_of,
+};
+
+use shift_calendar::shift_gen::{
+    DayRule, Incomplete, ShiftHoll, Staff, StaffGroup, StaffGroupList, WeekDecidedShift, WeekRule, WeekRuleTable,
+};
 
 /// ====================================================================
 /// 1. StaffGroupList の構築と、IDマップの作成
 /// ====================================================================
 // 戻り値をタプルにし、マップも一緒に返すように変更
-fn db2staff_group_domain(plan_config: &PlanConfig) -> (StaffGroupList, HashMap<i64, usize>) {
+// enrolled が false のメンバーは入れない (グループは空でも全部入れるので、グループの並びは変わらない)
+fn db2staff_group_domain(
+    plan_config: &PlanConfig,
+    enrolled: impl Fn(&StaffMember) -> bool,
+) -> (StaffGroupList, HashMap<i64, usize>) {
     let mut domain_groups = StaffGroupList::new();
     let mut group_id_map = HashMap::new(); // ★追加: DBのIDとインデックスの対応表
 
     for group_row in &plan_config.groups {
         let mut domain_members = StaffGroup::new(&group_row.group.name);
-        for member_row in &group_row.members {
+        for member_row in group_row.members.iter().filter(|m| enrolled(m)) {
             // DBのメンバー情報をドメインの Staff 型に変換
             domain_members.add_staff(&member_row.name);
         }
@@ -192,7 +500,267 @@ fn db2rule_domain<'a>(
     rule_dict
 }
 
+/// ====================================================================
+/// 3. 人員チェックの条件
+/// ====================================================================
+// groups の並びは db2staff_group_domain と同じ
+fn db2coverage_limits(plan_config: &PlanConfig) -> CoverageLimits {
+    let to_usize = |value: Option<i64>| value.map(|v| v.max(0) as usize);
+    CoverageLimits {
+        groups: plan_config.groups
+            .iter()
+            .map(|group_row| GroupCoverage {
+                name: group_row.group.name.clone(),
+                min_per_period: to_usize(group_row.group.min_per_period),
+            })
+            .collect(),
+        max_days_per_week: to_usize(plan_config.plan.max_days_per_week),
+    }
+}
+
+/// ====================================================================
+/// 4. メンバーの制約
+/// ====================================================================
+// (member_id, 制約) をメンバーの並び順で返す。制約の無いメンバーは含まれない
+fn db2staff_constraints(plan_config: &PlanConfig) -> Vec<(i64, ConstrainedStaff)> {
+    let to_usize = |value: Option<u32>| value.map(|v| v as usize);
+    // validate 済みなので読めない日付は無い前提 (読めなければ指定なし扱い)
+    let to_abs_day = |text: &Option<String>| text
+        .as_deref()
+        .and_then(|t| parse_employment_date(t).ok())
+        .and_then(abs_day_of_date);
+
+    let mut staff_list = Vec::new();
+    for (group_index, group_row) in plan_config.groups.iter().enumerate() {
+        for (staff_index, member) in group_row.members.iter().enumerate() {
+            let Some(c) = &member.constraints else { continue };
+            staff_list.push((member.id, ConstrainedStaff {
+                group_index,
+                staff_index,
+                name: member.name.clone(),
+                constraints: StaffConstraints {
+                    max_shifts_per_week: to_usize(c.max_shifts_per_week),
+                    max_shifts_per_month: to_usize(c.max_shifts_per_month),
+                    days_off: c.days_off.iter().map(|d| *d as usize).collect(),
+                    no_morning_after_afternoon: c.no_morning_after_afternoon,
+                    employed_from: to_abs_day(&c.employment_start),
+                    employed_until: to_abs_day(&c.employment_end),
+                },
+            }));
+        }
+    }
+    staff_list
+}
+
+/// ====================================================================
+/// 5. 週ごとの在籍メンバー
+/// ====================================================================
+// その週の月曜に在籍しているメンバーだけで導出するので、メンバーを足したり辞めさせたりしても過去の週は変わらない
+// groups の中の位置 (Staff::id) は在籍しているメンバーの中での位置で、
+// members[グループ][その位置] がグループの全メンバーの中での位置 (db2staff_group_domain を全員で呼んだときの並び)
+struct WeekRoster {
+    groups: StaffGroupList,
+    members: Vec<Vec<usize>>,
+    // 在籍しているメンバーだけ (Staff::id は groups と同じ位置)
+    staff: Vec<Staff>,
+    skilled_staff: Vec<SkilledStaff>,
+}
+
+impl WeekRoster {
+    fn new(plan_config: &PlanConfig, monday: NaiveDate) -> Self {
+        let (groups, _) = db2staff_group_domain(plan_config, |member| is_enrolled_on(member, monday));
+
+        let mut members = Vec::new();
+        let mut skilled_staff = Vec::new();
+        for (group_id, group_row) in plan_config.groups.iter().enumerate() {
+            let positions: Vec<usize> = group_row.members
+                .iter()
+                .enumerate()
+                .filter(|(_, member)| is_enrolled_on(member, monday))
+                .map(|(position, _)| position)
+                .collect();
+            for (id, position) in positions.iter().enumerate() {
+                let member = &group_row.members[*position];
+                skilled_staff.push(SkilledStaff {
+                    staff: Staff { name: member.name.clone(), group_id, id },
+                    skills: member.skills.clone(),
+                });
+            }
+            members.push(positions);
+        }
+
+        WeekRoster {
+            groups,
+            members,
+            staff: skilled_staff.iter().map(|s| s.staff.clone()).collect(),
+            skilled_staff,
+        }
+    }
+}
+
+/// `first_abs_week` から `weeks` 週分 (timeline_slice と同じ並び) の在籍メンバー
+fn db2week_rosters(plan_config: &PlanConfig, first_abs_week: AbsWeek, weeks: usize) -> Vec<WeekRoster> {
+    (0..weeks)
+        .map(|offset| WeekRoster::new(plan_config, date_of_abs_week(first_abs_week + offset, 0)))
+        .collect()
+}
+
+/// ====================================================================
+/// 6. 導出した後の入れ替え (ローテーションの方式・スキル)
+/// ====================================================================
+// 穴の並びは db2rule_domain と同じ (assignments の順)
+// staff の並びは db2staff_group_domain を全員で呼んだときと同じ (グループの何番目・メンバーの何番目)
+struct WeekAdjustment {
+    rule_slots: HashMap<RuleId, (WeekSlots, WeekSkillRule)>,
+    rotations: Vec<Option<Rotation>>,
+    staff: Vec<Staff>,
+    auto_swap_skills: bool,
+    // 週・日単位の調整 (member_id を staff と同じ並びの位置に付け替えたもの)
+    overrides: Vec<ShiftOverride>,
+}
+
+impl WeekAdjustment {
+    fn new(plan_config: &PlanConfig, group_id_map: &HashMap<i64, usize>) -> Self {
+        let mut rule_slots = HashMap::new();
+        for rule_row in &plan_config.rules {
+            let mut slots: [DaySlots; 7] = Default::default();
+            let mut skills: [DaySkillRule; 7] = Default::default();
+            for assign in &rule_row.assignments {
+                let Some(group_index) = group_id_map.get(&assign.target_group_id).copied() else { continue };
+                let slot = RuleSlot { group_index, member_index: assign.target_member_index };
+                let requirement = SlotRequirement { group_index, skills: assign.required_skills.clone() };
+                let (day_slots, day_skills) = (&mut slots[assign.weekday as usize], &mut skills[assign.weekday as usize]);
+                match assign.shift_time_type {
+                    ShiftTime::Morning => {
+                        day_slots.shift_morning.push(slot);
+                        day_skills.shift_morning.push(requirement);
+                    }
+                    ShiftTime::Afternoon => {
+                        day_slots.shift_afternoon.push(slot);
+                        day_skills.shift_afternoon.push(requirement);
+                    }
+                }
+            }
+            rule_slots.insert(rule_row.rule.id, (WeekSlots(slots), WeekSkillRule(skills)));
+        }
+
+        WeekAdjustment {
+            rule_slots,
+            rotations: plan_config.groups.iter().map(|group_row| group_row.group.rotation).collect(),
+            staff: plan_config.groups
+                .iter()
+                .enumerate()
+                .flat_map(|(group_id, group_row)| {
+                    group_row.members.iter().enumerate().map(move |(id, member)| Staff {
+                        name: member.name.clone(),
+                        group_id,
+                        id,
+                    })
+                })
+                .collect(),
+            auto_swap_skills: plan_config.plan.auto_swap_skills,
+            overrides: plan_config.overrides
+                .iter()
+                .filter_map(|o| {
+                    let (group_index, staff_index) = member_position(plan_config, o.entry.member_id)?;
+                    Some(ShiftOverride {
+                        abs_week: o.entry.abs_week,
+                        weekday: o.entry.weekday as usize,
+                        period: match o.entry.shift_time {
+                            ShiftTime::Morning => ShiftPeriod::Morning,
+                            ShiftTime::Afternoon => ShiftPeriod::Afternoon,
+                        },
+                        group_index,
+                        staff_index,
+                        action: o.entry.action,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// ローテーションの方式で置き換えてから、スキルをチェックする (`auto_swap_skills` なら入れ替える)
+    /// どちらもその週の在籍メンバーの中で行い、最後に Staff を全メンバーの中での位置に付け替える
+    /// `weeks` / `rosters` は `week_status_list` と同じ並び。戻り値は週ごとのスキルのチェック結果
+    fn apply<'a>(
+        &'a self,
+        weeks: &mut [Option<WeekDecidedShift<'a>>],
+        week_status_list: &[WeekStatus],
+        rosters: &'a [WeekRoster],
+    ) -> Vec<Vec<SkillIssue>> {
+        weeks
+            .iter_mut()
+            .zip(week_status_list)
+            .zip(rosters)
+            .map(|((week, status), roster)| {
+                let (Some(week), WeekStatus::Active { logical_delta, payload }) = (week, status) else { return vec![] };
+                let issues = match self.rule_slots.get(&payload.rule_id) {
+                    Some((slots, skills)) => {
+                        apply_rotation(week, slots, &roster.staff, &self.rotations, *logical_delta);
+                        check_skills(week, skills, &roster.skilled_staff, self.auto_swap_skills)
+                    }
+                    None => vec![],
+                };
+                for day in week.0.iter_mut() {
+                    for staff in day.shift_morning.iter_mut().chain(day.shift_afternoon.iter_mut()) {
+                        let id = roster.members[staff.group_id][staff.id];
+                        if let Some(full) = self.staff.iter().find(|s| s.group_id == staff.group_id && s.id == id) {
+                            *staff = full;
+                        }
+                    }
+                }
+                issues
+            })
+            .collect()
+    }
+}
+
+/// メンバーが何番目のグループの何番目か (db2staff_group_domain を全員で呼んだときの並び)
+fn member_position(plan_config: &PlanConfig, member_id: i64) -> Option<(usize, usize)> {
+    plan_config.groups.iter().enumerate().find_map(|(group_index, group_row)| {
+        group_row.members
+            .iter()
+            .position(|member| member.id == member_id)
+            .map(|staff_index| (group_index, staff_index))
+    })
+}
+
+/// 週ごとに、その週の在籍メンバーで導出し、ローテーションの方式・スキルの入れ替えと
+/// 週・日単位の調整を反映してから、固定した週を固定した内容に置き換えて `f` に渡す
+/// 固定した週の人はメンバーIDで今の位置に付け替える (削除したメンバーは誰とも一致しない)
+/// `week_status_list` の先頭は `first_abs_week`。固定した週のスキルのチェック結果は空
+/// (スキルのチェックは調整を当てる前の結果)
+fn with_resolved_weeks<T>(
+    plan_config: &PlanConfig,
+    locked_weeks: &[LockedWeek],
+    first_abs_week: AbsWeek,
+    week_status_list: &[WeekStatus],
+    f: impl FnOnce(&[Option<WeekDecidedShift>], Vec<Vec<SkillIssue>>, &LockedOverlay) -> T,
+) -> T {
+    let (_, group_id_map) = db2staff_group_domain(plan_config, |_| true);
+    let rosters = db2week_rosters(plan_config, first_abs_week, week_status_list.len());
+    let rule_dict = db2rule_domain(plan_config, &group_id_map);
+    let adjustment = WeekAdjustment::new(plan_config, &group_id_map);
+    let locked_weeks = rebind_members(locked_weeks, |member_id| member_position(plan_config, member_id));
+    let overlay = LockedOverlay::new(&locked_weeks, first_abs_week, week_status_list.len());
+
+    let mut decided = calculate_partial_shift_by_week(week_status_list, &rule_dict, |offset| &rosters[offset].groups);
+    let mut skill_issues = adjustment.apply(&mut decided, week_status_list, &rosters);
+    apply_overrides(&mut decided, first_abs_week, &adjustment.overrides, &adjustment.staff);
+    overlay.apply(&mut decided);
+    for (offset, issues) in skill_issues.iter_mut().enumerate() {
+        if overlay.is_locked(offset) {
+            issues.clear();
+        }
+    }
+    f(&decided, skill_issues, &overlay)
+}
+
 /// 週ごとのシフト導出計算をします
+/// 固定した週は、固定した内容をそのまま返します (locked)
+/// 導出した週には人員チェックの警告 (warnings) とスキルのチェック結果 (skillIssues) を、
+/// 月全体にはメンバーの制約の違反 (violations) を付けて返します
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn derive_monthly_shift(
     plan_id: i64,
@@ -200,22 +768,37 @@ pub async fn derive_monthly_shift(
     target_month: u32, // 0-11
     repo: State<'_, AppServices>,
 ) -> Result<MonthlyShiftResult, String> {
+    compute_monthly_shift(&repo, plan_id, target_year, target_month).await
+}
+
+/// derive_monthly_shift の本体
+/// リポジトリの実装に依存しないので、インメモリ構成でもそのまま動く
+pub async fn compute_monthly_shift<R, C>(
+    repo: &AppServices<R, C>,
+    plan_id: i64,
+    target_year: i32,
+    target_month: u32, // 0-11
+) -> Result<MonthlyShiftResult, String>
+where
+    R: RuleRepository,
+    C: CalendarRepository,
+{
     // 1. カレンダーManager（タイムライン）を取得
     let manager_opt = repo.calendar.find_by_plan_id(plan_id).await?;
 
-    let calendar = match manager_opt {
+    let plan_calendar = match manager_opt {
         Some(m) => m,
-        None => return Ok(MonthlyShiftResult { weeks: vec![] }), // データなし
+        None => return Ok(MonthlyShiftResult { weeks: vec![], violations: vec![] }), // データなし
     };
 
     // ★ ここでカレンダーIDと基準週を取り出します
-    let calendar_id = if let Some(cal_id) = calendar.id {
+    let calendar_id = if let Some(cal_id) = plan_calendar.id {
         cal_id
     } else {
         return Err(String::from("カレンダーを作成してください"))
     };
 
-    let base_abs_week = calendar.base_abs_week as usize; // 型がusizeの場合はキャスト
+    let base_abs_week = plan_calendar.calendar.base_abs_week; // 型がusizeの場合はキャスト
 
     // 2. 計算に必要な「辞書データ」をDBから全取得して構築
     //    (本来はRepositoryにこの変換ロジックを持たせるのが綺麗ですが、ここでやります)
@@ -229,46 +812,873 @@ pub async fn derive_monthly_shift(
 
     let range = calculate_weeks_in_month(target_year, target_month); // カレンダーは最大6週表示
 
-    let start_offset = start_week_abs - base_abs_week;
+    // 基準週より前の月を表示する場合は負になる (usizeのまま引くとpanicする)
+    // 基準週より前の週は空欄(None)として先頭に詰める
+    let start_offset = start_week_abs as i64 - base_abs_week as i64;
+    let leading_blank = (-start_offset).clamp(0, range as i64);
 
     let week_status_list = repo.calendar.fetch_status_range(
         calendar_id,
-        start_offset as i64,
-        range as i64).await?;
+        start_offset.max(0),
+        range as i64 - leading_blank).await?;
 
-    // databaseをドメインロジック向けに編集する
+    let first_derived_week = start_week_abs + leading_blank as usize;
+    let limits = db2coverage_limits(&plan_config);
+
+    // 3. メンバーの制約 (月のコマ数は、その月の日だけを数える)
+    let first_day = NaiveDate::from_ymd_opt(target_year, target_month + 1, 1)
+        .ok_or_else(|| String::from("年月が正しくありません"))?;
+    let next_first_day = first_day
+        .checked_add_months(chrono::Months::new(1))
+        .ok_or_else(|| String::from("年月が正しくありません"))?;
+    let month_days = match (abs_day_of_date(first_day), abs_day_of_date(next_first_day)) {
+        (Some(start), Some(end)) => start..end,
+        _ => return Err(String::from("base abs の計算に失敗しました")),
+    };
+    let constraints = db2staff_constraints(&plan_config);
+
+    // 4. コアロジック実行 (導出・入れ替え・固定を反映した結果に対して、人員チェック・制約のチェックを行う)
+    let locked_weeks = &plan_calendar.calendar.locked_weeks;
+    let (dto_weeks, violations) = with_resolved_weeks(
+        &plan_config,
+        locked_weeks,
+        first_derived_week,
+        &week_status_list,
+        |partial_shift, skill_issues, overlay| {
+            let violations: Vec<StaffViolationDto> = constraints
+                .into_iter()
+                .flat_map(|(member_id, staff)| {
+                    check_staff_constraints(first_derived_week, partial_shift, month_days.clone(), &[staff])
+                        .into_iter()
+                        .map(move |violation| StaffViolationDto {
+                            member_id,
+                            date: violation.abs_week().map(|abs_week| date_of_abs_week(abs_week, violation.weekday().unwrap_or(0))
+                                .format("%Y-%m-%d")
+                                .to_string()),
+                            message: violation.to_string(),
+                            violation,
+                        })
+                })
+                .collect();
+
+            let dto_weeks: Vec<Option<WeeklyShiftDto>> = std::iter::repeat_with(|| None)
+                .take(leading_blank as usize)
+                .chain(partial_shift.iter().zip(skill_issues).enumerate().map(|(offset, (week_opt, skill_issues))| {
+                    // 週データが存在する(Some)場合だけ、中身を変換する
+                    week_opt.as_ref().map(|week| {
+                        let warnings: Vec<CoverageWarningDto> = check_week(week, &limits)
+                            .into_iter()
+                            .map(|warning| CoverageWarningDto { message: warning.to_string(), warning })
+                            .collect();
+
+                        // 1週間分(7日)のデータをループして DailyShiftDto の Vec を作る
+                        let abs_week = first_derived_week + offset;
+                        let days_dto: Vec<DailyShiftDto> = week.0
+                            .iter()
+                            .enumerate()
+                            .map(|(weekday, day)| DailyShiftDto {
+                                morning: day.shift_morning.iter().map(|t| t.name.clone()).collect(),
+                                afternoon: day.shift_afternoon.iter().map(|t| t.name.clone()).collect(),
+                                holiday: holiday_table()
+                                    .name_of(date_of_abs_week(abs_week, weekday))
+                                    .map(str::to_string),
+                            })
+                            .collect();
+
+                        let skill_issues = skill_issues
+                            .into_iter()
+                            .map(|issue| SkillIssueDto { message: issue.to_string(), issue })
+                            .collect();
+
+                        // WeeklyShiftDto に詰める
+                        WeeklyShiftDto { days: days_dto, warnings, skill_issues, locked: overlay.is_locked(offset) }
+                    })
+                }))
+                .collect();
+            (dto_weeks, violations)
+        },
+    );
+
+    Ok(MonthlyShiftResult { weeks: dto_weeks, violations })
+}
+
+
+
+/// スタッフ1人分のシフトを返します ("来月はいつ出勤？")
+/// start_date / end_date は "YYYY-MM-DD" で、両端を含む
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn get_staff_schedule(
+    plan_id: i64,
+    member_id: i64,
+    start_date: String,
+    end_date: String,
+    repo: State<'_, AppServices>,
+) -> Result<Vec<StaffShiftDto>, String> {
+    compute_staff_schedule(&repo, plan_id, member_id, &start_date, &end_date).await
+}
+
+/// get_staff_schedule の本体
+/// 週ごとの導出は compute_monthly_shift と同じで、そこから1人分のコマだけを取り出す
+/// (ルールから導出してローテーションの方式・スキルの入れ替えと週・日単位の調整を反映した結果。
+/// 固定した週は固定した内容)
+/// 在籍期間の外の週には入らない (調整で足したコマは除く)
+pub async fn compute_staff_schedule<R, C>(
+    repo: &AppServices<R, C>,
+    plan_id: i64,
+    member_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<StaffShiftDto>, String>
+where
+    R: RuleRepository,
+    C: CalendarRepository,
+{
+    let (start, end) = parse_date_range(start_date, end_date)?;
+    let plan_config = repo.rule.get_plan_config(plan_id).await?;
 
-    // 1. DBからドメインへの変換と、IDマップの取得
-    let (domain_groups, group_id_map) = db2staff_group_domain(&plan_config); 
+    let (group_index, staff_index) = member_position(&plan_config, member_id)
+        .ok_or_else(|| not_found(format!("プラン {} のメンバー {}", plan_id, member_id)))?;
+    let group_name = plan_config.groups[group_index].group.name.clone();
 
-    // 2. マップを使ってルールを変換
-    let rule_dict = db2rule_domain(&plan_config, &group_id_map);
+    let plan_calendar = match repo.calendar.find_by_plan_id(plan_id).await? {
+        Some(c) => c,
+        None => return Ok(vec![]), // データなし
+    };
 
-    // 3. コアロジック実行
-    let partial_shift = calculate_partial_shift(&week_status_list, &rule_dict, &domain_groups);
+    let rule_names: HashMap<RuleId, &str> = plan_config.rules
+        .iter()
+        .map(|rule_row| (rule_row.rule.id, rule_row.rule.name.as_str()))
+        .collect();
 
-    let dto_weeks: Vec<Option<WeeklyShiftDto>> = partial_shift
+    let shifts = derive_member_shifts(&plan_config, &plan_calendar, start, end, &[(member_id, group_index, staff_index)])?;
+    Ok(shifts
         .into_iter()
-        .map(|week_opt| {
-            // 週データが存在する(Some)場合だけ、中身を変換する
-            week_opt.map(|week| {
+        .map(|shift| StaffShiftDto {
+            date: shift.date.format("%Y-%m-%d").to_string(),
+            shift_time: shift.shift_time,
+            group_name: group_name.clone(),
+            rule_name: rule_names
+                .get(&shift.rule_id)
+                .map(|name| name.to_string())
+                .unwrap_or_default(),
+            holiday: holiday_table().name_of(shift.date).map(str::to_string),
+        })
+        .collect())
+}
+
+/// "YYYY-MM-DD" の期間 (両端を含む)
+fn parse_date_range(start_date: &str, end_date: &str) -> Result<(NaiveDate, NaiveDate), String> {
+    let parse_date = |text: &str| NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| format!("日付は YYYY-MM-DD で指定してください: {}", text));
+    let start = parse_date(start_date)?;
+    let end = parse_date(end_date)?;
+    if end < start {
+        return Err(format!("終了日 {} が開始日 {} より前です", end_date, start_date));
+    }
+    Ok((start, end))
+}
+
+/// メンバーが入っているコマ1つ
+struct MemberShift {
+    member_id: i64,
+    date: NaiveDate,
+    shift_time: ShiftTime,
+    rule_id: RuleId, // その週に適用したルール
+}
+
+/// プランのメンバーが期間 (両端を含む) に入っているコマを、メンバーごとに日付順で返す
+/// targets は (member_id, グループの位置, グループ内の位置)
+/// 週ごとの導出は compute_monthly_shift と同じ (基準週より前は含めない。ShiftCalendarManager::staff_shifts と同じ)
+fn derive_member_shifts(
+    plan_config: &PlanConfig,
+    plan_calendar: &PlanCalendar,
+    start: NaiveDate,
+    end: NaiveDate,
+    targets: &[(i64, usize, usize)],
+) -> Result<Vec<MemberShift>, String> {
+    let abs_week_of = |date: NaiveDate| calculate_abs_week(date.year(), date.month0(), date.day())
+        .ok_or_else(|| format!("{} は1970年より前です", date));
+    let start_abs = abs_week_of(start)?;
+    let end_abs = abs_week_of(end)?;
 
-                // 1週間分(7日)のデータをループして DailyShiftDto の Vec を作る
-                let days_dto: Vec<DailyShiftDto> = week.0
+    let first_abs = start_abs.max(plan_calendar.calendar.base_abs_week);
+    let timeline_slice = plan_calendar.calendar.slice_by_abs(first_abs, (end_abs + 1).saturating_sub(first_abs));
+    let locked_weeks = &plan_calendar.calendar.locked_weeks;
+    let shifts: Vec<MemberShift> = with_resolved_weeks(plan_config, locked_weeks, first_abs, timeline_slice, |decided, _, _| {
+        targets
+            .iter()
+            .flat_map(|&(member_id, group_index, staff_index)| {
+                staff_shifts_of(timeline_slice, first_abs, decided, group_index, staff_index)
                     .into_iter()
-                    .map(|day| DailyShiftDto {
-                        morning: day.shift_morning.iter().map(|t| t.name.clone()).collect(),
-                        afternoon: day.shift_afternoon.iter().map(|t| t.name.clone()).collect(),
+                    .map(move |shift| MemberShift {
+                        member_id,
+                        date: date_of_abs_week(shift.abs_week, shift.weekday),
+                        shift_time: match shift.period {
+                            ShiftPeriod::Morning => ShiftTime::Morning,
+                            ShiftPeriod::Afternoon => ShiftTime::Afternoon,
+                        },
+                        rule_id: shift.payload.rule_id,
                     })
-                    .collect();
-
-                // WeeklyShiftDto に詰める
-                WeeklyShiftDto { days: days_dto }
             })
+            .collect()
+    });
+
+    // 週の途中から・途中までの指定なので、範囲外の曜日を落とす
+    Ok(shifts.into_iter().filter(|shift| start <= shift.date && shift.date <= end).collect())
+}
+
+/// 複数のプランのシフトをまとめて返します (plan_ids を省略するとすべてのプラン)
+/// start_date / end_date は "YYYY-MM-DD" で、両端を含む
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn get_team_schedule(
+    start_date: String,
+    end_date: String,
+    plan_ids: Option<Vec<i64>>,
+    repo: State<'_, AppServices>,
+) -> Result<Vec<TeamShiftDto>, String> {
+    compute_team_schedule(&repo, plan_ids.as_deref(), &start_date, &end_date).await
+}
+
+/// get_team_schedule の本体
+/// 日付・午前午後の順で、同じコマの中はプランを作った順・プランの中はグループとメンバーの並び
+/// 人にまとめたメンバーは、人の名前で返す
+pub async fn compute_team_schedule<R, C>(
+    repo: &AppServices<R, C>,
+    plan_ids: Option<&[PlanId]>,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<TeamShiftDto>, String>
+where
+    R: RuleRepository + PlanRepository,
+    C: CalendarRepository,
+{
+    let (start, end) = parse_date_range(start_date, end_date)?;
+    // list_plans は新しい順なので、作った順に並べ直す
+    let mut plans = repo.rule.list_plans().await?;
+    plans.sort_by_key(|plan| plan.id);
+    if let Some(&missing) = plan_ids.unwrap_or_default().iter().find(|&&id| !plans.iter().any(|p| p.id == id)) {
+        return Err(not_found(PlanItem::Plan(missing)));
+    }
+    let people: HashMap<i64, String> = repo.rule.list_people().await?
+        .into_iter()
+        .map(|person| (person.id, person.name))
+        .collect();
+
+    let mut shifts = vec![];
+    for plan in plans.iter().filter(|plan| plan_ids.is_none_or(|ids| ids.contains(&plan.id))) {
+        let Some(plan_calendar) = repo.calendar.find_by_plan_id(plan.id).await? else {
+            continue;
+        };
+        let plan_config = repo.rule.get_plan_config(plan.id).await?;
+
+        let members: HashMap<i64, (&StaffMember, &str)> = plan_config.groups
+            .iter()
+            .flat_map(|group_row| group_row.members
+                .iter()
+                .map(move |member| (member.id, (member, group_row.group.name.as_str()))))
+            .collect();
+        let targets: Vec<(i64, usize, usize)> = plan_config.groups
+            .iter()
+            .enumerate()
+            .flat_map(|(group_index, group_row)| group_row.members
+                .iter()
+                .enumerate()
+                .map(move |(staff_index, member)| (member.id, group_index, staff_index)))
+            .collect();
+
+        for shift in derive_member_shifts(&plan_config, &plan_calendar, start, end, &targets)? {
+            let (member, group_name) = members[&shift.member_id];
+            shifts.push(TeamShiftDto {
+                date: shift.date.format("%Y-%m-%d").to_string(),
+                shift_time: shift.shift_time,
+                person_id: member.person_id,
+                name: member.person_id
+                    .and_then(|person_id| people.get(&person_id))
+                    .unwrap_or(&member.name)
+                    .clone(),
+                plan_id: plan.id,
+                plan_name: plan.name.clone(),
+                member_id: member.id,
+                group_name: group_name.to_string(),
+                holiday: holiday_table().name_of(shift.date).map(str::to_string),
+            });
+        }
+    }
+
+    // 安定ソートなので、同じコマの中はプラン・メンバーの並びのまま
+    shifts.sort_by_key(|shift| (shift.date.clone(), shift.shift_time as i64));
+    Ok(shifts)
+}
+
+/// 同じ人 (人にまとめたメンバー) が、同じ日・同じ時間帯に複数のプランに入っているコマを返します
+/// start_date / end_date は "YYYY-MM-DD" で、両端を含む
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn get_cross_plan_conflicts(
+    start_date: String,
+    end_date: String,
+    repo: State<'_, AppServices>,
+) -> Result<Vec<CrossPlanConflictDto>, String> {
+    compute_cross_plan_conflicts(&repo, &start_date, &end_date).await
+}
+
+/// get_cross_plan_conflicts の本体 (日付・午前午後・人の順)
+/// 同じプランの中での重複は、月のシフトの人員チェック (warnings) で見つかるのでここでは数えない
+pub async fn compute_cross_plan_conflicts<R, C>(
+    repo: &AppServices<R, C>,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<CrossPlanConflictDto>, String>
+where
+    R: RuleRepository + PlanRepository,
+    C: CalendarRepository,
+{
+    let team = compute_team_schedule(repo, None, start_date, end_date).await?;
+
+    let mut slots: BTreeMap<(String, i64, i64), Vec<TeamShiftDto>> = BTreeMap::new();
+    for shift in team {
+        if let Some(person_id) = shift.person_id {
+            slots.entry((shift.date.clone(), shift.shift_time as i64, person_id)).or_default().push(shift);
+        }
+    }
+
+    Ok(slots
+        .into_iter()
+        .filter(|(_, shifts)| shifts.iter().any(|shift| shift.plan_id != shifts[0].plan_id))
+        .map(|((date, _, person_id), shifts)| {
+            let mut plan_names: Vec<&str> = shifts.iter().map(|shift| shift.plan_name.as_str()).collect();
+            plan_names.dedup();
+            let period = match shifts[0].shift_time {
+                ShiftTime::Morning => "午前",
+                ShiftTime::Afternoon => "午後",
+            };
+            CrossPlanConflictDto {
+                person_id,
+                name: shifts[0].name.clone(),
+                message: format!("{} が {} {} に {} で重なっています", shifts[0].name, date, period, plan_names.join("・")),
+                date,
+                shift_time: shifts[0].shift_time,
+                shifts,
+            }
+        })
+        .collect())
+}
+
+/// 祝日・休日の一覧 (カレンダーに表示する用)
+/// start_date / end_date は "YYYY-MM-DD" で、両端を含む
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn list_holidays(start_date: String, end_date: String) -> Result<Vec<HolidayDto>, String> {
+    compute_holidays(&start_date, &end_date)
+}
+
+/// list_holidays の本体 (同梱の表か、起動時に差し替えた表を使う)
+pub fn compute_holidays(start_date: &str, end_date: &str) -> Result<Vec<HolidayDto>, String> {
+    let parse_date = |text: &str| NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| format!("日付は YYYY-MM-DD で指定してください: {}", text));
+    let start = parse_date(start_date)?;
+    let end = parse_date(end_date)?;
+
+    Ok(holiday_table()
+        .between(start, end)
+        .into_iter()
+        .map(|(date, name)| HolidayDto { date: date.format("%Y-%m-%d").to_string(), name: name.to_string() })
+        .collect())
+}
+
+/// 月のカレンダーに表示する週 (1日を含む週と週数)
+fn month_weeks(target_year: i32, target_month: u32) -> Result<(AbsWeek, usize), String> {
+    let start_week_abs = calculate_abs_week(target_year, target_month, 1)
+        .ok_or_else(|| String::from("base abs の計算に失敗しました"))?;
+    Ok((start_week_abs, calculate_weeks_in_month(target_year, target_month) as usize))
+}
+
+/// 月のカレンダーに表示している週を、今の内容 (derive_monthly_shift と同じ) で固定します (公開)
+/// 固定した週は、後でルールやメンバーを変えても変わりません
+/// Activeな週だけを固定し、新しく固定した絶対週を返します (固定済みの週はそのまま)
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn lock_month(
+    plan_id: i64,
+    target_year: i32,
+    target_month: u32, // 0-11
+    expected_revision: Option<i64>,
+    repo: State<'_, AppServices>,
+) -> Result<Vec<AbsWeek>, String> {
+    let (_, locked) = repo
+        .with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| {
+            compute_lock_month(tx, plan_id, target_year, target_month).await
+        })
+        .await?;
+    if let (Some(&first), Some(&last)) = (locked.first(), locked.last()) {
+        repo.events.emit(ChangeEvent::TimelineChanged {
+            plan_id,
+            change: TimelineChange::Locked,
+            from_abs_week: first,
+            count: Some(last - first + 1),
+        });
+    }
+    Ok(locked)
+}
+
+/// lock_month の本体
+pub async fn compute_lock_month<R, C>(
+    repo: &AppServices<R, C>,
+    plan_id: i64,
+    target_year: i32,
+    target_month: u32, // 0-11
+) -> Result<Vec<AbsWeek>, String>
+where
+    R: RuleRepository,
+    C: CalendarRepository,
+{
+    let plan_calendar = repo.calendar
+        .find_by_plan_id(plan_id)
+        .await?
+        .ok_or_else(|| String::from("カレンダーを作成してください"))?;
+    let plan_config = repo.rule.get_plan_config(plan_id).await?;
+    let (start_week_abs, range) = month_weeks(target_year, target_month)?;
+
+    // 基準週より前の週は導出しないので固定もしない
+    let first_abs = start_week_abs.max(plan_calendar.calendar.base_abs_week);
+    let timeline_slice = plan_calendar.calendar.slice_by_abs(first_abs, (start_week_abs + range).saturating_sub(first_abs));
+    let locked_weeks = &plan_calendar.calendar.locked_weeks;
+    // 固定済みの週は導出し直さない (削除したメンバーのコマやメンバーIDが失われるので)
+    let mut weeks: Vec<LockedWeek> = with_resolved_weeks(&plan_config, locked_weeks, first_abs, timeline_slice, |decided, _, _| {
+        decided
+            .iter()
+            .enumerate()
+            .filter(|(offset, _)| !plan_calendar.calendar.is_locked(first_abs + offset))
+            .filter_map(|(offset, week)| week.as_ref().map(|week| LockedWeek::from_decided(first_abs + offset, week)))
+            .collect()
+    });
+    // 後でメンバーを削除・並び替えしても同じ人を指すよう、メンバーIDも保存する
+    for staff in weeks.iter_mut().flat_map(LockedWeek::staff_mut) {
+        staff.member_id = plan_config.groups
+            .get(staff.group_index)
+            .and_then(|group_row| group_row.members.get(staff.staff_index))
+            .map(|member| member.id);
+    }
+
+    let abs_weeks = weeks.iter().map(|week| week.abs_week).collect();
+    repo.calendar.lock_weeks(plan_id, weeks).await?;
+    Ok(abs_weeks)
+}
+
+/// 月のカレンダーに表示している週の固定を外します (ルールから導出し直すようになります)
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn unlock_month(
+    plan_id: i64,
+    target_year: i32,
+    target_month: u32, // 0-11
+    expected_revision: Option<i64>,
+    repo: State<'_, AppServices>,
+) -> Result<(), String> {
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| {
+        compute_unlock_month(tx, plan_id, target_year, target_month).await
+    })
+    .await?;
+    let (start_week_abs, range) = month_weeks(target_year, target_month)?;
+    repo.events.emit(ChangeEvent::TimelineChanged {
+        plan_id,
+        change: TimelineChange::Unlocked,
+        from_abs_week: start_week_abs,
+        count: Some(range),
+    });
+    Ok(())
+}
+
+/// unlock_month の本体
+pub async fn compute_unlock_month<R, C>(
+    repo: &AppServices<R, C>,
+    plan_id: i64,
+    target_year: i32,
+    target_month: u32, // 0-11
+) -> Result<(), String>
+where
+    C: CalendarRepository,
+{
+    let (start_week_abs, range) = month_weeks(target_year, target_month)?;
+    repo.calendar.unlock_weeks(plan_id, start_week_abs, range).await
+}
+
+/// 月のシフト表を印刷用の HTML に書き出します (output_path の拡張子は .html)
+/// group_id を指定すると、そのグループの人だけの表になります
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn export_month_schedule(
+    plan_id: i64,
+    target_year: i32,
+    target_month: u32, // 0-11
+    group_id: Option<i64>,
+    output_path: String,
+    repo: State<'_, AppServices>,
+) -> Result<(), String> {
+    let path = Path::new(&output_path);
+    check_output_path(path)?;
+    let month = compute_printable_month(&repo, plan_id, target_year, target_month, group_id).await?;
+    fs::write(path, render_html(&month))
+        .map_err(|e| format!("{} に書き込めません: {}", path.display(), e))
+}
+
+/// export_month_schedule の本体 (印刷する内容)
+/// 週ごとの導出は compute_monthly_shift と同じで、名前にグループ (色) と祝日を付ける
+/// カレンダーが無い月・基準週より前の週・スキップした週は、日付だけの行になる
+pub async fn compute_printable_month<R, C>(
+    repo: &AppServices<R, C>,
+    plan_id: i64,
+    target_year: i32,
+    target_month: u32, // 0-11
+    group_id: Option<i64>,
+) -> Result<PrintableMonth, String>
+where
+    R: RuleRepository,
+    C: CalendarRepository,
+{
+    let plan_config = repo.rule.get_plan_config(plan_id).await?;
+    let target_group = group_id
+        .map(|group_id| plan_config.groups
+            .iter()
+            .position(|group_row| group_row.group.id == group_id)
+            .ok_or_else(|| format!("プラン {} にグループ {} はありません", plan_id, group_id)))
+        .transpose()?;
+    let (start_week_abs, range) = month_weeks(target_year, target_month)?;
+
+    let plan_calendar = repo.calendar.find_by_plan_id(plan_id).await?;
+    let decided_weeks: Vec<Option<Vec<[Vec<PrintEntry>; 2]>>> = match &plan_calendar {
+        Some(plan_calendar) => {
+            // 基準週より前の週は導出しない
+            let first_abs = start_week_abs.max(plan_calendar.calendar.base_abs_week);
+            let timeline_slice = plan_calendar.calendar.slice_by_abs(first_abs, (start_week_abs + range).saturating_sub(first_abs));
+            let locked_weeks = &plan_calendar.calendar.locked_weeks;
+            let entries = |staff_list: &[&Staff]| -> Vec<PrintEntry> {
+                staff_list
+                    .iter()
+                    .filter(|staff| target_group.is_none_or(|group_index| staff.group_id == group_index))
+                    .map(|staff| PrintEntry { name: staff.name.clone(), group_index: staff.group_id })
+                    .collect()
+            };
+            let derived: Vec<Option<Vec<[Vec<PrintEntry>; 2]>>> =
+                with_resolved_weeks(&plan_config, locked_weeks, first_abs, timeline_slice, |decided, _, _| {
+                    decided
+                        .iter()
+                        .map(|week| week.as_ref().map(|week| week.0
+                            .iter()
+                            .map(|day| [entries(&day.shift_morning), entries(&day.shift_afternoon)])
+                            .collect()))
+                        .collect()
+                });
+            std::iter::repeat_with(|| None)
+                .take(first_abs.saturating_sub(start_week_abs))
+                .chain(derived)
+                .collect()
+        }
+        None => vec![],
+    };
+
+    let weeks = (0..range)
+        .map(|offset| {
+            let abs_week = start_week_abs + offset;
+            let mut decided = decided_weeks.get(offset).cloned().flatten();
+            let active = decided.is_some();
+            let days = (0..7)
+                .map(|weekday| {
+                    let date = date_of_abs_week(abs_week, weekday);
+                    let [morning, afternoon] = decided
+                        .as_mut()
+                        .map(|days| std::mem::take(&mut days[weekday]))
+                        .unwrap_or_default();
+                    PrintDay {
+                        date,
+                        in_month: date.year() == target_year && date.month0() == target_month,
+                        holiday: holiday_table().name_of(date).map(str::to_string),
+                        morning,
+                        afternoon,
+                    }
+                })
+                .collect();
+            PrintWeek { active, days }
         })
         .collect();
 
-    Ok(MonthlyShiftResult { weeks: dto_weeks })
+    let groups = plan_config.groups
+        .iter()
+        .enumerate()
+        .filter(|(group_index, _)| target_group.is_none_or(|target| *group_index == target))
+        .map(|(group_index, group_row)| (group_index, PrintGroup {
+            name: group_row.group.name.clone(),
+            color: group_color(group_index),
+        }))
+        .collect();
+
+    Ok(PrintableMonth {
+        plan_name: plan_config.plan.name.clone(),
+        year: target_year,
+        month: target_month + 1,
+        group_name: target_group.map(|group_index| plan_config.groups[group_index].group.name.clone()),
+        groups,
+        weeks,
+    })
 }
 
+/// 日付 ("YYYY-MM-DD") の週
+fn abs_week_of_date_text(text: &str) -> Result<AbsWeek, String> {
+    let date = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| format!("日付は YYYY-MM-DD で指定してください: {}", text))?;
+    calculate_abs_week(date.year(), date.month0(), date.day())
+        .ok_or_else(|| format!("{} は1970年より前です", date))
+}
+
+/// タイムラインでルール `rule_id` を使っている週
+fn weeks_using_rule(calendar: &ShiftCalendarManager, rule_id: RuleId) -> Vec<AbsWeek> {
+    calendar.timeline
+        .iter()
+        .enumerate()
+        .filter(|(_, status)| matches!(status, WeekStatus::Active { payload, .. } if payload.rule_id == rule_id))
+        .map(|(index, _)| calendar.base_abs_week + index)
+        .collect()
+}
+
+/// ルール `rule_id` を使っている固定した週
+fn locked_weeks_using_rule(calendar: &ShiftCalendarManager, rule_id: RuleId) -> Vec<AbsWeek> {
+    weeks_using_rule(calendar, rule_id)
+        .into_iter()
+        .filter(|&abs_week| calendar.is_locked(abs_week))
+        .collect()
+}
+
+fn monday_text(abs_week: AbsWeek) -> String {
+    date_of_abs_week(abs_week, 0).format("%Y-%m-%d").to_string()
+}
+
+/// ルールを変える前に、入っている人が変わるコマを返します (保存はしません)
+/// from_date ("YYYY-MM-DD") の週からタイムラインの末尾までを比べます
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn preview_rule_edit(
+    plan_id: i64,
+    edit: RuleEdit,
+    from_date: String,
+    repo: State<'_, AppServices>,
+) -> Result<RuleEditImpactDto, String> {
+    compute_rule_edit_impact(&repo, plan_id, &edit, &from_date).await
+}
+
+/// preview_rule_edit の本体
+/// 固定した週は表示は変わらないが、固定を外したときの違いとして含める (locked)
+pub async fn compute_rule_edit_impact<R, C>(
+    repo: &AppServices<R, C>,
+    plan_id: i64,
+    edit: &RuleEdit,
+    from_date: &str,
+) -> Result<RuleEditImpactDto, String>
+where
+    R: RuleRepository,
+    C: CalendarRepository,
+{
+    let from_abs = abs_week_of_date_text(from_date)?;
+
+    // PlanConfig は Clone できないので2回読む
+    let before_config = repo.rule.get_plan_config(plan_id).await?;
+    let mut after_config = repo.rule.get_plan_config(plan_id).await?;
+    let rule_id = edited_rule_id(&before_config, edit)?;
+    apply_edit_to_config(&mut after_config, edit)?;
+
+    let plan_calendar = match repo.calendar.find_by_plan_id(plan_id).await? {
+        Some(c) => c,
+        None => return Ok(RuleEditImpactDto { changes: vec![], locked_weeks: vec![] }), // 導出する週が無い
+    };
+    let calendar = &plan_calendar.calendar;
+
+    // 固定は重ねずに、ルールから導出したもの同士を比べる
+    let first_abs = from_abs.max(calendar.base_abs_week);
+    let timeline_slice = calendar.slice_by_abs(first_abs, calendar.end_abs_week().saturating_sub(first_abs));
+    let changes = with_resolved_weeks(&before_config, &[], first_abs, timeline_slice, |before, _, _| {
+        with_resolved_weeks(&after_config, &[], first_abs, timeline_slice, |after, _, _| {
+            diff_weeks(first_abs, before, after)
+        })
+    });
 
+    let staff_text = |names: &[String]| if names.is_empty() { String::from("(なし)") } else { names.join("・") };
+    Ok(RuleEditImpactDto {
+        changes: changes
+            .into_iter()
+            .map(|change| {
+                let date = date_of_abs_week(change.abs_week, change.weekday).format("%Y-%m-%d").to_string();
+                CellChangeDto {
+                    message: format!("{} {}: {} → {}", date, change.period, staff_text(&change.before), staff_text(&change.after)),
+                    date,
+                    locked: calendar.is_locked(change.abs_week),
+                    change,
+                }
+            })
+            .collect(),
+        locked_weeks: locked_weeks_using_rule(calendar, rule_id).into_iter().map(monday_text).collect(),
+    })
+}
+
+/// ルールを変えます (足す・消す穴・消すルールを指定する)
+/// allow_locked が false のときは、変えるルールを固定した週が使っていたら断ります
+/// (add_rule_assignment / delete_assignment も allow_locked = false でここを通る)
+/// 足した穴のIDを返します (それ以外は None)
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn apply_rule_edit(
+    plan_id: i64,
+    edit: RuleEdit,
+    allow_locked: bool,
+    expected_revision: Option<i64>,
+    repo: State<'_, AppServices>,
+) -> Result<Option<i64>, String> {
+    let deleted = match edit {
+        RuleEdit::AddAssignment { .. } => None,
+        RuleEdit::DeleteAssignment { assignment_id } => Some(ChangeTarget::Assignment(assignment_id)),
+        RuleEdit::DeleteRule { rule_id } => Some(ChangeTarget::Rule(rule_id)),
+    };
+    // 固定した週で断ったときも revision は進まない
+    let (_, added) = repo
+        .with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| {
+            compute_apply_rule_edit(tx, plan_id, edit, allow_locked).await
+        })
+        .await?;
+    let (target, action) = match (added, deleted) {
+        (Some(assignment_id), _) => (ChangeTarget::Assignment(assignment_id), ChangeAction::Created),
+        (None, Some(target)) => (target, ChangeAction::Deleted),
+        (None, None) => return Ok(None),
+    };
+    repo.events.emit(ChangeEvent::RuleChanged { plan_id, target, action });
+    Ok(added)
+}
+
+/// apply_rule_edit の本体
+pub async fn compute_apply_rule_edit<R, C>(
+    repo: &AppServices<R, C>,
+    plan_id: i64,
+    edit: RuleEdit,
+    allow_locked: bool,
+) -> Result<Option<i64>, String>
+where
+    R: RuleRepository,
+    C: CalendarRepository,
+{
+    let plan_config = repo.rule.get_plan_config(plan_id).await?;
+    let rule_id = edited_rule_id(&plan_config, &edit)?;
+
+    if !allow_locked {
+        if let Some(plan_calendar) = repo.calendar.find_by_plan_id(plan_id).await? {
+            let locked = locked_weeks_using_rule(&plan_calendar.calendar, rule_id);
+            if !locked.is_empty() {
+                let rule_name = plan_config.rules
+                    .iter()
+                    .find(|rule_row| rule_row.rule.id == rule_id)
+                    .map(|rule_row| rule_row.rule.name.as_str())
+                    .unwrap_or_default();
+                let mondays: Vec<String> = locked.into_iter().map(monday_text).collect();
+                return Err(format!("ルール「{}」は固定した週 ({} の週) で使われています", rule_name, mondays.join(", ")));
+            }
+        }
+    }
+
+    match edit {
+        RuleEdit::AddAssignment { rule_id, weekday, shift_time, group_id, member_index } => repo.rule
+            .add_rule_assignment(rule_id, weekday, shift_time, group_id, member_index)
+            .await
+            .map(Some),
+        RuleEdit::DeleteAssignment { assignment_id } => repo.rule.delete_assignment(assignment_id).await.map(|_| None),
+        RuleEdit::DeleteRule { rule_id } => delete_rule_in_timeline_checked(repo, plan_id, rule_id, &RuleDeletion::Refuse).await.map(|_| None),
+    }
+}
+
+/// add_rule_assignment の本体 (`edit` は `RuleEdit::AddAssignment`)
+/// 固定した週が使っているルールには足さない。足した穴のIDを返す
+pub async fn compute_add_rule_assignment<R, C>(repo: &AppServices<R, C>, plan_id: i64, edit: RuleEdit) -> Result<i64, String>
+where
+    R: RuleRepository,
+    C: CalendarRepository,
+{
+    compute_apply_rule_edit(repo, plan_id, edit, false)
+        .await?
+        .ok_or_else(|| String::from("穴を足す変更ではありません"))
+}
+
+/// タイムラインで使われているルールを消します
+/// 使っている週は mode に従って、そのままにして断る (refuse)・別のルールに付け替える (reassign)・Skippedにする (skip)
+/// 書き換えた週の月曜 ("YYYY-MM-DD") を返します
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn delete_weekly_rule_with(
+    plan_id: i64,
+    rule_id: i64,
+    mode: RuleDeletion,
+    expected_revision: Option<i64>,
+    repo: State<'_, AppServices>,
+) -> Result<Vec<String>, String> {
+    let (_, changed) = repo
+        .with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| {
+            delete_rule_in_timeline_checked(tx, plan_id, rule_id, &mode).await
+        })
+        .await?;
+    repo.events.emit(ChangeEvent::RuleChanged {
+        plan_id,
+        target: ChangeTarget::Rule(rule_id),
+        action: ChangeAction::Deleted,
+    });
+    if let (Some(&first), Some(&last)) = (changed.first(), changed.last()) {
+        repo.events.emit(ChangeEvent::TimelineChanged {
+            plan_id,
+            change: TimelineChange::Rewritten,
+            from_abs_week: first,
+            count: Some(last - first + 1),
+        });
+    }
+    Ok(changed.into_iter().map(monday_text).collect())
+}
+
+/// delete_weekly_rule_with の本体
+pub async fn compute_delete_weekly_rule_with<R, C>(
+    repo: &AppServices<R, C>,
+    plan_id: i64,
+    rule_id: i64,
+    mode: &RuleDeletion,
+) -> Result<Vec<String>, String>
+where
+    R: RuleRepository,
+    C: CalendarRepository,
+{
+    let changed = delete_rule_in_timeline_checked(repo, plan_id, rule_id, mode).await?;
+    Ok(changed.into_iter().map(monday_text).collect())
+}
+
+/// compute_delete_weekly_rule_with の本体 (書き換えた週を絶対週で返す)
+async fn delete_rule_in_timeline_checked<R, C>(
+    repo: &AppServices<R, C>,
+    plan_id: i64,
+    rule_id: i64,
+    mode: &RuleDeletion,
+) -> Result<Vec<AbsWeek>, String>
+where
+    R: RuleRepository,
+    C: CalendarRepository,
+{
+    // 断るときは、使っている週を日付で示す (リポジトリのエラーは絶対週)
+    if *mode == RuleDeletion::Refuse {
+        if let Some(plan_calendar) = repo.calendar.find_by_plan_id(plan_id).await? {
+            let used = weeks_using_rule(&plan_calendar.calendar, rule_id);
+            if !used.is_empty() {
+                let plan_config = repo.rule.get_plan_config(plan_id).await?;
+                let rule_name = plan_config.rules
+                    .iter()
+                    .find(|rule_row| rule_row.rule.id == rule_id)
+                    .map(|rule_row| rule_row.rule.name.as_str())
+                    .unwrap_or_default();
+                let mondays: Vec<String> = used.into_iter().map(monday_text).collect();
+                return Err(format!("ルール「{}」はタイムラインの {} 週 ({} の週) で使われています", rule_name, mondays.len(), mondays.join(", ")));
+            }
+        }
+    }
+
+    repo.calendar.delete_rule_in_timeline(plan_id, rule_id, mode).await
+}
@@ -1,16 +1,52 @@
 use serde::Serialize;
+use shift_timeline::{CellChange, CoverageWarning, SkillIssue, StaffViolation};
+
+use crate::domain::rule_model::ShiftTime;
 
 /// 1日分の確定シフト (フロントエンド表示用)
 #[derive(Debug, Serialize)]
 pub struct DailyShiftDto {
     pub morning: Vec<String>,   // 午前のアサイン名リスト (例: ["Tanaka", "Suzuki"])
     pub afternoon: Vec<String>, // 午後のアサイン名リスト
+    pub holiday: Option<String>, // 祝日・休日の名前 (例: "振替休日")
+}
+
+/// 人員チェックの警告1件
+/// `kind` / `weekday` などの項目に、画面にそのまま出せる `message` を加えたもの
+#[derive(Debug, Serialize)]
+pub struct CoverageWarningDto {
+    #[serde(flatten)]
+    pub warning: CoverageWarning,
+    pub message: String,
+}
+
+/// スキルのチェック結果1件 (持っていない人・入れ替えた人)
+#[derive(Debug, Serialize)]
+pub struct SkillIssueDto {
+    #[serde(flatten)]
+    pub issue: SkillIssue,
+    pub message: String,
 }
 
 /// 1週間分の確定シフト
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct WeeklyShiftDto {
-    pub days: Vec<DailyShiftDto>, // 0(Mon) ~ 6(Sun)
+    pub days: Vec<DailyShiftDto>, // 0(Mon) ~ 6(Sun) (スキルによる入れ替えは反映済み)
+    pub warnings: Vec<CoverageWarningDto>, // 空き・人数不足・重複・出勤日数超過
+    pub skill_issues: Vec<SkillIssueDto>,
+    pub locked: bool, // 固定した週 (ルールを変えても変わらない)
+}
+
+/// メンバーの制約の違反1件
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffViolationDto {
+    #[serde(flatten)]
+    pub violation: StaffViolation,
+    pub member_id: i64,
+    pub date: Option<String>, // "YYYY-MM-DD" (週の上限はその週の月曜、月の上限は None)
+    pub message: String,
 }
 
 /// コマンドの返り値
@@ -19,4 +55,72 @@ pub struct MonthlyShiftResult {
     // フロントエンドのカレンダー週順 (0, 1, 2...) に対応するデータ
     // None = その週はスキップ、または未生成
     pub weeks: Vec<Option<WeeklyShiftDto>>,
+    // メンバーの制約の違反 (メンバー順・日付順)
+    pub violations: Vec<StaffViolationDto>,
+}
+
+/// スタッフ1人分のシフトの1コマ (get_staff_schedule の返り値の要素)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffShiftDto {
+    pub date: String,          // "YYYY-MM-DD"
+    pub shift_time: ShiftTime, // "Morning" / "Afternoon"
+    pub group_name: String,
+    pub rule_name: String,     // その週に適用したルール
+    pub holiday: Option<String>, // 祝日・休日の名前
+}
+
+/// 複数のプランをまとめたシフトの1コマ (get_team_schedule の返り値の要素)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamShiftDto {
+    pub date: String,            // "YYYY-MM-DD"
+    pub shift_time: ShiftTime,   // "Morning" / "Afternoon"
+    pub person_id: Option<i64>,  // まとめていないメンバーは None
+    pub name: String,            // まとめた人の名前 (まとめていなければメンバーの名前)
+    pub plan_id: i64,
+    pub plan_name: String,
+    pub member_id: i64,
+    pub group_name: String,
+    pub holiday: Option<String>, // 祝日・休日の名前
+}
+
+/// 同じ人が同じ日・同じ時間帯に複数のプランに入っている重なり1件
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossPlanConflictDto {
+    pub person_id: i64,
+    pub name: String,
+    pub date: String,          // "YYYY-MM-DD"
+    pub shift_time: ShiftTime,
+    pub shifts: Vec<TeamShiftDto>, // 重なっているコマ (プランの順)
+    pub message: String,
+}
+
+/// 祝日・休日1日分 (list_holidays の返り値の要素)
+#[derive(Debug, Serialize)]
+pub struct HolidayDto {
+    pub date: String, // "YYYY-MM-DD"
+    pub name: String,
+}
+
+/// ルールを変えると入っている人が変わるコマ
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellChangeDto {
+    #[serde(flatten)]
+    pub change: CellChange,
+    pub date: String,    // "YYYY-MM-DD"
+    pub locked: bool,    // 固定した週 (表示は変わらないが、固定を外すと変わる)
+    pub message: String, // 表示用 (例: 2026-06-01 午前: 田中 → 佐藤)
+}
+
+/// preview_rule_edit の返り値
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleEditImpactDto {
+    // 指定した日の週からタイムラインの末尾まで (日付順)
+    pub changes: Vec<CellChangeDto>,
+    // 変えるルールを使っている固定した週の月曜 "YYYY-MM-DD" (apply_rule_edit はこの週があると断る)
+    pub locked_weeks: Vec<String>,
 }
@@ -0,0 +1,123 @@
+//! 変更の通知
+//!
+//! 書き換えるコマンド (`application::commands`) は、成功した後に `ChangeEvent` を
+//! `AppServices::events` に流す。デスクトップ版は `run()` で購読して、すべてのウィンドウに
+//! tauri のイベント (`ChangeEvent::name`) として送る。
+//! 受け取った側は種類と plan_id を見て、必要な所だけ読み直す
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::domain::shift_calendar_model::{AbsWeek, PlanId};
+
+/// 何をしたか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// 変えたもの (`{"type": "member", "id": 3}`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "id", rename_all = "camelCase")]
+pub enum ChangeTarget {
+    Group(i64),
+    Member(i64),
+    Rule(i64),
+    Assignment(i64),
+    Pattern(i64),
+    SkipRule(i64),
+    Override(i64),
+    Person(i64),
+}
+
+/// タイムラインの変え方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimelineChange {
+    Created,
+    Extended,
+    Truncated,
+    /// ルールを消したときに、使っていた週を付け替えた・Skippedにした
+    Rewritten,
+    Locked,
+    Unlocked,
+}
+
+/// バックエンドで起きた変更
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ChangeEvent {
+    /// プランの追加・削除・設定 (出勤日数の上限・スキルの入れ替え)
+    PlanChanged { plan_id: PlanId, action: ChangeAction },
+    /// グループ・メンバー
+    StaffChanged { plan_id: PlanId, target: ChangeTarget, action: ChangeAction },
+    /// ルール・穴・パターン・休業ルール・週・日単位の調整
+    RuleChanged { plan_id: PlanId, target: ChangeTarget, action: ChangeAction },
+    /// タイムライン。from_abs_week から count 週 (None = 最後まで) が変わった
+    TimelineChanged { plan_id: PlanId, change: TimelineChange, from_abs_week: AbsWeek, count: Option<usize> },
+    /// プランをまたいだ人
+    PeopleChanged { target: ChangeTarget, action: ChangeAction },
+}
+
+impl ChangeEvent {
+    /// tauri のイベント名
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeEvent::PlanChanged { .. } => "plan-changed",
+            ChangeEvent::StaffChanged { .. } => "staff-changed",
+            ChangeEvent::RuleChanged { .. } => "rule-changed",
+            ChangeEvent::TimelineChanged { .. } => "timeline-changed",
+            ChangeEvent::PeopleChanged { .. } => "people-changed",
+        }
+    }
+}
+
+/// 通知の中身 (変更の番号 + 変更)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangeNotice {
+    pub version: u64,
+    #[serde(flatten)]
+    pub event: ChangeEvent,
+}
+
+type Listener = Box<dyn Fn(&ChangeNotice) + Send + Sync>;
+
+/// 変更の通知先と変更の番号 (変更を流すたびに1増える)
+/// cloneしても同じ通知先・番号を共有する
+#[derive(Clone, Default)]
+pub struct ChangeEvents {
+    version: Arc<AtomicU64>,
+    listeners: Arc<Mutex<Vec<Listener>>>,
+}
+
+impl ChangeEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 変更を受け取る関数を足す (外す方法は無い。アプリの起動時に1回だけ呼ぶ想定)
+    pub fn subscribe(&self, listener: impl Fn(&ChangeNotice) + Send + Sync + 'static) {
+        self.listeners.lock().unwrap().push(Box::new(listener));
+    }
+
+    /// 番号を進めて、すべての通知先に流す
+    pub fn emit(&self, event: ChangeEvent) {
+        // 番号を振る所から流し終わるまでロックしておき、通知先には番号の順に届ける
+        let listeners = self.listeners.lock().unwrap();
+        let version = self.version.fetch_add(1, Ordering::SeqCst) + 1;
+        let notice = ChangeNotice { version, event };
+        for listener in listeners.iter() {
+            listener(&notice);
+        }
+    }
+
+    /// 今の変更の番号 (まだ何も変えていなければ 0)
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,1019 @@
+//! commands と同じ操作を REST/JSON で公開するルーター
+//!
+//! `shift_api_server` バイナリから使う (Tauri IPC を経由しないローカルサービス用)
+//! リクエストボディのキーは Tauri コマンドの引数と同じく camelCase
+//!
+//! | Method | Path                                   | 対応するコマンド          |
+//! |--------|----------------------------------------|---------------------------|
+//! | GET    | /plans                                 | list_all_plans            |
+//! | POST   | /plans                                 | create_new_plan           |
+//! | PUT    | /plans/{plan_id}                       | (update_plan_name)        |
+//! | PUT    | /plans/{plan_id}/max-days              | update_plan_max_days      |
+//! | PUT    | /plans/{plan_id}/auto-swap             | update_plan_auto_swap     |
+//! | DELETE | /plans/{plan_id}                       | delete_plan               |
+//! | GET    | /plans/{plan_id}/config                | get_plan_config           |
+//! | POST   | /plans/{plan_id}/groups                | add_staff_group           |
+//! | PUT    | /groups/{group_id}                     | update_group_name         |
+//! | PUT    | /groups/{group_id}/min-staff           | update_group_min_staff    |
+//! | PUT    | /groups/{group_id}/rotation            | update_group_rotation     |
+//! | DELETE | /groups/{group_id}                     | delete_staff_group        |
+//! | POST   | /groups/{group_id}/members             | add_staff_member          |
+//! | PUT    | /members/{member_id}                   | update_member_name        |
+//! | DELETE | /members/{member_id}                   | delete_staff_member       |
+//! | PUT    | /members/{member_id}/period            | update_member_period      |
+//! | PUT    | /members/{member_id}/constraints       | set_member_constraints    |
+//! | DELETE | /members/{member_id}/constraints       | clear_member_constraints  |
+//! | PUT    | /members/{member_id}/skills            | set_member_skills         |
+//! | PUT    | /members/{member_id}/person            | link_member_person        |
+//! | GET    | /people                                | list_people               |
+//! | POST   | /people                                | add_person                |
+//! | PUT    | /people/{person_id}                    | update_person_name        |
+//! | DELETE | /people/{person_id}                    | delete_person             |
+//! | POST   | /plans/{plan_id}/rules                 | add_weekly_rule           |
+//! | PUT    | /rules/{rule_id}                       | update_rule_name          |
+//! | DELETE | /rules/{rule_id}                       | delete_weekly_rule        |
+//! | DELETE | /plans/{plan_id}/rules/{rule_id}       | delete_weekly_rule_with   |
+//! | POST   | /rules/{rule_id}/assignments           | add_rule_assignment       |
+//! | DELETE | /assignments/{assignment_id}           | delete_assignment         |
+//! | PUT    | /assignments/{assignment_id}/skills    | set_assignment_skills     |
+//! | POST   | /plans/{plan_id}/rule-edits/preview    | preview_rule_edit         |
+//! | POST   | /plans/{plan_id}/rule-edits            | apply_rule_edit           |
+//! | GET    | /plans/{plan_id}/rule-patterns         | list_rule_patterns        |
+//! | POST   | /plans/{plan_id}/rule-patterns         | add_rule_pattern          |
+//! | DELETE | /rule-patterns/{pattern_id}            | delete_rule_pattern       |
+//! | POST   | /plans/{plan_id}/overrides             | add_shift_override        |
+//! | DELETE | /overrides/{override_id}               | delete_shift_override     |
+//! | GET    | /plans/{plan_id}/calendar              | get_calendar_state        |
+//! | POST   | /plans/{plan_id}/calendar              | create_calendar           |
+//! | POST   | /plans/{plan_id}/calendar/timeline     | append_timeline           |
+//! | DELETE | /plans/{plan_id}/calendar/timeline     | truncate_timeline (?from=)|
+//! | POST   | /plans/{plan_id}/calendar/timeline/pattern | extend_timeline_by_pattern |
+//! | GET    | /plans/{plan_id}/calendar/pattern-weeks | get_pattern_weeks        |
+//! | GET    | /plans/{plan_id}/skip-rules            | list_skip_rules           |
+//! | POST   | /plans/{plan_id}/skip-rules            | add_skip_rule             |
+//! | PUT    | /skip-rules/{skip_rule_id}             | update_skip_rule          |
+//! | DELETE | /skip-rules/{skip_rule_id}             | delete_skip_rule          |
+//! | POST   | /plans/{plan_id}/calendar/locks        | lock_month (?year=&month=)|
+//! | DELETE | /plans/{plan_id}/calendar/locks        | unlock_month (?year=&month=) |
+//! | GET    | /plans/{plan_id}/monthly-shift         | derive_monthly_shift      |
+//! | GET    | /plans/{plan_id}/members/{member_id}/schedule | get_staff_schedule (?from=&to=) |
+//! | GET    | /holidays                              | list_holidays (?from=&to=) |
+//! | GET    | /team/schedule                         | get_team_schedule (?from=&to=&plans=1,2) |
+//! | GET    | /team/conflicts                        | get_cross_plan_conflicts (?from=&to=) |
+//!
+//! プランの中身を書き換えるリクエストは `If-Match: <revision>` (`Plan.revision`) を付けられる。
+//! 今の revision と違えば何も書き換えずに `409 Conflict` を返す (付けなければ確かめない)
+//! 書き換えに失敗したときは revision も進まない (`AppServices::with_revision`)
+//!
+//! エラーは `{"error": "..."}` で返す。ステータスはエラーの書き出し (`domain::repository`) で決める
+//! - `REVISION_CONFLICT`: `409 Conflict`
+//! - `NOT_FOUND`: `404 Not Found` (パスやクエリで指したものが無い)
+//! - `DATABASE_ERROR`: `500 Internal Server Error`
+//! - それ以外 (入力の誤り・制約違反): `400 Bad Request`
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRequestParts, Path, Query, State},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use shift_timeline::Rotation;
+
+use crate::application::commands::{
+    compute_add_rule_assignment, compute_apply_rule_edit, compute_cross_plan_conflicts, compute_delete_weekly_rule_with, compute_holidays,
+    compute_lock_month, compute_monthly_shift, compute_rule_edit_impact, compute_staff_schedule, compute_team_schedule,
+    compute_unlock_month,
+};
+use crate::application::dto::{
+    CrossPlanConflictDto, HolidayDto, MonthlyShiftResult, RuleEditImpactDto, StaffShiftDto, TeamShiftDto,
+};
+use crate::application::revision::Transactional;
+use crate::domain::repository::{CalendarRepository, PlanRepository, RuleRepository, DATABASE_ERROR, NOT_FOUND, REVISION_CONFLICT};
+use crate::domain::{rule_model::*, shift_calendar_model::*};
+use crate::AppServices;
+
+type Services<R, C> = State<Arc<AppServices<R, C>>>;
+
+// =================================================================
+// リクエスト / レスポンス
+// =================================================================
+
+/// リポジトリのエラー文字列をそのまま返す
+pub struct ApiError(String);
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        Self(message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = if self.0.starts_with(REVISION_CONFLICT) {
+            StatusCode::CONFLICT
+        } else if self.0.starts_with(NOT_FOUND) {
+            StatusCode::NOT_FOUND
+        } else if self.0.starts_with(DATABASE_ERROR) {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::BAD_REQUEST
+        };
+        (status, Json(ErrorBody { error: self.0 })).into_response()
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+/// `If-Match: <revision>` (commands の expected_revision)
+/// 無ければ確かめずに書き換える。ETag の形 (`"3"`) でもよい
+pub struct IfMatch(pub Option<i64>);
+
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(header::IF_MATCH) else {
+            return Ok(Self(None));
+        };
+        let text = value.to_str().unwrap_or_default().trim().trim_matches('"');
+        text.parse()
+            .map(|revision| Self(Some(revision)))
+            .map_err(|_| ApiError(format!("If-Match は revision の数字にしてください: {}", text)))
+    }
+}
+
+/// 作成系のレスポンス
+#[derive(Serialize)]
+pub struct CreatedId {
+    pub id: i64,
+}
+
+fn created(id: i64) -> (StatusCode, Json<CreatedId>) {
+    (StatusCode::CREATED, Json(CreatedId { id }))
+}
+
+#[derive(Deserialize)]
+pub struct NameBody {
+    pub name: String,
+}
+
+/// `null` で解除
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaxDaysBody {
+    pub max_days_per_week: Option<u32>,
+}
+
+/// `null` で解除
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinStaffBody {
+    pub min_per_period: Option<u32>,
+}
+
+/// `null` で shift_gen の並べ方に戻す
+#[derive(Deserialize)]
+pub struct RotationBody {
+    pub rotation: Option<Rotation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoSwapBody {
+    pub auto_swap_skills: bool,
+}
+
+/// "YYYY-MM-DD"、`null` で指定なし
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberPeriodBody {
+    pub joined_on: Option<String>,
+    pub left_on: Option<String>,
+}
+
+/// `null` でまとめを外す
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberPersonBody {
+    pub person_id: Option<i64>,
+}
+
+/// 空で全部外す
+#[derive(Deserialize)]
+pub struct SkillsBody {
+    pub skills: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignmentBody {
+    pub weekday: i64,
+    pub shift_time: i64,
+    pub group_id: i64,
+    pub member_index: i64,
+}
+
+/// タイムラインで使っている週の扱い
+#[derive(Deserialize)]
+pub struct RuleDeletionBody {
+    pub mode: RuleDeletion,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleEditPreviewBody {
+    pub edit: RuleEdit,
+    /// "YYYY-MM-DD"
+    pub from_date: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleEditBody {
+    pub edit: RuleEdit,
+    #[serde(default)]
+    pub allow_locked: bool,
+}
+
+/// `{"name": ..., "kind": "sequence", "anchorAbsWeek": ..., "steps": [...]}` など
+#[derive(Deserialize)]
+pub struct RulePatternBody {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: RulePatternKind,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarBody {
+    pub base_abs_week: AbsWeek,
+    pub initial_delta: LogicalDelta,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineBody {
+    pub start_abs_week: AbsWeek,
+    /// `null` = Skipped
+    pub statuses: Vec<Option<RuleId>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternExtendBody {
+    pub pattern_id: i64,
+    pub weeks: usize,
+}
+
+#[derive(Deserialize)]
+pub struct TruncateQuery {
+    pub from: AbsWeek,
+}
+
+/// month は commands と同じく 0-11
+#[derive(Deserialize)]
+pub struct MonthQuery {
+    pub year: i32,
+    pub month: u32,
+}
+
+/// "YYYY-MM-DD" (両端を含む)
+#[derive(Deserialize)]
+pub struct DateRangeQuery {
+    pub from: String,
+    pub to: String,
+}
+
+/// plans はプランIDのカンマ区切り (省略するとすべてのプラン)
+#[derive(Deserialize)]
+pub struct TeamQuery {
+    pub from: String,
+    pub to: String,
+    pub plans: Option<String>,
+}
+
+// =================================================================
+// Router
+// =================================================================
+
+pub fn router<R, C>(services: AppServices<R, C>) -> Router
+where
+    R: PlanRepository + RuleRepository + 'static,
+    C: CalendarRepository + 'static,
+    AppServices<R, C>: Transactional,
+{
+    Router::new()
+        // --- Plan ---
+        .route("/plans", get(list_all_plans::<R, C>).post(create_new_plan::<R, C>))
+        .route("/plans/{plan_id}", put(update_plan_name::<R, C>).delete(delete_plan::<R, C>))
+        .route("/plans/{plan_id}/max-days", put(update_plan_max_days::<R, C>))
+        .route("/plans/{plan_id}/auto-swap", put(update_plan_auto_swap::<R, C>))
+        .route("/plans/{plan_id}/config", get(get_plan_config::<R, C>))
+        // --- Group / Member ---
+        .route("/plans/{plan_id}/groups", post(add_staff_group::<R, C>))
+        .route("/groups/{group_id}", put(update_group_name::<R, C>).delete(delete_staff_group::<R, C>))
+        .route("/groups/{group_id}/min-staff", put(update_group_min_staff::<R, C>))
+        .route("/groups/{group_id}/rotation", put(update_group_rotation::<R, C>))
+        .route("/groups/{group_id}/members", post(add_staff_member::<R, C>))
+        .route("/members/{member_id}", put(update_member_name::<R, C>).delete(delete_staff_member::<R, C>))
+        .route("/members/{member_id}/period", put(update_member_period::<R, C>))
+        .route(
+            "/members/{member_id}/constraints",
+            put(set_member_constraints::<R, C>).delete(clear_member_constraints::<R, C>),
+        )
+        .route("/members/{member_id}/skills", put(set_member_skills::<R, C>))
+        .route("/members/{member_id}/person", put(link_member_person::<R, C>))
+        .route("/people", get(list_people::<R, C>).post(add_person::<R, C>))
+        .route("/people/{person_id}", put(update_person_name::<R, C>).delete(delete_person::<R, C>))
+        // --- Rules ---
+        .route("/plans/{plan_id}/rules", post(add_weekly_rule::<R, C>))
+        .route("/rules/{rule_id}", put(update_rule_name::<R, C>).delete(delete_weekly_rule::<R, C>))
+        .route("/plans/{plan_id}/rules/{rule_id}", delete(delete_weekly_rule_with::<R, C>))
+        .route("/rules/{rule_id}/assignments", post(add_rule_assignment::<R, C>))
+        .route("/assignments/{assignment_id}", delete(delete_assignment::<R, C>))
+        .route("/assignments/{assignment_id}/skills", put(set_assignment_skills::<R, C>))
+        .route("/plans/{plan_id}/rule-edits/preview", post(preview_rule_edit::<R, C>))
+        .route("/plans/{plan_id}/rule-edits", post(apply_rule_edit::<R, C>))
+        .route("/plans/{plan_id}/rule-patterns", get(list_rule_patterns::<R, C>).post(add_rule_pattern::<R, C>))
+        .route("/rule-patterns/{pattern_id}", delete(delete_rule_pattern::<R, C>))
+        .route("/plans/{plan_id}/overrides", post(add_shift_override::<R, C>))
+        .route("/overrides/{override_id}", delete(delete_shift_override::<R, C>))
+        // --- Calendar ---
+        .route("/plans/{plan_id}/calendar", get(get_calendar_state::<R, C>).post(create_calendar::<R, C>))
+        .route("/plans/{plan_id}/calendar/timeline", post(append_timeline::<R, C>).delete(truncate_timeline::<R, C>))
+        .route("/plans/{plan_id}/calendar/timeline/pattern", post(extend_timeline_by_pattern::<R, C>))
+        .route("/plans/{plan_id}/calendar/pattern-weeks", get(get_pattern_weeks::<R, C>))
+        .route("/plans/{plan_id}/skip-rules", get(list_skip_rules::<R, C>).post(add_skip_rule::<R, C>))
+        .route("/skip-rules/{skip_rule_id}", put(update_skip_rule::<R, C>).delete(delete_skip_rule::<R, C>))
+        .route("/plans/{plan_id}/calendar/locks", post(lock_month::<R, C>).delete(unlock_month::<R, C>))
+        .route("/plans/{plan_id}/monthly-shift", get(derive_monthly_shift::<R, C>))
+        .route("/plans/{plan_id}/members/{member_id}/schedule", get(get_staff_schedule::<R, C>))
+        .route("/holidays", get(list_holidays))
+        .route("/team/schedule", get(get_team_schedule::<R, C>))
+        .route("/team/conflicts", get(get_cross_plan_conflicts::<R, C>))
+        .with_state(Arc::new(services))
+}
+
+// =================================================================
+// Handlers
+// =================================================================
+
+// --- Plan ---
+async fn list_all_plans<R: PlanRepository, C>(State(repo): Services<R, C>) -> ApiResult<Json<Vec<Plan>>> {
+    Ok(Json(repo.rule.list_plans().await?))
+}
+
+async fn create_new_plan<R: PlanRepository, C>(
+    State(repo): Services<R, C>,
+    Json(body): Json<NameBody>,
+) -> ApiResult<impl IntoResponse> {
+    Ok(created(repo.rule.create_plan(&body.name).await?))
+}
+
+async fn update_plan_name<R: PlanRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<NameBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.update_plan_name(plan_id, &body.name).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn update_plan_max_days<R: PlanRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<MaxDaysBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.update_plan_max_days(plan_id, body.max_days_per_week).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn update_plan_auto_swap<R: PlanRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<AutoSwapBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.update_plan_auto_swap(plan_id, body.auto_swap_skills).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_plan<R: PlanRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.delete_plan(plan_id).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_plan_config<R: RuleRepository, C>(
+    State(repo): Services<R, C>,
+    Path(plan_id): Path<PlanId>,
+) -> ApiResult<Json<PlanConfig>> {
+    Ok(Json(repo.rule.get_plan_config(plan_id).await?))
+}
+
+// --- Group / Member ---
+async fn add_staff_group<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<NameBody>,
+) -> ApiResult<impl IntoResponse>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.add_staff_group(plan_id, &body.name).await).await?;
+    Ok(created(id))
+}
+
+async fn update_group_name<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(group_id): Path<i64>,
+    Json(body): Json<NameBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Group(group_id), expected_revision, async |tx| tx.rule.update_group_name(group_id, &body.name).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn update_group_min_staff<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(group_id): Path<i64>,
+    Json(body): Json<MinStaffBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Group(group_id), expected_revision, async |tx| tx.rule.update_group_min_staff(group_id, body.min_per_period).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn update_group_rotation<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(group_id): Path<i64>,
+    Json(body): Json<RotationBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Group(group_id), expected_revision, async |tx| tx.rule.update_group_rotation(group_id, body.rotation).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_staff_group<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(group_id): Path<i64>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Group(group_id), expected_revision, async |tx| tx.rule.delete_staff_group(group_id).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn add_staff_member<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(group_id): Path<i64>,
+    Json(body): Json<NameBody>,
+) -> ApiResult<impl IntoResponse>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, id) = repo.with_revision(PlanItem::Group(group_id), expected_revision, async |tx| tx.rule.add_staff_member(group_id, &body.name).await).await?;
+    Ok(created(id))
+}
+
+async fn update_member_name<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(member_id): Path<i64>,
+    Json(body): Json<NameBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.update_member_name(member_id, &body.name).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_staff_member<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(member_id): Path<i64>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.delete_staff_member(member_id).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn update_member_period<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(member_id): Path<i64>,
+    Json(body): Json<MemberPeriodBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.update_member_period(member_id, body.joined_on.as_deref(), body.left_on.as_deref()).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_member_constraints<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(member_id): Path<i64>,
+    Json(body): Json<MemberConstraints>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.set_member_constraints(member_id, &body).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn clear_member_constraints<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(member_id): Path<i64>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.clear_member_constraints(member_id).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_member_skills<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(member_id): Path<i64>,
+    Json(body): Json<SkillsBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.set_member_skills(member_id, &body.skills).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn link_member_person<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(member_id): Path<i64>,
+    Json(body): Json<MemberPersonBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Member(member_id), expected_revision, async |tx| tx.rule.link_member_person(member_id, body.person_id).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// --- Person ---
+async fn list_people<R: RuleRepository, C>(State(repo): Services<R, C>) -> ApiResult<Json<Vec<Person>>> {
+    Ok(Json(repo.rule.list_people().await?))
+}
+
+async fn add_person<R: RuleRepository, C>(
+    State(repo): Services<R, C>,
+    Json(body): Json<NameBody>,
+) -> ApiResult<impl IntoResponse> {
+    Ok(created(repo.rule.add_person(&body.name).await?))
+}
+
+async fn update_person_name<R: RuleRepository, C>(
+    State(repo): Services<R, C>,
+    Path(person_id): Path<i64>,
+    Json(body): Json<NameBody>,
+) -> ApiResult<StatusCode> {
+    repo.rule.update_person_name(person_id, &body.name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_person<R: RuleRepository, C>(
+    State(repo): Services<R, C>,
+    Path(person_id): Path<i64>,
+) -> ApiResult<StatusCode> {
+    repo.rule.delete_person(person_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// --- Rules ---
+async fn add_weekly_rule<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<NameBody>,
+) -> ApiResult<impl IntoResponse>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.add_weekly_rule(plan_id, &body.name).await).await?;
+    Ok(created(id))
+}
+
+async fn update_rule_name<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(rule_id): Path<RuleId>,
+    Json(body): Json<NameBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Rule(rule_id), expected_revision, async |tx| tx.rule.update_rule_name(rule_id, &body.name).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// タイムラインで使われているルールは消さない (付け替え・Skippedにするときは /plans/{plan_id}/rules/{rule_id})
+async fn delete_weekly_rule<R: PlanRepository + RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(rule_id): Path<RuleId>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_plan_revision(PlanItem::Rule(rule_id), expected_revision, async |tx, plan_id| {
+        compute_delete_weekly_rule_with(tx, plan_id, rule_id, &RuleDeletion::Refuse).await
+    })
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 書き換えた週の月曜 ("YYYY-MM-DD") を返す
+async fn delete_weekly_rule_with<R: PlanRepository + RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path((plan_id, rule_id)): Path<(PlanId, RuleId)>,
+    Json(body): Json<RuleDeletionBody>,
+) -> ApiResult<Json<Vec<String>>>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, result) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| compute_delete_weekly_rule_with(tx, plan_id, rule_id, &body.mode).await).await?;
+    Ok(Json(result))
+}
+
+/// 固定した週が使っているルールは変えない (rule-edits の allowLocked: false と同じ)
+async fn add_rule_assignment<R: PlanRepository + RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(rule_id): Path<RuleId>,
+    Json(body): Json<AssignmentBody>,
+) -> ApiResult<impl IntoResponse>
+where
+    AppServices<R, C>: Transactional,
+{
+    let edit = RuleEdit::AddAssignment {
+        rule_id,
+        weekday: body.weekday,
+        shift_time: body.shift_time,
+        group_id: body.group_id,
+        member_index: body.member_index,
+    };
+    let (_, id) = repo
+        .with_plan_revision(PlanItem::Rule(rule_id), expected_revision, async |tx, plan_id| {
+            compute_add_rule_assignment(tx, plan_id, edit).await
+        })
+        .await?;
+    Ok(created(id))
+}
+
+/// 固定した週が使っているルールは変えない (rule-edits の allowLocked: false と同じ)
+async fn delete_assignment<R: PlanRepository + RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(assignment_id): Path<i64>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    let edit = RuleEdit::DeleteAssignment { assignment_id };
+    repo.with_plan_revision(PlanItem::Assignment(assignment_id), expected_revision, async |tx, plan_id| compute_apply_rule_edit(tx, plan_id, edit, false).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_assignment_skills<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(assignment_id): Path<i64>,
+    Json(body): Json<SkillsBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Assignment(assignment_id), expected_revision, async |tx| tx.rule.set_assignment_skills(assignment_id, &body.skills).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn preview_rule_edit<R: RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<RuleEditPreviewBody>,
+) -> ApiResult<Json<RuleEditImpactDto>> {
+    Ok(Json(compute_rule_edit_impact(&repo, plan_id, &body.edit, &body.from_date).await?))
+}
+
+/// 足した穴のIDを返す (それ以外は null)
+async fn apply_rule_edit<R: PlanRepository + RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<RuleEditBody>,
+) -> ApiResult<Json<Option<i64>>>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, result) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| compute_apply_rule_edit(tx, plan_id, body.edit, body.allow_locked).await).await?;
+    Ok(Json(result))
+}
+
+async fn list_rule_patterns<R: RuleRepository, C>(
+    State(repo): Services<R, C>,
+    Path(plan_id): Path<PlanId>,
+) -> ApiResult<Json<Vec<RulePattern>>> {
+    Ok(Json(repo.rule.list_rule_patterns(plan_id).await?))
+}
+
+async fn add_rule_pattern<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<RulePatternBody>,
+) -> ApiResult<impl IntoResponse>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.add_rule_pattern(plan_id, &body.name, &body.kind).await).await?;
+    Ok(created(id))
+}
+
+async fn delete_rule_pattern<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(pattern_id): Path<i64>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Pattern(pattern_id), expected_revision, async |tx| tx.rule.delete_rule_pattern(pattern_id).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// ボディは `{"absWeek": 2900, "weekday": "Friday", "shiftTime": "Morning", "memberId": 3, "action": "add"}`
+/// 一覧は get_plan_config の `overrides`
+async fn add_shift_override<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<OverrideEntry>,
+) -> ApiResult<impl IntoResponse>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.rule.add_shift_override(plan_id, &body).await).await?;
+    Ok(created(id))
+}
+
+async fn delete_shift_override<R: PlanRepository + RuleRepository, C>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(override_id): Path<i64>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Override(override_id), expected_revision, async |tx| tx.rule.delete_shift_override(override_id).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// --- Calendar ---
+
+/// カレンダー未作成のときは `null`
+async fn get_calendar_state<R, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    Path(plan_id): Path<PlanId>,
+) -> ApiResult<Json<Option<PlanCalendar>>> {
+    Ok(Json(repo.calendar.find_by_plan_id(plan_id).await?))
+}
+
+async fn create_calendar<R: PlanRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<CalendarBody>,
+) -> ApiResult<impl IntoResponse>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, id) = repo
+        .with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| {
+            tx.calendar.create_calendar(plan_id, body.base_abs_week, body.initial_delta).await
+        })
+        .await?;
+    Ok(created(id))
+}
+
+async fn append_timeline<R: PlanRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<TimelineBody>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| {
+        tx.calendar.try_to_append_timeline(plan_id, body.start_abs_week, body.statuses).await
+    })
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn truncate_timeline<R: PlanRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Query(query): Query<TruncateQuery>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.calendar.truncate_timeline(plan_id, query.from).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 最初に追加した絶対週を返す
+async fn extend_timeline_by_pattern<R: PlanRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<PatternExtendBody>,
+) -> ApiResult<Json<AbsWeek>>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, result) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.calendar.extend_timeline_by_pattern(plan_id, body.pattern_id, body.weeks).await).await?;
+    Ok(Json(result))
+}
+
+async fn get_pattern_weeks<R, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    Path(plan_id): Path<PlanId>,
+) -> ApiResult<Json<Vec<PatternWeek>>> {
+    Ok(Json(repo.calendar.find_pattern_weeks(plan_id).await?))
+}
+
+async fn list_skip_rules<R, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    Path(plan_id): Path<PlanId>,
+) -> ApiResult<Json<Vec<SkipRule>>> {
+    Ok(Json(repo.calendar.list_skip_rules(plan_id).await?))
+}
+
+/// ボディは `{"kind": "annualClosure", "from": "12-29", "to": "01-03"}` など
+async fn add_skip_rule<R: PlanRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Json(body): Json<SkipRuleKind>,
+) -> ApiResult<impl IntoResponse>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, id) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| tx.calendar.add_skip_rule(plan_id, &body).await).await?;
+    Ok(created(id))
+}
+
+async fn update_skip_rule<R: PlanRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(skip_rule_id): Path<i64>,
+    Json(body): Json<SkipRuleKind>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::SkipRule(skip_rule_id), expected_revision, async |tx| tx.calendar.update_skip_rule(skip_rule_id, &body).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_skip_rule<R: PlanRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(skip_rule_id): Path<i64>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::SkipRule(skip_rule_id), expected_revision, async |tx| tx.calendar.delete_skip_rule(skip_rule_id).await).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 固定した絶対週を返す
+async fn lock_month<R: PlanRepository + RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Query(query): Query<MonthQuery>,
+) -> ApiResult<Json<Vec<AbsWeek>>>
+where
+    AppServices<R, C>: Transactional,
+{
+    let (_, result) = repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| compute_lock_month(tx, plan_id, query.year, query.month).await).await?;
+    Ok(Json(result))
+}
+
+async fn unlock_month<R: PlanRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    IfMatch(expected_revision): IfMatch,
+    Path(plan_id): Path<PlanId>,
+    Query(query): Query<MonthQuery>,
+) -> ApiResult<StatusCode>
+where
+    AppServices<R, C>: Transactional,
+{
+    repo.with_revision(PlanItem::Plan(plan_id), expected_revision, async |tx| {
+        compute_unlock_month(tx, plan_id, query.year, query.month).await
+    })
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn derive_monthly_shift<R: RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    Path(plan_id): Path<PlanId>,
+    Query(query): Query<MonthQuery>,
+) -> ApiResult<Json<MonthlyShiftResult>> {
+    Ok(Json(compute_monthly_shift(&repo, plan_id, query.year, query.month).await?))
+}
+
+async fn get_staff_schedule<R: RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    Path((plan_id, member_id)): Path<(PlanId, i64)>,
+    Query(query): Query<DateRangeQuery>,
+) -> ApiResult<Json<Vec<StaffShiftDto>>> {
+    Ok(Json(compute_staff_schedule(&repo, plan_id, member_id, &query.from, &query.to).await?))
+}
+
+async fn list_holidays(Query(query): Query<DateRangeQuery>) -> ApiResult<Json<Vec<HolidayDto>>> {
+    Ok(Json(compute_holidays(&query.from, &query.to)?))
+}
+
+// --- Team (プランをまたいだ表示) ---
+async fn get_team_schedule<R: PlanRepository + RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    Query(query): Query<TeamQuery>,
+) -> ApiResult<Json<Vec<TeamShiftDto>>> {
+    let plan_ids = query.plans
+        .as_deref()
+        .map(|plans| plans
+            .split(',')
+            .map(|id| id.trim().parse::<PlanId>().map_err(|_| format!("plans はプランIDのカンマ区切りで指定してください: {}", plans)))
+            .collect::<Result<Vec<_>, _>>())
+        .transpose()?;
+    Ok(Json(compute_team_schedule(&repo, plan_ids.as_deref(), &query.from, &query.to).await?))
+}
+
+async fn get_cross_plan_conflicts<R: PlanRepository + RuleRepository, C: CalendarRepository>(
+    State(repo): Services<R, C>,
+    Query(query): Query<DateRangeQuery>,
+) -> ApiResult<Json<Vec<CrossPlanConflictDto>>> {
+    Ok(Json(compute_cross_plan_conflicts(&repo, &query.from, &query.to).await?))
+}
@@ -1,4 +1,9 @@
 pub mod commands;
 pub mod dto;
+pub mod events;
+#[cfg(feature = "http")]
+pub mod http_api;
+pub mod revision;
+pub mod schedule_print;
 pub mod time;
 
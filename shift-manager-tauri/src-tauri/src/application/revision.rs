@@ -0,0 +1,56 @@
+//! プランの revision を使った書き換え (楽観的排他)
+//!
+//! プランの中身を書き換えるコマンド・HTTP API は `AppServices::with_revision` を通す。
+//! claim_revision と書き換えを1つのトランザクションで行うので、書き換えに失敗したら revision も進まない
+//! (読み込み直さなくても、同じ revision のままやり直せる)
+
+use std::future::Future;
+use std::ops::AsyncFnOnce;
+
+use crate::domain::repository::PlanRepository;
+use crate::domain::rule_model::PlanItem;
+use crate::domain::shift_calendar_model::PlanId;
+use crate::AppServices;
+
+/// 書き換えを1つのトランザクションにまとめられる構成
+pub trait Transactional: Sized + Send + Sync {
+    /// トランザクションを始め、その中で読み書きする構成を返す
+    /// 返した構成での書き換えは commit するまで他から見えず、commit しないで捨てると取り消される
+    fn begin(&self) -> impl Future<Output = Result<Self, String>> + Send;
+
+    /// begin で返した構成の書き換えを確定する
+    fn commit(self) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+impl<R, C> AppServices<R, C>
+where
+    R: PlanRepository,
+    Self: Transactional,
+{
+    /// `item` の入っているプランの revision を確かめて進め、`write` までを1つのトランザクションで行う
+    /// `write` には同じトランザクションの中の構成を渡す (変更の通知は、返ってきてから元の構成で流す)
+    /// 競合・`write` の失敗のどちらでも何も残らない。戻り値は (プランのID, `write` の結果)
+    pub async fn with_revision<T>(
+        &self,
+        item: PlanItem,
+        expected_revision: Option<i64>,
+        write: impl AsyncFnOnce(&Self) -> Result<T, String>,
+    ) -> Result<(PlanId, T), String> {
+        self.with_plan_revision(item, expected_revision, async |tx, _| write(tx).await).await
+    }
+
+    /// `with_revision` の、`write` に `item` の入っているプランのIDも渡す版
+    /// (プランのIDを受け取る処理を、ルール・穴のIDだけで呼ぶとき)
+    pub async fn with_plan_revision<T>(
+        &self,
+        item: PlanItem,
+        expected_revision: Option<i64>,
+        write: impl AsyncFnOnce(&Self, PlanId) -> Result<T, String>,
+    ) -> Result<(PlanId, T), String> {
+        let tx = self.begin().await?;
+        let plan_id = tx.rule.claim_revision(item, expected_revision).await?;
+        let value = write(&tx, plan_id).await?;
+        tx.commit().await?;
+        Ok((plan_id, value))
+    }
+}
@@ -0,0 +1,209 @@
+//! 壁に貼る用の月のシフト表 (HTML)
+//!
+//! CSS を埋め込んだ1ファイルで完結し、ネットワークを使わない。
+//!
+//! PDF は書き出さない (日本語フォントを埋め込まないと、フォントを持たないビューアや
+//! プリンタで名前が出ないため)。PDF が要るときはブラウザの印刷から PDF に保存する
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use chrono::{Datelike, NaiveDate};
+
+/// グループの色 (デスクトップ版の画面の GROUP_COLORS と同じ並び)
+pub const GROUP_COLORS: [&str; 8] = [
+    "#e67e22", "#27ae60", "#2980b9", "#8e44ad", "#c0392b", "#16a085", "#d35400", "#2c3e50",
+];
+
+const WEEKDAY_LABELS: [&str; 7] = ["月", "火", "水", "木", "金", "土", "日"];
+
+/// グループの位置 (プランの中の並び) に対応する色
+pub fn group_color(group_index: usize) -> &'static str {
+    GROUP_COLORS[group_index % GROUP_COLORS.len()]
+}
+
+/// 出力先の拡張子が .html / .htm (または拡張子なし) かを確かめる
+pub fn check_output_path(path: &Path) -> Result<(), String> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "" | "html" | "htm" => Ok(()),
+        "pdf" => Err(format!(
+            "PDF には書き出せません。HTML に書き出し、ブラウザの印刷から PDF に保存してください: {}",
+            path.display()
+        )),
+        _ => Err(format!("出力先の拡張子は .html にしてください: {}", path.display())),
+    }
+}
+
+/// 表に載せるグループ (凡例)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintGroup {
+    pub name: String,
+    pub color: &'static str,
+}
+
+/// コマに入っている1人
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintEntry {
+    pub name: String,
+    pub group_index: usize, // PrintableMonth::groups の位置ではなく、プランの中のグループの位置
+}
+
+/// 1日分のマス
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintDay {
+    pub date: NaiveDate,
+    pub in_month: bool, // 前後の月の日は日付だけ薄く出す
+    pub holiday: Option<String>,
+    pub morning: Vec<PrintEntry>,
+    pub afternoon: Vec<PrintEntry>,
+}
+
+/// 1週分の行 (月〜日の7日)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintWeek {
+    pub active: bool, // false = スキップした週・未生成の週 (日付だけ出す)
+    pub days: Vec<PrintDay>,
+}
+
+/// 印刷する1か月分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintableMonth {
+    pub plan_name: String,
+    pub year: i32,
+    pub month: u32, // 1-12
+    /// 1グループだけの表ならそのグループ名
+    pub group_name: Option<String>,
+    pub groups: Vec<(usize, PrintGroup)>, // (プランの中のグループの位置, グループ)
+    pub weeks: Vec<PrintWeek>,
+}
+
+impl PrintableMonth {
+    pub fn title(&self) -> String {
+        match &self.group_name {
+            Some(group) => format!("{} {}年{}月 ({})", self.plan_name, self.year, self.month, group),
+            None => format!("{} {}年{}月", self.plan_name, self.year, self.month),
+        }
+    }
+}
+
+// ====================================================================
+// HTML
+// ====================================================================
+
+const HTML_STYLE: &str = "\
+@page { size: A4 landscape; margin: 10mm; }
+body { font-family: sans-serif; margin: 0; color: #333; }
+h1 { font-size: 18px; margin: 0 0 4px; }
+.legend { margin-bottom: 6px; font-size: 12px; }
+.legend span { display: inline-block; color: white; padding: 1px 8px; border-radius: 4px; margin-right: 6px; }
+table { width: 100%; border-collapse: collapse; table-layout: fixed; }
+th { font-size: 12px; background: #f4f4f4; border: 1px solid #999; }
+th.sat { color: #2980b9; }
+th.sun { color: #c0392b; }
+td { border: 1px solid #999; vertical-align: top; padding: 2px 4px; font-size: 11px; height: 90px; }
+td.outside { background: #f4f4f4; color: #bbb; }
+td.inactive { background: #fafafa; }
+.date { font-weight: bold; }
+.sun .date, .holiday-day .date, .holiday { color: #c0392b; }
+.sat .date { color: #2980b9; }
+.holiday { font-size: 10px; margin-left: 4px; font-weight: normal; }
+.slot { margin-top: 2px; }
+.time { font-size: 10px; font-weight: bold; margin-right: 2px; }
+.am { color: #e67e22; }
+.pm { color: #2980b9; }
+.staff { display: inline-block; border-left: 4px solid; padding-left: 3px; margin: 1px 4px 1px 0; }
+";
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn html_slot(out: &mut String, class: &str, label: &str, entries: &[PrintEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    let _ = write!(out, "<div class=\"slot\"><span class=\"time {}\">{}</span>", class, label);
+    for entry in entries {
+        let _ = write!(
+            out,
+            "<span class=\"staff\" style=\"border-color:{}\">{}</span>",
+            group_color(entry.group_index),
+            escape_html(&entry.name),
+        );
+    }
+    out.push_str("</div>");
+}
+
+/// CSS を埋め込んだ1ファイルの HTML (ブラウザで開いてそのまま印刷できる)
+pub fn render_html(month: &PrintableMonth) -> String {
+    let title = escape_html(&month.title());
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, HTML_STYLE, title,
+    );
+
+    out.push_str("<div class=\"legend\">");
+    for (_, group) in &month.groups {
+        let _ = write!(out, "<span style=\"background:{}\">{}</span>", group.color, escape_html(&group.name));
+    }
+    out.push_str("</div>\n<table>\n<thead><tr>");
+    for (weekday, label) in WEEKDAY_LABELS.iter().enumerate() {
+        let class = match weekday {
+            5 => " class=\"sat\"",
+            6 => " class=\"sun\"",
+            _ => "",
+        };
+        let _ = write!(out, "<th{}>{}</th>", class, label);
+    }
+    out.push_str("</tr></thead>\n<tbody>\n");
+
+    for week in &month.weeks {
+        out.push_str("<tr>");
+        for (weekday, day) in week.days.iter().enumerate() {
+            let mut classes = vec![];
+            match weekday {
+                5 => classes.push("sat"),
+                6 => classes.push("sun"),
+                _ => {}
+            }
+            if !day.in_month {
+                classes.push("outside");
+            } else if !week.active {
+                classes.push("inactive");
+            }
+            if day.holiday.is_some() {
+                classes.push("holiday-day");
+            }
+            let _ = write!(out, "<td class=\"{}\"><div class=\"date\">{}", classes.join(" "), day.date.day());
+            if let Some(holiday) = &day.holiday {
+                let _ = write!(out, "<span class=\"holiday\">{}</span>", escape_html(holiday));
+            }
+            out.push_str("</div>");
+            if day.in_month {
+                html_slot(&mut out, "am", "午前", &day.morning);
+                html_slot(&mut out, "pm", "午後", &day.afternoon);
+            }
+            out.push_str("</td>");
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    out
+}
@@ -1,4 +1,6 @@
 use chrono::{Datelike, Duration, NaiveDate};
+use shift_timeline::{AbsDay, abs_day};
+
 use crate::domain::shift_calendar_model::AbsWeek;
 
 /// 指定された年・月が、カレンダー上で何週（何行）になるかを計算する
@@ -73,3 +75,16 @@ pub fn calculate_abs_week(year: i32, month: u32, day: u32) -> Option<AbsWeek>  {
         None
     }
 }
+
+/// 日付から AbsDay (絶対週 * 7 + 曜日) を求める (1969/12/29 より前は None)
+pub fn abs_day_of_date(date: NaiveDate) -> Option<AbsDay> {
+    calculate_abs_week(date.year(), date.month0(), date.day())
+        .map(|abs_week| abs_day(abs_week, date.weekday().num_days_from_monday() as usize))
+}
+
+/// calculate_abs_week の逆: 絶対週と曜日 (0=月 〜 6=日) から日付を求める
+pub fn date_of_abs_week(abs_week: AbsWeek, weekday: usize) -> NaiveDate {
+    let week_base = NaiveDate::from_ymd_opt(1969, 12, 29)
+        .unwrap() /* safe unwrap */;
+    week_base + Duration::days((abs_week * 7 + weekday) as i64)
+}
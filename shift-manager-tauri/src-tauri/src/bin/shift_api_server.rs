@@ -0,0 +1,51 @@
+//! スケジューラーをローカルの REST/JSON サービスとして起動する
+//!
+//! ```sh
+//! cargo run --bin shift_api_server -- --db ./app.db --addr 127.0.0.1:8080
+//! ```
+//!
+//! エンドポイントの一覧は `application::http_api` を参照
+
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use shift_manager_tauri_lib::{application::http_api, load_holiday_table, open_database, AppServices};
+
+#[derive(Parser)]
+#[command(name = "shift_api_server")]
+#[command(about = "シフト管理のバックエンドを REST/JSON API として公開します", long_about = None)]
+struct Cli {
+    /// SQLiteファイル (デスクトップ版の app.db をそのまま指定できる)
+    #[arg(long, default_value = "app.db")]
+    db: PathBuf,
+
+    /// 待ち受けアドレス
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    addr: String,
+
+    /// 祝日の表 (CSV)。指定しなければ同梱の表を使う
+    #[arg(long)]
+    holidays: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), String> {
+    let args = Cli::parse();
+
+    let pool = open_database(&args.db).await?;
+    println!("Using DB at: {}", args.db.display());
+    if let Some(path) = &args.holidays {
+        load_holiday_table(path)?;
+        println!("Using holidays at: {}", path.display());
+    }
+
+    let listener = tokio::net::TcpListener::bind(&args.addr)
+        .await
+        .map_err(|e| format!("{} で待ち受けできません: {}", args.addr, e))?;
+    println!("Listening on http://{}", args.addr);
+
+    axum::serve(listener, http_api::router(AppServices::new(pool)))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -1,130 +1,253 @@
+use crate::domain::holiday::holiday_table;
+use crate::domain::rule_model::{RuleDeletion, RulePatternKind};
 use crate::domain::shift_calendar_model::{
-    AbsWeek, 
-    ShiftCalendarManager, 
+    AbsWeek,
+    AppendWeekErrorKind,
+    ShiftCalendarManager,
     WeekStatus,
-    LogicalDelta,
     RuleId,
+    RuleRef,
+    SkipRule,
+    SkipRuleKind,
 };
 
 use shift_calendar::shift_gen::{
-    gen_one_week_shift,
     WeekRuleTable,
     WeekDecidedShift,
     StaffGroupList,
     Incomplete
 };
 
-// TODO このエラーを本当にここに置くべきか
-#[derive(Debug)]
-pub enum AppendWeekErrorKind {
-    /// 予定の上書きエラー
-    AttemptedToOverwrite,
-    /// 連続しない予定エラー
-    NotConsecutiveShifts,
-    /// 加減突破
-    UnderFlow,
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::HashMap;
+
+/// 指定された期間のシフトのみを計算する純粋関数
+/// (計算本体は shift_timeline と共通)
+///
+/// - `timeline_slice`: 計算対象の週のステータス（例: 4週間分だけ）
+/// - `rule_map`: rule_id から 実際のWeekRule へのマップ (必要な分だけ)
+/// - `staff_groups`: スタッフリスト (これはサイズが小さいので全件でもOKだが、最適化も可能)
+pub fn calculate_partial_shift<'a>(
+    timeline_slice: &[WeekStatus],
+    rule_map: &HashMap<RuleId, WeekRuleTable<'a, Incomplete>>, // ID -> Rule
+    staff_group_list: &'a StaffGroupList,
+) -> Vec<Option<WeekDecidedShift<'a>>> {
+    shift_timeline::calculate_partial_shift(
+        timeline_slice,
+        |rule: &RuleRef| rule_map.get(&rule.rule_id),
+        staff_group_list
+    )
+}
+
+/// `calculate_partial_shift` の、週ごとに別のスタッフリストを使う版
+/// (在籍期間があり、週によって在籍しているメンバーが違う場合)
+///
+/// - `staff_group_list_of`: `timeline_slice` の中の位置 (0始まり) から、その週のスタッフリストを引く関数
+pub fn calculate_partial_shift_by_week<'a>(
+    timeline_slice: &[WeekStatus],
+    rule_map: &HashMap<RuleId, WeekRuleTable<'a, Incomplete>>,
+    staff_group_list_of: impl Fn(usize) -> &'a StaffGroupList,
+) -> Vec<Option<WeekDecidedShift<'a>>> {
+    shift_timeline::calculate_partial_shift_by_week(
+        timeline_slice,
+        |rule: &RuleRef| rule_map.get(&rule.rule_id),
+        staff_group_list_of,
+    )
+}
+
+/// 復元済みのタイムラインに週を追記する (SQLite実装・インメモリ実装で共通)
+/// 重なり部分のチェックとlogical_deltaの採番は ShiftCalendarManager::apply_weeks に任せる
+///
+/// 戻り値は新しく追加された週が始まる index (= 追記前の timeline の長さ)
+pub fn append_rule_weeks(
+    calendar: &mut ShiftCalendarManager,
+    start_abs_week: AbsWeek,
+    statuses: Vec<Option<RuleId>>,
+) -> Result<usize, String> {
+    let current_len = calendar.timeline.len();
+
+    let weeks: Vec<Option<RuleRef>> = statuses
+        .into_iter()
+        .map(|status| status.map(|rule_id| RuleRef { rule_id }))
+        .collect();
+
+    calendar
+        .apply_weeks(start_abs_week, &weeks)
+        .map_err(|e| match e {
+            // 【仕様】 歯抜けエラー
+            // カレンダー作成直後の場合、start_abs_week は必ず base_abs_week でなければエラーになる
+            AppendWeekErrorKind::NotConsecutiveShifts => format!(
+                "タイムラインに空きがあります。絶対週 {} からデータを連続させてください。",
+                calendar.end_abs_week()
+            ),
+            other => other.to_string(),
+        })?;
+
+    Ok(current_len)
 }
 
-impl ShiftCalendarManager {
-
-    fn abs_to_index(
-        &self, 
-        abs_week: AbsWeek
-    ) -> Result<usize, AppendWeekErrorKind> {
-        // self.delta_to_index(self.abs_to_delta(abs_week)?)
-        if abs_week < self.base_abs_week {
-            Err(AppendWeekErrorKind::UnderFlow)
-        } else {
-            Ok(abs_week - self.base_abs_week)
+/// ルールを消す前に、そのルールを使っている週を `mode` に従って書き換える (SQLite実装・インメモリ実装で共通)
+/// Refuse で使っている週がある場合はエラーで、何も変えない
+///
+/// 戻り値は書き換えた週 (絶対週の順)
+pub fn release_rule(
+    calendar: &mut ShiftCalendarManager,
+    rule_id: RuleId,
+    mode: &RuleDeletion,
+) -> Result<Vec<AbsWeek>, String> {
+    let uses_rule = |payload: &RuleRef| payload.rule_id == rule_id;
+    match mode {
+        RuleDeletion::Refuse => {
+            let used: Vec<String> = calendar.timeline
+                .iter()
+                .enumerate()
+                .filter(|(_, status)| matches!(status, WeekStatus::Active { payload, .. } if uses_rule(payload)))
+                .map(|(index, _)| (calendar.base_abs_week + index).to_string())
+                .collect();
+            if used.is_empty() {
+                Ok(vec![])
+            } else {
+                Err(format!("ルール {} はタイムラインの絶対週 {} で使われています", rule_id, used.join(", ")))
+            }
+        }
+        RuleDeletion::Reassign { to_rule_id } => {
+            if *to_rule_id == rule_id {
+                return Err(String::from("付け替え先に消すルールは指定できません"));
+            }
+            Ok(calendar.reassign_weeks(uses_rule, RuleRef { rule_id: *to_rule_id }))
         }
+        RuleDeletion::Skip => Ok(calendar.skip_weeks(uses_rule)),
     }
+}
+
+
+/// 絶対週の月曜 (絶対週 0 の月曜は 1969/12/29)
+fn monday_of(abs_week: AbsWeek) -> NaiveDate {
+    let week_base = NaiveDate::from_ymd_opt(1969, 12, 29)
+        .unwrap() /* safe unwrap */;
+    week_base + Duration::weeks(abs_week as i64)
+}
 
-    /// 直近の有効なDeltaを探すヘルパー
-    fn find_last_active_delta(&self) -> Option<LogicalDelta> {
-        self.timeline.iter().rev().find_map(|slot| match slot {
-            WeekStatus::Active {
-                logical_delta, rule_id:_
-            } => Some(*logical_delta),
-            WeekStatus::Skipped => None,
+/// 絶対週の ISO週番号
+fn iso_week_number(abs_week: AbsWeek) -> u32 {
+    monday_of(abs_week).iso_week().week()
+}
+
+/// パターンで `start_abs_week` から `count` 週分に使うルール (`None` = Skipped)
+/// そのまま `append_rule_weeks` に渡せる
+pub fn pattern_statuses(kind: &RulePatternKind, start_abs_week: AbsWeek, count: usize) -> Vec<Option<RuleId>> {
+    (start_abs_week..start_abs_week + count)
+        .map(|abs_week| match kind {
+            RulePatternKind::Sequence { anchor_abs_week, steps } => {
+                let cycle: usize = steps.iter().map(|step| step.weeks).sum();
+                if cycle == 0 {
+                    return None;
+                }
+                // anchor より前の週も、さかのぼって繰り返した位置にする
+                let mut position = (abs_week as i64 - *anchor_abs_week as i64).rem_euclid(cycle as i64) as usize;
+                steps.iter().find_map(|step| {
+                    if position < step.weeks {
+                        Some(step.rule_id)
+                    } else {
+                        position -= step.weeks;
+                        None
+                    }
+                })?
+            }
+            RulePatternKind::IsoWeekParity { odd, even } => {
+                if iso_week_number(abs_week) % 2 == 1 { *odd } else { *even }
+            }
         })
-    }
+        .collect()
+}
 
-    /// 【重要】指定した絶対週以降をすべて削除する（Truncate）
-    /// 配列を短くするだけなので極めて高速かつ安全
-    pub fn truncate_from(&mut self, target_abs_week: AbsWeek) {
-        if target_abs_week < self.base_abs_week {
-            // 開始地点より前を指定されたら全消し
-            self.timeline.clear();
-            // 必要なら start_abs_week 自体を書き換えるロジックも検討
-            return;
-        }
+/// "MM-DD" (2/29 も書ける)
+fn parse_month_day(text: &str) -> Result<(u32, u32), String> {
+    NaiveDate::parse_from_str(&format!("2000-{}", text), "%Y-%m-%d")
+        .map(|date| (date.month(), date.day()))
+        .map_err(|_| format!("月日は MM-DD の形式で指定してください: {}", text))
+}
 
-        let keep_len = (target_abs_week - self.base_abs_week) as usize;
-        if keep_len < self.timeline.len() {
-            self.timeline.truncate(keep_len);
+fn parse_date(text: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| format!("日付は YYYY-MM-DD の形式で指定してください: {}", text))
+}
+
+impl SkipRuleKind {
+    /// 保存する前の検査 (日付の形式と、日付の一覧が空でないこと、祝日の日数が1以上であること)
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            SkipRuleKind::AnnualClosure { from, to } => {
+                parse_month_day(from)?;
+                parse_month_day(to)?;
+                Ok(())
+            }
+            SkipRuleKind::LastWeekOfMonth => Ok(()),
+            SkipRuleKind::Dates { dates } => {
+                if dates.is_empty() {
+                    return Err(String::from("日付を1つ以上指定してください"));
+                }
+                dates.iter().try_for_each(|date| parse_date(date).map(|_| ()))
+            }
+            SkipRuleKind::NationalHolidays { min_days } => {
+                if *min_days == 0 {
+                    return Err(String::from("祝日の日数は1以上にしてください"));
+                }
+                Ok(())
+            }
         }
     }
 
-    /// シフトの導出
-    /// base_abs_weekを下回る場合でも返せる場合があるが
-    /// skipを使うことで週の途中からルールを開始することは可能なので対応しない
-    pub fn derive_shift<'a>(
-        &self,
-        rule_map: &HashMap<RuleId, WeekRuleTable<'a, Incomplete>>, // ID -> Rule
-        staff_group_list: &'a StaffGroupList,
-        gen_week_abs: AbsWeek,  // 生成の始点となる絶対週
-        gen_range: usize,       // 何週間分のシフトを作成するか
-    ) -> Vec<Option<WeekDecidedShift<'a>>>{
-        if let Ok(index) = self.abs_to_index(gen_week_abs) {
-            if index + gen_range < self.timeline.len() {
-                calculate_partial_shift(
-    &self.timeline[
-                        index..index + gen_range
-                    ], rule_map, staff_group_list
-                )
-            } else if index < self.timeline.len() {
-                calculate_partial_shift(
-                    &self.timeline[
-                    index..
-                ], 
-                rule_map,
-                staff_group_list)
-            } else {
-                Vec::new()
+    /// 絶対週がこのルールでSkippedになるか (形式の正しくない日付は当たらない)
+    pub fn matches(&self, abs_week: AbsWeek) -> bool {
+        let monday = monday_of(abs_week);
+        let days = || (0..7).map(move |offset| monday + Duration::days(offset));
+        match self {
+            SkipRuleKind::AnnualClosure { from, to } => {
+                let (Ok(from), Ok(to)) = (parse_month_day(from), parse_month_day(to)) else {
+                    return false;
+                };
+                days().any(|day| {
+                    let month_day = (day.month(), day.day());
+                    if from <= to {
+                        from <= month_day && month_day <= to
+                    } else {
+                        // 年をまたぐ期間
+                        from <= month_day || month_day <= to
+                    }
+                })
+            }
+            SkipRuleKind::LastWeekOfMonth => (monday + Duration::weeks(1)).month() != monday.month(),
+            SkipRuleKind::Dates { dates } => dates
+                .iter()
+                .filter_map(|date| parse_date(date).ok())
+                .any(|date| monday <= date && date < monday + Duration::weeks(1)),
+            SkipRuleKind::NationalHolidays { min_days } => {
+                days().filter(|day| holiday_table().name_of(*day).is_some()).count() >= *min_days
             }
-        } else {
-            Vec::new()
         }
     }
 }
 
-use std::collections::HashMap;
-
-/// 指定された期間のシフトのみを計算する純粋関数
-///
-/// - `timeline_slice`: 計算対象の週のステータス（例: 4週間分だけ）
-/// - `rule_map`: rule_id から 実際のWeekRule へのマップ (必要な分だけ)
-/// - `staff_groups`: スタッフリスト (これはサイズが小さいので全件でもOKだが、最適化も可能)
-pub fn calculate_partial_shift<'a>(
-    timeline_slice: &[WeekStatus],
-    rule_map: &HashMap<RuleId, WeekRuleTable<'a, Incomplete>>, // ID -> Rule
-    staff_group_list: &'a StaffGroupList,
-) -> Vec<Option<WeekDecidedShift<'a>>> {
-    timeline_slice
-        .iter().map(|i|{
-            if let WeekStatus::Active { logical_delta , rule_id} = i {
-                rule_map
-                    .get(rule_id)
-                    .map(|week_rule_table| 
-                        gen_one_week_shift(
-                            week_rule_table, 
-                            staff_group_list,
-                            *logical_delta
-                        )
-                    )
-            } else {
+/// 追記する週のうち、タイムラインにまだ無い週に休業ルールを当てる (当たった週は `None` = Skipped)
+/// すでにある週と重なる部分は `append_rule_weeks` で食い違いを検査するので、そのまま残す
+pub fn apply_skip_rules(
+    calendar: &ShiftCalendarManager,
+    start_abs_week: AbsWeek,
+    statuses: Vec<Option<RuleId>>,
+    skip_rules: &[SkipRule],
+) -> Vec<Option<RuleId>> {
+    let end_abs_week = calendar.end_abs_week();
+    statuses
+        .into_iter()
+        .enumerate()
+        .map(|(index, status)| {
+            let abs_week = start_abs_week + index;
+            if abs_week >= end_abs_week && skip_rules.iter().any(|rule| rule.kind.matches(abs_week)) {
                 None
+            } else {
+                status
             }
-        }).collect()
+        })
+        .collect()
 }
-
@@ -0,0 +1,80 @@
+//! 日本の祝日・休日 (振替休日・国民の休日を含む)
+//!
+//! 同梱の表 (`data/jp_holidays.csv`) を使う。新しい年の祝日は
+//! 同じ形式のファイルを `install_holiday_table` で差し替えて使える (ネットワークは使わない)
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+
+const BUNDLED_CSV: &str = include_str!("../../data/jp_holidays.csv");
+
+static HOLIDAY_TABLE: OnceLock<HolidayTable> = OnceLock::new();
+
+/// 祝日の表 (日付 → 名前)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HolidayTable {
+    holidays: BTreeMap<NaiveDate, String>,
+}
+
+impl HolidayTable {
+    /// `date,name` のCSV ("YYYY-MM-DD,元日")。`#` で始まる行と見出しの行は読み飛ばす
+    pub fn parse_csv(text: &str) -> Result<Self, String> {
+        let mut holidays = BTreeMap::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line == "date,name" {
+                continue;
+            }
+            let (date, name) = line
+                .split_once(',')
+                .ok_or_else(|| format!("祝日の表の {} 行目に「日付,名前」がありません", index + 1))?;
+            let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("祝日の表の {} 行目の日付が正しくありません: {}", index + 1, date))?;
+            holidays.insert(date, name.trim().to_string());
+        }
+        Ok(Self { holidays })
+    }
+
+    /// アプリに同梱した表
+    pub fn bundled() -> Self {
+        Self::parse_csv(BUNDLED_CSV).expect("bundled holiday table must be valid")
+    }
+
+    /// 祝日ならその名前
+    pub fn name_of(&self, date: NaiveDate) -> Option<&str> {
+        self.holidays.get(&date).map(String::as_str)
+    }
+
+    /// `from` から `to` まで (両端を含む) の祝日 (日付順)
+    pub fn between(&self, from: NaiveDate, to: NaiveDate) -> Vec<(NaiveDate, &str)> {
+        if from > to {
+            return vec![];
+        }
+        self.holidays
+            .range(from..=to)
+            .map(|(date, name)| (*date, name.as_str()))
+            .collect()
+    }
+
+    /// 表に入っている最初と最後の年
+    pub fn years(&self) -> Option<(i32, i32)> {
+        use chrono::Datelike;
+        let first = self.holidays.keys().next()?;
+        let last = self.holidays.keys().next_back()?;
+        Some((first.year(), last.year()))
+    }
+}
+
+/// 使う祝日の表 (差し替えていなければ同梱の表)
+pub fn holiday_table() -> &'static HolidayTable {
+    HOLIDAY_TABLE.get_or_init(HolidayTable::bundled)
+}
+
+/// 祝日の表を差し替える (起動時に、祝日の表を使う前に1回だけ)
+pub fn install_holiday_table(table: HolidayTable) -> Result<(), String> {
+    HOLIDAY_TABLE
+        .set(table)
+        .map_err(|_| String::from("祝日の表はすでに読み込まれています"))
+}
@@ -2,6 +2,10 @@
 pub mod rule_model;
 pub mod shift_calendar_model;
 
+// ports
+pub mod repository;
+
 // logic
 pub mod calendar_logic;
+pub mod holiday;
 pub mod rule_logic;
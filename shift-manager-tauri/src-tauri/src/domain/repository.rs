@@ -0,0 +1,291 @@
+//! 永続化層に依存しないリポジトリの定義
+//!
+//! - `infrastructure::rule_repo` / `infrastructure::calendar_repo`: SQLite実装
+//! - `infrastructure::memory_repo`: インメモリ実装 (マイグレーション不要のテスト用)
+//!
+//! シグネチャにはsqlxの型を出さないので、WASM側のJSON状態などでも実装できる
+//! tauriのコマンドから呼べるように、戻り値のFutureはSendを要求する
+
+use std::future::Future;
+
+use shift_timeline::Rotation;
+
+use crate::domain::{
+    rule_model::{
+        MemberConstraints, OverrideEntry, Person, Plan, PlanConfig, PlanItem, RuleDeletion, RulePattern, RulePatternKind, WeeklyRule,
+    },
+    shift_calendar_model::{
+        AbsWeek, LockedWeek, LogicalDelta, PatternWeek, PlanCalendar, PlanId, RuleId, SkipRule, SkipRuleKind, WeekStatus,
+    },
+};
+
+// =================================================================
+// 1. Plan
+// =================================================================
+
+/// 楽観的排他で断ったときのエラーの書き出し (HTTP API はこれで始まるエラーを 409 Conflict にする)
+pub const REVISION_CONFLICT: &str = "プランが他の画面で変更されています";
+
+/// 指したもの (プラン・カレンダー・メンバーなど) が無いときのエラーの書き出し (HTTP API は 404 Not Found にする)
+pub const NOT_FOUND: &str = "見つかりません";
+
+/// DBの読み書きそのものが失敗したときのエラーの書き出し (HTTP API は 500 Internal Server Error にする)
+/// 制約違反 (外部キーなど) は入力の誤りなので付けない
+pub const DATABASE_ERROR: &str = "DBのエラー";
+
+/// `what` が無いときのエラー
+pub fn not_found(what: impl std::fmt::Display) -> String {
+    format!("{}: {}", NOT_FOUND, what)
+}
+
+/// `claim_revision` で、読み込んだときの revision が今と違ったときのエラー
+pub fn revision_conflict(plan_id: PlanId, expected: i64, current: i64) -> String {
+    format!(
+        "{} (Plan ID: {}, 読み込んだときの revision: {}, 今の revision: {})。読み込み直してください",
+        REVISION_CONFLICT, plan_id, expected, current
+    )
+}
+
+pub trait PlanRepository: Send + Sync {
+    fn create_plan(&self, name: &str) -> impl Future<Output = Result<PlanId, String>> + Send;
+
+    fn list_plans(&self) -> impl Future<Output = Result<Vec<Plan>, String>> + Send;
+
+    /// 子要素（グループ・ルール・カレンダー）もまとめて削除される
+    fn delete_plan(&self, plan_id: PlanId) -> impl Future<Output = Result<(), String>> + Send;
+
+    fn update_plan_name(&self, plan_id: PlanId, name: &str) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// 週の出勤日数の上限 (`None` で解除)
+    fn update_plan_max_days(&self, plan_id: PlanId, max_days_per_week: Option<u32>) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// スキルを持っていない人を、同じグループの中で入れ替えるかどうか
+    fn update_plan_auto_swap(&self, plan_id: PlanId, auto_swap_skills: bool) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// `item` の入っているプランの revision を1つ進めて、プランのIDを返す (書き換える前に呼ぶ)
+    /// `expected_revision` が今の revision と違えば進めずに `revision_conflict` のエラー (`None` なら確かめない)
+    /// 確かめるのと進めるのは分けずに行うので、同じ revision を元にした書き換えは1つしか通らない
+    /// `item` が無い場合はエラー
+    fn claim_revision(
+        &self,
+        item: PlanItem,
+        expected_revision: Option<i64>,
+    ) -> impl Future<Output = Result<PlanId, String>> + Send;
+}
+
+// =================================================================
+// 2. Staff Group / Member / Weekly Rule / Assignment
+// =================================================================
+pub trait RuleRepository: Send + Sync {
+    // --- Group ---
+    fn add_staff_group(&self, plan_id: PlanId, name: &str) -> impl Future<Output = Result<i64, String>> + Send;
+
+    fn delete_staff_group(&self, group_id: i64) -> impl Future<Output = Result<(), String>> + Send;
+
+    fn update_group_name(&self, group_id: i64, name: &str) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// 1コマあたりの最低人数 (`None` で解除)
+    fn update_group_min_staff(&self, group_id: i64, min_per_period: Option<u32>) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// ローテーションの方式 (`None` で shift_gen の並べ方に戻す)
+    fn update_group_rotation(&self, group_id: i64, rotation: Option<Rotation>) -> impl Future<Output = Result<(), String>> + Send;
+
+    // --- Member ---
+    fn add_staff_member(&self, group_id: i64, name: &str) -> impl Future<Output = Result<i64, String>> + Send;
+
+    fn delete_staff_member(&self, member_id: i64) -> impl Future<Output = Result<(), String>> + Send;
+
+    fn update_member_name(&self, member_id: i64, name: &str) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// 在籍期間 ("YYYY-MM-DD", `None` で指定なし)
+    /// 辞めた人は削除せずに `left_on` を入れれば、過去の週のシフトは変わらない
+    fn update_member_period(
+        &self,
+        member_id: i64,
+        joined_on: Option<&str>,
+        left_on: Option<&str>,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// メンバーの制約を丸ごと差し替える (休みの曜日も含む)
+    /// 日付の形式・雇用期間の前後がおかしい場合はエラー
+    fn set_member_constraints(
+        &self,
+        member_id: i64,
+        constraints: &MemberConstraints,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// メンバーの制約をなくす
+    fn clear_member_constraints(&self, member_id: i64) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// メンバーのスキルを丸ごと差し替える (空で全部外す)
+    fn set_member_skills(&self, member_id: i64, skills: &[String]) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// メンバーをプランをまたいだ人にまとめる (`None` で外す)。存在しない人はエラー
+    fn link_member_person(&self, member_id: i64, person_id: Option<i64>) -> impl Future<Output = Result<(), String>> + Send;
+
+    // --- Person (プランをまたいだ同じ人) ---
+    fn add_person(&self, name: &str) -> impl Future<Output = Result<i64, String>> + Send;
+
+    fn update_person_name(&self, person_id: i64, name: &str) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// 人を消す (まとめていたメンバーは残り、まとめだけが外れる)
+    fn delete_person(&self, person_id: i64) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// 追加した順
+    fn list_people(&self) -> impl Future<Output = Result<Vec<Person>, String>> + Send;
+
+    // --- Weekly Rule ---
+    fn add_weekly_rule(&self, plan_id: PlanId, name: &str) -> impl Future<Output = Result<RuleId, String>> + Send;
+
+    fn delete_weekly_rule(&self, rule_id: RuleId) -> impl Future<Output = Result<(), String>> + Send;
+
+    fn update_rule_name(&self, rule_id: RuleId, name: &str) -> impl Future<Output = Result<(), String>> + Send;
+
+    // --- Assignment ---
+    fn add_rule_assignment(
+        &self,
+        rule_id: RuleId,
+        weekday: i64,
+        shift_time: i64,
+        group_id: i64,
+        member_index: i64
+    ) -> impl Future<Output = Result<i64, String>> + Send;
+
+    fn delete_assignment(&self, assignment_id: i64) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// 穴に必要なスキルを丸ごと差し替える (空で誰でもよい)
+    fn set_assignment_skills(&self, assignment_id: i64, skills: &[String]) -> impl Future<Output = Result<(), String>> + Send;
+
+    // --- Rule Pattern ---
+
+    /// ルールの並び方 (パターン) を追加する
+    /// 区切りが無い・0週の区切りがある・プランに無いルールを使っている場合はエラー
+    fn add_rule_pattern(
+        &self,
+        plan_id: PlanId,
+        name: &str,
+        kind: &RulePatternKind,
+    ) -> impl Future<Output = Result<i64, String>> + Send;
+
+    /// パターンを消す (そのパターンで追加した週は残り、どのパターンで追加したかの記録だけが消える)
+    fn delete_rule_pattern(&self, pattern_id: i64) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// プランのパターンを追加した順に取得する
+    fn list_rule_patterns(&self, plan_id: PlanId) -> impl Future<Output = Result<Vec<RulePattern>, String>> + Send;
+
+    // --- Shift Override ---
+
+    /// 週・日単位の調整を追加する (追加した順に当てる。取得は `get_plan_config` の `overrides`)
+    /// プランに無いメンバーを指定した場合はエラー
+    fn add_shift_override(&self, plan_id: PlanId, entry: &OverrideEntry) -> impl Future<Output = Result<i64, String>> + Send;
+
+    fn delete_shift_override(&self, override_id: i64) -> impl Future<Output = Result<(), String>> + Send;
+
+    // --- Fetch ---
+
+    /// 特定のプランに紐づくすべての設定（グループ、メンバー、ルール、アサイン）を取得する
+    fn get_plan_config(&self, plan_id: PlanId) -> impl Future<Output = Result<PlanConfig, String>> + Send;
+
+    /// ルールIDと名前だけのリストをソート順で取得する
+    fn get_rules_sorted(&self, plan_id: PlanId) -> impl Future<Output = Result<Vec<WeeklyRule>, String>> + Send;
+}
+
+// =================================================================
+// 3. Calendar (Timeline)
+// =================================================================
+pub trait CalendarRepository: Send + Sync {
+    /// 新しいシフトカレンダーを作成する
+    /// すでに同じ plan_id のカレンダーが存在する場合はエラーを返す
+    fn create_calendar(
+        &self,
+        plan_id: PlanId,
+        base_abs_week: AbsWeek,
+        initial_delta: LogicalDelta,
+    ) -> impl Future<Output = Result<i64, String>> + Send;
+
+    /// タイムラインに週を追記する (`None` = Skipped)
+    /// 新しく増える週のうち、プランの休業ルールに当たる週はSkippedにする
+    /// 確定済みの週と食い違う場合や歯抜けになる場合はエラー
+    fn try_to_append_timeline(
+        &self,
+        plan_id: PlanId,
+        start_abs_week: AbsWeek,
+        statuses: Vec<Option<RuleId>>,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// 指定した絶対週以降のタイムラインを削除する (WASM版の reset_from_this_month 相当)
+    /// 基準週より前を指定した場合はタイムライン全体を削除する (削除した週の固定も外れる)
+    fn truncate_timeline(
+        &self,
+        plan_id: PlanId,
+        from_abs_week: AbsWeek,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// base_abs_week を付け替える (ShiftCalendarManager::rebase)
+    /// 後ろへ動かすと手前の週を捨てて initial_delta に繰り込み (捨てた週の固定も外れる)、前へ動かすとSkippedで埋める
+    fn rebase_calendar(
+        &self,
+        plan_id: PlanId,
+        new_base_abs_week: AbsWeek,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// 確定 (公開) した週を固定する (ShiftCalendarManager::lock_week)
+    /// 固定済みの週は置き換える。Activeでない週が含まれる場合はエラーで、何も書き換えない
+    fn lock_weeks(
+        &self,
+        plan_id: PlanId,
+        weeks: Vec<LockedWeek>,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// `from_abs_week` から `count` 週分の固定を外す (固定していない週は何もしない)
+    fn unlock_weeks(
+        &self,
+        plan_id: PlanId,
+        from_abs_week: AbsWeek,
+        count: usize,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// プランのルールを消す。タイムラインでそのルールを使っている週は `mode` に従って書き換える
+    /// (週の書き換えとルールの削除は1つのトランザクションで、失敗したときは何も変えない)
+    /// カレンダーが無いプランではルールを消すだけ。書き換えた週 (絶対週の順) を返す
+    fn delete_rule_in_timeline(
+        &self,
+        plan_id: PlanId,
+        rule_id: RuleId,
+        mode: &RuleDeletion,
+    ) -> impl Future<Output = Result<Vec<AbsWeek>, String>> + Send;
+
+    /// タイムラインの末尾から、プランのパターン `pattern_id` で `count` 週を追加する
+    /// 追加した週にはパターンを記録する (`find_pattern_weeks`)。休業ルールに当たる週はSkippedにする
+    /// 追加した最初の絶対週を返す
+    fn extend_timeline_by_pattern(
+        &self,
+        plan_id: PlanId,
+        pattern_id: i64,
+        count: usize,
+    ) -> impl Future<Output = Result<AbsWeek, String>> + Send;
+
+    /// パターンから追加した週 (絶対週の順)
+    fn find_pattern_weeks(&self, plan_id: PlanId) -> impl Future<Output = Result<Vec<PatternWeek>, String>> + Send;
+
+    /// 休業ルールを追加する (すでにタイムラインにある週は変えない)
+    fn add_skip_rule(&self, plan_id: PlanId, kind: &SkipRuleKind) -> impl Future<Output = Result<i64, String>> + Send;
+
+    fn update_skip_rule(&self, skip_rule_id: i64, kind: &SkipRuleKind) -> impl Future<Output = Result<(), String>> + Send;
+
+    fn delete_skip_rule(&self, skip_rule_id: i64) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// 追加した順
+    fn list_skip_rules(&self, plan_id: PlanId) -> impl Future<Output = Result<Vec<SkipRule>, String>> + Send;
+
+    /// 固定した週 (`calendar.locked_weeks`) も含めて返す
+    fn find_by_plan_id(&self, plan_id: PlanId) -> impl Future<Output = Result<Option<PlanCalendar>, String>> + Send;
+
+    /// 指定された範囲（offset start から count 分）のステータスだけを取得
+    fn fetch_status_range(
+        &self,
+        calendar_id: i64,
+        start_offset: i64,
+        count: i64
+    ) -> impl Future<Output = Result<Vec<WeekStatus>, String>> + Send;
+}
@@ -0,0 +1,185 @@
+//! ルール設定まわりの、永続化に依存しない処理
+
+use chrono::NaiveDate;
+
+use shift_timeline::Rotation;
+
+use crate::domain::rule_model::{
+    MemberConstraints, PlanConfig, RuleAssignment, RuleEdit, RulePatternKind, ShiftTime, StaffMember, Weekday,
+};
+
+/// 雇用期間の日付 ("YYYY-MM-DD")
+pub fn parse_employment_date(text: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| format!("日付は YYYY-MM-DD で指定してください: {}", text))
+}
+
+/// staff_groups.rotation / rotation_param の値から方式を組み立てる
+pub fn rotation_from_columns(kind: Option<&str>, param: Option<i64>) -> Result<Option<Rotation>, String> {
+    let Some(kind) = kind else { return Ok(None) };
+    let param = || param.ok_or_else(|| format!("ローテーション {} には値が必要です", kind));
+    let rotation = match kind {
+        "fixed" => Rotation::Fixed,
+        "byWeek" => Rotation::ByWeek,
+        "byDay" => Rotation::ByDay,
+        "everyNWeeks" => Rotation::EveryNWeeks {
+            weeks: usize::try_from(param()?).map_err(|e| e.to_string())?,
+        },
+        // seed は u64 のビット列をそのまま i64 に入れている
+        "shuffle" => Rotation::Shuffle { seed: param()? as u64 },
+        _ => return Err(format!("不明なローテーションです: {}", kind)),
+    };
+    Ok(Some(rotation))
+}
+
+/// rotation_from_columns の逆
+pub fn rotation_to_columns(rotation: Option<&Rotation>) -> (Option<&'static str>, Option<i64>) {
+    match rotation {
+        None => (None, None),
+        Some(Rotation::Fixed) => (Some("fixed"), None),
+        Some(Rotation::ByWeek) => (Some("byWeek"), None),
+        Some(Rotation::ByDay) => (Some("byDay"), None),
+        Some(Rotation::EveryNWeeks { weeks }) => (Some("everyNWeeks"), Some(*weeks as i64)),
+        Some(Rotation::Shuffle { seed }) => (Some("shuffle"), Some(*seed as i64)),
+    }
+}
+
+/// 保存する前の検査 (everyNWeeks は1週以上)
+pub fn validate_rotation(rotation: Option<&Rotation>) -> Result<(), String> {
+    match rotation {
+        Some(Rotation::EveryNWeeks { weeks: 0 }) => Err(String::from("everyNWeeks の週数は1以上にしてください")),
+        _ => Ok(()),
+    }
+}
+
+/// 保存するスキルの並び (前後の空白を除き、空を捨て、重複を除いて名前順)
+pub fn normalize_skills(skills: &[String]) -> Vec<String> {
+    let mut skills: Vec<String> = skills
+        .iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    skills.sort();
+    skills.dedup();
+    skills
+}
+
+/// 期間 ("YYYY-MM-DD", 両端を含む) の検査 (日付の形式と前後)
+/// `label` はエラーメッセージに出す期間の名前
+fn validate_period(label: &str, start: Option<&str>, end: Option<&str>) -> Result<(), String> {
+    let start = start.map(parse_employment_date).transpose()?;
+    let end = end.map(parse_employment_date).transpose()?;
+    if let (Some(start), Some(end)) = (start, end) {
+        if end < start {
+            return Err(format!("{}の終了日 {} が開始日 {} より前です", label, end, start));
+        }
+    }
+    Ok(())
+}
+
+/// 在籍期間の検査 (日付の形式と前後)
+pub fn validate_member_period(joined_on: Option<&str>, left_on: Option<&str>) -> Result<(), String> {
+    validate_period("在籍期間", joined_on, left_on)
+}
+
+/// `monday` の週に在籍しているか (週の月曜が在籍期間に入っている)
+/// validate 済みなので読めない日付は無い前提 (読めなければ指定なし扱い)
+pub fn is_enrolled_on(member: &StaffMember, monday: NaiveDate) -> bool {
+    let date = |text: &Option<String>| text.as_deref().and_then(|t| parse_employment_date(t).ok());
+    date(&member.joined_on).is_none_or(|joined| joined <= monday)
+        && date(&member.left_on).is_none_or(|left| monday <= left)
+}
+
+/// `edit` で変わるルールのID (ルール・穴がプランに無い場合はエラー)
+pub fn edited_rule_id(plan_config: &PlanConfig, edit: &RuleEdit) -> Result<i64, String> {
+    let rule_id = match *edit {
+        RuleEdit::AddAssignment { rule_id, .. } | RuleEdit::DeleteRule { rule_id } => rule_id,
+        RuleEdit::DeleteAssignment { assignment_id } => plan_config.rules
+            .iter()
+            .flat_map(|rule_row| &rule_row.assignments)
+            .find(|assign| assign.id == assignment_id)
+            .map(|assign| assign.weekly_rule_id)
+            .ok_or_else(|| format!("プラン {} に穴 {} はありません", plan_config.plan.id, assignment_id))?,
+    };
+    if !plan_config.rules.iter().any(|rule_row| rule_row.rule.id == rule_id) {
+        return Err(format!("プラン {} にルール {} はありません", plan_config.plan.id, rule_id));
+    }
+    Ok(rule_id)
+}
+
+/// `edit` を保存した後の設定にする (保存はしない)
+/// 足す穴のIDは 0 (保存したときに決まる)
+pub fn apply_rule_edit(plan_config: &mut PlanConfig, edit: &RuleEdit) -> Result<(), String> {
+    let rule_id = edited_rule_id(plan_config, edit)?;
+    match *edit {
+        RuleEdit::AddAssignment { weekday, shift_time, group_id, member_index, .. } => {
+            if !plan_config.groups.iter().any(|group_row| group_row.group.id == group_id) {
+                return Err(format!("プラン {} にグループ {} はありません", plan_config.plan.id, group_id));
+            }
+            let assignment = RuleAssignment {
+                id: 0,
+                weekly_rule_id: rule_id,
+                weekday: Weekday::try_from(weekday)?,
+                shift_time_type: ShiftTime::try_from(shift_time)?,
+                target_group_id: group_id,
+                target_member_index: usize::try_from(member_index).map_err(|e| e.to_string())?,
+                required_skills: vec![],
+            };
+            if let Some(rule_row) = plan_config.rules.iter_mut().find(|rule_row| rule_row.rule.id == rule_id) {
+                rule_row.assignments.push(assignment);
+            }
+        }
+        RuleEdit::DeleteAssignment { assignment_id } => {
+            for rule_row in &mut plan_config.rules {
+                rule_row.assignments.retain(|assign| assign.id != assignment_id);
+            }
+        }
+        RuleEdit::DeleteRule { .. } => plan_config.rules.retain(|rule_row| rule_row.rule.id != rule_id),
+    }
+    Ok(())
+}
+
+impl MemberConstraints {
+    /// 保存する前の検査 (日付の形式と雇用期間の前後)
+    pub fn validate(&self) -> Result<(), String> {
+        validate_period("雇用期間", self.employment_start.as_deref(), self.employment_end.as_deref())
+    }
+}
+
+impl RulePatternKind {
+    /// 保存する前の検査 (区切りが無い・0週の区切りがある場合はエラー)
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            RulePatternKind::Sequence { steps, .. } => {
+                if steps.is_empty() {
+                    return Err(String::from("パターンには1つ以上の区切りが必要です"));
+                }
+                if steps.iter().any(|step| step.weeks == 0) {
+                    return Err(String::from("パターンの区切りは1週以上にしてください"));
+                }
+                Ok(())
+            }
+            RulePatternKind::IsoWeekParity { .. } => Ok(()),
+        }
+    }
+
+    /// パターンが使っているルール
+    pub fn rule_ids(&self) -> Vec<i64> {
+        match self {
+            RulePatternKind::Sequence { steps, .. } => steps.iter().filter_map(|step| step.rule_id).collect(),
+            RulePatternKind::IsoWeekParity { odd, even } => odd.iter().chain(even).copied().collect(),
+        }
+    }
+
+    /// ルールを消したときに、そのルールの部分をSkippedにする (rule_pattern_steps の ON DELETE SET NULL)
+    pub fn forget_rule(&mut self, rule_id: i64) {
+        let forget = |slot: &mut Option<i64>| if *slot == Some(rule_id) { *slot = None };
+        match self {
+            RulePatternKind::Sequence { steps, .. } => steps.iter_mut().for_each(|step| forget(&mut step.rule_id)),
+            RulePatternKind::IsoWeekParity { odd, even } => {
+                forget(odd);
+                forget(even);
+            }
+        }
+    }
+}
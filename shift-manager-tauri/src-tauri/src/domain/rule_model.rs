@@ -1,4 +1,5 @@
-use serde::{Serialize};
+use serde::{Deserialize, Serialize};
+use shift_timeline::{OverrideAction, Rotation};
 //
 // Rules
 //
@@ -6,33 +7,110 @@ use serde::{Serialize};
 use sqlx::{FromRow, prelude::Type};
 
 // --- 1. Plan (設定セット/親) ---
-#[derive(Debug, Serialize, FromRow)]
+#[derive(Debug, Serialize, FromRow, Clone)]
 pub struct Plan {
     pub id: i64,
     pub name: String,
     // created_at はRust側で扱わないなら省略可
+    /// 1人あたりの週の出勤日数の上限 (None = 指定なし)
+    pub max_days_per_week: Option<i64>,
+    /// スキルを持っていない人が入ったら、同じグループの中で入れ替える
+    pub auto_swap_skills: bool,
+    /// 楽観的排他用の番号 (プランの中身を書き換えるたびに1増える)
+    pub revision: i64,
+}
+
+// --- 1a. Plan Item (revision を進めるプランを探す用) ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanItem {
+    Plan(i64),
+    Group(i64),
+    Member(i64),
+    Rule(i64),
+    Assignment(i64),
+    Pattern(i64),
+    SkipRule(i64),
+    Override(i64),
+}
+
+impl std::fmt::Display for PlanItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanItem::Plan(id) => write!(f, "Plan ID: {}", id),
+            PlanItem::Group(id) => write!(f, "Group ID: {}", id),
+            PlanItem::Member(id) => write!(f, "Member ID: {}", id),
+            PlanItem::Rule(id) => write!(f, "Rule ID: {}", id),
+            PlanItem::Assignment(id) => write!(f, "Assignment ID: {}", id),
+            PlanItem::Pattern(id) => write!(f, "Pattern ID: {}", id),
+            PlanItem::SkipRule(id) => write!(f, "Skip rule ID: {}", id),
+            PlanItem::Override(id) => write!(f, "Override ID: {}", id),
+        }
+    }
 }
 
 // --- 2. Staff Group ---
-#[derive(Debug, Serialize, FromRow)]
+#[derive(Debug, Serialize, FromRow, Clone)]
 pub struct StaffGroup {
     pub id: i64,
     pub plan_id: i64,
     pub name: String,
     pub sort_order: i64,
+    /// 1コマあたりの最低人数 (None = 指定なし)
+    pub min_per_period: Option<i64>,
+    /// ローテーションの方式 (rotation / rotation_param 列。None = shift_gen の並べ方)
+    #[sqlx(skip)]
+    pub rotation: Option<Rotation>,
 }
 
 // --- 3. Staff Member ---
-#[derive(Debug, Serialize, FromRow)]
+#[derive(Debug, Serialize, FromRow, Clone)]
 pub struct StaffMember {
     pub id: i64,
     pub group_id: i64,
     pub name: String,
     pub sort_order: i64,
+    // 在籍期間 ("YYYY-MM-DD", 両端を含む。None = 指定なし)
+    // 週の月曜に在籍していない週は、その週のシフトに入らない
+    pub joined_on: Option<String>,
+    pub left_on: Option<String>,
+    /// プランをまたいだ同じ人 (people.id。None = まとめていない)
+    pub person_id: Option<i64>,
+    /// staff_member_constraints に行が無ければ None
+    #[sqlx(skip)]
+    pub constraints: Option<MemberConstraints>,
+    /// 持っているスキル (staff_member_skills, 名前順)
+    #[sqlx(skip)]
+    pub skills: Vec<String>,
+}
+
+// --- 3a. Person ---
+/// プランをまたいだ同じ人
+/// 各プランのメンバー (StaffMember::person_id) をまとめて、プランをまたいだ重なりを調べる
+#[derive(Debug, Serialize, FromRow, Clone, PartialEq)]
+pub struct Person {
+    pub id: i64,
+    pub name: String,
+}
+
+// --- 3b. Member Constraints ---
+/// メンバーごとの制約 (staff_member_constraints / staff_member_days_off)
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct MemberConstraints {
+    // コマ数の上限 (午前・午後をそれぞれ1コマと数える)
+    pub max_shifts_per_week: Option<u32>,
+    pub max_shifts_per_month: Option<u32>,
+    #[serde(default)]
+    pub days_off: Vec<Weekday>,
+    /// 午後に入った翌日の午前には入らない
+    #[serde(default)]
+    pub no_morning_after_afternoon: bool,
+    // 雇用期間 ("YYYY-MM-DD", 両端を含む)
+    pub employment_start: Option<String>,
+    pub employment_end: Option<String>,
 }
 
 // --- 4. Weekly Rule ---
-#[derive(Debug, Serialize, FromRow)]
+#[derive(Debug, Serialize, FromRow, Clone)]
 pub struct WeeklyRule {
     pub id: i64,
     pub plan_id: i64,
@@ -41,7 +119,7 @@ pub struct WeeklyRule {
 }
 
 /// 曜日を表す Enum (DBの 0~6 と自動マッピング)
-#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type)]
 #[repr(i64)] // DBの INTEGER(i64) として扱う指定
 pub enum Weekday {
     Monday = 0,
@@ -53,14 +131,43 @@ pub enum Weekday {
     Sunday = 6,
 }
 
+impl TryFrom<i64> for Weekday {
+    type Error = String;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Weekday::Monday),
+            1 => Ok(Weekday::Tuesday),
+            2 => Ok(Weekday::Wednesday),
+            3 => Ok(Weekday::Thursday),
+            4 => Ok(Weekday::Friday),
+            5 => Ok(Weekday::Saturday),
+            6 => Ok(Weekday::Sunday),
+            other => Err(format!("Invalid weekday: {}", other)),
+        }
+    }
+}
+
 /// シフト時間帯を表す Enum (DBの 0~1 と自動マッピング)
-#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type)]
 #[repr(i64)]
 pub enum ShiftTime {
     Morning = 0,
     Afternoon = 1,
 }
 
+impl TryFrom<i64> for ShiftTime {
+    type Error = String;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ShiftTime::Morning),
+            1 => Ok(ShiftTime::Afternoon),
+            other => Err(format!("Invalid shift time: {}", other)),
+        }
+    }
+}
+
 // --- 5. Rule Assignment (Holl) ---
 // #[derive(Debug, Serialize, FromRow, Clone)]
 // // #[serde(rename_all = "camelCase")] // JS側は camelCase が一般的
@@ -87,6 +194,10 @@ pub struct RuleAssignment {
     // インデックスや順序を表す場合は usize にしておくのがRustの定石
     #[sqlx(try_from = "i64")]
     pub target_member_index: usize, 
+
+    /// この穴に入る人が持っている必要があるスキル (rule_assignment_skills, 名前順)
+    #[sqlx(skip)]
+    pub required_skills: Vec<String>,
 }
 
 // --- 複合データ (フロントエンドに一括で返す用) ---
@@ -97,6 +208,8 @@ pub struct PlanConfig {
     pub plan: Plan,
     pub groups: Vec<StaffGroupWithMembers>,
     pub rules: Vec<WeeklyRuleWithAssignments>,
+    /// 週・日単位の調整 (追加した順に当てる)
+    pub overrides: Vec<PlanOverride>,
 }
 
 #[derive(Debug, Serialize)]
@@ -110,3 +223,89 @@ pub struct WeeklyRuleWithAssignments {
     pub rule: WeeklyRule,
     pub assignments: Vec<RuleAssignment>,
 }
+
+// --- ルールの変更 (保存する前に影響を確認する用) ---
+/// `add_rule_assignment` / `delete_assignment` / `delete_weekly_rule` と同じ変更
+/// 引数は各コマンドと同じ (weekday: 0-6, shift_time: 0-1)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum RuleEdit {
+    AddAssignment {
+        rule_id: i64,
+        weekday: i64,
+        shift_time: i64,
+        group_id: i64,
+        member_index: i64,
+    },
+    DeleteAssignment {
+        assignment_id: i64,
+    },
+    DeleteRule {
+        rule_id: i64,
+    },
+}
+
+// --- タイムラインで使われているルールの削除 ---
+/// ルールを使っている週 (weekly_statuses) をどうするか
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum RuleDeletion {
+    /// 使っている週があれば消さない
+    Refuse,
+    /// 使っている週を同じプランの別のルールに付け替える (ローテーションの位置は変わらない)
+    Reassign { to_rule_id: i64 },
+    /// 使っている週をSkippedにする (後ろの週のローテーションが詰まる)
+    Skip,
+}
+
+// --- ルールの並び方 (パターン) ---
+/// パターンの1区切り (`rule_id` を `weeks` 週続ける。`None` はSkipped)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternStep {
+    pub rule_id: Option<i64>,
+    pub weeks: usize,
+}
+
+/// 週ごとのルールの決め方
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum RulePatternKind {
+    /// steps を順に繰り返す (A/B 交互 = [A 1週, B 1週]、A を3週のあと B = [A 3週, B 1週])
+    /// `anchor_abs_week` の週が steps の先頭になる
+    Sequence { anchor_abs_week: usize, steps: Vec<PatternStep> },
+    /// ISO週番号が奇数の週と偶数の週で分ける (`None` はSkipped)
+    IsoWeekParity { odd: Option<i64>, even: Option<i64> },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RulePattern {
+    pub id: i64,
+    pub plan_id: i64,
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: RulePatternKind,
+}
+
+// --- 週・日単位の調整 ---
+/// 1コマの調整 (メンバー `member_id` を `abs_week` 週の `weekday` の `shift_time` に足す・外す)
+/// WASM版の `ShiftOverride` と同じだが、並び替え・削除でずれないようにメンバーIDで持つ
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OverrideEntry {
+    pub abs_week: usize,
+    pub weekday: Weekday,
+    pub shift_time: ShiftTime,
+    pub member_id: i64,
+    pub action: OverrideAction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanOverride {
+    pub id: i64,
+    pub plan_id: i64,
+    #[serde(flatten)]
+    pub entry: OverrideEntry,
+}
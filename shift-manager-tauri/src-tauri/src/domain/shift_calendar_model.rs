@@ -1,31 +1,67 @@
 use serde::{Serialize, Deserialize};
 // 絶対週番号と論理デルタの型エイリアス
-pub type AbsWeek = usize;
-pub type LogicalDelta = usize;
+pub use shift_timeline::{AbsWeek, LogicalDelta, AppendWeekErrorKind, LockedWeek};
 pub type RuleId = i64;
 pub type PlanId = i64;
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
-pub enum WeekStatus {
-    Active { logical_delta: LogicalDelta, rule_id: RuleId },
-    Skipped,
+/// Activeな週に適用するルール
+/// (WeekStatus::Active の中に flatten されるので JSON上は `rule_id` フィールドになる)
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+pub struct RuleRef {
+    pub rule_id: RuleId,
 }
 
-/// シフトカレンダー管理者（メイン構造体）
-#[derive(Debug, Deserialize, Serialize)]
+pub type WeekStatus = shift_timeline::WeekStatus<RuleRef>;
+
+/// パターンから追加した週 (weekly_statuses.pattern_id)
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternWeek {
+    pub abs_week: AbsWeek,
+    pub pattern_id: i64,
+}
+
+/// 週を追加するときに自動でSkippedにする週の決め方
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SkipRuleKind {
+    /// 毎年の休業期間 ("MM-DD"、年をまたいでもよい 例: 12-29 〜 01-03)
+    /// 週 (月〜日) のどれかの日が期間に入っていればSkipped
+    AnnualClosure { from: String, to: String },
+    /// 毎月の最後の週 (月曜がその月の最後の月曜の週)
+    LastWeekOfMonth,
+    /// 日付 ("YYYY-MM-DD") の入っている週
+    Dates { dates: Vec<String> },
+    /// 週 (月〜日) に祝日・休日が `min_days` 日以上ある週 (祝日の表は domain::holiday)
+    NationalHolidays { min_days: usize },
+}
+
+/// プランの休業ルール (skip_rules)
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SkipRule {
+    pub id: i64,
+    pub plan_id: PlanId,
+    #[serde(flatten)]
+    pub kind: SkipRuleKind,
+}
+
+/// タイムライン本体 (WASM版と共通の実装)
+pub type ShiftCalendarManager = shift_timeline::ShiftCalendarManager<RuleRef>;
+
+/// プランに紐づいたシフトカレンダー（メイン構造体）
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct ShiftCalendarManager {
+pub struct PlanCalendar {
     // DB保存時にIDが必要な場合に備えてOptionにしていますが、
     // 新規作成時はNone、読み込み時はSomeになります。
     // フロントエンドとのやり取りだけなら無くても構いません。
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub id: Option<i64>, 
-    
-    pub plan_id: PlanId,
-    pub base_abs_week: AbsWeek,
-    pub initial_delta: LogicalDelta,
-    pub timeline: Vec<WeekStatus>,
-}
-
+    pub id: Option<i64>,
 
+    pub plan_id: PlanId,
 
+    // baseAbsWeek, initialDelta, timeline はトップレベルに展開される
+    #[serde(flatten)]
+    pub calendar: ShiftCalendarManager,
+}
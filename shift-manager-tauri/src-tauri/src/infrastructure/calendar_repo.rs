@@ -1,4 +1,6 @@
 use sqlx::{ 
+    Connection,
+    Executor,
     QueryBuilder,
     SqlitePool,
     Sqlite,
@@ -7,16 +9,30 @@ use sqlx::{
 };
 
 use crate::domain::{
-    rule_model::{WeeklyRule, RuleAssignment},
+    calendar_logic::{append_rule_weeks, apply_skip_rules, pattern_statuses, release_rule},
+    repository::{not_found, CalendarRepository},
+    rule_model::{WeeklyRule, RuleAssignment, RuleDeletion, ShiftTime},
     shift_calendar_model::{
         WeekStatus,
         PlanId,
-        ShiftCalendarManager
+        PlanCalendar,
+        RuleId,
+        RuleRef,
+        ShiftCalendarManager,
+        AbsWeek,
+        LockedWeek,
+        PatternWeek,
+        SkipRule,
+        SkipRuleKind,
     }
 };
+use crate::infrastructure::db::{db_error, Db};
+use crate::infrastructure::rule_repo::fetch_rule_patterns;
+use shift_timeline::LockedStaff;
 
-pub struct CalendarRepository {
-    pool: SqlitePool,
+/// CalendarRepository の SQLite 実装
+pub struct SqliteCalendarRepository {
+    db: Db,
 }
 
 // =====================
@@ -42,6 +58,18 @@ struct WeekStatusRow {
     logical_delta: Option<i64>,
 }
 
+// 固定した週の読み込み用 (誰も入っていない週は slot 側が全部NULLの1行になる)
+#[derive(FromRow)]
+struct LockedSlotRow {
+    abs_week: i64,
+    weekday: Option<i64>,
+    shift_time_type: Option<i64>,
+    group_index: Option<i64>,
+    staff_index: Option<i64>,
+    name: Option<String>,
+    member_id: Option<i64>,
+}
+
 use serde::Serialize;
 
 // フロントエンドやロジック層に渡すための「リッチな」構造体
@@ -63,7 +91,9 @@ impl TryFrom<WeekStatusRow> for WeekStatus {
         match row.status_type.as_str() {
             "Active" => Ok(WeekStatus::Active {
                 logical_delta: row.logical_delta.ok_or("Active status missing delta")? as usize,
-                rule_id: row.rule_id.ok_or("Active status missing rule_id")?,
+                payload: RuleRef {
+                    rule_id: row.rule_id.ok_or("Active status missing rule_id")?,
+                },
             }),
             "Skipped" => Ok(WeekStatus::Skipped),
             other => Err(format!("Unknown status type: {}", other)),
@@ -71,28 +101,38 @@ impl TryFrom<WeekStatusRow> for WeekStatus {
     }
 }
 
-impl CalendarRepository {
+impl SqliteCalendarRepository {
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self { db: Db::Pool(pool) }
     }
 
-    /// 新しいシフトカレンダーを作成する
-    /// すでに同じ plan_id のカレンダーが存在する場合はエラーを返す
-    pub async fn create_calendar(
+    /// `Db::begin` で始めたトランザクションの中で読み書きする
+    pub fn with_db(db: Db) -> Self {
+        Self { db }
+    }
+
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+}
+
+impl CalendarRepository for SqliteCalendarRepository {
+    async fn create_calendar(
         &self,
         plan_id: i64,
         base_abs_week: usize,
         initial_delta: usize,
     ) -> Result<i64, String> {
 
-        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
 
         // 1. 既存カレンダーのチェック（重複作成の防止）
         let existing = sqlx::query("SELECT id FROM shift_calendars WHERE plan_id = ?")
             .bind(plan_id)
             .fetch_optional(&mut *tx)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
 
         if let Some(row) = existing {
             let existing_id: i64 = row.get("id");
@@ -113,143 +153,520 @@ impl CalendarRepository {
         .bind(initial_delta as i64)
         .execute(&mut *tx)
         .await
-        .map_err(|e| e.to_string())?
+        .map_err(db_error)?
         .last_insert_rowid();
 
-        tx.commit().await.map_err(|e| e.to_string())?;
+        tx.commit().await.map_err(db_error)?;
 
         // 成功した場合は、新しく作られたIDを返す
         Ok(new_calendar_id)
     }
 
-    pub async fn try_to_append_timeline(
+    /// タイムラインに週を追記する
+    /// 既存部分との重なりのチェックとlogical_deltaの採番は
+    /// ShiftCalendarManager::apply_weeks (WASM版と共通) に任せ、
+    /// 新しく増えた週だけをINSERTする
+    async fn try_to_append_timeline(
         &self,
         plan_id: i64,
         start_abs_week: usize,
-        status_iterator: impl IntoIterator<Item = Option<i64>>,
+        statuses: Vec<Option<RuleId>>,
     ) -> Result<(), String> {
 
-        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
 
         // 1. カレンダー情報の取得
-        let cal_row = sqlx::query("SELECT id, base_abs_week FROM shift_calendars WHERE plan_id = ?")
+        let header = sqlx::query_as::<Sqlite, CalendarHeaderRow>(
+            "SELECT id, plan_id, base_abs_week, initial_delta FROM shift_calendars WHERE plan_id = ?"
+        )
             .bind(plan_id)
             .fetch_optional(&mut *tx)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
+
+        // 2. 現在のタイムラインを復元 (追記には関係しないので固定した週は読まない)
+        let mut calendar = ShiftCalendarManager {
+            base_abs_week: header.base_abs_week as usize,
+            initial_delta: header.initial_delta as usize,
+            timeline: fetch_timeline(&mut *tx, header.id).await?,
+            locked_weeks: Vec::new(),
+        };
 
-        let cal = cal_row.ok_or_else(|| format!("Plan ID: {} のカレンダーが存在しません。", plan_id))?;
-        let calendar_id: i64 = cal.get("id");
-        let base_abs_week: i64 = cal.get("base_abs_week");
+        // 3. 休業ルールを当ててから、ドメインロジックで追記 (重なり部分のチェック込み)
+        let skip_rules = fetch_skip_rules(&mut *tx, plan_id).await?;
+        let statuses = apply_skip_rules(&calendar, start_abs_week, statuses, &skip_rules);
+        let current_len = append_rule_weeks(&mut calendar, start_abs_week, statuses)?;
+
+        // 4. ループ処理：増えた未来の要素だけをINSERT
+        for (offset, status) in calendar.timeline.iter().enumerate().skip(current_len) {
+            let (st_type, delta_to_save, r_id) = match status {
+                WeekStatus::Active { logical_delta, payload } =>
+                    ("Active", Some(*logical_delta as i64), Some(payload.rule_id)),
+                WeekStatus::Skipped => ("Skipped", None, None),
+            };
 
-        // 2. 現在のDBの「末尾（cursor）」と「logical_delta」を取得
-        // DBが空（作成直後）の場合は -1 となる
-        let row = sqlx::query(
-            "SELECT MAX(week_offset) as max_offset, MAX(logical_delta) as max_logical_delta
-             FROM weekly_statuses WHERE calendar_id = ?"
+            sqlx::query(
+                "INSERT INTO weekly_statuses (calendar_id, week_offset, status_type, logical_delta, rule_id)
+                 VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(header.id)
+            .bind(offset as i64)
+            .bind(st_type)
+            .bind(delta_to_save)
+            .bind(r_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        }
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn truncate_timeline(&self, plan_id: i64, from_abs_week: usize) -> Result<(), String> {
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
+
+        let header = sqlx::query_as::<Sqlite, CalendarHeaderRow>(
+            "SELECT id, plan_id, base_abs_week, initial_delta FROM shift_calendars WHERE plan_id = ?"
         )
-        .bind(calendar_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
+            .bind(plan_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
 
-        let current_db_cursor: i64 = row.try_get("max_offset").unwrap_or(None).unwrap_or(-1);
-        let mut local_logical_delta: i64 = row.try_get("max_logical_delta").unwrap_or(None).unwrap_or(-1);
+        // ShiftCalendarManager::truncate_from と同じく、基準週より前なら全消し
+        let keep_len = (from_abs_week as i64 - header.base_abs_week).max(0);
 
-        // 今回のリストの開始オフセット
-        let start_offset = (start_abs_week as i64) - base_abs_week;
+        sqlx::query("DELETE FROM weekly_statuses WHERE calendar_id = ? AND week_offset >= ?")
+            .bind(header.id)
+            .bind(keep_len)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
 
-        // ====================================================================
-        // ★ バリデーションとスキップ計算
-        // ====================================================================
+        delete_locked_weeks(&mut tx, header.id, from_abs_week as i64, i64::MAX).await?;
 
-        // 【仕様】 歯抜けエラーのチェック
-        // カレンダー作成直後 (cursor=-1) の場合、start_offset は必ず 0 (base_abs_week) でなければエラーになる
-        if start_offset > current_db_cursor + 1 {
-            let missing_week = base_abs_week as i64 + current_db_cursor + 1;
-            return Err(format!(
-                "タイムラインに空きがあります。絶対週 {} からデータを連続させてください。",
-                missing_week
-            ));
+        tx.commit().await.map_err(db_error)?;
+        Ok(())
+    }
+
+    /// 付け替え後の initial_delta と範囲のチェックは ShiftCalendarManager::rebase に任せ、
+    /// week_offset をずらして差分だけを書き込む
+    async fn rebase_calendar(&self, plan_id: i64, new_base_abs_week: usize) -> Result<(), String> {
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
+
+        let header = sqlx::query_as::<Sqlite, CalendarHeaderRow>(
+            "SELECT id, plan_id, base_abs_week, initial_delta FROM shift_calendars WHERE plan_id = ?"
+        )
+            .bind(plan_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
+
+        // 固定した週は下で絶対週のまま消すので読まない
+        let mut calendar = ShiftCalendarManager {
+            base_abs_week: header.base_abs_week as usize,
+            initial_delta: header.initial_delta as usize,
+            timeline: fetch_timeline(&mut *tx, header.id).await?,
+            locked_weeks: Vec::new(),
+        };
+        calendar.rebase(new_base_abs_week).map_err(|e| e.to_string())?;
+
+        // 正なら手前を捨てる週数、負なら先頭に足す週数
+        let shift = new_base_abs_week as i64 - header.base_abs_week;
+
+        sqlx::query("DELETE FROM weekly_statuses WHERE calendar_id = ? AND week_offset < ?")
+            .bind(header.id)
+            .bind(shift)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        sqlx::query("UPDATE weekly_statuses SET week_offset = week_offset - ? WHERE calendar_id = ?")
+            .bind(shift)
+            .bind(header.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        for offset in 0..(-shift).max(0) {
+            sqlx::query(
+                "INSERT INTO weekly_statuses (calendar_id, week_offset, status_type, logical_delta, rule_id)
+                 VALUES (?, ?, 'Skipped', NULL, NULL)"
+            )
+            .bind(header.id)
+            .bind(offset)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
         }
 
-        // かぶっている要素数を計算
-        let overlap_count = std::cmp::max(0, current_db_cursor - start_offset + 1);
+        // 固定は絶対週で持っているので、捨てた週の分を消すだけでよい
+        delete_locked_weeks(&mut tx, header.id, 0, new_base_abs_week as i64).await?;
 
-        // かぶっている分をスキップした新しいイテレータ
-        let new_items = status_iterator.into_iter().skip(overlap_count as usize);
+        sqlx::query("UPDATE shift_calendars SET base_abs_week = ?, initial_delta = ? WHERE id = ?")
+            .bind(calendar.base_abs_week as i64)
+            .bind(calendar.initial_delta as i64)
+            .bind(header.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
 
-        // 実際の INSERT 開始位置
-        let insert_start_offset = start_offset + overlap_count;
+        tx.commit().await.map_err(db_error)?;
+        Ok(())
+    }
 
-        // 3. ループ処理：残った未来の要素だけをINSERT
-        for (index, status_opt) in new_items.enumerate() {
-            let target_offset = insert_start_offset + index as i64;
+    /// Activeかどうかのチェックは ShiftCalendarManager::lock_week に任せ、
+    /// 固定済みの週は消してから書き直す
+    async fn lock_weeks(&self, plan_id: i64, weeks: Vec<LockedWeek>) -> Result<(), String> {
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
 
-            let (st_type, delta_to_save, r_id) = match status_opt {
-                Some(rule_id) => {
-                    local_logical_delta += 1;
-                    ("Active", Some(local_logical_delta), Some(rule_id))
-                },
-                None => ("Skipped", None, None),
+        let header = sqlx::query_as::<Sqlite, CalendarHeaderRow>(
+            "SELECT id, plan_id, base_abs_week, initial_delta FROM shift_calendars WHERE plan_id = ?"
+        )
+            .bind(plan_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
+
+        let mut calendar = ShiftCalendarManager {
+            base_abs_week: header.base_abs_week as usize,
+            initial_delta: header.initial_delta as usize,
+            timeline: fetch_timeline(&mut *tx, header.id).await?,
+            locked_weeks: Vec::new(),
+        };
+
+        for week in weeks {
+            let abs_week = week.abs_week as i64;
+            calendar.lock_week(week.clone()).map_err(|e| e.to_string())?;
+
+            delete_locked_weeks(&mut tx, header.id, abs_week, abs_week + 1).await?;
+            sqlx::query("INSERT INTO locked_weeks (calendar_id, abs_week) VALUES (?, ?)")
+                .bind(header.id)
+                .bind(abs_week)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            for (weekday, day) in week.days.iter().enumerate() {
+                for (shift_time, staff_list) in [(ShiftTime::Morning, &day.morning), (ShiftTime::Afternoon, &day.afternoon)] {
+                    for (position, staff) in staff_list.iter().enumerate() {
+                        sqlx::query(
+                            "INSERT INTO locked_week_slots
+                             (calendar_id, abs_week, weekday, shift_time_type, position, group_index, staff_index, name, member_id)
+                             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+                        )
+                        .bind(header.id)
+                        .bind(abs_week)
+                        .bind(weekday as i64)
+                        .bind(shift_time as i64)
+                        .bind(position as i64)
+                        .bind(staff.group_index as i64)
+                        .bind(staff.staff_index as i64)
+                        .bind(&staff.name)
+                        .bind(staff.member_id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(db_error)?;
+                    }
+                }
+            }
+        }
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn unlock_weeks(&self, plan_id: i64, from_abs_week: AbsWeek, count: usize) -> Result<(), String> {
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
+
+        let calendar_id: i64 = sqlx::query("SELECT id FROM shift_calendars WHERE plan_id = ?")
+            .bind(plan_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?
+            .get("id");
+
+        delete_locked_weeks(&mut tx, calendar_id, from_abs_week as i64, (from_abs_week + count) as i64).await?;
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(())
+    }
+
+    /// 週の書き換えは calendar_logic::release_rule に任せ、変わった行だけを書き直す
+    /// (Skip は後ろの週の logical_delta も変わるので、最初にSkippedにした週から後ろを書き直す)
+    async fn delete_rule_in_timeline(
+        &self,
+        plan_id: PlanId,
+        rule_id: RuleId,
+        mode: &RuleDeletion,
+    ) -> Result<Vec<AbsWeek>, String> {
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
+
+        // 消すルール・付け替え先のルールが同じプランのものか
+        let mut rule_ids = vec![rule_id];
+        if let RuleDeletion::Reassign { to_rule_id } = mode {
+            rule_ids.push(*to_rule_id);
+        }
+        for id in rule_ids {
+            let owner: Option<i64> = sqlx::query_scalar("SELECT plan_id FROM weekly_rules WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            if owner != Some(plan_id) {
+                return Err(format!("プラン {} にルール {} はありません", plan_id, id));
+            }
+        }
+
+        let header = sqlx::query_as::<Sqlite, CalendarHeaderRow>(
+            "SELECT id, plan_id, base_abs_week, initial_delta FROM shift_calendars WHERE plan_id = ?"
+        )
+            .bind(plan_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        let mut changed = Vec::new();
+        if let Some(header) = header {
+            // 固定は下で絶対週のまま消すので読まない
+            let mut calendar = ShiftCalendarManager {
+                base_abs_week: header.base_abs_week as usize,
+                initial_delta: header.initial_delta as usize,
+                timeline: fetch_timeline(&mut *tx, header.id).await?,
+                locked_weeks: Vec::new(),
+            };
+            changed = release_rule(&mut calendar, rule_id, mode)?;
+
+            match mode {
+                RuleDeletion::Refuse => {}
+                RuleDeletion::Reassign { to_rule_id } => {
+                    sqlx::query("UPDATE weekly_statuses SET rule_id = ? WHERE calendar_id = ? AND rule_id = ?")
+                        .bind(to_rule_id)
+                        .bind(header.id)
+                        .bind(rule_id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(db_error)?;
+                }
+                RuleDeletion::Skip => {
+                    let first_offset = changed.first().map_or(calendar.timeline.len(), |w| w - calendar.base_abs_week);
+                    for (offset, status) in calendar.timeline.iter().enumerate().skip(first_offset) {
+                        let (st_type, delta_to_save, r_id) = match status {
+                            WeekStatus::Active { logical_delta, payload } =>
+                                ("Active", Some(*logical_delta as i64), Some(payload.rule_id)),
+                            WeekStatus::Skipped => ("Skipped", None, None),
+                        };
+                        sqlx::query(
+                            "UPDATE weekly_statuses SET status_type = ?, logical_delta = ?, rule_id = ?
+                             WHERE calendar_id = ? AND week_offset = ?"
+                        )
+                        .bind(st_type)
+                        .bind(delta_to_save)
+                        .bind(r_id)
+                        .bind(header.id)
+                        .bind(offset as i64)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(db_error)?;
+                    }
+                    for abs_week in &changed {
+                        delete_locked_weeks(&mut tx, header.id, *abs_week as i64, *abs_week as i64 + 1).await?;
+                    }
+                }
+            }
+        }
+
+        // 割り当て (rule_assignments) はカスケードで消える
+        // 別のプランのタイムラインが使っている場合は外部キー制約で失敗する
+        sqlx::query("DELETE FROM weekly_rules WHERE id = ?")
+            .bind(rule_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(changed)
+    }
+
+    /// 追記は try_to_append_timeline と同じく append_rule_weeks に任せ、増えた週に pattern_id を付けてINSERTする
+    async fn extend_timeline_by_pattern(&self, plan_id: i64, pattern_id: i64, count: usize) -> Result<AbsWeek, String> {
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
+
+        let pattern = fetch_rule_patterns(&mut *tx, plan_id)
+            .await?
+            .into_iter()
+            .find(|pattern| pattern.id == pattern_id)
+            .ok_or_else(|| format!("プラン {} にパターン {} はありません", plan_id, pattern_id))?;
+
+        let header = sqlx::query_as::<Sqlite, CalendarHeaderRow>(
+            "SELECT id, plan_id, base_abs_week, initial_delta FROM shift_calendars WHERE plan_id = ?"
+        )
+            .bind(plan_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
+
+        let mut calendar = ShiftCalendarManager {
+            base_abs_week: header.base_abs_week as usize,
+            initial_delta: header.initial_delta as usize,
+            timeline: fetch_timeline(&mut *tx, header.id).await?,
+            locked_weeks: Vec::new(),
+        };
+        let start_abs_week = calendar.end_abs_week();
+        let skip_rules = fetch_skip_rules(&mut *tx, plan_id).await?;
+        let statuses = apply_skip_rules(&calendar, start_abs_week, pattern_statuses(&pattern.kind, start_abs_week, count), &skip_rules);
+        let current_len = append_rule_weeks(&mut calendar, start_abs_week, statuses)?;
+
+        for (offset, status) in calendar.timeline.iter().enumerate().skip(current_len) {
+            let (st_type, delta_to_save, r_id) = match status {
+                WeekStatus::Active { logical_delta, payload } =>
+                    ("Active", Some(*logical_delta as i64), Some(payload.rule_id)),
+                WeekStatus::Skipped => ("Skipped", None, None),
             };
 
             sqlx::query(
-                "INSERT INTO weekly_statuses (calendar_id, week_offset, status_type, logical_delta, rule_id)
-                 VALUES (?, ?, ?, ?, ?)"
+                "INSERT INTO weekly_statuses (calendar_id, week_offset, status_type, logical_delta, rule_id, pattern_id)
+                 VALUES (?, ?, ?, ?, ?, ?)"
             )
-            .bind(calendar_id)
-            .bind(target_offset)
+            .bind(header.id)
+            .bind(offset as i64)
             .bind(st_type)
             .bind(delta_to_save)
             .bind(r_id)
+            .bind(pattern_id)
             .execute(&mut *tx)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
         }
 
-        tx.commit().await.map_err(|e| e.to_string())?;
+        tx.commit().await.map_err(db_error)?;
+        Ok(start_abs_week)
+    }
+
+    async fn find_pattern_weeks(&self, plan_id: i64) -> Result<Vec<PatternWeek>, String> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as("
+            SELECT c.base_abs_week + s.week_offset, s.pattern_id
+            FROM weekly_statuses s
+            JOIN shift_calendars c ON c.id = s.calendar_id
+            WHERE c.plan_id = ? AND s.pattern_id IS NOT NULL
+            ORDER BY s.week_offset ASC")
+            .bind(plan_id)
+            .fetch_all(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(abs_week, pattern_id)| PatternWeek { abs_week: abs_week as usize, pattern_id })
+            .collect())
+    }
+
+    async fn add_skip_rule(&self, plan_id: i64, kind: &SkipRuleKind) -> Result<i64, String> {
+        kind.validate()?;
+        let (kind_name, from, to, min_days) = skip_rule_columns(kind);
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
+
+        let id = sqlx::query("INSERT INTO skip_rules (plan_id, kind, from_month_day, to_month_day, min_days) VALUES (?, ?, ?, ?, ?)")
+            .bind(plan_id)
+            .bind(kind_name)
+            .bind(from)
+            .bind(to)
+            .bind(min_days)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .last_insert_rowid();
+        insert_skip_rule_dates(&mut tx, id, kind).await?;
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(id)
+    }
+
+    async fn update_skip_rule(&self, skip_rule_id: i64, kind: &SkipRuleKind) -> Result<(), String> {
+        kind.validate()?;
+        let (kind_name, from, to, min_days) = skip_rule_columns(kind);
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
+
+        sqlx::query("UPDATE skip_rules SET kind = ?, from_month_day = ?, to_month_day = ?, min_days = ? WHERE id = ?")
+            .bind(kind_name)
+            .bind(from)
+            .bind(to)
+            .bind(min_days)
+            .bind(skip_rule_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        sqlx::query("DELETE FROM skip_rule_dates WHERE skip_rule_id = ?")
+            .bind(skip_rule_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        insert_skip_rule_dates(&mut tx, skip_rule_id, kind).await?;
+
+        tx.commit().await.map_err(db_error)?;
         Ok(())
     }
 
-    pub async fn find_by_plan_id(&self, plan_id: i64) -> Result<Option<ShiftCalendarManager>, String> {
+    async fn delete_skip_rule(&self, skip_rule_id: i64) -> Result<(), String> {
+        sqlx::query("DELETE FROM skip_rules WHERE id = ?")
+            .bind(skip_rule_id)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn list_skip_rules(&self, plan_id: i64) -> Result<Vec<SkipRule>, String> {
+        fetch_skip_rules(&mut *self.db.conn().await?, plan_id).await
+    }
+
+    async fn find_by_plan_id(&self, plan_id: i64) -> Result<Option<PlanCalendar>, String> {
         let header_opt: Option<CalendarHeaderRow> = sqlx::query_as::<Sqlite, CalendarHeaderRow>("
             SELECT id, plan_id, base_abs_week, initial_delta 
             FROM shift_calendars 
             WHERE plan_id = ? LIMIT 1")
             .bind(plan_id)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
 
         let header = match header_opt {
             Some(h) => h,
             None => return Ok(None),
         };
 
-        let rows: Vec<WeekStatusRow> = sqlx::query_as("
-            SELECT week_offset, status_type, logical_delta, rule_id 
-            FROM weekly_statuses 
-            WHERE calendar_id = ?
-            ORDER BY week_offset ASC")
-            .bind(header.id)
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| e.to_string())?;
+        let timeline = fetch_timeline(&mut *self.db.conn().await?, header.id).await?;
+        let locked_weeks = fetch_locked_weeks(&mut *self.db.conn().await?, header.id).await?;
 
-        let timeline = rows.into_iter().map(|row| row.try_into()).collect::<Result<Vec<_>,_>>()?;
-
-        Ok(Some(ShiftCalendarManager {
+        Ok(Some(PlanCalendar {
             id: Some(header.id),
             plan_id: header.plan_id,
-            base_abs_week: header.base_abs_week as usize,
-            initial_delta: header.initial_delta as usize,
-            timeline,
+            calendar: ShiftCalendarManager {
+                base_abs_week: header.base_abs_week as usize,
+                initial_delta: header.initial_delta as usize,
+                timeline,
+                locked_weeks,
+            },
         }))
     }
 
-    /// 指定された範囲（offset start から count 分）のステータスだけを取得
-    pub async fn fetch_status_range(
+    async fn fetch_status_range(
         &self,
         calendar_id: i64,
         start_offset: i64,
@@ -264,9 +681,9 @@ impl CalendarRepository {
         .bind(calendar_id)
         .bind(start_offset)
         .bind(start_offset + count)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(db_error)?;
 
         // DTO -> Domain Model 変換 (省略)
         let statuses = rows.into_iter()
@@ -275,7 +692,9 @@ impl CalendarRepository {
 
         Ok(statuses)
     }
+}
 
+impl SqliteCalendarRepository {
     /// IDリストに含まれるルールだけを取得 (IN句を使用)
     pub async fn fetch_rules_by_ids(
         &self,
@@ -306,9 +725,9 @@ impl CalendarRepository {
         // 実行
         let rules = query_builder
             .build_query_as::<WeeklyRule>()
-            .fetch_all(&self.pool)
+            .fetch_all(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
 
         // ---------------------------------------------------
         // 2. Assignmentsの一括取得 (N+1問題の解消)
@@ -335,9 +754,9 @@ impl CalendarRepository {
 
         let assignments = assign_builder
             .build_query_as::<RuleAssignment>()
-            .fetch_all(&self.pool)
+            .fetch_all(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
 
         // ---------------------------------------------------
         // 3. メモリ上で結合 (Group By)
@@ -368,7 +787,7 @@ impl CalendarRepository {
 
     /// デバッグ用：指定したプランのタイムラインデータをDBから取得して表示する
     pub async fn debug_print_timeline(&self, plan_id: i64) -> Result<(), String> {
-        let mut conn = self.pool.acquire().await.map_err(|e| e.to_string())?;
+        let mut conn = self.db.conn().await?;
 
         // 1. まずカレンダーの基本情報を取得
         let cal_row = sqlx::query(
@@ -377,7 +796,7 @@ impl CalendarRepository {
         .bind(plan_id)
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(db_error)?;
 
         // カレンダーが存在しない場合は終了
         let cal_row = match cal_row {
@@ -407,7 +826,7 @@ impl CalendarRepository {
         .bind(calendar_id)
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(db_error)?;
 
         if status_rows.is_empty() {
             println!("| (データなし)                                                |");
@@ -440,3 +859,169 @@ impl CalendarRepository {
     }
 }
 
+/// カレンダーのタイムライン全体を week_offset 順に取得する
+/// (プール・トランザクションのどちらからでも呼べるようにExecutorを受け取る)
+async fn fetch_timeline<'e, E>(executor: E, calendar_id: i64) -> Result<Vec<WeekStatus>, String>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let rows: Vec<WeekStatusRow> = sqlx::query_as("
+        SELECT week_offset, status_type, logical_delta, rule_id 
+        FROM weekly_statuses 
+        WHERE calendar_id = ?
+        ORDER BY week_offset ASC")
+        .bind(calendar_id)
+        .fetch_all(executor)
+        .await
+        .map_err(db_error)?;
+
+    rows.into_iter().map(|row| row.try_into()).collect()
+}
+
+/// 固定した週を絶対週の順に取得する
+async fn fetch_locked_weeks<'e, E>(executor: E, calendar_id: i64) -> Result<Vec<LockedWeek>, String>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let rows: Vec<LockedSlotRow> = sqlx::query_as("
+        SELECT w.abs_week, s.weekday, s.shift_time_type, s.group_index, s.staff_index, s.name, s.member_id
+        FROM locked_weeks w
+        LEFT JOIN locked_week_slots s ON s.calendar_id = w.calendar_id AND s.abs_week = w.abs_week
+        WHERE w.calendar_id = ?
+        ORDER BY w.abs_week ASC, s.weekday ASC, s.shift_time_type ASC, s.position ASC")
+        .bind(calendar_id)
+        .fetch_all(executor)
+        .await
+        .map_err(db_error)?;
+
+    let mut weeks: Vec<LockedWeek> = Vec::new();
+    for row in rows {
+        let abs_week = row.abs_week as usize;
+        if weeks.last().map(|w| w.abs_week) != Some(abs_week) {
+            weeks.push(LockedWeek { abs_week, days: Default::default() });
+        }
+        let (Some(weekday), Some(shift_time_type), Some(group_index), Some(staff_index), Some(name)) =
+            (row.weekday, row.shift_time_type, row.group_index, row.staff_index, row.name)
+        else {
+            continue;
+        };
+
+        let day = weeks
+            .last_mut()
+            .and_then(|w| w.days.get_mut(weekday as usize))
+            .ok_or_else(|| format!("Invalid weekday: {}", weekday))?;
+        let staff_list = match ShiftTime::try_from(shift_time_type)? {
+            ShiftTime::Morning => &mut day.morning,
+            ShiftTime::Afternoon => &mut day.afternoon,
+        };
+        staff_list.push(LockedStaff {
+            name,
+            group_index: group_index as usize,
+            staff_index: staff_index as usize,
+            member_id: row.member_id,
+        });
+    }
+    Ok(weeks)
+}
+
+/// skip_rules と skip_rule_dates の1行 (dates 以外は date が NULL)
+#[derive(FromRow)]
+struct SkipRuleRow {
+    id: i64,
+    plan_id: i64,
+    kind: String,
+    from_month_day: Option<String>,
+    to_month_day: Option<String>,
+    min_days: Option<i64>,
+    date: Option<String>,
+}
+
+/// プランの休業ルールを追加した順に取得する (日付は日付順)
+async fn fetch_skip_rules<'e, E>(executor: E, plan_id: i64) -> Result<Vec<SkipRule>, String>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let rows: Vec<SkipRuleRow> = sqlx::query_as("
+        SELECT r.id, r.plan_id, r.kind, r.from_month_day, r.to_month_day, r.min_days, d.date
+        FROM skip_rules r
+        LEFT JOIN skip_rule_dates d ON d.skip_rule_id = r.id
+        WHERE r.plan_id = ?
+        ORDER BY r.id ASC, d.date ASC")
+        .bind(plan_id)
+        .fetch_all(executor)
+        .await
+        .map_err(db_error)?;
+
+    let mut rules: Vec<SkipRule> = Vec::new();
+    for row in rows {
+        if rules.last().map(|r| r.id) != Some(row.id) {
+            let kind = match row.kind.as_str() {
+                "annualClosure" => SkipRuleKind::AnnualClosure {
+                    from: row.from_month_day.unwrap_or_default(),
+                    to: row.to_month_day.unwrap_or_default(),
+                },
+                "lastWeekOfMonth" => SkipRuleKind::LastWeekOfMonth,
+                "dates" => SkipRuleKind::Dates { dates: vec![] },
+                "nationalHolidays" => SkipRuleKind::NationalHolidays { min_days: row.min_days.unwrap_or(1) as usize },
+                other => return Err(format!("Unknown skip rule kind: {}", other)),
+            };
+            rules.push(SkipRule { id: row.id, plan_id: row.plan_id, kind });
+        }
+        if let (Some(date), Some(SkipRule { kind: SkipRuleKind::Dates { dates }, .. })) = (row.date, rules.last_mut()) {
+            dates.push(date);
+        }
+    }
+    Ok(rules)
+}
+
+/// skip_rules の kind, from_month_day, to_month_day, min_days
+fn skip_rule_columns(kind: &SkipRuleKind) -> (&'static str, Option<&str>, Option<&str>, Option<i64>) {
+    match kind {
+        SkipRuleKind::AnnualClosure { from, to } => ("annualClosure", Some(from.as_str()), Some(to.as_str()), None),
+        SkipRuleKind::LastWeekOfMonth => ("lastWeekOfMonth", None, None, None),
+        SkipRuleKind::Dates { .. } => ("dates", None, None, None),
+        SkipRuleKind::NationalHolidays { min_days } => ("nationalHolidays", None, None, Some(*min_days as i64)),
+    }
+}
+
+/// dates の日付を skip_rule_dates に入れる (重複は1つにまとめる)
+async fn insert_skip_rule_dates(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    skip_rule_id: i64,
+    kind: &SkipRuleKind,
+) -> Result<(), String> {
+    let SkipRuleKind::Dates { dates } = kind else {
+        return Ok(());
+    };
+    for date in dates {
+        sqlx::query("INSERT OR IGNORE INTO skip_rule_dates (skip_rule_id, date) VALUES (?, ?)")
+            .bind(skip_rule_id)
+            .bind(date)
+            .execute(&mut **tx)
+            .await
+            .map_err(db_error)?;
+    }
+    Ok(())
+}
+
+/// 絶対週が `[from, to)` の固定を外す
+async fn delete_locked_weeks(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    calendar_id: i64,
+    from: i64,
+    to: i64,
+) -> Result<(), String> {
+    for query in [
+        "DELETE FROM locked_week_slots WHERE calendar_id = ? AND abs_week >= ? AND abs_week < ?",
+        "DELETE FROM locked_weeks WHERE calendar_id = ? AND abs_week >= ? AND abs_week < ?",
+    ] {
+        sqlx::query(query)
+            .bind(calendar_id)
+            .bind(from)
+            .bind(to)
+            .execute(&mut **tx)
+            .await
+            .map_err(db_error)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! SQLite のリポジトリが SQL を流す先
+//!
+//! ふだんはプールから1文ごとに接続を借りる。
+//! `begin` で始めたトランザクションの中では、cloneした rule / calendar のリポジトリが同じ接続を使い回す
+//! (`commit` するまで他の接続からは見えず、`commit` しないで捨てると取り消される)
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use sqlx::{error::ErrorKind, pool::PoolConnection, Sqlite, SqliteConnection, SqlitePool, Transaction};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::domain::repository::{not_found, DATABASE_ERROR};
+
+/// sqlx のエラーをリポジトリのエラーにする
+/// 制約違反はそのまま (入力の誤り)、行が無いときは `NOT_FOUND`、それ以外は `DATABASE_ERROR` を付ける
+pub fn db_error(e: sqlx::Error) -> String {
+    match &e {
+        sqlx::Error::RowNotFound => not_found("対象の行"),
+        sqlx::Error::Database(db) if !matches!(db.kind(), ErrorKind::Other) => e.to_string(),
+        _ => format!("{}: {}", DATABASE_ERROR, e),
+    }
+}
+
+#[derive(Clone)]
+pub enum Db {
+    Pool(SqlitePool),
+    Transaction(Arc<Mutex<Transaction<'static, Sqlite>>>),
+}
+
+impl Db {
+    /// 1文 (またはメソッドの中の1つのトランザクション) を流す間だけ使う接続
+    /// トランザクションの中では、返した接続を持っている間は他の文を流せないので、持ったまま別のメソッドを呼ばない
+    pub async fn conn(&self) -> Result<DbConn<'_>, String> {
+        match self {
+            Db::Pool(pool) => pool.acquire().await.map(DbConn::Pooled).map_err(db_error),
+            Db::Transaction(tx) => Ok(DbConn::Transaction(tx.lock().await)),
+        }
+    }
+
+    /// トランザクションを始める (返した Db をcloneしたものは同じトランザクションを使う)
+    pub async fn begin(&self) -> Result<Db, String> {
+        match self {
+            Db::Pool(pool) => {
+                let tx = pool.begin().await.map_err(db_error)?;
+                Ok(Db::Transaction(Arc::new(Mutex::new(tx))))
+            }
+            Db::Transaction(_) => Err(String::from("トランザクションの中でトランザクションは始められません")),
+        }
+    }
+
+    /// `begin` で始めたトランザクションを確定する (cloneしたものを全部捨ててから呼ぶ)
+    pub async fn commit(self) -> Result<(), String> {
+        match self {
+            Db::Pool(_) => Ok(()),
+            Db::Transaction(tx) => {
+                let tx = Arc::try_unwrap(tx)
+                    .map_err(|_| String::from("トランザクションを使っているリポジトリが残っています"))?;
+                tx.into_inner().commit().await.map_err(db_error)
+            }
+        }
+    }
+}
+
+/// `Db::conn` で借りた接続
+pub enum DbConn<'a> {
+    Pooled(PoolConnection<Sqlite>),
+    Transaction(MutexGuard<'a, Transaction<'static, Sqlite>>),
+}
+
+impl Deref for DbConn<'_> {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &SqliteConnection {
+        match self {
+            DbConn::Pooled(conn) => conn,
+            DbConn::Transaction(tx) => tx,
+        }
+    }
+}
+
+impl DerefMut for DbConn<'_> {
+    fn deref_mut(&mut self) -> &mut SqliteConnection {
+        match self {
+            DbConn::Pooled(conn) => conn,
+            DbConn::Transaction(tx) => tx,
+        }
+    }
+}
@@ -0,0 +1,899 @@
+//! リポジトリのインメモリ実装
+//!
+//! マイグレーション無しで commands のロジックをテストするためのバックエンド
+//! SQLite版と同じ振る舞い (ソート順・カスケード削除・外部キー制約) を真似している
+
+use std::cmp::Reverse;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use shift_timeline::Rotation;
+
+use crate::domain::{
+    calendar_logic::{append_rule_weeks, apply_skip_rules, pattern_statuses, release_rule},
+    rule_logic::{normalize_skills, validate_member_period, validate_rotation},
+    repository::{not_found, revision_conflict, CalendarRepository, PlanRepository, RuleRepository},
+    rule_model::*,
+    shift_calendar_model::{
+        AbsWeek, LockedWeek, LogicalDelta, PatternWeek, PlanCalendar, PlanId, RuleId,
+        ShiftCalendarManager, SkipRule, SkipRuleKind, WeekStatus,
+    },
+};
+
+const FOREIGN_KEY_ERROR: &str = "FOREIGN KEY constraint failed";
+
+#[derive(Clone, Default)]
+struct MemoryState {
+    // AUTOINCREMENT の代わり (全テーブル共通の連番)
+    next_id: i64,
+    plans: Vec<Plan>,
+    groups: Vec<StaffGroup>,
+    members: Vec<StaffMember>,
+    rules: Vec<WeeklyRule>,
+    assignments: Vec<RuleAssignment>,
+    calendars: Vec<PlanCalendar>,
+    // staff_member_constraints / staff_member_days_off (member_id, 制約)
+    member_constraints: Vec<(i64, MemberConstraints)>,
+    // staff_member_skills / rule_assignment_skills (id, スキル)
+    member_skills: Vec<(i64, Vec<String>)>,
+    assignment_skills: Vec<(i64, Vec<String>)>,
+    patterns: Vec<RulePattern>,
+    // weekly_statuses.pattern_id (plan_id, 週)
+    pattern_weeks: Vec<(PlanId, PatternWeek)>,
+    skip_rules: Vec<SkipRule>,
+    overrides: Vec<PlanOverride>,
+    people: Vec<Person>,
+}
+
+impl MemoryState {
+    fn issue_id(&mut self) -> i64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn plan_exists(&self, plan_id: PlanId) -> bool {
+        self.plans.iter().any(|p| p.id == plan_id)
+    }
+
+    /// `item` の入っているプラン (rule_repo の owner_plan_query と同じ)
+    fn owner_plan(&self, item: PlanItem) -> Option<PlanId> {
+        let group_plan = |group_id: i64| self.groups.iter().find(|g| g.id == group_id).map(|g| g.plan_id);
+        let rule_plan = |rule_id: RuleId| self.rules.iter().find(|r| r.id == rule_id).map(|r| r.plan_id);
+        match item {
+            PlanItem::Plan(id) => self.plan_exists(id).then_some(id),
+            PlanItem::Group(id) => group_plan(id),
+            PlanItem::Member(id) => self.members.iter().find(|m| m.id == id).and_then(|m| group_plan(m.group_id)),
+            PlanItem::Rule(id) => rule_plan(id),
+            PlanItem::Assignment(id) => self.assignments.iter().find(|a| a.id == id).and_then(|a| rule_plan(a.weekly_rule_id)),
+            PlanItem::Pattern(id) => self.patterns.iter().find(|p| p.id == id).map(|p| p.plan_id),
+            PlanItem::SkipRule(id) => self.skip_rules.iter().find(|r| r.id == id).map(|r| r.plan_id),
+            PlanItem::Override(id) => self.overrides.iter().find(|o| o.id == id).map(|o| o.plan_id),
+        }
+    }
+
+    fn rule_is_used(&self, rule_id: RuleId) -> bool {
+        self.calendars.iter().any(|c| {
+            c.calendar.timeline.iter().any(|status| matches!(
+                status,
+                WeekStatus::Active { payload, .. } if payload.rule_id == rule_id
+            ))
+        })
+    }
+
+    // --- ON DELETE CASCADE 相当 ---
+
+    fn remove_group(&mut self, group_id: i64) {
+        let member_ids: Vec<i64> = self.members.iter()
+            .filter(|m| m.group_id == group_id)
+            .map(|m| m.id)
+            .collect();
+        for member_id in member_ids {
+            self.remove_member(member_id);
+        }
+        self.groups.retain(|g| g.id != group_id);
+    }
+
+    fn remove_member(&mut self, member_id: i64) {
+        self.member_constraints.retain(|(id, _)| *id != member_id);
+        self.member_skills.retain(|(id, _)| *id != member_id);
+        self.overrides.retain(|o| o.entry.member_id != member_id);
+        self.members.retain(|m| m.id != member_id);
+    }
+
+    fn remove_rule(&mut self, rule_id: RuleId) {
+        let assignment_ids: Vec<i64> = self.assignments.iter()
+            .filter(|a| a.weekly_rule_id == rule_id)
+            .map(|a| a.id)
+            .collect();
+        for assignment_id in assignment_ids {
+            self.remove_assignment(assignment_id);
+        }
+        // rule_pattern_steps.rule_id は ON DELETE SET NULL
+        for pattern in &mut self.patterns {
+            pattern.kind.forget_rule(rule_id);
+        }
+        self.rules.retain(|r| r.id != rule_id);
+    }
+
+    fn remove_assignment(&mut self, assignment_id: i64) {
+        self.assignment_skills.retain(|(id, _)| *id != assignment_id);
+        self.assignments.retain(|a| a.id != assignment_id);
+    }
+
+    fn skip_rules_of(&self, plan_id: PlanId) -> Vec<SkipRule> {
+        self.skip_rules.iter().filter(|r| r.plan_id == plan_id).cloned().collect()
+    }
+
+    // SQLite版と同じく、日付は重複を除いて日付順
+    fn normalize_skip_rule(kind: &SkipRuleKind) -> SkipRuleKind {
+        let mut kind = kind.clone();
+        if let SkipRuleKind::Dates { dates } = &mut kind {
+            dates.sort();
+            dates.dedup();
+        }
+        kind
+    }
+
+    fn skills_of(list: &[(i64, Vec<String>)], id: i64) -> Vec<String> {
+        list.iter()
+            .find(|(owner, _)| *owner == id)
+            .map(|(_, skills)| skills.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// PlanRepository / RuleRepository / CalendarRepository のインメモリ実装
+/// cloneしても同じデータを共有する (SqlitePool と同じ感覚で使える)
+#[derive(Clone, Default)]
+pub struct InMemoryRepository {
+    state: Arc<Mutex<MemoryState>>,
+    // begin で写した元のデータ (commit で書き戻す)
+    origin: Option<Arc<Mutex<MemoryState>>>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// SQLite版のトランザクションの代わりに、今のデータを写したものを読み書きする
+    /// cloneしたものは同じ写しを使う。commit するまで元のデータは変わらず、commit しないで捨てると取り消される
+    /// (テスト用なので、その間に元のデータを書き換える他の操作は考えない。commit で上書きされる)
+    pub fn begin(&self) -> Result<Self, String> {
+        let copy = self.lock()?.clone();
+        Ok(Self { state: Arc::new(Mutex::new(copy)), origin: Some(self.state.clone()) })
+    }
+
+    /// begin で写したデータを元に書き戻す
+    pub fn commit(&self) -> Result<(), String> {
+        let Some(origin) = &self.origin else {
+            return Ok(());
+        };
+        let copy = self.lock()?.clone();
+        *origin.lock().map_err(|e| e.to_string())? = copy;
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, MemoryState>, String> {
+        self.state.lock().map_err(|e| e.to_string())
+    }
+}
+
+// =================================================================
+// 1. Plan
+// =================================================================
+
+impl PlanRepository for InMemoryRepository {
+    async fn create_plan(&self, name: &str) -> Result<PlanId, String> {
+        let mut state = self.lock()?;
+        let id = state.issue_id();
+        state.plans.push(Plan { id, name: name.to_string(), max_days_per_week: None, auto_swap_skills: false, revision: 0 });
+        Ok(id)
+    }
+
+    async fn list_plans(&self) -> Result<Vec<Plan>, String> {
+        let state = self.lock()?;
+        let mut plans = state.plans.clone();
+        plans.sort_by_key(|p| Reverse(p.id));
+        Ok(plans)
+    }
+
+    async fn delete_plan(&self, plan_id: PlanId) -> Result<(), String> {
+        let mut state = self.lock()?;
+
+        let group_ids: Vec<i64> = state.groups.iter()
+            .filter(|g| g.plan_id == plan_id)
+            .map(|g| g.id)
+            .collect();
+        let rule_ids: Vec<RuleId> = state.rules.iter()
+            .filter(|r| r.plan_id == plan_id)
+            .map(|r| r.id)
+            .collect();
+
+        for rule_id in rule_ids {
+            state.remove_rule(rule_id);
+        }
+        for group_id in group_ids {
+            state.remove_group(group_id);
+        }
+        state.pattern_weeks.retain(|(id, _)| *id != plan_id);
+        state.skip_rules.retain(|r| r.plan_id != plan_id);
+        state.overrides.retain(|o| o.plan_id != plan_id);
+        state.patterns.retain(|p| p.plan_id != plan_id);
+        state.calendars.retain(|c| c.plan_id != plan_id);
+        state.plans.retain(|p| p.id != plan_id);
+        Ok(())
+    }
+
+    async fn update_plan_name(&self, plan_id: PlanId, name: &str) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if let Some(plan) = state.plans.iter_mut().find(|p| p.id == plan_id) {
+            plan.name = name.to_string();
+        }
+        Ok(())
+    }
+
+    async fn update_plan_max_days(&self, plan_id: PlanId, max_days_per_week: Option<u32>) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if let Some(plan) = state.plans.iter_mut().find(|p| p.id == plan_id) {
+            plan.max_days_per_week = max_days_per_week.map(i64::from);
+        }
+        Ok(())
+    }
+
+    async fn update_plan_auto_swap(&self, plan_id: PlanId, auto_swap_skills: bool) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if let Some(plan) = state.plans.iter_mut().find(|p| p.id == plan_id) {
+            plan.auto_swap_skills = auto_swap_skills;
+        }
+        Ok(())
+    }
+
+    async fn claim_revision(&self, item: PlanItem, expected_revision: Option<i64>) -> Result<PlanId, String> {
+        let mut state = self.lock()?;
+        let plan_id = state.owner_plan(item).ok_or_else(|| not_found(item))?;
+        let plan = state.plans.iter_mut().find(|p| p.id == plan_id).ok_or_else(|| not_found(item))?;
+        if let Some(expected) = expected_revision {
+            if plan.revision != expected {
+                return Err(revision_conflict(plan_id, expected, plan.revision));
+            }
+        }
+        plan.revision += 1;
+        Ok(plan_id)
+    }
+}
+
+// =================================================================
+// 2. Staff Group / Member / Weekly Rule / Assignment
+// =================================================================
+
+impl RuleRepository for InMemoryRepository {
+    async fn add_staff_group(&self, plan_id: PlanId, name: &str) -> Result<i64, String> {
+        let mut state = self.lock()?;
+        if !state.plan_exists(plan_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+
+        let sort_order = state.groups.iter()
+            .filter(|g| g.plan_id == plan_id)
+            .map(|g| g.sort_order + 1)
+            .max()
+            .unwrap_or(0);
+
+        let id = state.issue_id();
+        state.groups.push(StaffGroup {
+            id,
+            plan_id,
+            name: name.to_string(),
+            sort_order,
+            min_per_period: None,
+            rotation: None,
+        });
+        Ok(id)
+    }
+
+    async fn delete_staff_group(&self, group_id: i64) -> Result<(), String> {
+        let mut state = self.lock()?;
+        // rule_assignments.target_group_id には CASCADE が付いていない
+        if state.assignments.iter().any(|a| a.target_group_id == group_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+        state.remove_group(group_id);
+        Ok(())
+    }
+
+    async fn update_group_name(&self, group_id: i64, name: &str) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if let Some(group) = state.groups.iter_mut().find(|g| g.id == group_id) {
+            group.name = name.to_string();
+        }
+        Ok(())
+    }
+
+    async fn update_group_min_staff(&self, group_id: i64, min_per_period: Option<u32>) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if let Some(group) = state.groups.iter_mut().find(|g| g.id == group_id) {
+            group.min_per_period = min_per_period.map(i64::from);
+        }
+        Ok(())
+    }
+
+    async fn update_group_rotation(&self, group_id: i64, rotation: Option<Rotation>) -> Result<(), String> {
+        validate_rotation(rotation.as_ref())?;
+        let mut state = self.lock()?;
+        if let Some(group) = state.groups.iter_mut().find(|g| g.id == group_id) {
+            group.rotation = rotation;
+        }
+        Ok(())
+    }
+
+    async fn add_staff_member(&self, group_id: i64, name: &str) -> Result<i64, String> {
+        let mut state = self.lock()?;
+        if !state.groups.iter().any(|g| g.id == group_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+
+        let sort_order = state.members.iter()
+            .filter(|m| m.group_id == group_id)
+            .map(|m| m.sort_order + 1)
+            .max()
+            .unwrap_or(0);
+
+        let id = state.issue_id();
+        state.members.push(StaffMember {
+            id,
+            group_id,
+            name: name.to_string(),
+            sort_order,
+            joined_on: None,
+            left_on: None,
+            person_id: None,
+            constraints: None,
+            skills: vec![],
+        });
+        Ok(id)
+    }
+
+    async fn delete_staff_member(&self, member_id: i64) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.remove_member(member_id);
+        Ok(())
+    }
+
+    async fn update_member_name(&self, member_id: i64, name: &str) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if let Some(member) = state.members.iter_mut().find(|m| m.id == member_id) {
+            member.name = name.to_string();
+        }
+        Ok(())
+    }
+
+    async fn update_member_period(&self, member_id: i64, joined_on: Option<&str>, left_on: Option<&str>) -> Result<(), String> {
+        validate_member_period(joined_on, left_on)?;
+        let mut state = self.lock()?;
+        if let Some(member) = state.members.iter_mut().find(|m| m.id == member_id) {
+            member.joined_on = joined_on.map(str::to_string);
+            member.left_on = left_on.map(str::to_string);
+        }
+        Ok(())
+    }
+
+    async fn set_member_constraints(&self, member_id: i64, constraints: &MemberConstraints) -> Result<(), String> {
+        constraints.validate()?;
+
+        let mut state = self.lock()?;
+        if !state.members.iter().any(|m| m.id == member_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+
+        // SQLite版と同じく、休みの曜日は重複を除いて曜日順
+        let mut constraints = constraints.clone();
+        constraints.days_off.sort_by_key(|d| *d as i64);
+        constraints.days_off.dedup();
+
+        state.member_constraints.retain(|(id, _)| *id != member_id);
+        state.member_constraints.push((member_id, constraints));
+        Ok(())
+    }
+
+    async fn clear_member_constraints(&self, member_id: i64) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.member_constraints.retain(|(id, _)| *id != member_id);
+        Ok(())
+    }
+
+    async fn set_member_skills(&self, member_id: i64, skills: &[String]) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if !state.members.iter().any(|m| m.id == member_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+        state.member_skills.retain(|(id, _)| *id != member_id);
+        state.member_skills.push((member_id, normalize_skills(skills)));
+        Ok(())
+    }
+
+    async fn link_member_person(&self, member_id: i64, person_id: Option<i64>) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if person_id.is_some_and(|person_id| !state.people.iter().any(|p| p.id == person_id)) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+        if let Some(member) = state.members.iter_mut().find(|m| m.id == member_id) {
+            member.person_id = person_id;
+        }
+        Ok(())
+    }
+
+    async fn add_person(&self, name: &str) -> Result<i64, String> {
+        let mut state = self.lock()?;
+        let id = state.issue_id();
+        state.people.push(Person { id, name: name.to_string() });
+        Ok(id)
+    }
+
+    async fn update_person_name(&self, person_id: i64, name: &str) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if let Some(person) = state.people.iter_mut().find(|p| p.id == person_id) {
+            person.name = name.to_string();
+        }
+        Ok(())
+    }
+
+    async fn delete_person(&self, person_id: i64) -> Result<(), String> {
+        let mut state = self.lock()?;
+        // ON DELETE SET NULL 相当
+        for member in state.members.iter_mut().filter(|m| m.person_id == Some(person_id)) {
+            member.person_id = None;
+        }
+        state.people.retain(|p| p.id != person_id);
+        Ok(())
+    }
+
+    async fn list_people(&self) -> Result<Vec<Person>, String> {
+        Ok(self.lock()?.people.clone())
+    }
+
+    async fn add_weekly_rule(&self, plan_id: PlanId, name: &str) -> Result<RuleId, String> {
+        let mut state = self.lock()?;
+        if !state.plan_exists(plan_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+
+        let sort_order = state.rules.iter()
+            .filter(|r| r.plan_id == plan_id)
+            .map(|r| r.sort_order + 1)
+            .max()
+            .unwrap_or(0);
+
+        let id = state.issue_id();
+        state.rules.push(WeeklyRule { id, plan_id, name: name.to_string(), sort_order });
+        Ok(id)
+    }
+
+    async fn delete_weekly_rule(&self, rule_id: RuleId) -> Result<(), String> {
+        let mut state = self.lock()?;
+        // weekly_statuses.rule_id には CASCADE が付いていない
+        if state.rule_is_used(rule_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+        state.remove_rule(rule_id);
+        Ok(())
+    }
+
+    async fn update_rule_name(&self, rule_id: RuleId, name: &str) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if let Some(rule) = state.rules.iter_mut().find(|r| r.id == rule_id) {
+            rule.name = name.to_string();
+        }
+        Ok(())
+    }
+
+    async fn add_rule_assignment(
+        &self,
+        rule_id: RuleId,
+        weekday: i64,
+        shift_time: i64,
+        group_id: i64,
+        member_index: i64
+    ) -> Result<i64, String> {
+        let weekday = Weekday::try_from(weekday)?;
+        let shift_time_type = ShiftTime::try_from(shift_time)?;
+        let target_member_index = usize::try_from(member_index).map_err(|e| e.to_string())?;
+
+        let mut state = self.lock()?;
+        if !state.rules.iter().any(|r| r.id == rule_id)
+            || !state.groups.iter().any(|g| g.id == group_id)
+        {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+
+        let id = state.issue_id();
+        state.assignments.push(RuleAssignment {
+            id,
+            weekly_rule_id: rule_id,
+            weekday,
+            shift_time_type,
+            target_group_id: group_id,
+            target_member_index,
+            required_skills: vec![],
+        });
+        Ok(id)
+    }
+
+    async fn delete_assignment(&self, assignment_id: i64) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.remove_assignment(assignment_id);
+        Ok(())
+    }
+
+    async fn set_assignment_skills(&self, assignment_id: i64, skills: &[String]) -> Result<(), String> {
+        let mut state = self.lock()?;
+        if !state.assignments.iter().any(|a| a.id == assignment_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+        state.assignment_skills.retain(|(id, _)| *id != assignment_id);
+        state.assignment_skills.push((assignment_id, normalize_skills(skills)));
+        Ok(())
+    }
+
+    async fn add_rule_pattern(&self, plan_id: PlanId, name: &str, kind: &RulePatternKind) -> Result<i64, String> {
+        kind.validate()?;
+        let mut state = self.lock()?;
+        if !state.plan_exists(plan_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+        for rule_id in kind.rule_ids() {
+            if !state.rules.iter().any(|r| r.id == rule_id && r.plan_id == plan_id) {
+                return Err(format!("プラン {} にルール {} はありません", plan_id, rule_id));
+            }
+        }
+
+        let id = state.issue_id();
+        state.patterns.push(RulePattern { id, plan_id, name: name.to_string(), kind: kind.clone() });
+        Ok(id)
+    }
+
+    async fn delete_rule_pattern(&self, pattern_id: i64) -> Result<(), String> {
+        let mut state = self.lock()?;
+        // weekly_statuses.pattern_id は ON DELETE SET NULL
+        state.pattern_weeks.retain(|(_, week)| week.pattern_id != pattern_id);
+        state.patterns.retain(|p| p.id != pattern_id);
+        Ok(())
+    }
+
+    async fn list_rule_patterns(&self, plan_id: PlanId) -> Result<Vec<RulePattern>, String> {
+        let state = self.lock()?;
+        Ok(state.patterns.iter().filter(|p| p.plan_id == plan_id).cloned().collect())
+    }
+
+    async fn add_shift_override(&self, plan_id: PlanId, entry: &OverrideEntry) -> Result<i64, String> {
+        let mut state = self.lock()?;
+        if state.owner_plan(PlanItem::Member(entry.member_id)) != Some(plan_id) {
+            return Err(format!("プラン {} にメンバー {} はいません", plan_id, entry.member_id));
+        }
+        let id = state.issue_id();
+        state.overrides.push(PlanOverride { id, plan_id, entry: entry.clone() });
+        Ok(id)
+    }
+
+    async fn delete_shift_override(&self, override_id: i64) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.overrides.retain(|o| o.id != override_id);
+        Ok(())
+    }
+
+    async fn get_plan_config(&self, plan_id: PlanId) -> Result<PlanConfig, String> {
+        let state = self.lock()?;
+
+        let plan = state.plans.iter()
+            .find(|p| p.id == plan_id)
+            .cloned()
+            .ok_or_else(|| not_found(PlanItem::Plan(plan_id)))?;
+
+        let mut groups: Vec<StaffGroup> = state.groups.iter()
+            .filter(|g| g.plan_id == plan_id)
+            .cloned()
+            .collect();
+        groups.sort_by_key(|g| g.sort_order);
+
+        let groups = groups.into_iter()
+            .map(|group| {
+                let mut members: Vec<StaffMember> = state.members.iter()
+                    .filter(|m| m.group_id == group.id)
+                    .cloned()
+                    .map(|mut m| {
+                        m.constraints = state.member_constraints.iter()
+                            .find(|(id, _)| *id == m.id)
+                            .map(|(_, c)| c.clone());
+                        m.skills = MemoryState::skills_of(&state.member_skills, m.id);
+                        m
+                    })
+                    .collect();
+                members.sort_by_key(|m| m.sort_order);
+                StaffGroupWithMembers { group, members }
+            })
+            .collect();
+
+        let mut rules: Vec<WeeklyRule> = state.rules.iter()
+            .filter(|r| r.plan_id == plan_id)
+            .cloned()
+            .collect();
+        rules.sort_by_key(|r| r.sort_order);
+
+        let rules = rules.into_iter()
+            .map(|rule| {
+                let assignments = state.assignments.iter()
+                    .filter(|a| a.weekly_rule_id == rule.id)
+                    .cloned()
+                    .map(|mut a| {
+                        a.required_skills = MemoryState::skills_of(&state.assignment_skills, a.id);
+                        a
+                    })
+                    .collect();
+                WeeklyRuleWithAssignments { rule, assignments }
+            })
+            .collect();
+
+        let overrides = state.overrides.iter().filter(|o| o.plan_id == plan_id).cloned().collect();
+
+        Ok(PlanConfig { plan, groups, rules, overrides })
+    }
+
+    async fn get_rules_sorted(&self, plan_id: PlanId) -> Result<Vec<WeeklyRule>, String> {
+        let state = self.lock()?;
+        let mut rules: Vec<WeeklyRule> = state.rules.iter()
+            .filter(|r| r.plan_id == plan_id)
+            .cloned()
+            .collect();
+        rules.sort_by_key(|r| r.sort_order);
+        Ok(rules)
+    }
+}
+
+// =================================================================
+// 3. Calendar (Timeline)
+// =================================================================
+
+impl CalendarRepository for InMemoryRepository {
+    async fn create_calendar(
+        &self,
+        plan_id: PlanId,
+        base_abs_week: AbsWeek,
+        initial_delta: LogicalDelta,
+    ) -> Result<i64, String> {
+        let mut state = self.lock()?;
+
+        if let Some(existing) = state.calendars.iter().find(|c| c.plan_id == plan_id) {
+            return Err(format!(
+                "Plan ID: {} のカレンダーは既に存在します (Calendar ID: {})",
+                plan_id,
+                existing.id.unwrap_or_default()
+            ));
+        }
+        if !state.plan_exists(plan_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+
+        let id = state.issue_id();
+        state.calendars.push(PlanCalendar {
+            id: Some(id),
+            plan_id,
+            calendar: ShiftCalendarManager::new(base_abs_week, initial_delta),
+        });
+        Ok(id)
+    }
+
+    async fn try_to_append_timeline(
+        &self,
+        plan_id: PlanId,
+        start_abs_week: AbsWeek,
+        statuses: Vec<Option<RuleId>>,
+    ) -> Result<(), String> {
+        let mut state = self.lock()?;
+
+        if statuses.iter().flatten().any(|rule_id| !state.rules.iter().any(|r| r.id == *rule_id)) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+
+        let skip_rules = state.skip_rules_of(plan_id);
+        let plan_calendar = state.calendars.iter_mut()
+            .find(|c| c.plan_id == plan_id)
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
+
+        // 失敗したときに途中まで書き換わらないよう、複製に対して追記してから差し替える
+        let mut calendar = plan_calendar.calendar.clone();
+        let statuses = apply_skip_rules(&calendar, start_abs_week, statuses, &skip_rules);
+        append_rule_weeks(&mut calendar, start_abs_week, statuses)?;
+        plan_calendar.calendar = calendar;
+        Ok(())
+    }
+
+    async fn truncate_timeline(&self, plan_id: PlanId, from_abs_week: AbsWeek) -> Result<(), String> {
+        let mut state = self.lock()?;
+        let plan_calendar = state.calendars.iter_mut()
+            .find(|c| c.plan_id == plan_id)
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
+
+        plan_calendar.calendar.truncate_from(from_abs_week);
+        state.pattern_weeks.retain(|(id, week)| *id != plan_id || week.abs_week < from_abs_week);
+        Ok(())
+    }
+
+    async fn rebase_calendar(&self, plan_id: PlanId, new_base_abs_week: AbsWeek) -> Result<(), String> {
+        let mut state = self.lock()?;
+        let plan_calendar = state.calendars.iter_mut()
+            .find(|c| c.plan_id == plan_id)
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
+
+        plan_calendar.calendar
+            .rebase(new_base_abs_week)
+            .map_err(|e| e.to_string())?;
+        state.pattern_weeks.retain(|(id, week)| *id != plan_id || week.abs_week >= new_base_abs_week);
+        Ok(())
+    }
+
+    async fn lock_weeks(&self, plan_id: PlanId, weeks: Vec<LockedWeek>) -> Result<(), String> {
+        let mut state = self.lock()?;
+        let plan_calendar = state.calendars.iter_mut()
+            .find(|c| c.plan_id == plan_id)
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
+
+        // 途中の週で失敗したときに一部だけ固定されないよう、複製に対して固定してから差し替える
+        let mut calendar = plan_calendar.calendar.clone();
+        for week in weeks {
+            calendar.lock_week(week).map_err(|e| e.to_string())?;
+        }
+        plan_calendar.calendar = calendar;
+        Ok(())
+    }
+
+    async fn unlock_weeks(&self, plan_id: PlanId, from_abs_week: AbsWeek, count: usize) -> Result<(), String> {
+        let mut state = self.lock()?;
+        let plan_calendar = state.calendars.iter_mut()
+            .find(|c| c.plan_id == plan_id)
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
+
+        plan_calendar.calendar.unlock_weeks(from_abs_week, count);
+        Ok(())
+    }
+
+    async fn delete_rule_in_timeline(
+        &self,
+        plan_id: PlanId,
+        rule_id: RuleId,
+        mode: &RuleDeletion,
+    ) -> Result<Vec<AbsWeek>, String> {
+        let mut state = self.lock()?;
+
+        let mut rule_ids = vec![rule_id];
+        if let RuleDeletion::Reassign { to_rule_id } = mode {
+            rule_ids.push(*to_rule_id);
+        }
+        for id in rule_ids {
+            if !state.rules.iter().any(|r| r.id == id && r.plan_id == plan_id) {
+                return Err(format!("プラン {} にルール {} はありません", plan_id, id));
+            }
+        }
+
+        // 途中で失敗したときに書き換わらないよう、複製に対して書き換えてから差し替える
+        let mut changed = Vec::new();
+        let mut released = None;
+        if let Some(plan_calendar) = state.calendars.iter().find(|c| c.plan_id == plan_id) {
+            let mut calendar = plan_calendar.calendar.clone();
+            changed = release_rule(&mut calendar, rule_id, mode)?;
+            released = Some(calendar);
+        }
+
+        // 別のプランのタイムラインが使っている (weekly_statuses.rule_id には CASCADE が付いていない)
+        if state.calendars.iter().any(|c| c.plan_id != plan_id && c.calendar.timeline.iter().any(|status| matches!(
+            status,
+            WeekStatus::Active { payload, .. } if payload.rule_id == rule_id
+        ))) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+
+        if let Some(calendar) = released {
+            if let Some(plan_calendar) = state.calendars.iter_mut().find(|c| c.plan_id == plan_id) {
+                plan_calendar.calendar = calendar;
+            }
+        }
+        state.remove_rule(rule_id);
+        Ok(changed)
+    }
+
+    async fn extend_timeline_by_pattern(
+        &self,
+        plan_id: PlanId,
+        pattern_id: i64,
+        count: usize,
+    ) -> Result<AbsWeek, String> {
+        let mut state = self.lock()?;
+
+        let kind = state.patterns.iter()
+            .find(|p| p.id == pattern_id && p.plan_id == plan_id)
+            .map(|p| p.kind.clone())
+            .ok_or_else(|| format!("プラン {} にパターン {} はありません", plan_id, pattern_id))?;
+
+        let skip_rules = state.skip_rules_of(plan_id);
+        let plan_calendar = state.calendars.iter_mut()
+            .find(|c| c.plan_id == plan_id)
+            .ok_or_else(|| not_found(format!("Plan ID: {} のカレンダー", plan_id)))?;
+
+        let mut calendar = plan_calendar.calendar.clone();
+        let start_abs_week = calendar.end_abs_week();
+        let statuses = apply_skip_rules(&calendar, start_abs_week, pattern_statuses(&kind, start_abs_week, count), &skip_rules);
+        append_rule_weeks(&mut calendar, start_abs_week, statuses)?;
+        plan_calendar.calendar = calendar;
+
+        for abs_week in start_abs_week..start_abs_week + count {
+            state.pattern_weeks.push((plan_id, PatternWeek { abs_week, pattern_id }));
+        }
+        Ok(start_abs_week)
+    }
+
+    async fn find_pattern_weeks(&self, plan_id: PlanId) -> Result<Vec<PatternWeek>, String> {
+        let state = self.lock()?;
+        let mut weeks: Vec<PatternWeek> = state.pattern_weeks.iter()
+            .filter(|(id, _)| *id == plan_id)
+            .map(|(_, week)| week.clone())
+            .collect();
+        weeks.sort_by_key(|week| week.abs_week);
+        Ok(weeks)
+    }
+
+    async fn add_skip_rule(&self, plan_id: PlanId, kind: &SkipRuleKind) -> Result<i64, String> {
+        kind.validate()?;
+        let mut state = self.lock()?;
+        if !state.plan_exists(plan_id) {
+            return Err(FOREIGN_KEY_ERROR.to_string());
+        }
+
+        let id = state.issue_id();
+        state.skip_rules.push(SkipRule { id, plan_id, kind: MemoryState::normalize_skip_rule(kind) });
+        Ok(id)
+    }
+
+    async fn update_skip_rule(&self, skip_rule_id: i64, kind: &SkipRuleKind) -> Result<(), String> {
+        kind.validate()?;
+        let mut state = self.lock()?;
+        if let Some(rule) = state.skip_rules.iter_mut().find(|r| r.id == skip_rule_id) {
+            rule.kind = MemoryState::normalize_skip_rule(kind);
+        }
+        Ok(())
+    }
+
+    async fn delete_skip_rule(&self, skip_rule_id: i64) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.skip_rules.retain(|r| r.id != skip_rule_id);
+        Ok(())
+    }
+
+    async fn list_skip_rules(&self, plan_id: PlanId) -> Result<Vec<SkipRule>, String> {
+        let state = self.lock()?;
+        Ok(state.skip_rules_of(plan_id))
+    }
+
+    async fn find_by_plan_id(&self, plan_id: PlanId) -> Result<Option<PlanCalendar>, String> {
+        let state = self.lock()?;
+        Ok(state.calendars.iter().find(|c| c.plan_id == plan_id).cloned())
+    }
+
+    async fn fetch_status_range(
+        &self,
+        calendar_id: i64,
+        start_offset: i64,
+        count: i64
+    ) -> Result<Vec<WeekStatus>, String> {
+        let state = self.lock()?;
+
+        let timeline = match state.calendars.iter().find(|c| c.id == Some(calendar_id)) {
+            Some(c) => &c.calendar.timeline,
+            None => return Ok(vec![]),
+        };
+
+        // SQL版と同じく、範囲外 (負のoffsetなど) は単に含まれない
+        let len = timeline.len() as i64;
+        let start = start_offset.clamp(0, len);
+        let end = (start_offset + count).clamp(start, len);
+
+        Ok(timeline[start as usize..end as usize].to_vec())
+    }
+}
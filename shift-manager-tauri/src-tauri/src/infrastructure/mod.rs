@@ -1,2 +1,4 @@
 pub mod calendar_repo;
+pub mod db;
+pub mod memory_repo;
 pub mod rule_repo;
@@ -1,195 +1,574 @@
-use sqlx::SqlitePool;
+use sqlx::{Connection, Executor, Sqlite, SqlitePool};
+use shift_timeline::{OverrideAction, Rotation};
+
+use crate::domain::rule_logic::{
+    normalize_skills, rotation_from_columns, rotation_to_columns, validate_member_period, validate_rotation,
+};
 use crate::domain::rule_model::*;
+use crate::domain::repository::{not_found, revision_conflict, PlanRepository, RuleRepository};
+use crate::infrastructure::db::{db_error, Db};
+
+/// staff_member_constraints の1行 (休みの曜日は別テーブル)
+#[derive(sqlx::FromRow)]
+struct ConstraintRow {
+    max_shifts_per_week: Option<u32>,
+    max_shifts_per_month: Option<u32>,
+    no_morning_after_afternoon: bool,
+    employment_start: Option<String>,
+    employment_end: Option<String>,
+}
+
+/// rule_patterns と rule_pattern_steps の1行 (区切りの無いパターンは steps 側が全部NULL)
+#[derive(sqlx::FromRow)]
+struct PatternStepRow {
+    id: i64,
+    plan_id: i64,
+    name: String,
+    kind: String,
+    anchor_abs_week: i64,
+    position: Option<i64>,
+    rule_id: Option<i64>,
+    weeks: Option<i64>,
+}
+
+/// shift_overrides の1行 (action は 'add' / 'remove')
+#[derive(sqlx::FromRow)]
+struct OverrideRow {
+    id: i64,
+    plan_id: i64,
+    abs_week: i64,
+    weekday: Weekday,
+    shift_time_type: ShiftTime,
+    member_id: i64,
+    action: String,
+}
+
+/// `item` の入っているプランのIDを返す副問い合わせ (? は item のID)
+fn owner_plan_query(item: PlanItem) -> (&'static str, i64) {
+    match item {
+        PlanItem::Plan(id) => ("SELECT id FROM plans WHERE id = ?", id),
+        PlanItem::Group(id) => ("SELECT plan_id FROM staff_groups WHERE id = ?", id),
+        PlanItem::Member(id) => (
+            "SELECT g.plan_id FROM staff_members m JOIN staff_groups g ON g.id = m.group_id WHERE m.id = ?",
+            id,
+        ),
+        PlanItem::Rule(id) => ("SELECT plan_id FROM weekly_rules WHERE id = ?", id),
+        PlanItem::Assignment(id) => (
+            "SELECT r.plan_id FROM rule_assignments a JOIN weekly_rules r ON r.id = a.weekly_rule_id WHERE a.id = ?",
+            id,
+        ),
+        PlanItem::Pattern(id) => ("SELECT plan_id FROM rule_patterns WHERE id = ?", id),
+        PlanItem::SkipRule(id) => ("SELECT plan_id FROM skip_rules WHERE id = ?", id),
+        PlanItem::Override(id) => ("SELECT plan_id FROM shift_overrides WHERE id = ?", id),
+    }
+}
+
+/// プランのパターンを追加した順に取得する (CalendarRepository からも使う)
+pub(crate) async fn fetch_rule_patterns<'e, E>(executor: E, plan_id: i64) -> Result<Vec<RulePattern>, String>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let rows: Vec<PatternStepRow> = sqlx::query_as("
+        SELECT p.id, p.plan_id, p.name, p.kind, p.anchor_abs_week, s.position, s.rule_id, s.weeks
+        FROM rule_patterns p
+        LEFT JOIN rule_pattern_steps s ON s.pattern_id = p.id
+        WHERE p.plan_id = ?
+        ORDER BY p.id ASC, s.position ASC")
+        .bind(plan_id)
+        .fetch_all(executor)
+        .await
+        .map_err(db_error)?;
+
+    let mut patterns: Vec<(RulePattern, Vec<PatternStep>)> = Vec::new();
+    for row in rows {
+        if patterns.last().map(|(p, _)| p.id) != Some(row.id) {
+            let kind = match row.kind.as_str() {
+                "sequence" => RulePatternKind::Sequence { anchor_abs_week: row.anchor_abs_week as usize, steps: vec![] },
+                "isoWeekParity" => RulePatternKind::IsoWeekParity { odd: None, even: None },
+                other => return Err(format!("Unknown pattern kind: {}", other)),
+            };
+            patterns.push((RulePattern { id: row.id, plan_id: row.plan_id, name: row.name, kind }, vec![]));
+        }
+        if let (Some(_), Some(weeks), Some((_, steps))) = (row.position, row.weeks, patterns.last_mut()) {
+            steps.push(PatternStep { rule_id: row.rule_id, weeks: weeks as usize });
+        }
+    }
+
+    Ok(patterns
+        .into_iter()
+        .map(|(mut pattern, rows)| {
+            match &mut pattern.kind {
+                RulePatternKind::Sequence { steps, .. } => *steps = rows,
+                RulePatternKind::IsoWeekParity { odd, even } => {
+                    *odd = rows.first().and_then(|step| step.rule_id);
+                    *even = rows.get(1).and_then(|step| step.rule_id);
+                }
+            }
+            pattern
+        })
+        .collect())
+}
 
-pub struct RuleRepository {
-    pool: SqlitePool,
+/// PlanRepository / RuleRepository の SQLite 実装
+pub struct SqliteRuleRepository {
+    db: Db,
 }
 
-impl RuleRepository {
+impl SqliteRuleRepository {
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self { db: Db::Pool(pool) }
     }
 
-    // =================================================================
-    // 1. Plan Operations (プラン操作)
-    // =================================================================
+    /// `Db::begin` で始めたトランザクションの中で読み書きする
+    pub fn with_db(db: Db) -> Self {
+        Self { db }
+    }
+
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+
+    async fn fetch_member_constraints(&self, member_id: i64) -> Result<Option<MemberConstraints>, String> {
+        let row: Option<ConstraintRow> = sqlx::query_as(
+            "SELECT max_shifts_per_week, max_shifts_per_month, no_morning_after_afternoon, employment_start, employment_end
+             FROM staff_member_constraints WHERE member_id = ?"
+        )
+        .bind(member_id)
+        .fetch_optional(&mut *self.db.conn().await?)
+        .await
+        .map_err(db_error)?;
+
+        let Some(row) = row else { return Ok(None) };
+
+        let days_off: Vec<Weekday> = sqlx::query_scalar(
+            "SELECT weekday FROM staff_member_days_off WHERE member_id = ? ORDER BY weekday ASC"
+        )
+        .bind(member_id)
+        .fetch_all(&mut *self.db.conn().await?)
+        .await
+        .map_err(db_error)?;
+
+        Ok(Some(MemberConstraints {
+            max_shifts_per_week: row.max_shifts_per_week,
+            max_shifts_per_month: row.max_shifts_per_month,
+            days_off,
+            no_morning_after_afternoon: row.no_morning_after_afternoon,
+            employment_start: row.employment_start,
+            employment_end: row.employment_end,
+        }))
+    }
+
+    async fn fetch_group_rotation(&self, group_id: i64) -> Result<Option<Rotation>, String> {
+        let (kind, param): (Option<String>, Option<i64>) = sqlx::query_as(
+            "SELECT rotation, rotation_param FROM staff_groups WHERE id = ?"
+        )
+        .bind(group_id)
+        .fetch_one(&mut *self.db.conn().await?)
+        .await
+        .map_err(db_error)?;
+        rotation_from_columns(kind.as_deref(), param)
+    }
+
+    /// `table` の `owner_column` が `owner_id` の行のスキル (名前順)
+    async fn fetch_skills(&self, table: &str, owner_column: &str, owner_id: i64) -> Result<Vec<String>, String> {
+        sqlx::query_scalar(&format!(
+            "SELECT skill FROM {} WHERE {} = ? ORDER BY skill ASC", table, owner_column
+        ))
+        .bind(owner_id)
+        .fetch_all(&mut *self.db.conn().await?)
+        .await
+        .map_err(db_error)
+    }
+
+    /// `table` の `owner_column` が `owner_id` の行を `skills` で置き換える
+    async fn replace_skills(&self, table: &str, owner_column: &str, owner_id: i64, skills: &[String]) -> Result<(), String> {
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
+
+        sqlx::query(&format!("DELETE FROM {} WHERE {} = ?", table, owner_column))
+            .bind(owner_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        for skill in normalize_skills(skills) {
+            sqlx::query(&format!("INSERT INTO {} ({}, skill) VALUES (?, ?)", table, owner_column))
+                .bind(owner_id)
+                .bind(skill)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+        }
+
+        tx.commit().await.map_err(db_error)
+    }
+}
+
+// =================================================================
+// 1. Plan Operations (プラン操作)
+// =================================================================
+
+impl PlanRepository for SqliteRuleRepository {
 
-    pub async fn create_plan(&self, name: &str) -> Result<i64, String> {
+    async fn create_plan(&self, name: &str) -> Result<i64, String> {
         let id = sqlx::query("INSERT INTO plans (name) VALUES (?)")
             .bind(name)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?
+            .map_err(db_error)?
             .last_insert_rowid();
         Ok(id)
     }
 
     // TODO test を追加
-    pub async fn list_plans(&self) -> Result<Vec<Plan>, String> {
-        sqlx::query_as::<_, Plan>("SELECT id, name FROM plans ORDER BY id DESC")
-            .fetch_all(&self.pool)
+    async fn list_plans(&self) -> Result<Vec<Plan>, String> {
+        sqlx::query_as::<_, Plan>("SELECT id, name, max_days_per_week, auto_swap_skills, revision FROM plans ORDER BY id DESC")
+            .fetch_all(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(db_error)
     }
 
-    pub async fn delete_plan(&self, plan_id: i64) -> Result<(), String> {
+    async fn delete_plan(&self, plan_id: i64) -> Result<(), String> {
         // ON DELETE CASCADE により、子要素も全削除される
         sqlx::query("DELETE FROM plans WHERE id = ?")
             .bind(plan_id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
         Ok(())
     }
 
     // TODO testを追加
-    pub async fn update_plan_name(&self, plan_id: i64, name: &str) -> Result<(), String> {
+    async fn update_plan_name(&self, plan_id: i64, name: &str) -> Result<(), String> {
         sqlx::query("UPDATE plans SET name = ? WHERE id = ?")
             .bind(name)
             .bind(plan_id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
         Ok(())
     }
 
+    async fn update_plan_max_days(&self, plan_id: i64, max_days_per_week: Option<u32>) -> Result<(), String> {
+        sqlx::query("UPDATE plans SET max_days_per_week = ? WHERE id = ?")
+            .bind(max_days_per_week)
+            .bind(plan_id)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn update_plan_auto_swap(&self, plan_id: i64, auto_swap_skills: bool) -> Result<(), String> {
+        sqlx::query("UPDATE plans SET auto_swap_skills = ? WHERE id = ?")
+            .bind(auto_swap_skills)
+            .bind(plan_id)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn claim_revision(&self, item: PlanItem, expected_revision: Option<i64>) -> Result<i64, String> {
+        let (owner_query, item_id) = owner_plan_query(item);
+
+        // 確かめるのと進めるのを1つの文で行う (間に他の書き換えが入らない)
+        let claimed: Option<i64> = sqlx::query_scalar(&format!(
+            "UPDATE plans SET revision = revision + 1
+             WHERE id = ({}) AND (? IS NULL OR revision = ?)
+             RETURNING id",
+            owner_query
+        ))
+        .bind(item_id)
+        .bind(expected_revision)
+        .bind(expected_revision)
+        .fetch_optional(&mut *self.db.conn().await?)
+        .await
+        .map_err(db_error)?;
+        if let Some(plan_id) = claimed {
+            return Ok(plan_id);
+        }
+
+        // 進められなかった理由 (無い / revision が違う) を調べる
+        let current: Option<(i64, i64)> = sqlx::query_as(&format!(
+            "SELECT id, revision FROM plans WHERE id = ({})",
+            owner_query
+        ))
+        .bind(item_id)
+        .fetch_optional(&mut *self.db.conn().await?)
+        .await
+        .map_err(db_error)?;
+        match (current, expected_revision) {
+            (Some((plan_id, current)), Some(expected)) => Err(revision_conflict(plan_id, expected, current)),
+            _ => Err(not_found(item)),
+        }
+    }
+}
+
+impl RuleRepository for SqliteRuleRepository {
     // =================================================================
     // 2. Staff Group & Member Operations
     // =================================================================
 
-    pub async fn add_staff_group(&self, plan_id: i64, name: &str) -> Result<i64, String> {
-        // 現在の最大sort_orderを取得して +1 する
-        let next_order: i64 = sqlx::query_scalar(
-            "SELECT COALESCE(MAX(sort_order), -1) + 1 FROM staff_groups WHERE plan_id = ?"
+    async fn add_staff_group(&self, plan_id: i64, name: &str) -> Result<i64, String> {
+        // 現在の最大sort_orderに +1 する
+        // 読むのと足すのを1つの文にして、同時に足しても sort_order が重ならないようにする
+        let id = sqlx::query(
+            "INSERT INTO staff_groups (plan_id, name, sort_order)
+             SELECT ?, ?, COALESCE(MAX(sort_order), -1) + 1 FROM staff_groups WHERE plan_id = ?"
         )
         .bind(plan_id)
-        .fetch_one(&self.pool)
+        .bind(name)
+        .bind(plan_id)
+        .execute(&mut *self.db.conn().await?)
         .await
-        .map_err(|e| e.to_string())?;
-
-        let id = sqlx::query("INSERT INTO staff_groups (plan_id, name, sort_order) VALUES (?, ?, ?)")
-            .bind(plan_id)
-            .bind(name)
-            .bind(next_order)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| e.to_string())?
-            .last_insert_rowid();
+        .map_err(db_error)?
+        .last_insert_rowid();
         Ok(id)
     }
 
     // TODO testを追加
-    pub async fn delete_staff_group(&self, group_id: i64) -> Result<(), String> {
+    async fn delete_staff_group(&self, group_id: i64) -> Result<(), String> {
         sqlx::query("DELETE FROM staff_groups WHERE id = ?")
             .bind(group_id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
         Ok(())
     }
 
     // TODO testを追加
-    pub async fn update_group_name(&self, group_id: i64, name: &str) -> Result<(), String> {
+    async fn update_group_name(&self, group_id: i64, name: &str) -> Result<(), String> {
         sqlx::query("UPDATE staff_groups SET name = ? WHERE id = ?")
             .bind(name)
             .bind(group_id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn update_group_min_staff(&self, group_id: i64, min_per_period: Option<u32>) -> Result<(), String> {
+        sqlx::query("UPDATE staff_groups SET min_per_period = ? WHERE id = ?")
+            .bind(min_per_period)
+            .bind(group_id)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn update_group_rotation(&self, group_id: i64, rotation: Option<Rotation>) -> Result<(), String> {
+        validate_rotation(rotation.as_ref())?;
+        let (kind, param) = rotation_to_columns(rotation.as_ref());
+        sqlx::query("UPDATE staff_groups SET rotation = ?, rotation_param = ? WHERE id = ?")
+            .bind(kind)
+            .bind(param)
+            .bind(group_id)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
         Ok(())
     }
 
     // --- Member ---
 
-    pub async fn add_staff_member(&self, group_id: i64, name: &str) -> Result<i64, String> {
-        let next_order: i64 = sqlx::query_scalar(
-            "SELECT COALESCE(MAX(sort_order), -1) + 1 FROM staff_members WHERE group_id = ?"
+    async fn add_staff_member(&self, group_id: i64, name: &str) -> Result<i64, String> {
+        // add_staff_group と同じく、sort_order は INSERT の中で決める
+        let id = sqlx::query(
+            "INSERT INTO staff_members (group_id, name, sort_order)
+             SELECT ?, ?, COALESCE(MAX(sort_order), -1) + 1 FROM staff_members WHERE group_id = ?"
         )
         .bind(group_id)
-        .fetch_one(&self.pool)
+        .bind(name)
+        .bind(group_id)
+        .execute(&mut *self.db.conn().await?)
         .await
-        .map_err(|e| e.to_string())?;
-
-        let id = sqlx::query("INSERT INTO staff_members (group_id, name, sort_order) VALUES (?, ?, ?)")
-            .bind(group_id)
-            .bind(name)
-            .bind(next_order)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| e.to_string())?
-            .last_insert_rowid();
+        .map_err(db_error)?
+        .last_insert_rowid();
         Ok(id)
     }
 
     // TODO testを追加
-    pub async fn delete_staff_member(&self, member_id: i64) -> Result<(), String> {
+    async fn delete_staff_member(&self, member_id: i64) -> Result<(), String> {
         sqlx::query("DELETE FROM staff_members WHERE id = ?")
             .bind(member_id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
         Ok(())
     }
 
     // TODO testを追加
-    pub async fn update_member_name(&self, member_id: i64, name: &str) -> Result<(), String> {
+    async fn update_member_name(&self, member_id: i64, name: &str) -> Result<(), String> {
         sqlx::query("UPDATE staff_members SET name = ? WHERE id = ?")
             .bind(name)
             .bind(member_id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
         Ok(())
     }
 
-    // =================================================================
-    // 3. Weekly Rule & Assignment Operations
-    // =================================================================
+    async fn update_member_period(&self, member_id: i64, joined_on: Option<&str>, left_on: Option<&str>) -> Result<(), String> {
+        validate_member_period(joined_on, left_on)?;
+        sqlx::query("UPDATE staff_members SET joined_on = ?, left_on = ? WHERE id = ?")
+            .bind(joined_on)
+            .bind(left_on)
+            .bind(member_id)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    // --- Member Constraints ---
+
+    async fn set_member_constraints(&self, member_id: i64, constraints: &MemberConstraints) -> Result<(), String> {
+        constraints.validate()?;
+
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
 
-    pub async fn add_weekly_rule(&self, plan_id: i64, name: &str) -> Result<i64, String> {
-        let next_order: i64 = sqlx::query_scalar(
-            "SELECT COALESCE(MAX(sort_order), -1) + 1 FROM weekly_rules WHERE plan_id = ?"
+        sqlx::query(
+            "INSERT OR REPLACE INTO staff_member_constraints
+                (member_id, max_shifts_per_week, max_shifts_per_month, no_morning_after_afternoon, employment_start, employment_end)
+             VALUES (?, ?, ?, ?, ?, ?)"
         )
-        .bind(plan_id)
-        .fetch_one(&self.pool)
+        .bind(member_id)
+        .bind(constraints.max_shifts_per_week)
+        .bind(constraints.max_shifts_per_month)
+        .bind(constraints.no_morning_after_afternoon)
+        .bind(&constraints.employment_start)
+        .bind(&constraints.employment_end)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(db_error)?;
 
-        let id = sqlx::query("INSERT INTO weekly_rules (plan_id, name, sort_order) VALUES (?, ?, ?)")
-            .bind(plan_id)
+        sqlx::query("DELETE FROM staff_member_days_off WHERE member_id = ?")
+            .bind(member_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        for weekday in &constraints.days_off {
+            sqlx::query("INSERT OR IGNORE INTO staff_member_days_off (member_id, weekday) VALUES (?, ?)")
+                .bind(member_id)
+                .bind(*weekday as i64)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+        }
+
+        tx.commit().await.map_err(db_error)
+    }
+
+    async fn clear_member_constraints(&self, member_id: i64) -> Result<(), String> {
+        for table in ["staff_member_constraints", "staff_member_days_off"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE member_id = ?", table))
+                .bind(member_id)
+                .execute(&mut *self.db.conn().await?)
+                .await
+                .map_err(db_error)?;
+        }
+        Ok(())
+    }
+
+    async fn set_member_skills(&self, member_id: i64, skills: &[String]) -> Result<(), String> {
+        self.replace_skills("staff_member_skills", "member_id", member_id, skills).await
+    }
+
+    async fn link_member_person(&self, member_id: i64, person_id: Option<i64>) -> Result<(), String> {
+        sqlx::query("UPDATE staff_members SET person_id = ? WHERE id = ?")
+            .bind(person_id)
+            .bind(member_id)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    // --- Person ---
+
+    async fn add_person(&self, name: &str) -> Result<i64, String> {
+        let id = sqlx::query("INSERT INTO people (name) VALUES (?)")
             .bind(name)
-            .bind(next_order)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?
+            .map_err(db_error)?
             .last_insert_rowid();
         Ok(id)
     }
 
+    async fn update_person_name(&self, person_id: i64, name: &str) -> Result<(), String> {
+        sqlx::query("UPDATE people SET name = ? WHERE id = ?")
+            .bind(name)
+            .bind(person_id)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn delete_person(&self, person_id: i64) -> Result<(), String> {
+        // staff_members.person_id は ON DELETE SET NULL
+        sqlx::query("DELETE FROM people WHERE id = ?")
+            .bind(person_id)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn list_people(&self) -> Result<Vec<Person>, String> {
+        sqlx::query_as("SELECT id, name FROM people ORDER BY id ASC")
+            .fetch_all(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)
+    }
+
+    // =================================================================
+    // 3. Weekly Rule & Assignment Operations
+    // =================================================================
+
+    async fn add_weekly_rule(&self, plan_id: i64, name: &str) -> Result<i64, String> {
+        // add_staff_group と同じく、sort_order は INSERT の中で決める
+        let id = sqlx::query(
+            "INSERT INTO weekly_rules (plan_id, name, sort_order)
+             SELECT ?, ?, COALESCE(MAX(sort_order), -1) + 1 FROM weekly_rules WHERE plan_id = ?"
+        )
+        .bind(plan_id)
+        .bind(name)
+        .bind(plan_id)
+        .execute(&mut *self.db.conn().await?)
+        .await
+        .map_err(db_error)?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
     // TODO testを追加
-    pub async fn delete_weekly_rule(&self, rule_id: i64) -> Result<(), String> {
+    async fn delete_weekly_rule(&self, rule_id: i64) -> Result<(), String> {
         sqlx::query("DELETE FROM weekly_rules WHERE id = ?")
             .bind(rule_id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
         Ok(())
     }
 
     // TODO testを追加
-    pub async fn update_rule_name(&self, rule_id: i64, name: &str) -> Result<(), String> {
+    async fn update_rule_name(&self, rule_id: i64, name: &str) -> Result<(), String> {
         sqlx::query("UPDATE weekly_rules SET name = ? WHERE id = ?")
             .bind(name)
             .bind(rule_id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
         Ok(())
     }
 
     // --- Assignment ---
 
-    pub async fn add_rule_assignment(
+    async fn add_rule_assignment(
         &self,
         rule_id: i64,
         weekday: i64,
@@ -206,20 +585,131 @@ impl RuleRepository {
         .bind(shift_time)
         .bind(group_id)
         .bind(member_index)
-        .execute(&self.pool)
+        .execute(&mut *self.db.conn().await?)
         .await
-        .map_err(|e| e.to_string())?
+        .map_err(db_error)?
         .last_insert_rowid();
         Ok(id)
     }
 
     // TODO test を追加
-    pub async fn delete_assignment(&self, assignment_id: i64) -> Result<(), String> {
+    async fn delete_assignment(&self, assignment_id: i64) -> Result<(), String> {
         sqlx::query("DELETE FROM rule_assignments WHERE id = ?")
             .bind(assignment_id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn set_assignment_skills(&self, assignment_id: i64, skills: &[String]) -> Result<(), String> {
+        self.replace_skills("rule_assignment_skills", "assignment_id", assignment_id, skills).await
+    }
+
+    // --- Rule Pattern ---
+
+    async fn add_rule_pattern(&self, plan_id: i64, name: &str, kind: &RulePatternKind) -> Result<i64, String> {
+        kind.validate()?;
+        let mut conn = self.db.conn().await?;
+        let mut tx = conn.begin().await.map_err(db_error)?;
+
+        for rule_id in kind.rule_ids() {
+            let owner: Option<i64> = sqlx::query_scalar("SELECT plan_id FROM weekly_rules WHERE id = ?")
+                .bind(rule_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            if owner != Some(plan_id) {
+                return Err(format!("プラン {} にルール {} はありません", plan_id, rule_id));
+            }
+        }
+
+        let (kind_name, anchor_abs_week, steps) = match kind {
+            RulePatternKind::Sequence { anchor_abs_week, steps } => ("sequence", *anchor_abs_week, steps.clone()),
+            RulePatternKind::IsoWeekParity { odd, even } => ("isoWeekParity", 0, vec![
+                PatternStep { rule_id: *odd, weeks: 1 },
+                PatternStep { rule_id: *even, weeks: 1 },
+            ]),
+        };
+        let id = sqlx::query("INSERT INTO rule_patterns (plan_id, name, kind, anchor_abs_week) VALUES (?, ?, ?, ?)")
+            .bind(plan_id)
+            .bind(name)
+            .bind(kind_name)
+            .bind(anchor_abs_week as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .last_insert_rowid();
+
+        for (position, step) in steps.iter().enumerate() {
+            sqlx::query("INSERT INTO rule_pattern_steps (pattern_id, position, rule_id, weeks) VALUES (?, ?, ?, ?)")
+                .bind(id)
+                .bind(position as i64)
+                .bind(step.rule_id)
+                .bind(step.weeks as i64)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+        }
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(id)
+    }
+
+    async fn delete_rule_pattern(&self, pattern_id: i64) -> Result<(), String> {
+        sqlx::query("DELETE FROM rule_patterns WHERE id = ?")
+            .bind(pattern_id)
+            .execute(&mut *self.db.conn().await?)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn list_rule_patterns(&self, plan_id: i64) -> Result<Vec<RulePattern>, String> {
+        fetch_rule_patterns(&mut *self.db.conn().await?, plan_id).await
+    }
+
+    // --- Shift Override ---
+
+    async fn add_shift_override(&self, plan_id: i64, entry: &OverrideEntry) -> Result<i64, String> {
+        let owner: Option<i64> = sqlx::query_scalar(
+            "SELECT g.plan_id FROM staff_members m JOIN staff_groups g ON g.id = m.group_id WHERE m.id = ?"
+        )
+        .bind(entry.member_id)
+        .fetch_optional(&mut *self.db.conn().await?)
+        .await
+        .map_err(db_error)?;
+        if owner != Some(plan_id) {
+            return Err(format!("プラン {} にメンバー {} はいません", plan_id, entry.member_id));
+        }
+
+        let action = match entry.action {
+            OverrideAction::Add => "add",
+            OverrideAction::Remove => "remove",
+        };
+        let id = sqlx::query(
+            "INSERT INTO shift_overrides (plan_id, abs_week, weekday, shift_time_type, member_id, action)
+             VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(plan_id)
+        .bind(entry.abs_week as i64)
+        .bind(entry.weekday)
+        .bind(entry.shift_time)
+        .bind(entry.member_id)
+        .bind(action)
+        .execute(&mut *self.db.conn().await?)
+        .await
+        .map_err(db_error)?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    async fn delete_shift_override(&self, override_id: i64) -> Result<(), String> {
+        sqlx::query("DELETE FROM shift_overrides WHERE id = ?")
+            .bind(override_id)
+            .execute(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
         Ok(())
     }
 
@@ -229,33 +719,45 @@ impl RuleRepository {
 
     /// 特定のプランに紐づくすべての設定（グループ、メンバー、ルール、アサイン）を取得する
     /// フロントエンドの初期化や再描画に使用
-    pub async fn get_plan_config(&self, plan_id: i64) -> Result<PlanConfig, String> {
+    async fn get_plan_config(&self, plan_id: i64) -> Result<PlanConfig, String> {
         // 1. Plan
-        let plan: Plan = sqlx::query_as("SELECT id, name FROM plans WHERE id = ?")
+        let plan: Plan = sqlx::query_as("SELECT id, name, max_days_per_week, auto_swap_skills, revision FROM plans WHERE id = ?")
             .bind(plan_id)
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| format!("Plan not found: {}", e))?;
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => not_found(PlanItem::Plan(plan_id)),
+                e => db_error(e),
+            })?;
 
         // 2. Groups
-        let groups_rows: Vec<StaffGroup> = sqlx::query_as(
-            "SELECT id, plan_id, name, sort_order FROM staff_groups WHERE plan_id = ? ORDER BY sort_order ASC"
+        let mut groups_rows: Vec<StaffGroup> = sqlx::query_as(
+            "SELECT id, plan_id, name, sort_order, min_per_period FROM staff_groups WHERE plan_id = ? ORDER BY sort_order ASC"
         )
         .bind(plan_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(db_error)?;
+
+        for group in &mut groups_rows {
+            group.rotation = self.fetch_group_rotation(group.id).await?;
+        }
 
         // 3. Members (Loop Query - データ量が少なければこれで十分)
         let mut groups_with_members = Vec::new();
         for g in groups_rows {
-            let members: Vec<StaffMember> = sqlx::query_as(
-                "SELECT id, group_id, name, sort_order FROM staff_members WHERE group_id = ? ORDER BY sort_order ASC"
+            let mut members: Vec<StaffMember> = sqlx::query_as(
+                "SELECT id, group_id, name, sort_order, joined_on, left_on, person_id FROM staff_members WHERE group_id = ? ORDER BY sort_order ASC"
             )
             .bind(g.id)
-            .fetch_all(&self.pool)
+            .fetch_all(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
+
+            for member in &mut members {
+                member.constraints = self.fetch_member_constraints(member.id).await?;
+                member.skills = self.fetch_skills("staff_member_skills", "member_id", member.id).await?;
+            }
 
             groups_with_members.push(StaffGroupWithMembers {
                 group: g,
@@ -268,21 +770,25 @@ impl RuleRepository {
             "SELECT id, plan_id, name, sort_order FROM weekly_rules WHERE plan_id = ? ORDER BY sort_order ASC"
         )
         .bind(plan_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(db_error)?;
 
         // 5. Assignments (Loop Query)
         let mut rules_with_assignments = Vec::new();
         for r in rules_rows {
-            let assignments: Vec<RuleAssignment> = sqlx::query_as(
+            let mut assignments: Vec<RuleAssignment> = sqlx::query_as(
                 "SELECT id, weekly_rule_id, weekday, shift_time_type, target_group_id, target_member_index
                  FROM rule_assignments WHERE weekly_rule_id = ?"
             )
             .bind(r.id)
-            .fetch_all(&self.pool)
+            .fetch_all(&mut *self.db.conn().await?)
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(db_error)?;
+
+            for assignment in &mut assignments {
+                assignment.required_skills = self.fetch_skills("rule_assignment_skills", "assignment_id", assignment.id).await?;
+            }
 
             rules_with_assignments.push(WeeklyRuleWithAssignments {
                 rule: r,
@@ -290,23 +796,49 @@ impl RuleRepository {
             });
         }
 
+        // 6. Overrides (追加した順)
+        let override_rows: Vec<OverrideRow> = sqlx::query_as(
+            "SELECT id, plan_id, abs_week, weekday, shift_time_type, member_id, action
+             FROM shift_overrides WHERE plan_id = ? ORDER BY id ASC"
+        )
+        .bind(plan_id)
+        .fetch_all(&mut *self.db.conn().await?)
+        .await
+        .map_err(db_error)?;
+
+        let overrides = override_rows
+            .into_iter()
+            .map(|row| PlanOverride {
+                id: row.id,
+                plan_id: row.plan_id,
+                entry: OverrideEntry {
+                    abs_week: row.abs_week as usize,
+                    weekday: row.weekday,
+                    shift_time: row.shift_time_type,
+                    member_id: row.member_id,
+                    action: if row.action == "add" { OverrideAction::Add } else { OverrideAction::Remove },
+                },
+            })
+            .collect();
+
         Ok(PlanConfig {
             plan,
             groups: groups_with_members,
             rules: rules_with_assignments,
+            overrides,
         })
     }
 
     // Generate用に、ルールIDと名前だけのリストをソート順で取得する軽量メソッド
     // TODO test を追加
-    pub async fn get_rules_sorted(&self, plan_id: i64) -> Result<Vec<WeeklyRule>, String> {
+    async fn get_rules_sorted(&self, plan_id: i64) -> Result<Vec<WeeklyRule>, String> {
         sqlx::query_as::<_, WeeklyRule>(
             "SELECT id, plan_id, name, sort_order FROM weekly_rules WHERE plan_id = ? ORDER BY sort_order ASC"
         )
         .bind(plan_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(db_error)
     }
 }
 
@@ -6,36 +6,125 @@ use sqlx::{
 };
 
 use std::fs;
+use std::path::Path;
 
-use tauri::Manager;
+#[cfg(feature = "desktop")]
+use tauri::{Emitter, Manager};
 
 pub mod domain;
 pub mod infrastructure;
 pub mod application;
 
 use sqlx::SqlitePool;
-use infrastructure::calendar_repo::CalendarRepository;
-use infrastructure::rule_repo::RuleRepository;
+use application::events::ChangeEvents;
+use application::revision::Transactional;
+use infrastructure::calendar_repo::SqliteCalendarRepository;
+use infrastructure::memory_repo::InMemoryRepository;
+use infrastructure::rule_repo::SqliteRuleRepository;
 
 // 全てのリポジトリを保持するコンテナ
-pub struct AppServices {
-    pub calendar: CalendarRepository,
-    pub rule: RuleRepository,
+// 型パラメータを差し替えると永続化先を変えられる (デフォルトはSQLite)
+// - rule: domain::repository::{PlanRepository, RuleRepository}
+// - calendar: domain::repository::CalendarRepository
+// - events: 書き換えるコマンドが流す変更の通知 (application::events)
+pub struct AppServices<R = SqliteRuleRepository, C = SqliteCalendarRepository> {
+    pub calendar: C,
+    pub rule: R,
+    pub events: ChangeEvents,
 }
 
 impl AppServices {
     pub fn new(pool: SqlitePool) -> Self {
         Self {
             // poolは内部で参照カウントされているのでcloneしても低コスト
-            calendar: CalendarRepository::new(pool.clone()),
-            rule: RuleRepository::new(pool),
+            calendar: SqliteCalendarRepository::new(pool.clone()),
+            rule: SqliteRuleRepository::new(pool),
+            events: ChangeEvents::new(),
         }
     }
 }
 
+impl Transactional for AppServices {
+    async fn begin(&self) -> Result<Self, String> {
+        // rule と calendar は同じプールを使っているので、同じトランザクションに載せる
+        let db = self.rule.db().begin().await?;
+        Ok(Self {
+            calendar: SqliteCalendarRepository::with_db(db.clone()),
+            rule: SqliteRuleRepository::with_db(db),
+            events: self.events.clone(),
+        })
+    }
+
+    async fn commit(self) -> Result<(), String> {
+        let db = self.rule.db().clone();
+        drop(self);
+        db.commit().await
+    }
+}
+
+impl AppServices<InMemoryRepository, InMemoryRepository> {
+    /// マイグレーション不要のインメモリ構成 (テスト用)
+    pub fn in_memory() -> Self {
+        let repo = InMemoryRepository::new();
+        Self {
+            calendar: repo.clone(),
+            rule: repo,
+            events: ChangeEvents::new(),
+        }
+    }
+}
+
+impl Transactional for AppServices<InMemoryRepository, InMemoryRepository> {
+    async fn begin(&self) -> Result<Self, String> {
+        let repo = self.rule.begin()?;
+        Ok(Self {
+            calendar: repo.clone(),
+            rule: repo,
+            events: self.events.clone(),
+        })
+    }
+
+    async fn commit(self) -> Result<(), String> {
+        self.rule.commit()
+    }
+}
+
+/// SQLiteファイルを開いてマイグレーションを適用する
+/// (デスクトップアプリとAPIサーバーで同じスキーマを使う)
+pub async fn open_database(db_path: &Path) -> Result<SqlitePool, String> {
+    // --- DB 接続設定 ---
+    let options = SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(true); // <--- これが重要！ファイルがなければ作る
+
+    // --- DB 接続 ---
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(options) // connect ではなく connect_with を使う
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // テーブル
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(pool)
+}
+
+/// 祝日の表を CSV ファイル (`data/jp_holidays.csv` と同じ形式) に差し替える
+/// (デスクトップアプリとAPIサーバーで同じ形式を使う)
+pub fn load_holiday_table(csv_path: &Path) -> Result<(), String> {
+    let text = fs::read_to_string(csv_path)
+        .map_err(|e| format!("{} を読めません: {}", csv_path.display(), e))?;
+    domain::holiday::install_holiday_table(domain::holiday::HolidayTable::parse_csv(&text)?)
+}
+
 // =====================
 // greet
 // =====================
+#[cfg(feature = "desktop")]
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
@@ -47,6 +136,7 @@ fn greet(name: &str) -> String {
 // =====================
 // Tauri エントリポイント
 // =====================
+#[cfg(feature = "desktop")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -67,26 +157,35 @@ pub fn run() {
 
                 println!("Using DB at: {}", db_path.display());
 
-                // --- DB 接続設定 ---
-                let options = SqliteConnectOptions::new()
-                    .filename(&db_path)
-                    .create_if_missing(true); // <--- これが重要！ファイルがなければ作る
+                // --- 祝日の表 (置いてあれば同梱の表の代わりに使う) ---
+                // 利用者が編集するファイルなので、読めない・壊れているときは起動を止めずに同梱の表を使う
+                // (エラーには何行目が正しくないかが入る)
+                let holidays_path = app_data_dir.join("jp_holidays.csv");
+                if holidays_path.exists() {
+                    match load_holiday_table(&holidays_path) {
+                        Ok(()) => println!("Using holidays at: {}", holidays_path.display()),
+                        Err(e) => eprintln!(
+                            "failed to load holidays at {}: {} (using the bundled table)",
+                            holidays_path.display(),
+                            e
+                        ),
+                    }
+                }
 
-                // --- DB 接続 ---
-                let pool = SqlitePoolOptions::new()
-                    .max_connections(5)
-                    .connect_with(options) // connect ではなく connect_with を使う
+                let pool = open_database(&db_path)
                     .await
                     .expect("failed to open db");
 
-                // 2. テーブル
-                sqlx::migrate!("./migrations")
-                    .run(&pool)
-                    .await
-                    .expect("failed to run migrations");
-
                 let services = AppServices::new(pool);
 
+                // --- 変更の通知をすべてのウィンドウに送る ---
+                let handle = app.handle().clone();
+                services.events.subscribe(move |notice| {
+                    if let Err(e) = handle.emit(notice.event.name(), notice) {
+                        eprintln!("failed to emit {}: {}", notice.event.name(), e);
+                    }
+                });
+
                 // --- State に登録 ---
                 app.manage(services);
             });
@@ -100,19 +199,56 @@ pub fn run() {
             application::commands::list_all_plans,
             application::commands::delete_plan,
             application::commands::get_plan_config,
+            application::commands::update_plan_max_days,
+            application::commands::update_plan_auto_swap,
             application::commands::add_staff_group,
             application::commands::delete_staff_group,
             application::commands::update_group_name,
+            application::commands::update_group_min_staff,
+            application::commands::update_group_rotation,
             application::commands::add_staff_member,
             application::commands::delete_staff_member,
             application::commands::update_member_name,
+            application::commands::update_member_period,
+            application::commands::set_member_constraints,
+            application::commands::clear_member_constraints,
+            application::commands::set_member_skills,
+            application::commands::link_member_person,
+            application::commands::add_person,
+            application::commands::update_person_name,
+            application::commands::delete_person,
+            application::commands::list_people,
             application::commands::add_weekly_rule,
             application::commands::delete_weekly_rule,
             application::commands::update_rule_name,
             application::commands::add_rule_assignment,
             application::commands::delete_assignment,
+            application::commands::set_assignment_skills,
+            application::commands::add_rule_pattern,
+            application::commands::delete_rule_pattern,
+            application::commands::list_rule_patterns,
+            application::commands::add_shift_override,
+            application::commands::delete_shift_override,
             application::commands::get_calendar_state,
+            application::commands::truncate_timeline,
+            application::commands::extend_timeline_by_pattern,
+            application::commands::get_pattern_weeks,
+            application::commands::add_skip_rule,
+            application::commands::update_skip_rule,
+            application::commands::delete_skip_rule,
+            application::commands::list_skip_rules,
             application::commands::derive_monthly_shift,
+            application::commands::lock_month,
+            application::commands::unlock_month,
+            application::commands::preview_rule_edit,
+            application::commands::apply_rule_edit,
+            application::commands::delete_weekly_rule_with,
+            application::commands::get_staff_schedule,
+            application::commands::list_holidays,
+            application::commands::export_month_schedule,
+            application::commands::get_team_schedule,
+            application::commands::get_cross_plan_conflicts,
+            application::commands::get_change_version,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
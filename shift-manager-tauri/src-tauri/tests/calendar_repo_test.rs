@@ -3,14 +3,18 @@ mod calendar_repo_tests {
     use sqlx::sqlite::SqlitePoolOptions;
     use sqlx::{SqlitePool};
 
+    use shift_timeline::LockedStaff;
+
     use shift_manager_tauri_lib::{
         domain::{
-            shift_calendar_model::{WeekStatus},
+            repository::{CalendarRepository, PlanRepository, RuleRepository},
+            rule_model::{PatternStep, RuleDeletion, RulePatternKind},
+            shift_calendar_model::{LockedWeek, PatternWeek, RuleRef, SkipRuleKind, WeekStatus},
         },
-        // RuleRepository をインポート
+        // SQLite実装をインポート
         infrastructure::{
-            calendar_repo::CalendarRepository,
-            rule_repo::RuleRepository,
+            calendar_repo::SqliteCalendarRepository,
+            rule_repo::SqliteRuleRepository,
         },
     };
 
@@ -72,8 +76,8 @@ mod calendar_repo_tests {
     async fn test_fetch_rules_by_ids() {
         let pool = setup_test_db().await;
         // 両方のリポジトリをインスタンス化
-        let cal_repo = CalendarRepository::new(pool.clone());
-        let rule_repo = RuleRepository::new(pool.clone());
+        let cal_repo = SqliteCalendarRepository::new(pool.clone());
+        let rule_repo = SqliteRuleRepository::new(pool.clone());
 
         // [Setup] RuleRepository を使ってデータを構築
         let plan_id = rule_repo.create_plan("Test Plan").await.expect("Failed to create plan");
@@ -112,8 +116,8 @@ mod calendar_repo_tests {
     async fn test_fetch_status_range() {
         // [Setup]
         let pool = setup_test_db().await;
-        let cal_repo = CalendarRepository::new(pool.clone());
-        let rule_repo = RuleRepository::new(pool.clone());
+        let cal_repo = SqliteCalendarRepository::new(pool.clone());
+        let rule_repo = SqliteRuleRepository::new(pool.clone());
 
         // RuleRepositoryで基本データ作成
         let plan_id = rule_repo.create_plan("Plan").await.unwrap();
@@ -136,9 +140,9 @@ mod calendar_repo_tests {
         assert_eq!(results.len(), 3);
         assert!(matches!(results[0], WeekStatus::Skipped));
         match &results[1] {
-            WeekStatus::Active { logical_delta, rule_id: r_id } => {
+            WeekStatus::Active { logical_delta, payload } => {
                 assert_eq!(*logical_delta, 11);
-                assert_eq!(*r_id as i64, rule_id);
+                assert_eq!(payload.rule_id, rule_id);
             },
             _ => panic!("Expected Active for offset 2"),
         }
@@ -149,8 +153,8 @@ mod calendar_repo_tests {
     async fn test_save_timeline_debug_print_timeline() {
         // [Setup]
         let pool = setup_test_db().await;
-        let cal_repo = CalendarRepository::new(pool.clone());
-        let rule_repo = RuleRepository::new(pool.clone());
+        let cal_repo = SqliteCalendarRepository::new(pool.clone());
+        let rule_repo = SqliteRuleRepository::new(pool.clone());
 
         let plan_id = rule_repo.create_plan("Test Plan").await.expect("Failed plan");
         let rule_a = rule_repo.add_weekly_rule(plan_id, "Rule A").await.expect("Failed rule A");
@@ -176,9 +180,9 @@ mod calendar_repo_tests {
 
         cal_repo.try_to_append_timeline(
             plan_id,
-            2922, // ここから生成を開始したい絶対週
+            2922, // ここから生成を開始したい絶対週 (2922 は確定済みの週と一致させる)
             vec![
-                Some(rule_a),
+                None,
                 Some(rule_b),
                 None,
                 Some(rule_a),
@@ -189,4 +193,369 @@ mod calendar_repo_tests {
 
         let _ = cal_repo.debug_print_timeline(plan_id).await;
     }
+
+    #[tokio::test]
+    async fn test_append_timeline_rejects_overwrite() {
+        // [Setup]
+        let pool = setup_test_db().await;
+        let cal_repo = SqliteCalendarRepository::new(pool.clone());
+        let rule_repo = SqliteRuleRepository::new(pool.clone());
+
+        let plan_id = rule_repo.create_plan("Plan").await.unwrap();
+        let rule_a = rule_repo.add_weekly_rule(plan_id, "Rule A").await.unwrap();
+        let rule_b = rule_repo.add_weekly_rule(plan_id, "Rule B").await.unwrap();
+
+        cal_repo.create_calendar(plan_id, 100, 5).await.unwrap();
+        cal_repo.try_to_append_timeline(plan_id, 100, vec![Some(rule_a), None]).await.unwrap();
+
+        // [Act & Assert] 確定済みの週を別ルールで上書きしようとするとエラー
+        let r = cal_repo.try_to_append_timeline(plan_id, 100, vec![Some(rule_b), None, Some(rule_a)]).await;
+        assert!(r.is_err());
+
+        // 歯抜けの追加もエラー
+        let r = cal_repo.try_to_append_timeline(plan_id, 103, vec![Some(rule_a)]).await;
+        assert!(r.is_err());
+
+        // 一致する重なりなら、はみ出た分だけ追記される (initial_delta から採番)
+        cal_repo.try_to_append_timeline(plan_id, 101, vec![None, Some(rule_b)]).await.unwrap();
+
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.timeline.len(), 3);
+        match &calendar.timeline[2] {
+            WeekStatus::Active { logical_delta, payload } => {
+                assert_eq!(*logical_delta, 6);
+                assert_eq!(payload.rule_id, rule_b);
+            },
+            _ => panic!("Expected Active for offset 2"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_truncate_timeline() {
+        // [Setup]
+        let pool = setup_test_db().await;
+        let cal_repo = SqliteCalendarRepository::new(pool.clone());
+        let rule_repo = SqliteRuleRepository::new(pool.clone());
+
+        let plan_id = rule_repo.create_plan("Plan").await.unwrap();
+        let rule_a = rule_repo.add_weekly_rule(plan_id, "Rule A").await.unwrap();
+
+        cal_repo.create_calendar(plan_id, 100, 0).await.unwrap();
+        cal_repo.try_to_append_timeline(plan_id, 100, vec![Some(rule_a), None, Some(rule_a)]).await.unwrap();
+
+        // [Act] 絶対週 101 以降を削除
+        cal_repo.truncate_timeline(plan_id, 101).await.unwrap();
+
+        // [Assert] 削除した位置から再び追記できる
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.timeline.len(), 1);
+        cal_repo.try_to_append_timeline(plan_id, 101, vec![Some(rule_a)]).await.unwrap();
+
+        // 基準週より前を指定すると全消し
+        cal_repo.truncate_timeline(plan_id, 50).await.unwrap();
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert!(calendar.timeline.is_empty());
+
+        // カレンダーが無いプランはエラー
+        let other_plan = rule_repo.create_plan("Other").await.unwrap();
+        assert!(cal_repo.truncate_timeline(other_plan, 100).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rebase_calendar() {
+        // [Setup]
+        let pool = setup_test_db().await;
+        let cal_repo = SqliteCalendarRepository::new(pool.clone());
+        let rule_repo = SqliteRuleRepository::new(pool.clone());
+
+        let plan_id = rule_repo.create_plan("Plan").await.unwrap();
+        let rule_a = rule_repo.add_weekly_rule(plan_id, "Rule A").await.unwrap();
+
+        cal_repo.create_calendar(plan_id, 100, 0).await.unwrap();
+        cal_repo.try_to_append_timeline(plan_id, 100, vec![Some(rule_a), None, Some(rule_a), Some(rule_a)]).await.unwrap();
+        let before = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+
+        // [Act] 基準週を 102 へ進める
+        cal_repo.rebase_calendar(plan_id, 102).await.unwrap();
+
+        // [Assert] 捨てた週のActive (1週) が initial_delta に入り、残りの週は変わらない
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.base_abs_week, 102);
+        assert_eq!(calendar.initial_delta, 1);
+        assert_eq!(calendar.slice_by_abs(102, 2), before.slice_by_abs(102, 2));
+
+        // 追記は従来どおり続きのdeltaになる
+        cal_repo.try_to_append_timeline(plan_id, 104, vec![Some(rule_a)]).await.unwrap();
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.timeline[2], WeekStatus::Active { logical_delta: 3, payload: RuleRef { rule_id: rule_a } });
+
+        // [Act] 基準週を 99 へ戻すと、増えた週はSkipped
+        cal_repo.rebase_calendar(plan_id, 99).await.unwrap();
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.base_abs_week, 99);
+        assert_eq!(calendar.initial_delta, 1);
+        assert_eq!(calendar.get_skip_list(), vec![true, true, true, false, false, false]);
+        assert!(calendar.delta_mismatches().is_empty());
+
+        // 末尾より後ろへは動かせない (何も変わらない)
+        assert!(cal_repo.rebase_calendar(plan_id, 106).await.is_err());
+        let after_error = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(after_error.base_abs_week, 99);
+        assert_eq!(after_error.timeline, calendar.timeline);
+    }
+
+    #[tokio::test]
+    async fn test_lock_weeks() {
+        // [Setup]
+        let pool = setup_test_db().await;
+        let cal_repo = SqliteCalendarRepository::new(pool.clone());
+        let rule_repo = SqliteRuleRepository::new(pool.clone());
+
+        let plan_id = rule_repo.create_plan("Plan").await.unwrap();
+        let rule_a = rule_repo.add_weekly_rule(plan_id, "Rule A").await.unwrap();
+        cal_repo.create_calendar(plan_id, 100, 0).await.unwrap();
+        cal_repo.try_to_append_timeline(plan_id, 100, vec![Some(rule_a), None, Some(rule_a), Some(rule_a)]).await.unwrap();
+
+        // メンバーIDは無くてもよい (この列を追加する前に固定した週)
+        let staff = |name: &str, staff_index, member_id| LockedStaff { name: name.to_string(), group_index: 0, staff_index, member_id };
+        let mut week_100 = LockedWeek { abs_week: 100, days: Default::default() };
+        week_100.days[0].morning = vec![staff("田中", 0, Some(7)), staff("佐藤", 1, None)];
+        week_100.days[6].afternoon = vec![staff("佐藤", 1, None)];
+        // 誰も入っていない週も固定できる
+        let empty_103 = LockedWeek { abs_week: 103, days: Default::default() };
+
+        // [Act]
+        cal_repo.lock_weeks(plan_id, vec![empty_103.clone(), week_100.clone()]).await.unwrap();
+
+        // [Assert] 絶対週の順に、コマの中の並びもそのまま読める
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.locked_weeks, vec![week_100.clone(), empty_103.clone()]);
+
+        // 固定済みの週は置き換える
+        let mut week_100_v2 = week_100.clone();
+        week_100_v2.days[0].morning.pop();
+        cal_repo.lock_weeks(plan_id, vec![week_100_v2.clone()]).await.unwrap();
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.locked_weeks, vec![week_100_v2.clone(), empty_103.clone()]);
+
+        // Skipped の週が含まれていたら何も書き換えない
+        let skipped_101 = LockedWeek { abs_week: 101, days: Default::default() };
+        let err = cal_repo.lock_weeks(plan_id, vec![week_100.clone(), skipped_101]).await.unwrap_err();
+        assert_eq!(err, "絶対週 101 はシフトを導出する週ではありません");
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.locked_weeks, vec![week_100_v2.clone(), empty_103.clone()]);
+
+        // 削除した週・基準週より前にした週の固定は外れる
+        cal_repo.truncate_timeline(plan_id, 103).await.unwrap();
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.locked_weeks, vec![week_100_v2.clone()]);
+
+        cal_repo.lock_weeks(plan_id, vec![LockedWeek { abs_week: 102, days: Default::default() }]).await.unwrap();
+        cal_repo.rebase_calendar(plan_id, 101).await.unwrap();
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.locked_weeks.iter().map(|w| w.abs_week).collect::<Vec<_>>(), vec![102]);
+
+        cal_repo.unlock_weeks(plan_id, 100, 5).await.unwrap();
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert!(calendar.locked_weeks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_rule_in_timeline() {
+        // [Arrange]
+        let pool = setup_test_db().await;
+        let cal_repo = SqliteCalendarRepository::new(pool.clone());
+        let rule_repo = SqliteRuleRepository::new(pool.clone());
+
+        let plan_id = rule_repo.create_plan("Plan").await.unwrap();
+        let other_plan = rule_repo.create_plan("Other").await.unwrap();
+        let rule_a = rule_repo.add_weekly_rule(plan_id, "Rule A").await.unwrap();
+        let rule_b = rule_repo.add_weekly_rule(plan_id, "Rule B").await.unwrap();
+        let rule_c = rule_repo.add_weekly_rule(plan_id, "Rule C").await.unwrap();
+        let other_rule = rule_repo.add_weekly_rule(other_plan, "Other").await.unwrap();
+        cal_repo.create_calendar(plan_id, 100, 0).await.unwrap();
+        cal_repo.try_to_append_timeline(plan_id, 100, vec![Some(rule_a), Some(rule_b), None, Some(rule_a), Some(rule_b)]).await.unwrap();
+        cal_repo.lock_weeks(plan_id, vec![
+            LockedWeek { abs_week: 103, days: Default::default() },
+            LockedWeek { abs_week: 104, days: Default::default() },
+        ]).await.unwrap();
+        let before = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+
+        // [Act & Assert] 使っている週があれば断り、何も変えない
+        let err = cal_repo.delete_rule_in_timeline(plan_id, rule_a, &RuleDeletion::Refuse).await.unwrap_err();
+        assert_eq!(err, format!("ルール {} はタイムラインの絶対週 100, 103 で使われています", rule_a));
+        // 別のプランのルールには付け替えられない
+        let err = cal_repo.delete_rule_in_timeline(plan_id, rule_a, &RuleDeletion::Reassign { to_rule_id: other_rule }).await.unwrap_err();
+        assert_eq!(err, format!("プラン {} にルール {} はありません", plan_id, other_rule));
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.timeline, before.timeline);
+        assert_eq!(rule_repo.get_rules_sorted(plan_id).await.unwrap().len(), 3);
+
+        // 使っていないルールはそのまま消える
+        assert!(cal_repo.delete_rule_in_timeline(plan_id, rule_c, &RuleDeletion::Refuse).await.unwrap().is_empty());
+
+        // 付け替え: logical_delta も固定もそのまま
+        let changed = cal_repo.delete_rule_in_timeline(plan_id, rule_a, &RuleDeletion::Reassign { to_rule_id: rule_b }).await.unwrap();
+        assert_eq!(changed, vec![100, 103]);
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.timeline[3], WeekStatus::Active { logical_delta: 2, payload: RuleRef { rule_id: rule_b } });
+        assert_eq!(calendar.locked_weeks.len(), 2);
+        assert_eq!(rule_repo.get_rules_sorted(plan_id).await.unwrap().iter().map(|r| r.id).collect::<Vec<_>>(), vec![rule_b]);
+
+        // Skipped: 全部の週がSkippedになり、固定も外れる
+        let rule_d = rule_repo.add_weekly_rule(plan_id, "Rule D").await.unwrap();
+        cal_repo.try_to_append_timeline(plan_id, 105, vec![Some(rule_d)]).await.unwrap();
+        let changed = cal_repo.delete_rule_in_timeline(plan_id, rule_b, &RuleDeletion::Skip).await.unwrap();
+        assert_eq!(changed, vec![100, 101, 103, 104]);
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.timeline[..5], vec![WeekStatus::Skipped; 5][..]);
+        // 後ろの週の logical_delta は詰まる
+        assert_eq!(calendar.timeline[5], WeekStatus::Active { logical_delta: 0, payload: RuleRef { rule_id: rule_d } });
+        assert!(calendar.locked_weeks.is_empty());
+        assert!(calendar.delta_mismatches().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_extend_timeline_by_pattern() {
+        // [Arrange]
+        let pool = setup_test_db().await;
+        let cal_repo = SqliteCalendarRepository::new(pool.clone());
+        let rule_repo = SqliteRuleRepository::new(pool.clone());
+
+        let plan_id = rule_repo.create_plan("Plan").await.unwrap();
+        let rule_a = rule_repo.add_weekly_rule(plan_id, "Rule A").await.unwrap();
+        let rule_b = rule_repo.add_weekly_rule(plan_id, "Rule B").await.unwrap();
+        cal_repo.create_calendar(plan_id, 100, 0).await.unwrap();
+        cal_repo.try_to_append_timeline(plan_id, 100, vec![Some(rule_a)]).await.unwrap();
+
+        // A を3週、B を1週 (絶対週 100 から数える)
+        let sequence = RulePatternKind::Sequence {
+            anchor_abs_week: 100,
+            steps: vec![
+                PatternStep { rule_id: Some(rule_a), weeks: 3 },
+                PatternStep { rule_id: Some(rule_b), weeks: 1 },
+            ],
+        };
+        let sequence_id = rule_repo.add_rule_pattern(plan_id, "A×3/B", &sequence).await.unwrap();
+        // 絶対週 104 は ISO 52週 (偶数)、105 は ISO 1週 (奇数)
+        let parity = RulePatternKind::IsoWeekParity { odd: Some(rule_b), even: None };
+        let parity_id = rule_repo.add_rule_pattern(plan_id, "奇数週はB", &parity).await.unwrap();
+
+        let patterns = rule_repo.list_rule_patterns(plan_id).await.unwrap();
+        assert_eq!(patterns.iter().map(|p| (p.id, p.kind.clone())).collect::<Vec<_>>(), vec![
+            (sequence_id, sequence),
+            (parity_id, parity),
+        ]);
+
+        // [Act] 末尾 (絶対週 101) から延ばす
+        let start = cal_repo.extend_timeline_by_pattern(plan_id, sequence_id, 3).await.unwrap();
+        assert_eq!(start, 101);
+        let start = cal_repo.extend_timeline_by_pattern(plan_id, parity_id, 2).await.unwrap();
+        assert_eq!(start, 104);
+
+        // [Assert]
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        let active = |logical_delta, rule_id| WeekStatus::Active { logical_delta, payload: RuleRef { rule_id } };
+        assert_eq!(calendar.timeline, vec![
+            active(0, rule_a),
+            active(1, rule_a),
+            active(2, rule_a),
+            active(3, rule_b),
+            WeekStatus::Skipped,
+            active(4, rule_b),
+        ]);
+        assert_eq!(cal_repo.find_pattern_weeks(plan_id).await.unwrap(), vec![
+            PatternWeek { abs_week: 101, pattern_id: sequence_id },
+            PatternWeek { abs_week: 102, pattern_id: sequence_id },
+            PatternWeek { abs_week: 103, pattern_id: sequence_id },
+            PatternWeek { abs_week: 104, pattern_id: parity_id },
+            PatternWeek { abs_week: 105, pattern_id: parity_id },
+        ]);
+
+        // パターンを消しても週は残り、記録だけが外れる
+        rule_repo.delete_rule_pattern(sequence_id).await.unwrap();
+        assert_eq!(cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar.timeline, calendar.timeline);
+        assert_eq!(cal_repo.find_pattern_weeks(plan_id).await.unwrap().len(), 2);
+
+        // 他のプランのパターンやルールは使えない
+        let other_plan = rule_repo.create_plan("Other").await.unwrap();
+        let err = cal_repo.extend_timeline_by_pattern(other_plan, parity_id, 1).await.unwrap_err();
+        assert_eq!(err, format!("プラン {} にパターン {} はありません", other_plan, parity_id));
+        let err = rule_repo.add_rule_pattern(other_plan, "X", &RulePatternKind::IsoWeekParity { odd: Some(rule_a), even: None })
+            .await
+            .unwrap_err();
+        assert_eq!(err, format!("プラン {} にルール {} はありません", other_plan, rule_a));
+
+        // 切り詰めた週の記録も消える
+        cal_repo.truncate_timeline(plan_id, 105).await.unwrap();
+        assert_eq!(cal_repo.find_pattern_weeks(plan_id).await.unwrap(), vec![
+            PatternWeek { abs_week: 104, pattern_id: parity_id },
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_skip_rules() {
+        // [Arrange]
+        let pool = setup_test_db().await;
+        let cal_repo = SqliteCalendarRepository::new(pool.clone());
+        let rule_repo = SqliteRuleRepository::new(pool.clone());
+
+        let plan_id = rule_repo.create_plan("Plan").await.unwrap();
+        let rule_a = rule_repo.add_weekly_rule(plan_id, "Rule A").await.unwrap();
+        // 絶対週 2972 の月曜は 2026-12-14
+        cal_repo.create_calendar(plan_id, 2972, 0).await.unwrap();
+
+        let new_year = SkipRuleKind::AnnualClosure { from: "12-29".to_string(), to: "01-03".to_string() };
+        let new_year_id = cal_repo.add_skip_rule(plan_id, &new_year).await.unwrap();
+        let dates_id = cal_repo.add_skip_rule(plan_id, &SkipRuleKind::Dates {
+            dates: vec!["2027-02-11".to_string(), "2027-01-05".to_string(), "2027-02-11".to_string()],
+        }).await.unwrap();
+
+        // 形式の正しくないルールは保存しない
+        assert!(cal_repo.add_skip_rule(plan_id, &SkipRuleKind::AnnualClosure { from: "13-01".to_string(), to: "01-03".to_string() }).await.is_err());
+        assert!(cal_repo.add_skip_rule(plan_id, &SkipRuleKind::Dates { dates: vec![] }).await.is_err());
+
+        let rules = cal_repo.list_skip_rules(plan_id).await.unwrap();
+        assert_eq!(rules.iter().map(|r| (r.id, r.kind.clone())).collect::<Vec<_>>(), vec![
+            (new_year_id, new_year),
+            (dates_id, SkipRuleKind::Dates { dates: vec!["2027-01-05".to_string(), "2027-02-11".to_string()] }),
+        ]);
+
+        // [Act] 12/14 の週から10週追記する
+        cal_repo.try_to_append_timeline(plan_id, 2972, vec![Some(rule_a); 10]).await.unwrap();
+
+        // [Assert] 12/28 (年末年始)、1/4 (1/5)、2/8 (2/11) の週がSkipped
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        let skipped: Vec<usize> = calendar.timeline.iter()
+            .enumerate()
+            .filter(|(_, status)| **status == WeekStatus::Skipped)
+            .map(|(offset, _)| 2972 + offset)
+            .collect();
+        assert_eq!(skipped, vec![2974, 2975, 2980]);
+        assert_eq!(calendar.timeline[9], WeekStatus::Active { logical_delta: 6, payload: RuleRef { rule_id: rule_a } });
+        let before_timeline = calendar.timeline.clone();
+
+        // 毎月の最後の週: すでにある週は変えず、後から追加する週だけに当たる
+        let last_week_id = cal_repo.add_skip_rule(plan_id, &SkipRuleKind::LastWeekOfMonth).await.unwrap();
+        cal_repo.try_to_append_timeline(plan_id, 2982, vec![Some(rule_a); 6]).await.unwrap();
+        let before_len = calendar.timeline.len();
+        let calendar = cal_repo.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.timeline[..before_len], before_timeline[..]);
+        // 2/22 (2982) と 3/29 (2987) の週
+        assert_eq!(calendar.timeline[2982 - 2972], WeekStatus::Skipped);
+        assert_eq!(calendar.timeline[2987 - 2972], WeekStatus::Skipped);
+
+        // 編集と削除
+        cal_repo.update_skip_rule(last_week_id, &SkipRuleKind::Dates { dates: vec!["2027-04-01".to_string()] }).await.unwrap();
+        cal_repo.delete_skip_rule(new_year_id).await.unwrap();
+        let rules = cal_repo.list_skip_rules(plan_id).await.unwrap();
+        assert_eq!(rules.iter().map(|r| r.id).collect::<Vec<_>>(), vec![dates_id, last_week_id]);
+        assert_eq!(rules[1].kind, SkipRuleKind::Dates { dates: vec!["2027-04-01".to_string()] });
+        cal_repo.update_skip_rule(dates_id, &SkipRuleKind::NationalHolidays { min_days: 2 }).await.unwrap();
+        assert_eq!(cal_repo.list_skip_rules(plan_id).await.unwrap()[0].kind, SkipRuleKind::NationalHolidays { min_days: 2 });
+
+        // プランを消すと一緒に消える
+        rule_repo.delete_plan(plan_id).await.unwrap();
+        assert!(cal_repo.list_skip_rules(plan_id).await.unwrap().is_empty());
+    }
 }
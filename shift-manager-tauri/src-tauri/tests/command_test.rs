@@ -58,19 +58,19 @@ mod command_tests {
         assert!(plan_id > 0);
 
         // 3. [コマンド実行] スタッフグループとメンバーの作成
-        let group_id = add_staff_group(plan_id, "正社員".to_string(), state.clone()).await.unwrap();
-        let member1_id = add_staff_member(group_id, "田中".to_string(), state.clone()).await.unwrap();
-        let member2_id = add_staff_member(group_id, "佐藤".to_string(), state.clone()).await.unwrap();
+        let group_id = add_staff_group(plan_id, "正社員".to_string(), None, state.clone()).await.unwrap();
+        let member1_id = add_staff_member(group_id, "田中".to_string(), None, state.clone()).await.unwrap();
+        let member2_id = add_staff_member(group_id, "佐藤".to_string(), None, state.clone()).await.unwrap();
 
         // 4. [コマンド実行] ルールとアサインの作成
-        let rule_id = add_weekly_rule(plan_id, "標準ルール".to_string(), state.clone()).await.unwrap();
+        let rule_id = add_weekly_rule(plan_id, "標準ルール".to_string(), None, state.clone()).await.unwrap();
         // 月曜日(1) の 午前(0) に グループ(group_id) の 0番目の人 をアサイン
         let _assign_id = add_rule_assignment(
             rule_id,
             0,
             0,
             group_id,
-            0,
+            0, None,
             state.clone())
             .await
             .unwrap();
@@ -95,11 +95,11 @@ mod command_tests {
 
         // 6. [コマンド実行] カレンダー作成とタイムラインの追記
         // base=2920 からスタート
-        create_calendar(plan_id, 2920, 0, state.clone()).await.unwrap();
+        create_calendar(plan_id, 2920, 0, None, state.clone()).await.unwrap();
 
         // 2920週から 3週間分のルールをセット (Active, Active, Skipped)
         let timeline_data = vec![Some(rule_id), Some(rule_id), None];
-        append_timeline(plan_id, 2920, timeline_data, state.clone()).await.unwrap();
+        append_timeline(plan_id, 2920, timeline_data, None, state.clone()).await.unwrap();
 
         // 7. [コマンド実行] シフトの導出テスト (現状はダミーデータが返るか確認)
         // 2026年 1月のシフトをリクエスト
@@ -124,11 +124,11 @@ mod command_tests {
         // 1〜4. データ作成 (省略・前回のコードと同じ)
         let plan_name = "2026年 シフト計画".to_string();
         let plan_id = create_new_plan(plan_name, state.clone()).await.unwrap();
-        let group_id = add_staff_group(plan_id, "正社員".to_string(), state.clone()).await.unwrap();
-        let member1_id = add_staff_member(group_id, "田中".to_string(), state.clone()).await.unwrap();
-        let member2_id = add_staff_member(group_id, "佐藤".to_string(), state.clone()).await.unwrap();
-        let rule_id = add_weekly_rule(plan_id, "標準ルール".to_string(), state.clone()).await.unwrap();
-        let _assign_id = add_rule_assignment(rule_id, 1, 0, group_id, 0, state.clone()).await.unwrap();
+        let group_id = add_staff_group(plan_id, "正社員".to_string(), None, state.clone()).await.unwrap();
+        let member1_id = add_staff_member(group_id, "田中".to_string(), None, state.clone()).await.unwrap();
+        let member2_id = add_staff_member(group_id, "佐藤".to_string(), None, state.clone()).await.unwrap();
+        let rule_id = add_weekly_rule(plan_id, "標準ルール".to_string(), None, state.clone()).await.unwrap();
+        let _assign_id = add_rule_assignment(rule_id, 1, 0, group_id, 0, None, state.clone()).await.unwrap();
 
         // =================================================================
         // ★ 追加1：ルール設定のわかりやすいデバッグ表示
@@ -144,9 +144,9 @@ mod command_tests {
         let abs_week = calculate_abs_week(2026, 0, 1) .unwrap();
 
         // 6. カレンダー作成とタイムライン追記 (前回のまま)
-        create_calendar(plan_id, abs_week, 0, state.clone()).await.unwrap();
+        create_calendar(plan_id, abs_week, 0, None, state.clone()).await.unwrap();
         let timeline_data = vec![Some(rule_id), Some(rule_id), None];
-        append_timeline(plan_id, abs_week, timeline_data, state.clone()).await.unwrap();
+        append_timeline(plan_id, abs_week, timeline_data, None, state.clone()).await.unwrap();
 
         // =================================================================
         // ★ 追加2：タイムラインのデバッグ表示（Repositoryのメソッドを直接呼ぶ）
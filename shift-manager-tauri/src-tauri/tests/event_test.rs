@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod event_tests {
+    use std::sync::{Arc, Mutex};
+
+    use sqlx::sqlite::SqlitePoolOptions;
+    use tauri::Manager;
+
+    use shift_manager_tauri_lib::{
+        application::commands::*,
+        application::events::{ChangeAction, ChangeEvent, ChangeNotice, ChangeTarget, TimelineChange},
+        AppServices,
+    };
+
+    async fn setup_test_services() -> AppServices {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create memory pool");
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        AppServices::new(pool)
+    }
+
+    #[tokio::test]
+    async fn test_commands_emit_change_events() {
+        let services = setup_test_services().await;
+        let received: Arc<Mutex<Vec<ChangeNotice>>> = Arc::default();
+        let sink = received.clone();
+        services.events.subscribe(move |notice| sink.lock().unwrap().push(notice.clone()));
+
+        let app = tauri::test::mock_builder()
+            .manage(services)
+            .build(tauri::generate_context!())
+            .unwrap();
+        let state = app.state::<AppServices>();
+
+        let plan_id = create_new_plan("通知".to_string(), state).await.unwrap();
+        let group_id = add_staff_group(plan_id, "正社員".to_string(), None, state).await.unwrap();
+        let member_id = add_staff_member(group_id, "田中".to_string(), None, state).await.unwrap();
+        let rule_id = add_weekly_rule(plan_id, "標準".to_string(), None, state).await.unwrap();
+        create_calendar(plan_id, 100, 0, None, state).await.unwrap();
+        append_timeline(plan_id, 100, vec![Some(rule_id), None], None, state).await.unwrap();
+        truncate_timeline(plan_id, 101, None, state).await.unwrap();
+
+        // 失敗したコマンドは通知しない
+        assert!(link_member_person(member_id, Some(999), None, state).await.is_err());
+
+        let events: Vec<ChangeEvent> = received.lock().unwrap().iter().map(|notice| notice.event.clone()).collect();
+        assert_eq!(events, vec![
+            ChangeEvent::PlanChanged { plan_id, action: ChangeAction::Created },
+            ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Group(group_id), action: ChangeAction::Created },
+            ChangeEvent::StaffChanged { plan_id, target: ChangeTarget::Member(member_id), action: ChangeAction::Created },
+            ChangeEvent::RuleChanged { plan_id, target: ChangeTarget::Rule(rule_id), action: ChangeAction::Created },
+            ChangeEvent::TimelineChanged { plan_id, change: TimelineChange::Created, from_abs_week: 100, count: None },
+            ChangeEvent::TimelineChanged { plan_id, change: TimelineChange::Extended, from_abs_week: 100, count: Some(2) },
+            ChangeEvent::TimelineChanged { plan_id, change: TimelineChange::Truncated, from_abs_week: 101, count: None },
+        ]);
+
+        // 番号は1から順に振られ、get_change_version は最後の番号
+        let versions: Vec<u64> = received.lock().unwrap().iter().map(|notice| notice.version).collect();
+        assert_eq!(versions, (1..=7).collect::<Vec<u64>>());
+        assert_eq!(get_change_version(state).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_change_notice_payload() {
+        let services = setup_test_services().await;
+        let received: Arc<Mutex<Vec<ChangeNotice>>> = Arc::default();
+        let sink = received.clone();
+        services.events.subscribe(move |notice| sink.lock().unwrap().push(notice.clone()));
+
+        let app = tauri::test::mock_builder()
+            .manage(services)
+            .build(tauri::generate_context!())
+            .unwrap();
+        let state = app.state::<AppServices>();
+
+        let person_id = add_person("佐藤".to_string(), state).await.unwrap();
+
+        let notice = received.lock().unwrap()[0].clone();
+        assert_eq!(notice.event.name(), "people-changed");
+        assert_eq!(serde_json::to_value(&notice).unwrap(), serde_json::json!({
+            "version": 1,
+            "kind": "peopleChanged",
+            "target": { "type": "person", "id": person_id },
+            "action": "created",
+        }));
+    }
+}
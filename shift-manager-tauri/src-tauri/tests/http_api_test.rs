@@ -0,0 +1,394 @@
+#[cfg(test)]
+mod http_api_tests {
+    use serde_json::{json, Value};
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::SqlitePool;
+
+    use shift_manager_tauri_lib::{
+        application::{http_api, time::calculate_abs_week},
+        domain::repository::NOT_FOUND,
+        AppServices,
+    };
+
+    async fn setup_test_db() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create memory pool");
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        pool
+    }
+
+    /// localhost の空いているポートでサーバーを起動し、ベースURLを返す
+    async fn spawn_server() -> String {
+        let pool = setup_test_db().await;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            axum::serve(listener, http_api::router(AppServices::new(pool)))
+                .await
+                .unwrap();
+        });
+
+        format!("http://{}", addr)
+    }
+
+    async fn post_id(client: &reqwest::Client, url: String, body: Value) -> i64 {
+        let res = client.post(url).json(&body).send().await.unwrap();
+        assert_eq!(res.status(), 201);
+        res.json::<Value>().await.unwrap()["id"].as_i64().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_full_scenario_over_http() {
+        let base = spawn_server().await;
+        let client = reqwest::Client::new();
+
+        // 1. プラン・グループ・メンバー・ルール・アサイン
+        let plan_id = post_id(&client, format!("{base}/plans"), json!({ "name": "2026年 シフト計画" })).await;
+        let group_id = post_id(&client, format!("{base}/plans/{plan_id}/groups"), json!({ "name": "正社員" })).await;
+        let tanaka_id = post_id(&client, format!("{base}/groups/{group_id}/members"), json!({ "name": "田中" })).await;
+        post_id(&client, format!("{base}/groups/{group_id}/members"), json!({ "name": "佐藤" })).await;
+        let rule_id = post_id(&client, format!("{base}/plans/{plan_id}/rules"), json!({ "name": "標準ルール" })).await;
+        post_id(
+            &client,
+            format!("{base}/rules/{rule_id}/assignments"),
+            json!({ "weekday": 0, "shiftTime": 0, "groupId": group_id, "memberIndex": 0 }),
+        ).await;
+
+        let plans: Value = client.get(format!("{base}/plans")).send().await.unwrap().json().await.unwrap();
+        assert_eq!(plans[0]["id"], plan_id);
+
+        let config: Value = client.get(format!("{base}/plans/{plan_id}/config"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(config["groups"][0]["members"].as_array().unwrap().len(), 2);
+        assert_eq!(config["rules"][0]["assignments"][0]["target_group_id"], group_id);
+
+        // 2. カレンダー作成とタイムライン追記
+        let calendar: Value = client.get(format!("{base}/plans/{plan_id}/calendar"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert!(calendar.is_null());
+
+        // 2026年6月1日(月) の週から開始
+        let abs_week = calculate_abs_week(2026, 5, 1).unwrap();
+        post_id(
+            &client,
+            format!("{base}/plans/{plan_id}/calendar"),
+            json!({ "baseAbsWeek": abs_week, "initialDelta": 0 }),
+        ).await;
+
+        let res = client.post(format!("{base}/plans/{plan_id}/calendar/timeline"))
+            .json(&json!({ "startAbsWeek": abs_week, "statuses": [rule_id, null] }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 204);
+
+        let calendar: Value = client.get(format!("{base}/plans/{plan_id}/calendar"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(calendar["baseAbsWeek"], abs_week);
+        assert_eq!(calendar["timeline"][0]["Active"]["rule_id"], rule_id);
+        assert_eq!(calendar["timeline"][1], "Skipped");
+
+        // 3. 月ごとのシフト導出
+        let monthly: Value = client.get(format!("{base}/plans/{plan_id}/monthly-shift?year=2026&month=5"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(monthly["weeks"][0]["days"][0]["morning"][0], "田中");
+        assert!(monthly["weeks"][1].is_null());
+        // 月曜午前以外は誰もいない
+        assert_eq!(monthly["weeks"][0]["warnings"].as_array().unwrap().len(), 13);
+        assert_eq!(monthly["weeks"][0]["warnings"][0], json!({
+            "kind": "emptyPeriod", "weekday": 0, "period": "Afternoon", "message": "月曜 午後: 誰も入っていません"
+        }));
+
+        // 人員チェックの条件
+        let res = client.put(format!("{base}/groups/{group_id}/min-staff"))
+            .json(&json!({ "minPerPeriod": 2 }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 204);
+        let monthly: Value = client.get(format!("{base}/plans/{plan_id}/monthly-shift?year=2026&month=5"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(monthly["weeks"][0]["warnings"][0]["kind"], "belowMinimum");
+
+        // 4. 1人分のシフト
+        let schedule: Value = client
+            .get(format!("{base}/plans/{plan_id}/members/{tanaka_id}/schedule?from=2026-06-01&to=2026-06-30"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(schedule, json!([
+            { "date": "2026-06-01", "shiftTime": "Morning", "groupName": "正社員", "ruleName": "標準ルール", "holiday": null }
+        ]));
+
+        // 5. ルールを変える前に、変わるコマを確かめてから保存する
+        let edit = json!({ "kind": "addAssignment", "ruleId": rule_id, "weekday": 0, "shiftTime": 1, "groupId": group_id, "memberIndex": 0 });
+        let impact: Value = client.post(format!("{base}/plans/{plan_id}/rule-edits/preview"))
+            .json(&json!({ "edit": edit, "fromDate": "2026-06-01" }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(impact, json!({
+            "changes": [{
+                "absWeek": abs_week, "weekday": 0, "period": "Afternoon", "before": [], "after": ["田中"],
+                "date": "2026-06-01", "locked": false, "message": "2026-06-01 午後: (なし) → 田中"
+            }],
+            "lockedWeeks": []
+        }));
+
+        let res = client.post(format!("{base}/plans/{plan_id}/rule-edits"))
+            .json(&json!({ "edit": edit }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert!(res.json::<Value>().await.unwrap().is_i64());
+
+        // 6. タイムラインで使っているルールの削除
+        let res = client.delete(format!("{base}/rules/{rule_id}")).send().await.unwrap();
+        assert_eq!(res.status(), 400);
+        let res = client.delete(format!("{base}/plans/{plan_id}/rules/{rule_id}"))
+            .json(&json!({ "mode": { "kind": "refuse" } }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 400);
+        let body: Value = res.json().await.unwrap();
+        assert_eq!(body["error"], "ルール「標準ルール」はタイムラインの 1 週 (2026-06-01 の週) で使われています");
+
+        let changed: Value = client.delete(format!("{base}/plans/{plan_id}/rules/{rule_id}"))
+            .json(&json!({ "mode": { "kind": "skip" } }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(changed, json!(["2026-06-01"]));
+        let calendar: Value = client.get(format!("{base}/plans/{plan_id}/calendar"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(calendar["timeline"], json!(["Skipped", "Skipped"]));
+
+        // 7. ルールのパターンでタイムラインを延ばす
+        let alternate_rule = post_id(&client, format!("{base}/plans/{plan_id}/rules"), json!({ "name": "隔週" })).await;
+        let pattern_id = post_id(
+            &client,
+            format!("{base}/plans/{plan_id}/rule-patterns"),
+            json!({
+                "name": "隔週",
+                "kind": "sequence",
+                "anchorAbsWeek": 0,
+                "steps": [{ "ruleId": alternate_rule, "weeks": 1 }, { "ruleId": null, "weeks": 1 }],
+            }),
+        ).await;
+        let patterns: Value = client.get(format!("{base}/plans/{plan_id}/rule-patterns"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(patterns[0]["id"], pattern_id);
+        assert_eq!(patterns[0]["kind"], "sequence");
+
+        let start: Value = client.post(format!("{base}/plans/{plan_id}/calendar/timeline/pattern"))
+            .json(&json!({ "patternId": pattern_id, "weeks": 2 }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        let start = start.as_u64().unwrap();
+        let weeks: Value = client.get(format!("{base}/plans/{plan_id}/calendar/pattern-weeks"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(weeks, json!([
+            { "absWeek": start, "patternId": pattern_id },
+            { "absWeek": start + 1, "patternId": pattern_id },
+        ]));
+
+        let res = client.delete(format!("{base}/rule-patterns/{pattern_id}")).send().await.unwrap();
+        assert_eq!(res.status(), 204);
+        let weeks: Value = client.get(format!("{base}/plans/{plan_id}/calendar/pattern-weeks"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(weeks, json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_people_and_team_over_http() {
+        let base = spawn_server().await;
+        let client = reqwest::Client::new();
+        let abs_week = calculate_abs_week(2026, 5, 1).unwrap();
+
+        // 2つの店の田中を1人にまとめる
+        let person_id = post_id(&client, format!("{base}/people"), json!({ "name": "田中" })).await;
+        for shop in ["本店", "駅前店"] {
+            let plan_id = post_id(&client, format!("{base}/plans"), json!({ "name": shop })).await;
+            let group_id = post_id(&client, format!("{base}/plans/{plan_id}/groups"), json!({ "name": "正社員" })).await;
+            let member_id = post_id(&client, format!("{base}/groups/{group_id}/members"), json!({ "name": "田中" })).await;
+            let rule_id = post_id(&client, format!("{base}/plans/{plan_id}/rules"), json!({ "name": "標準ルール" })).await;
+            post_id(
+                &client,
+                format!("{base}/rules/{rule_id}/assignments"),
+                json!({ "weekday": 0, "shiftTime": 0, "groupId": group_id, "memberIndex": 0 }),
+            ).await;
+            post_id(&client, format!("{base}/plans/{plan_id}/calendar"), json!({ "baseAbsWeek": abs_week, "initialDelta": 0 })).await;
+            client.post(format!("{base}/plans/{plan_id}/calendar/timeline"))
+                .json(&json!({ "startAbsWeek": abs_week, "statuses": [rule_id] }))
+                .send().await.unwrap();
+            let res = client.put(format!("{base}/members/{member_id}/person"))
+                .json(&json!({ "personId": person_id }))
+                .send().await.unwrap();
+            assert_eq!(res.status(), 204);
+        }
+
+        let people: Value = client.get(format!("{base}/people")).send().await.unwrap().json().await.unwrap();
+        assert_eq!(people, json!([{ "id": person_id, "name": "田中" }]));
+
+        let team: Value = client.get(format!("{base}/team/schedule?from=2026-06-01&to=2026-06-07"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(team.as_array().unwrap().len(), 2);
+        assert_eq!(team[0]["planName"], "本店");
+        assert_eq!(team[0]["personId"], person_id);
+        let plan_id = team[1]["planId"].as_i64().unwrap();
+        let team: Value = client.get(format!("{base}/team/schedule?from=2026-06-01&to=2026-06-07&plans={plan_id}"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(team.as_array().unwrap().len(), 1);
+        let res = client.get(format!("{base}/team/schedule?from=2026-06-01&to=2026-06-07&plans=a")).send().await.unwrap();
+        assert_eq!(res.status(), 400);
+
+        let conflicts: Value = client.get(format!("{base}/team/conflicts?from=2026-06-01&to=2026-06-30"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(conflicts[0]["date"], "2026-06-01");
+        assert_eq!(conflicts[0]["shiftTime"], "Morning");
+        assert_eq!(conflicts[0]["message"], "田中 が 2026-06-01 午前 に 本店・駅前店 で重なっています");
+
+        // 人を消すと重なりではなくなる
+        let res = client.delete(format!("{base}/people/{person_id}")).send().await.unwrap();
+        assert_eq!(res.status(), 204);
+        let conflicts: Value = client.get(format!("{base}/team/conflicts?from=2026-06-01&to=2026-06-30"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(conflicts, json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_reported_as_json() {
+        let base = spawn_server().await;
+        let client = reqwest::Client::new();
+
+        // 存在しないプラン (読み出しも書き換えも 404)
+        let res = client.get(format!("{base}/plans/9999/config")).send().await.unwrap();
+        assert_eq!(res.status(), 404);
+        let body: Value = res.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().starts_with(NOT_FOUND));
+        let res = client.delete(format!("{base}/members/9999")).send().await.unwrap();
+        assert_eq!(res.status(), 404);
+
+        // 歯抜けの追記
+        let plan_id = post_id(&client, format!("{base}/plans"), json!({ "name": "Plan" })).await;
+        post_id(
+            &client,
+            format!("{base}/plans/{plan_id}/calendar"),
+            json!({ "baseAbsWeek": 100, "initialDelta": 0 }),
+        ).await;
+        let res = client.post(format!("{base}/plans/{plan_id}/calendar/timeline"))
+            .json(&json!({ "startAbsWeek": 105, "statuses": [null] }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 400);
+
+        // 名前の変更と削除
+        let res = client.put(format!("{base}/plans/{plan_id}"))
+            .json(&json!({ "name": "Renamed" }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 204);
+        let plans: Value = client.get(format!("{base}/plans")).send().await.unwrap().json().await.unwrap();
+        assert_eq!(plans[0]["name"], "Renamed");
+
+        let res = client.delete(format!("{base}/plans/{plan_id}")).send().await.unwrap();
+        assert_eq!(res.status(), 204);
+        let plans: Value = client.get(format!("{base}/plans")).send().await.unwrap().json().await.unwrap();
+        assert!(plans.as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stale_revision_is_conflict() {
+        let base = spawn_server().await;
+        let client = reqwest::Client::new();
+
+        let plan_id = post_id(&client, format!("{base}/plans"), json!({ "name": "Plan" })).await;
+        let revision_of = || {
+            let client = &client;
+            let base = &base;
+            async move {
+                let config: Value = client.get(format!("{base}/plans/{plan_id}/config"))
+                    .send().await.unwrap()
+                    .json().await.unwrap();
+                config["plan"]["revision"].as_i64().unwrap()
+            }
+        };
+        assert_eq!(revision_of().await, 0);
+
+        // 読み込んだときの revision を付けて書き換える
+        let res = client.post(format!("{base}/plans/{plan_id}/groups"))
+            .header("If-Match", "\"0\"")
+            .json(&json!({ "name": "正社員" }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 201);
+        assert_eq!(revision_of().await, 1);
+
+        // 同じ revision でもう一度書き換えようとすると 409
+        let res = client.post(format!("{base}/plans/{plan_id}/rules"))
+            .header("If-Match", "0")
+            .json(&json!({ "name": "標準ルール" }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 409);
+        let body: Value = res.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().starts_with("プランが他の画面で変更されています"));
+        let config: Value = client.get(format!("{base}/plans/{plan_id}/config"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert!(config["rules"].as_array().unwrap().is_empty());
+
+        // 数字でない If-Match
+        let res = client.put(format!("{base}/plans/{plan_id}"))
+            .header("If-Match", "*")
+            .json(&json!({ "name": "Renamed" }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 400);
+        assert_eq!(revision_of().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_keeps_revision() {
+        let base = spawn_server().await;
+        let client = reqwest::Client::new();
+
+        let plan_id = post_id(&client, format!("{base}/plans"), json!({ "name": "Plan" })).await;
+        let group_id = post_id(&client, format!("{base}/plans/{plan_id}/groups"), json!({ "name": "正社員" })).await;
+        let member_id = post_id(&client, format!("{base}/groups/{group_id}/members"), json!({ "name": "田中" })).await;
+        let revision_of = || {
+            let client = &client;
+            let base = &base;
+            async move {
+                let config: Value = client.get(format!("{base}/plans/{plan_id}/config"))
+                    .send().await.unwrap()
+                    .json().await.unwrap();
+                config["plan"]["revision"].as_i64().unwrap()
+            }
+        };
+        assert_eq!(revision_of().await, 2);
+
+        // 書き換えに失敗したら revision も進まない
+        let res = client.put(format!("{base}/members/{member_id}/period"))
+            .header("If-Match", "2")
+            .json(&json!({ "joinedOn": "2026-13-01", "leftOn": null }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 400);
+        assert_eq!(revision_of().await, 2);
+
+        // 同じ revision のままやり直せる (409 にならない)
+        let res = client.put(format!("{base}/members/{member_id}/period"))
+            .header("If-Match", "2")
+            .json(&json!({ "joinedOn": "2026-04-01", "leftOn": null }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), 204);
+        assert_eq!(revision_of().await, 3);
+    }
+}
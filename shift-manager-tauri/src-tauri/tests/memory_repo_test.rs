@@ -0,0 +1,996 @@
+#[cfg(test)]
+mod memory_repo_tests {
+    use shift_manager_tauri_lib::{
+        application::{
+            commands::{
+                compute_add_rule_assignment, compute_apply_rule_edit, compute_cross_plan_conflicts, compute_delete_weekly_rule_with, compute_holidays, compute_lock_month, compute_monthly_shift, compute_printable_month,
+                compute_rule_edit_impact, compute_staff_schedule, compute_team_schedule, compute_unlock_month,
+            },
+            dto::{MonthlyShiftResult, StaffShiftDto},
+            schedule_print::{check_output_path, render_html, PrintEntry, GROUP_COLORS},
+            time::calculate_abs_week,
+        },
+        domain::{
+            holiday::HolidayTable,
+            repository::{CalendarRepository, PlanRepository, RuleRepository, REVISION_CONFLICT},
+            rule_model::{
+                MemberConstraints, OverrideEntry, PatternStep, PlanItem, RuleDeletion, RuleEdit, RulePatternKind, ShiftTime, Weekday,
+            },
+            shift_calendar_model::{PatternWeek, SkipRuleKind, WeekStatus},
+        },
+        infrastructure::memory_repo::InMemoryRepository,
+        AppServices,
+    };
+    use shift_timeline::{OverrideAction, Rotation};
+
+    /// プラン「Plan」・グループ「正社員」・メンバー・ルール「標準」を作る (アサインやカレンダーは各テストで足す)
+    /// 戻り値は (プランのID, グループのID, `members` の順のメンバーのID, ルールのID)
+    async fn setup_plan<const N: usize>(
+        services: &AppServices<InMemoryRepository, InMemoryRepository>,
+        members: [&str; N],
+    ) -> (i64, i64, [i64; N], i64) {
+        let plan_id = services.rule.create_plan("Plan").await.unwrap();
+        let group_id = services.rule.add_staff_group(plan_id, "正社員").await.unwrap();
+        let mut member_ids = [0; N];
+        for (id, name) in member_ids.iter_mut().zip(members) {
+            *id = services.rule.add_staff_member(group_id, name).await.unwrap();
+        }
+        let rule_id = services.rule.add_weekly_rule(plan_id, "標準").await.unwrap();
+        (plan_id, group_id, member_ids, rule_id)
+    }
+
+    // マイグレーション無しでセットアップできる
+    #[tokio::test]
+    async fn test_plan_config_without_db() {
+        let services = AppServices::in_memory();
+        let repo = &services.rule;
+
+        let old_plan = repo.create_plan("Old").await.unwrap();
+        let plan_id = repo.create_plan("Test Plan 2026").await.unwrap();
+
+        let group_id = repo.add_staff_group(plan_id, "Kitchen").await.unwrap();
+        repo.add_staff_member(group_id, "Tanaka").await.unwrap();
+        repo.add_staff_member(group_id, "Suzuki").await.unwrap();
+
+        let rule_id = repo.add_weekly_rule(plan_id, "Basic Week").await.unwrap();
+        repo.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+        repo.add_rule_assignment(rule_id, 0, 1, group_id, 1).await.unwrap();
+
+        // 不正な曜日は弾く
+        assert!(repo.add_rule_assignment(rule_id, 7, 0, group_id, 0).await.is_err());
+
+        // 新しいプランが先頭 (SQLite版の ORDER BY id DESC と同じ)
+        let plans = repo.list_plans().await.unwrap();
+        assert_eq!(plans.iter().map(|p| p.id).collect::<Vec<_>>(), vec![plan_id, old_plan]);
+
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert_eq!(config.plan.name, "Test Plan 2026");
+        assert_eq!(config.groups.len(), 1);
+        assert_eq!(config.groups[0].members[1].name, "Suzuki");
+        assert_eq!(config.groups[0].members[1].sort_order, 1);
+        assert_eq!(config.rules[0].assignments.len(), 2);
+
+        assert!(repo.get_plan_config(9999).await.is_err());
+    }
+
+    // revision はSQLite版と同じく、合っていれば進め、古ければ断る
+    #[tokio::test]
+    async fn test_revision_conflict() {
+        let services = AppServices::in_memory();
+        let repo = &services.rule;
+
+        let plan_id = repo.create_plan("Plan").await.unwrap();
+        let group_id = repo.add_staff_group(plan_id, "Group").await.unwrap();
+        let rule_id = repo.add_weekly_rule(plan_id, "Rule").await.unwrap();
+        assert_eq!(repo.get_plan_config(plan_id).await.unwrap().plan.revision, 0);
+
+        assert_eq!(repo.claim_revision(PlanItem::Rule(rule_id), Some(0)).await, Ok(plan_id));
+        let err = repo.claim_revision(PlanItem::Group(group_id), Some(0)).await.unwrap_err();
+        assert!(err.starts_with(REVISION_CONFLICT), "{}", err);
+        assert_eq!(repo.get_plan_config(plan_id).await.unwrap().plan.revision, 1);
+        assert!(repo.claim_revision(PlanItem::Rule(9999), None).await.is_err());
+
+        // 書き換えに失敗したら revision も書き換えも残らない
+        let err = services
+            .with_revision(PlanItem::Plan(plan_id), Some(1), async |tx| {
+                tx.rule.add_staff_group(plan_id, "Extra").await?;
+                Err::<(), _>(String::from("失敗"))
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err, "失敗");
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert_eq!(config.plan.revision, 1);
+        assert_eq!(config.groups.len(), 1);
+
+        // 同じ revision のままやり直せる
+        let (claimed, group2_id) = services
+            .with_revision(PlanItem::Plan(plan_id), Some(1), async |tx| tx.rule.add_staff_group(plan_id, "Extra").await)
+            .await
+            .unwrap();
+        assert_eq!(claimed, plan_id);
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert_eq!(config.plan.revision, 2);
+        assert_eq!(config.groups.iter().map(|g| g.group.id).collect::<Vec<_>>(), vec![group_id, group2_id]);
+    }
+
+    #[tokio::test]
+    async fn test_delete_constraints() {
+        let services = AppServices::in_memory();
+
+        let plan_id = services.rule.create_plan("Plan").await.unwrap();
+        let group_id = services.rule.add_staff_group(plan_id, "Group").await.unwrap();
+        services.rule.add_staff_member(group_id, "A").await.unwrap();
+        let rule_id = services.rule.add_weekly_rule(plan_id, "Rule").await.unwrap();
+        services.rule.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+
+        services.calendar.create_calendar(plan_id, 100, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, 100, vec![Some(rule_id)]).await.unwrap();
+
+        // アサインから参照されているグループ、タイムラインから参照されているルールは消せない
+        assert!(services.rule.delete_staff_group(group_id).await.is_err());
+        assert!(services.rule.delete_weekly_rule(rule_id).await.is_err());
+
+        // プランごとならまとめて消える
+        services.rule.delete_plan(plan_id).await.unwrap();
+        assert!(services.rule.list_plans().await.unwrap().is_empty());
+        assert!(services.calendar.find_by_plan_id(plan_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_append_timeline() {
+        let services = AppServices::in_memory();
+        let cal_repo = &services.calendar;
+
+        let plan_id = services.rule.create_plan("Plan").await.unwrap();
+        let rule_a = services.rule.add_weekly_rule(plan_id, "A").await.unwrap();
+        let rule_b = services.rule.add_weekly_rule(plan_id, "B").await.unwrap();
+
+        let calendar_id = cal_repo.create_calendar(plan_id, 100, 0).await.unwrap();
+        assert!(cal_repo.create_calendar(plan_id, 100, 0).await.is_err());
+
+        cal_repo.try_to_append_timeline(plan_id, 100, vec![Some(rule_a), None]).await.unwrap();
+
+        // 確定済みの週と食い違う
+        assert!(cal_repo.try_to_append_timeline(plan_id, 100, vec![Some(rule_b)]).await.is_err());
+        // 歯抜け
+        assert!(cal_repo.try_to_append_timeline(plan_id, 103, vec![Some(rule_a)]).await.is_err());
+        // 存在しないルール
+        assert!(cal_repo.try_to_append_timeline(plan_id, 102, vec![Some(9999)]).await.is_err());
+
+        cal_repo.try_to_append_timeline(plan_id, 101, vec![None, Some(rule_b)]).await.unwrap();
+
+        let statuses = cal_repo.fetch_status_range(calendar_id, 1, 10).await.unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses[0].is_skipped());
+        match &statuses[1] {
+            WeekStatus::Active { logical_delta, payload } => {
+                assert_eq!(*logical_delta, 1);
+                assert_eq!(payload.rule_id, rule_b);
+            }
+            WeekStatus::Skipped => panic!("Active のはず"),
+        }
+    }
+
+    // commands のロジックをDB無しで実行する
+    #[tokio::test]
+    async fn test_compute_monthly_shift() {
+        let services = AppServices::in_memory();
+
+        let plan_id = services.rule.create_plan("Plan").await.unwrap();
+        let group_id = services.rule.add_staff_group(plan_id, "正社員").await.unwrap();
+        services.rule.add_staff_member(group_id, "田中").await.unwrap();
+        services.rule.add_staff_member(group_id, "佐藤").await.unwrap();
+        let rule_id = services.rule.add_weekly_rule(plan_id, "標準ルール").await.unwrap();
+        services.rule.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+
+        // 2026年6月1日(月) の週から開始
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar
+            .try_to_append_timeline(plan_id, base, vec![Some(rule_id), None, Some(rule_id)])
+            .await
+            .unwrap();
+
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert_eq!(result.weeks.len(), 3);
+        assert_eq!(result.weeks[0].as_ref().unwrap().days[0].morning, vec!["田中"]);
+        assert!(result.weeks[1].is_none());
+        assert!(result.weeks[2].is_some());
+
+        // 基準週より前の月 (2026年4月) は空欄で埋まる (panicしない)
+        let before = compute_monthly_shift(&services, plan_id, 2026, 3).await.unwrap();
+        assert_eq!(before.weeks.len(), 5);
+        assert!(before.weeks.iter().all(|w| w.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_compute_staff_schedule() {
+        let services = AppServices::in_memory();
+
+        let plan_id = services.rule.create_plan("Plan").await.unwrap();
+        let full_time = services.rule.add_staff_group(plan_id, "正社員").await.unwrap();
+        let tanaka = services.rule.add_staff_member(full_time, "田中").await.unwrap();
+        let part_time = services.rule.add_staff_group(plan_id, "パート").await.unwrap();
+        let sato = services.rule.add_staff_member(part_time, "佐藤").await.unwrap();
+
+        let standard = services.rule.add_weekly_rule(plan_id, "標準").await.unwrap();
+        services.rule.add_rule_assignment(standard, 0, 0, full_time, 0).await.unwrap();
+        let saturday = services.rule.add_weekly_rule(plan_id, "土曜").await.unwrap();
+        services.rule.add_rule_assignment(saturday, 5, 1, full_time, 0).await.unwrap();
+
+        // 2026年6月1日(月) の週から 標準, skip, 土曜
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar
+            .try_to_append_timeline(plan_id, base, vec![Some(standard), None, Some(saturday)])
+            .await
+            .unwrap();
+
+        let schedule = compute_staff_schedule(&services, plan_id, tanaka, "2026-06-01", "2026-06-30").await.unwrap();
+        let found: Vec<_> = schedule
+            .iter()
+            .map(|s| (s.date.as_str(), format!("{:?}", s.shift_time), s.group_name.as_str(), s.rule_name.as_str()))
+            .collect();
+        assert_eq!(found, vec![
+            ("2026-06-01", String::from("Morning"), "正社員", "標準"),
+            ("2026-06-20", String::from("Afternoon"), "正社員", "土曜"),
+        ]);
+
+        // 週の途中から・途中まで
+        let schedule = compute_staff_schedule(&services, plan_id, tanaka, "2026-06-02", "2026-06-19").await.unwrap();
+        assert!(schedule.is_empty());
+
+        // アサインの無いメンバー
+        let schedule = compute_staff_schedule(&services, plan_id, sato, "2026-06-01", "2026-06-30").await.unwrap();
+        assert!(schedule.is_empty());
+
+        // 不正な指定
+        assert!(compute_staff_schedule(&services, plan_id, 9999, "2026-06-01", "2026-06-30").await.is_err());
+        assert!(compute_staff_schedule(&services, plan_id, tanaka, "2026/06/01", "2026-06-30").await.is_err());
+        assert!(compute_staff_schedule(&services, plan_id, tanaka, "2026-06-30", "2026-06-01").await.is_err());
+    }
+
+    // 週・日単位の調整で足したコマ・外したコマが1人分のシフトに反映される
+    #[tokio::test]
+    async fn test_staff_schedule_with_overrides() {
+        let services = AppServices::in_memory();
+
+        let (plan_id, group_id, [tanaka, sato], rule_id) = setup_plan(&services, ["田中", "佐藤"]).await;
+        services.rule.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+        services.rule.add_rule_assignment(rule_id, 2, 1, group_id, 0).await.unwrap();
+
+        // 2026年6月1日(月) の週
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id)]).await.unwrap();
+
+        let entry = |weekday, shift_time, member_id, action| OverrideEntry { abs_week: base, weekday, shift_time, member_id, action };
+        // 田中を金曜午前に足し、水曜午後から外す
+        services.rule.add_shift_override(plan_id, &entry(Weekday::Friday, ShiftTime::Morning, tanaka, OverrideAction::Add)).await.unwrap();
+        let removed = services.rule
+            .add_shift_override(plan_id, &entry(Weekday::Wednesday, ShiftTime::Afternoon, tanaka, OverrideAction::Remove))
+            .await
+            .unwrap();
+
+        let dates = |schedule: Vec<StaffShiftDto>| {
+            schedule.into_iter().map(|s| (s.date, format!("{:?}", s.shift_time))).collect::<Vec<_>>()
+        };
+        let schedule = compute_staff_schedule(&services, plan_id, tanaka, "2026-06-01", "2026-06-07").await.unwrap();
+        assert_eq!(dates(schedule), vec![
+            (String::from("2026-06-01"), String::from("Morning")),
+            (String::from("2026-06-05"), String::from("Morning")),
+        ]);
+        // 月ごとのシフトも同じ
+        let monthly = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        let first_week = monthly.weeks[0].as_ref().unwrap();
+        assert_eq!(first_week.days[4].morning, vec!["田中"]);
+        assert!(first_week.days[2].afternoon.is_empty());
+        // 他のメンバーは変わらない
+        assert!(compute_staff_schedule(&services, plan_id, sato, "2026-06-01", "2026-06-07").await.unwrap().is_empty());
+
+        // 調整を消すと元に戻る
+        services.rule.delete_shift_override(removed).await.unwrap();
+        let schedule = compute_staff_schedule(&services, plan_id, tanaka, "2026-06-01", "2026-06-07").await.unwrap();
+        assert_eq!(schedule.len(), 3);
+
+        // 他のプランのメンバーは指定できない
+        let other_plan = services.rule.create_plan("Other").await.unwrap();
+        let other_group = services.rule.add_staff_group(other_plan, "正社員").await.unwrap();
+        let other = services.rule.add_staff_member(other_group, "鈴木").await.unwrap();
+        assert!(services.rule.add_shift_override(plan_id, &entry(Weekday::Monday, ShiftTime::Morning, other, OverrideAction::Add)).await.is_err());
+
+        // メンバーを消すと、そのメンバーの調整も消える
+        services.rule.delete_staff_member(tanaka).await.unwrap();
+        assert!(services.rule.get_plan_config(plan_id).await.unwrap().overrides.is_empty());
+    }
+
+    // 導出した週に人員チェックの警告が付く
+    #[tokio::test]
+    async fn test_compute_monthly_shift_warnings() {
+        let services = AppServices::in_memory();
+
+        let (plan_id, group_id, _, rule_id) = setup_plan(&services, ["田中"]).await;
+        // 月〜金の午前・午後に田中、月曜午前だけ2回
+        for weekday in 0..5 {
+            services.rule.add_rule_assignment(rule_id, weekday, 0, group_id, 0).await.unwrap();
+            services.rule.add_rule_assignment(rule_id, weekday, 1, group_id, 0).await.unwrap();
+        }
+        services.rule.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id)]).await.unwrap();
+
+        let kinds = |result: &MonthlyShiftResult| -> Vec<String> {
+            result.weeks[0].as_ref().unwrap().warnings
+                .iter()
+                .map(|w| serde_json::to_value(w).unwrap()["kind"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // 条件なし: 土日の空きと月曜午前の重複
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert_eq!(kinds(&result), vec!["doubleBooked", "emptyPeriod", "emptyPeriod", "emptyPeriod", "emptyPeriod"]);
+        assert_eq!(result.weeks[0].as_ref().unwrap().warnings[0].message, "月曜 午前: 田中 が2回以上入っています");
+
+        // 週4日まで・1コマ2人以上
+        services.rule.update_plan_max_days(plan_id, Some(4)).await.unwrap();
+        services.rule.update_group_min_staff(group_id, Some(2)).await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        let kinds = kinds(&result);
+        assert_eq!(kinds.iter().filter(|k| *k == "belowMinimum").count(), 9); // 月曜午前以外の平日
+        assert_eq!(kinds.last().unwrap(), "tooManyDays");
+
+        let json = serde_json::to_value(&result.weeks[0].as_ref().unwrap().warnings[1]).unwrap();
+        assert_eq!(json["weekday"], 0);
+        assert_eq!(json["period"], "Afternoon");
+        assert_eq!(json["required"], 2);
+        assert_eq!(json["assigned"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_compute_monthly_shift_violations() {
+        let services = AppServices::in_memory();
+
+        let (plan_id, group_id, [tanaka, _], rule_id) = setup_plan(&services, ["田中", "佐藤"]).await;
+        // 月〜金の午前・午後に田中、土曜午前に佐藤
+        for weekday in 0..5 {
+            services.rule.add_rule_assignment(rule_id, weekday, 0, group_id, 0).await.unwrap();
+            services.rule.add_rule_assignment(rule_id, weekday, 1, group_id, 0).await.unwrap();
+        }
+        services.rule.add_rule_assignment(rule_id, 5, 0, group_id, 1).await.unwrap();
+
+        // 2026/6/1 は月曜
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id)]).await.unwrap();
+
+        // 制約なし
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert!(result.violations.is_empty());
+
+        services.rule.set_member_constraints(tanaka, &MemberConstraints {
+            max_shifts_per_week: Some(8),
+            max_shifts_per_month: Some(9),
+            days_off: vec![Weekday::Wednesday],
+            no_morning_after_afternoon: true,
+            employment_start: None,
+            employment_end: Some(String::from("2026-06-04")),
+        }).await.unwrap();
+
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        let found: Vec<(String, Option<String>)> = result.violations
+            .iter()
+            .map(|v| (
+                serde_json::to_value(v).unwrap()["kind"].as_str().unwrap().to_string(),
+                v.date.clone(),
+            ))
+            .collect();
+        let day = |d: u32| Some(format!("2026-06-{:02}", d));
+        assert_eq!(found, vec![
+            (String::from("noRest"), day(2)),
+            (String::from("dayOff"), day(3)),
+            (String::from("noRest"), day(3)),
+            (String::from("dayOff"), day(3)),
+            (String::from("noRest"), day(4)),
+            (String::from("noRest"), day(5)),
+            (String::from("outsideEmployment"), day(5)),
+            (String::from("outsideEmployment"), day(5)),
+            (String::from("tooManyShiftsInWeek"), day(1)),
+            (String::from("tooManyShiftsInMonth"), None),
+        ]);
+        assert!(result.violations.iter().all(|v| v.member_id == tanaka));
+        assert_eq!(result.violations[1].message, "水曜 午前: 田中 は休みの曜日です");
+        assert_eq!(result.violations[9].message, "田中 のコマ数が月 10 です (上限 9)");
+
+        let json = serde_json::to_value(&result.violations[8]).unwrap();
+        assert_eq!(json["memberId"], tanaka);
+        assert_eq!(json["shifts"], 10);
+        assert_eq!(json["max"], 8);
+
+        services.rule.clear_member_constraints(tanaka).await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert!(result.violations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_compute_monthly_shift_skills() {
+        let services = AppServices::in_memory();
+
+        let (plan_id, group_id, [_, sato], rule_id) = setup_plan(&services, ["田中", "佐藤"]).await;
+        // 月曜午前に田中 (開店が必要)
+        let assignment_id = services.rule.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+        services.rule.set_assignment_skills(assignment_id, &[String::from("開店")]).await.unwrap();
+
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id)]).await.unwrap();
+
+        // 誰も開店を持っていない
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        let week = result.weeks[0].as_ref().unwrap();
+        assert_eq!(week.skill_issues.len(), 1);
+        assert_eq!(week.skill_issues[0].message, "月曜 午前: 田中 は 開店 を持っていません");
+        let json = serde_json::to_value(week).unwrap();
+        assert_eq!(json["skillIssues"][0]["kind"], "unqualified");
+        assert_eq!(json["skillIssues"][0]["missing"][0], "開店");
+
+        // 佐藤が開店を持っていても、入れ替えなければ報告だけ
+        services.rule.set_member_skills(sato, &[String::from("開店")]).await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        let week = result.weeks[0].as_ref().unwrap();
+        assert_eq!(week.days[0].morning, vec!["田中"]);
+        assert_eq!(week.skill_issues.len(), 1);
+
+        // 入れ替える
+        services.rule.update_plan_auto_swap(plan_id, true).await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        let week = result.weeks[0].as_ref().unwrap();
+        assert_eq!(week.days[0].morning, vec!["佐藤"]);
+        assert_eq!(week.skill_issues[0].message, "月曜 午前: 田中 の代わりに 佐藤 を入れました");
+    }
+
+    #[tokio::test]
+    async fn test_compute_monthly_shift_rotation() {
+        let services = AppServices::in_memory();
+
+        let (plan_id, group_id, [tanaka, _, _], rule_id) = setup_plan(&services, ["田中", "佐藤", "鈴木"]).await;
+        // 毎日午前に0番の人
+        for weekday in 0..7 {
+            services.rule.add_rule_assignment(rule_id, weekday, 0, group_id, 0).await.unwrap();
+        }
+
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id); 5]).await.unwrap();
+
+        let mornings = |result: &MonthlyShiftResult, week: usize| -> Vec<String> {
+            result.weeks[week].as_ref().unwrap().days.iter().map(|d| d.morning.join(",")).collect()
+        };
+
+        // ローテーションしない
+        services.rule.update_group_rotation(group_id, Some(Rotation::Fixed)).await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert!(mornings(&result, 0).iter().chain(&mornings(&result, 1)).all(|name| name == "田中"));
+        let schedule = compute_staff_schedule(&services, plan_id, tanaka, "2026-06-01", "2026-06-14").await.unwrap();
+        assert_eq!(schedule.len(), 14);
+
+        // 日ごと (週をまたいでも続けてずれる)
+        services.rule.update_group_rotation(group_id, Some(Rotation::ByDay)).await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert_eq!(mornings(&result, 0), vec!["田中", "佐藤", "鈴木", "田中", "佐藤", "鈴木", "田中"]);
+        assert_eq!(mornings(&result, 1)[0], "佐藤");
+        // 1人分のシフトも同じ入れ替えになる
+        let schedule = compute_staff_schedule(&services, plan_id, tanaka, "2026-06-01", "2026-06-07").await.unwrap();
+        let dates: Vec<_> = schedule.iter().map(|s| s.date.as_str()).collect();
+        assert_eq!(dates, vec!["2026-06-01", "2026-06-04", "2026-06-07"]);
+
+        // 2週ごと
+        services.rule.update_group_rotation(group_id, Some(Rotation::EveryNWeeks { weeks: 2 })).await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        let first_days: Vec<_> = (0..5).map(|week| mornings(&result, week)[0].clone()).collect();
+        assert_eq!(first_days, vec!["田中", "田中", "佐藤", "佐藤", "鈴木"]);
+    }
+
+    #[tokio::test]
+    async fn test_compute_monthly_shift_member_period() {
+        let services = AppServices::in_memory();
+
+        let (plan_id, group_id, [tanaka, _], rule_id) = setup_plan(&services, ["田中", "佐藤"]).await;
+        services.rule.update_group_rotation(group_id, Some(Rotation::ByWeek)).await.unwrap();
+        // 毎日午前に0番の人 (週ごとに1人ずつずれる)
+        for weekday in 0..7 {
+            services.rule.add_rule_assignment(rule_id, weekday, 0, group_id, 0).await.unwrap();
+        }
+
+        // 2026-06-01 (月) から5週
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id); 5]).await.unwrap();
+
+        let mondays = |result: &MonthlyShiftResult| -> Vec<String> {
+            result.weeks.iter().map(|week| week.as_ref().unwrap().days[0].morning.join(",")).collect()
+        };
+        let before = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert_eq!(mondays(&before), vec!["田中", "佐藤", "田中", "佐藤", "田中"]);
+
+        // 3週目から加わった人は、それより前の週を変えない
+        let suzuki = services.rule.add_staff_member(group_id, "鈴木").await.unwrap();
+        services.rule.update_member_period(suzuki, Some("2026-06-15"), None).await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert_eq!(mondays(&result)[..2], mondays(&before)[..2]);
+        assert_eq!(mondays(&result)[2], "鈴木");
+
+        // 辞めた人は削除せずに left_on を入れる (それより前の週は変わらない)
+        let joined = mondays(&result);
+        services.rule.update_member_period(tanaka, None, Some("2026-06-21")).await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert_eq!(mondays(&result)[..3], joined[..3]);
+        assert_eq!(mondays(&result)[3..], ["鈴木", "佐藤"]);
+        assert!(result.weeks[3..]
+            .iter()
+            .flat_map(|week| &week.as_ref().unwrap().days)
+            .all(|day| !day.morning.contains(&String::from("田中"))));
+
+        // 1人分のシフトも在籍期間の中だけ
+        let schedule = compute_staff_schedule(&services, plan_id, tanaka, "2026-06-01", "2026-06-30").await.unwrap();
+        assert!(!schedule.is_empty());
+        assert!(schedule.iter().all(|s| s.date.as_str() <= "2026-06-21"));
+        let schedule = compute_staff_schedule(&services, plan_id, suzuki, "2026-06-01", "2026-06-14").await.unwrap();
+        assert!(schedule.is_empty());
+    }
+
+    // 固定した週は、ルールやメンバーを変えても変わらない
+    #[tokio::test]
+    async fn test_compute_monthly_shift_locked_weeks() {
+        let services = AppServices::in_memory();
+
+        let (plan_id, group_id, [tanaka, _], rule_id) = setup_plan(&services, ["田中", "佐藤"]).await;
+        services.rule.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+
+        // 2026-06-01 (月) から5週 (6月は5週表示)
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id), Some(rule_id), None, Some(rule_id), Some(rule_id)]).await.unwrap();
+
+        let mondays = |result: &MonthlyShiftResult| -> Vec<Option<String>> {
+            result.weeks.iter().map(|week| week.as_ref().map(|w| w.days[0].morning.join(","))).collect()
+        };
+        let before = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert!(before.weeks.iter().flatten().all(|week| !week.locked));
+
+        // Activeな週だけを固定する
+        let locked = compute_lock_month(&services, plan_id, 2026, 5).await.unwrap();
+        assert_eq!(locked, vec![base, base + 1, base + 3, base + 4]);
+
+        // ルールとメンバーを変えても、固定した週は変わらない
+        let suzuki = services.rule.add_staff_member(group_id, "鈴木").await.unwrap();
+        services.rule.update_group_rotation(group_id, Some(Rotation::Fixed)).await.unwrap();
+        services.rule.add_rule_assignment(rule_id, 0, 0, group_id, 2).await.unwrap();
+        services.rule.update_member_name(tanaka, "田中 (旧姓)").await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert_eq!(mondays(&result), mondays(&before));
+        assert!(result.weeks.iter().flatten().all(|week| week.locked));
+
+        let schedule = compute_staff_schedule(&services, plan_id, suzuki, "2026-06-01", "2026-06-30").await.unwrap();
+        assert!(schedule.is_empty());
+
+        // 固定を外すと今のルールから導出し直す
+        compute_unlock_month(&services, plan_id, 2026, 5).await.unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert_eq!(mondays(&result)[0].as_deref(), Some("田中 (旧姓),鈴木"));
+        assert!(result.weeks.iter().flatten().all(|week| !week.locked));
+
+        // カレンダーが無いプランは固定できない
+        let other_plan = services.rule.create_plan("Other").await.unwrap();
+        assert!(compute_lock_month(&services, other_plan, 2026, 5).await.is_err());
+    }
+
+    // 固定した後にメンバーを削除しても、固定した週のコマが次のメンバーにずれない
+    #[tokio::test]
+    async fn test_locked_weeks_after_member_deleted() {
+        let services = AppServices::in_memory();
+
+        let (plan_id, group_id, [tanaka, sato], rule_id) = setup_plan(&services, ["田中", "佐藤"]).await;
+        // 月曜午前に 田中、水曜午後に 佐藤
+        services.rule.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+        services.rule.add_rule_assignment(rule_id, 2, 1, group_id, 1).await.unwrap();
+
+        let base = calculate_abs_week(2026, 5, 1).unwrap(); // 2026-06-01 (月)
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id)]).await.unwrap();
+        compute_lock_month(&services, plan_id, 2026, 5).await.unwrap();
+
+        // 田中 を削除すると 佐藤 がグループの先頭になるが、田中 の固定したコマは 佐藤 のものにならない
+        services.rule.delete_staff_member(tanaka).await.unwrap();
+        let schedule = compute_staff_schedule(&services, plan_id, sato, "2026-06-01", "2026-06-07").await.unwrap();
+        assert_eq!(
+            schedule.iter().map(|s| (s.date.as_str(), format!("{:?}", s.shift_time))).collect::<Vec<_>>(),
+            vec![("2026-06-03", String::from("Afternoon"))],
+        );
+
+        // 固定した週の表示は変わらない (削除したメンバーは名前のまま)
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        let week = result.weeks[0].as_ref().unwrap();
+        assert!(week.locked);
+        assert_eq!(week.days[0].morning, vec!["田中"]);
+        assert_eq!(week.days[2].afternoon, vec!["佐藤"]);
+        // 佐藤 の制約のチェックにも、田中 のコマは数えない
+        services.rule
+            .set_member_constraints(sato, &MemberConstraints { max_shifts_per_week: Some(1), ..Default::default() })
+            .await
+            .unwrap();
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert!(result.violations.is_empty());
+
+        // もう一度固定しても、固定済みの週は固定し直さない (田中 のコマは 佐藤 にならない)
+        assert!(compute_lock_month(&services, plan_id, 2026, 5).await.unwrap().is_empty());
+        let schedule = compute_staff_schedule(&services, plan_id, sato, "2026-06-01", "2026-06-07").await.unwrap();
+        assert_eq!(schedule.len(), 1);
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert_eq!(result.weeks[0].as_ref().unwrap().days[0].morning, vec!["田中"]);
+    }
+
+    // ルールを変える前に、変わるコマと固定した週を確かめる
+    #[tokio::test]
+    async fn test_rule_edit_impact() {
+        let services = AppServices::in_memory();
+
+        let (plan_id, group_id, _, rule_id) = setup_plan(&services, ["田中", "佐藤"]).await;
+        services.rule.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+
+        // 2026-06-01 (月) から6週。6月の表示 (5週) だけを固定する
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id); 6]).await.unwrap();
+        compute_lock_month(&services, plan_id, 2026, 5).await.unwrap();
+
+        // 月曜午後に1人足す
+        let edit = RuleEdit::AddAssignment { rule_id, weekday: 0, shift_time: 1, group_id, member_index: 0 };
+        let impact = compute_rule_edit_impact(&services, plan_id, &edit, "2026-06-03").await.unwrap();
+        assert_eq!(impact.changes.len(), 6);
+        assert!(impact.changes.iter().all(|c| c.change.weekday == 0 && c.change.before.is_empty() && c.change.after.len() == 1));
+        assert!(impact.changes[0].message.starts_with("2026-06-01 午後: (なし) → "));
+        let locked: Vec<bool> = impact.changes.iter().map(|c| c.locked).collect();
+        assert_eq!(locked, vec![true, true, true, true, true, false]);
+        assert_eq!(impact.locked_weeks.len(), 5);
+        assert_eq!(impact.locked_weeks[0], "2026-06-01");
+
+        // 指定した日の週から。保存はしていない
+        let impact = compute_rule_edit_impact(&services, plan_id, &edit, "2026-07-06").await.unwrap();
+        assert_eq!(impact.changes.len(), 1);
+        assert_eq!(impact.changes[0].date, "2026-07-06");
+        assert_eq!(services.rule.get_plan_config(plan_id).await.unwrap().rules[0].assignments.len(), 1);
+
+        // 固定した週が使っているルールは、allow_locked が無いと変えない
+        let err = compute_apply_rule_edit(&services, plan_id, edit.clone(), false).await.unwrap_err();
+        assert!(err.contains("固定した週"), "{}", err);
+        // ルールIDだけで穴を足す操作 (add_rule_assignment) も同じように断り、revision は進まない
+        let revision = services.rule.list_plans().await.unwrap()[0].revision;
+        let err = services
+            .with_plan_revision(PlanItem::Rule(rule_id), None, async |tx, plan_id| compute_add_rule_assignment(tx, plan_id, edit.clone()).await)
+            .await
+            .unwrap_err();
+        assert!(err.contains("固定した週"), "{}", err);
+        assert_eq!(services.rule.list_plans().await.unwrap()[0].revision, revision);
+        let added = compute_apply_rule_edit(&services, plan_id, edit, true).await.unwrap();
+        assert!(added.is_some());
+        assert_eq!(services.rule.get_plan_config(plan_id).await.unwrap().rules[0].assignments.len(), 2);
+
+        // 固定を外せばそのまま変えられる
+        compute_unlock_month(&services, plan_id, 2026, 5).await.unwrap();
+        let edit = RuleEdit::DeleteAssignment { assignment_id: added.unwrap() };
+        assert_eq!(compute_rule_edit_impact(&services, plan_id, &edit, "2026-06-01").await.unwrap().changes.len(), 6);
+        assert_eq!(compute_apply_rule_edit(&services, plan_id, edit, false).await.unwrap(), None);
+
+        // 無い穴・ルールはエラー
+        assert!(compute_rule_edit_impact(&services, plan_id, &RuleEdit::DeleteAssignment { assignment_id: 999 }, "2026-06-01").await.is_err());
+        assert!(compute_apply_rule_edit(&services, plan_id, RuleEdit::DeleteRule { rule_id: 999 }, false).await.is_err());
+    }
+
+    // タイムラインで使われているルールの削除
+    #[tokio::test]
+    async fn test_delete_weekly_rule_with() {
+        let services = AppServices::in_memory();
+
+        let plan_id = services.rule.create_plan("Plan").await.unwrap();
+        let group_id = services.rule.add_staff_group(plan_id, "正社員").await.unwrap();
+        services.rule.add_staff_member(group_id, "田中").await.unwrap();
+        let weekday_rule = services.rule.add_weekly_rule(plan_id, "平日").await.unwrap();
+        let holiday_rule = services.rule.add_weekly_rule(plan_id, "祝日週").await.unwrap();
+        services.rule.add_rule_assignment(weekday_rule, 0, 0, group_id, 0).await.unwrap();
+
+        let base = calculate_abs_week(2026, 5, 1).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(weekday_rule), Some(holiday_rule), Some(holiday_rule)]).await.unwrap();
+
+        // 使っている週を日付で示して断る
+        let err = compute_delete_weekly_rule_with(&services, plan_id, holiday_rule, &RuleDeletion::Refuse).await.unwrap_err();
+        assert_eq!(err, "ルール「祝日週」はタイムラインの 2 週 (2026-06-08, 2026-06-15 の週) で使われています");
+        // リポジトリのモードを指定しない削除は今まで通り外部キー制約で失敗する
+        assert!(services.rule.delete_weekly_rule(holiday_rule).await.is_err());
+        // ルールIDだけで消す操作 (delete_weekly_rule) も同じように断る
+        let refused = services
+            .with_plan_revision(PlanItem::Rule(holiday_rule), None, async |tx, plan_id| {
+                compute_delete_weekly_rule_with(tx, plan_id, holiday_rule, &RuleDeletion::Refuse).await
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(refused, err);
+
+        let changed = compute_delete_weekly_rule_with(&services, plan_id, holiday_rule, &RuleDeletion::Reassign { to_rule_id: weekday_rule }).await.unwrap();
+        assert_eq!(changed, vec!["2026-06-08", "2026-06-15"]);
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert!(result.weeks[..3].iter().all(|week| week.as_ref().unwrap().days[0].morning == vec!["田中"]));
+
+        let changed = compute_delete_weekly_rule_with(&services, plan_id, weekday_rule, &RuleDeletion::Skip).await.unwrap();
+        assert_eq!(changed.len(), 3);
+        assert!(services.rule.get_plan_config(plan_id).await.unwrap().rules.is_empty());
+        let result = compute_monthly_shift(&services, plan_id, 2026, 5).await.unwrap();
+        assert!(result.weeks.iter().all(Option::is_none));
+    }
+
+    // パターンでタイムラインを延ばす
+    #[tokio::test]
+    async fn test_extend_timeline_by_pattern() {
+        let services = AppServices::in_memory();
+
+        let plan_id = services.rule.create_plan("Plan").await.unwrap();
+        let rule_a = services.rule.add_weekly_rule(plan_id, "A").await.unwrap();
+        let rule_b = services.rule.add_weekly_rule(plan_id, "B").await.unwrap();
+
+        // 区切りの無いパターン・0週の区切りは作れない
+        let empty = RulePatternKind::Sequence { anchor_abs_week: 0, steps: vec![] };
+        assert!(services.rule.add_rule_pattern(plan_id, "空", &empty).await.is_err());
+        let zero = RulePatternKind::Sequence { anchor_abs_week: 0, steps: vec![PatternStep { rule_id: Some(rule_a), weeks: 0 }] };
+        assert!(services.rule.add_rule_pattern(plan_id, "0週", &zero).await.is_err());
+
+        // 2026-12-21 は ISO 52週、2026年は53週まであるので奇数週が2回続く
+        let base = calculate_abs_week(2026, 11, 21).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        let parity = RulePatternKind::IsoWeekParity { odd: Some(rule_a), even: Some(rule_b) };
+        let parity_id = services.rule.add_rule_pattern(plan_id, "奇数週A", &parity).await.unwrap();
+        assert_eq!(services.calendar.extend_timeline_by_pattern(plan_id, parity_id, 3).await.unwrap(), base);
+
+        // A/B 交互はアンカーの週から数える
+        let alternate = RulePatternKind::Sequence {
+            anchor_abs_week: base,
+            steps: vec![
+                PatternStep { rule_id: Some(rule_a), weeks: 1 },
+                PatternStep { rule_id: Some(rule_b), weeks: 1 },
+            ],
+        };
+        let alternate_id = services.rule.add_rule_pattern(plan_id, "A/B", &alternate).await.unwrap();
+        assert_eq!(services.calendar.extend_timeline_by_pattern(plan_id, alternate_id, 2).await.unwrap(), base + 3);
+
+        let calendar = services.calendar.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        let rules: Vec<_> = calendar.timeline.iter()
+            .map(|status| match status {
+                WeekStatus::Active { payload, .. } => Some(payload.rule_id),
+                WeekStatus::Skipped => None,
+            })
+            .collect();
+        assert_eq!(rules, vec![Some(rule_b), Some(rule_a), Some(rule_a), Some(rule_b), Some(rule_a)]);
+        let weeks = services.calendar.find_pattern_weeks(plan_id).await.unwrap();
+        assert_eq!(weeks.iter().map(|w| w.pattern_id).collect::<Vec<_>>(), vec![parity_id, parity_id, parity_id, alternate_id, alternate_id]);
+        assert_eq!(weeks[3], PatternWeek { abs_week: base + 3, pattern_id: alternate_id });
+
+        // ルールを消すとパターンの中では Skipped 扱いになる
+        compute_delete_weekly_rule_with(&services, plan_id, rule_b, &RuleDeletion::Skip).await.unwrap();
+        let patterns = services.rule.list_rule_patterns(plan_id).await.unwrap();
+        assert_eq!(patterns[0].kind, RulePatternKind::IsoWeekParity { odd: Some(rule_a), even: None });
+        // Skipped にした週もパターンの記録は残る
+        assert_eq!(services.calendar.find_pattern_weeks(plan_id).await.unwrap(), weeks);
+
+        // 無いパターンはエラー
+        assert!(services.calendar.extend_timeline_by_pattern(plan_id, 999, 1).await.is_err());
+    }
+
+    // 休業ルールはパターンで延ばした週にも当たる
+    #[tokio::test]
+    async fn test_skip_rules() {
+        let services = AppServices::in_memory();
+
+        let plan_id = services.rule.create_plan("Plan").await.unwrap();
+        let rule_a = services.rule.add_weekly_rule(plan_id, "A").await.unwrap();
+        let pattern = RulePatternKind::Sequence { anchor_abs_week: 0, steps: vec![PatternStep { rule_id: Some(rule_a), weeks: 1 }] };
+        let pattern_id = services.rule.add_rule_pattern(plan_id, "毎週A", &pattern).await.unwrap();
+
+        // お盆 (8/13 〜 8/16)
+        let obon = SkipRuleKind::AnnualClosure { from: "08-13".to_string(), to: "08-16".to_string() };
+        services.calendar.add_skip_rule(plan_id, &obon).await.unwrap();
+        assert!(services.calendar.add_skip_rule(999, &obon).await.is_err());
+
+        let base = calculate_abs_week(2026, 7, 3).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.extend_timeline_by_pattern(plan_id, pattern_id, 4).await.unwrap();
+
+        // 8/10 の週 (8/13 〜 8/16) だけがSkipped
+        let calendar = services.calendar.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        let skipped: Vec<bool> = calendar.timeline.iter().map(|status| *status == WeekStatus::Skipped).collect();
+        assert_eq!(skipped, vec![false, true, false, false]);
+        assert_eq!(services.calendar.find_pattern_weeks(plan_id).await.unwrap().len(), 4);
+
+        // すでにある週と重なる追記は、休業ルールではなく今の週と比べる
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_a), None, Some(rule_a)]).await.unwrap();
+        assert!(services.calendar.try_to_append_timeline(plan_id, base + 1, vec![Some(rule_a)]).await.is_err());
+
+        services.calendar.update_skip_rule(
+            services.calendar.list_skip_rules(plan_id).await.unwrap()[0].id,
+            &SkipRuleKind::LastWeekOfMonth,
+        ).await.unwrap();
+        assert_eq!(services.calendar.list_skip_rules(plan_id).await.unwrap()[0].kind, SkipRuleKind::LastWeekOfMonth);
+
+        services.rule.delete_plan(plan_id).await.unwrap();
+        assert!(services.calendar.list_skip_rules(plan_id).await.unwrap().is_empty());
+    }
+
+    // 祝日・休日 (振替休日・国民の休日を含む)
+    #[tokio::test]
+    async fn test_holidays() {
+        let holidays = compute_holidays("2026-05-01", "2026-05-31").unwrap();
+        assert_eq!(
+            holidays.iter().map(|h| (h.date.as_str(), h.name.as_str())).collect::<Vec<_>>(),
+            vec![("2026-05-03", "憲法記念日"), ("2026-05-04", "みどりの日"), ("2026-05-05", "こどもの日"), ("2026-05-06", "振替休日")],
+        );
+        assert_eq!(compute_holidays("2026-09-22", "2026-09-22").unwrap()[0].name, "国民の休日");
+        assert!(compute_holidays("2026/05/01", "2026-05-31").is_err());
+
+        // 差し替え用の表の形式
+        let table = HolidayTable::parse_csv("# コメント\ndate,name\n2031-01-01,元日\n").unwrap();
+        assert_eq!(table.years(), Some((2031, 2031)));
+        assert!(HolidayTable::parse_csv("2031-13-01,元日").is_err());
+        // 壊れた表は何行目かをエラーに出す (デスクトップ版は記録して同梱の表を使う)
+        assert_eq!(
+            HolidayTable::parse_csv("date,name\n2031-01-01,元日\n2031-13-01,元日\n").unwrap_err(),
+            "祝日の表の 3 行目の日付が正しくありません: 2031-13-01",
+        );
+        assert_eq!(
+            HolidayTable::parse_csv("2031-01-01,元日\n2031-02-11\n").unwrap_err(),
+            "祝日の表の 2 行目に「日付,名前」がありません",
+        );
+        let (first, last) = HolidayTable::bundled().years().unwrap();
+        assert!(first <= 2026 && 2030 <= last);
+
+        // 導出したシフトの日に祝日の名前が付く
+        let services = AppServices::in_memory();
+        let plan_id = services.rule.create_plan("Plan").await.unwrap();
+        let group_id = services.rule.add_staff_group(plan_id, "正社員").await.unwrap();
+        let member_id = services.rule.add_staff_member(group_id, "田中").await.unwrap();
+        let rule_id = services.rule.add_weekly_rule(plan_id, "標準ルール").await.unwrap();
+        services.rule.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+
+        // 祝日が3日以上ある週 (5/4 の週) はSkipped。4/27 の週は 4/29 と 5/3 の2日
+        services.calendar.add_skip_rule(plan_id, &SkipRuleKind::NationalHolidays { min_days: 3 }).await.unwrap();
+        assert!(services.calendar.add_skip_rule(plan_id, &SkipRuleKind::NationalHolidays { min_days: 0 }).await.is_err());
+        let base = calculate_abs_week(2026, 3, 27).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id); 3]).await.unwrap();
+        let calendar = services.calendar.find_by_plan_id(plan_id).await.unwrap().unwrap().calendar;
+        assert_eq!(calendar.timeline[1], WeekStatus::Skipped);
+
+        let result = compute_monthly_shift(&services, plan_id, 2026, 3).await.unwrap();
+        let last_week = result.weeks.last().unwrap().as_ref().unwrap();
+        assert_eq!(last_week.days[2].holiday.as_deref(), Some("昭和の日"));
+        assert_eq!(last_week.days[0].holiday, None);
+
+        let schedule = compute_staff_schedule(&services, plan_id, member_id, "2026-05-11", "2026-05-17").await.unwrap();
+        assert_eq!(schedule.len(), 1);
+        assert_eq!(schedule[0].holiday, None);
+    }
+
+    #[tokio::test]
+    async fn test_print_month() {
+        let services = AppServices::in_memory();
+        let plan_id = services.rule.create_plan("Plan & Co").await.unwrap();
+        let full_id = services.rule.add_staff_group(plan_id, "正社員").await.unwrap();
+        let part_id = services.rule.add_staff_group(plan_id, "パート").await.unwrap();
+        services.rule.add_staff_member(full_id, "田中").await.unwrap();
+        services.rule.add_staff_member(part_id, "佐藤").await.unwrap();
+        let rule_id = services.rule.add_weekly_rule(plan_id, "標準ルール").await.unwrap();
+        services.rule.add_rule_assignment(rule_id, 0, 0, full_id, 0).await.unwrap();
+        services.rule.add_rule_assignment(rule_id, 0, 1, part_id, 0).await.unwrap();
+
+        // 2026年5月は 4/27 〜 5/31 の5週。基準週は 5/4 の週で、5/11 の週はSkipped、5/18 以降は未生成
+        let base = calculate_abs_week(2026, 4, 4).unwrap();
+        services.calendar.create_calendar(plan_id, base, 0).await.unwrap();
+        services.calendar.try_to_append_timeline(plan_id, base, vec![Some(rule_id), None]).await.unwrap();
+
+        let month = compute_printable_month(&services, plan_id, 2026, 4, None).await.unwrap();
+        assert_eq!(month.title(), "Plan & Co 2026年5月");
+        assert_eq!(month.weeks.len(), 5);
+        assert_eq!(month.weeks.iter().map(|week| week.active).collect::<Vec<_>>(), vec![false, true, false, false, false]);
+        assert!(!month.weeks[0].days[0].in_month); // 4/27
+        assert!(month.weeks[0].days[4].in_month); // 5/1
+
+        let monday = &month.weeks[1].days[0];
+        assert_eq!(monday.date.to_string(), "2026-05-04");
+        assert_eq!(monday.holiday.as_deref(), Some("みどりの日"));
+        assert_eq!(monday.morning, vec![PrintEntry { name: String::from("田中"), group_index: 0 }]);
+        assert_eq!(monday.afternoon, vec![PrintEntry { name: String::from("佐藤"), group_index: 1 }]);
+        assert!(month.weeks[2].days[0].morning.is_empty());
+
+        // 1グループだけの表
+        let part_month = compute_printable_month(&services, plan_id, 2026, 4, Some(part_id)).await.unwrap();
+        assert_eq!(part_month.title(), "Plan & Co 2026年5月 (パート)");
+        assert_eq!(part_month.groups.len(), 1);
+        assert_eq!(part_month.groups[0].1.color, GROUP_COLORS[1]);
+        assert!(part_month.weeks[1].days[0].morning.is_empty());
+        assert_eq!(part_month.weeks[1].days[0].afternoon.len(), 1);
+        assert!(compute_printable_month(&services, plan_id, 2026, 4, Some(9999)).await.is_err());
+
+        // HTML は1ファイルで完結する
+        let html = render_html(&month);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Plan &amp; Co 2026年5月</title>"));
+        assert!(html.contains("みどりの日"));
+        assert!(html.contains("border-color:#27ae60\">佐藤</span>"));
+        assert!(!html.contains("http"));
+
+        // 書き出すのは HTML だけ (PDF はブラウザの印刷から作る)
+        assert_eq!(check_output_path("wall.HTML".as_ref()), Ok(()));
+        assert_eq!(check_output_path("wall".as_ref()), Ok(()));
+        assert!(check_output_path("wall.pdf".as_ref()).unwrap_err().contains("ブラウザの印刷"));
+        assert!(check_output_path("wall.png".as_ref()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cross_plan_conflicts() {
+        let services = AppServices::in_memory();
+        let monday = calculate_abs_week(2026, 5, 1).unwrap(); // 2026-06-01 (月)
+
+        // 2つの店に田中がいて、どちらも月曜の午前に入る。本店の鈴木は人にまとめない
+        let mut shops = vec![];
+        for (shop, names) in [("本店", vec!["田中", "鈴木"]), ("駅前店", vec!["田中"])] {
+            let plan_id = services.rule.create_plan(shop).await.unwrap();
+            let group_id = services.rule.add_staff_group(plan_id, "正社員").await.unwrap();
+            let mut members = vec![];
+            for name in &names {
+                members.push(services.rule.add_staff_member(group_id, name).await.unwrap());
+            }
+            let rule_id = services.rule.add_weekly_rule(plan_id, "標準ルール").await.unwrap();
+            for index in 0..names.len() as i64 {
+                services.rule.add_rule_assignment(rule_id, 0, 0, group_id, index).await.unwrap();
+            }
+            services.calendar.create_calendar(plan_id, monday, 0).await.unwrap();
+            services.calendar.try_to_append_timeline(plan_id, monday, vec![Some(rule_id)]).await.unwrap();
+            shops.push((plan_id, members));
+        }
+        let (main_shop, main_members) = &shops[0];
+        let (station_shop, station_members) = &shops[1];
+
+        let tanaka = services.rule.add_person("田中 太郎").await.unwrap();
+        assert!(services.rule.link_member_person(main_members[0], Some(9999)).await.is_err());
+        services.rule.link_member_person(main_members[0], Some(tanaka)).await.unwrap();
+        services.rule.link_member_person(station_members[0], Some(tanaka)).await.unwrap();
+
+        // まとめた表示 (日付・午前午後の順、同じコマの中はプランの順)
+        let team = compute_team_schedule(&services, None, "2026-06-01", "2026-06-07").await.unwrap();
+        assert_eq!(
+            team.iter().map(|s| (s.plan_name.as_str(), s.name.as_str(), s.person_id)).collect::<Vec<_>>(),
+            vec![("本店", "田中 太郎", Some(tanaka)), ("本店", "鈴木", None), ("駅前店", "田中 太郎", Some(tanaka))],
+        );
+        assert!(team.iter().all(|s| s.date == "2026-06-01"));
+        let station_only = compute_team_schedule(&services, Some(&[*station_shop]), "2026-06-01", "2026-06-07").await.unwrap();
+        assert_eq!(station_only.len(), 1);
+        assert_eq!(station_only[0].member_id, station_members[0]);
+        assert!(compute_team_schedule(&services, Some(&[9999]), "2026-06-01", "2026-06-07").await.is_err());
+        assert!(compute_team_schedule(&services, None, "2026-06-07", "2026-06-01").await.is_err());
+
+        // 重なり
+        let conflicts = compute_cross_plan_conflicts(&services, "2026-06-01", "2026-06-30").await.unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].person_id, tanaka);
+        assert_eq!(conflicts[0].date, "2026-06-01");
+        assert_eq!(conflicts[0].shifts.iter().map(|s| s.plan_id).collect::<Vec<_>>(), vec![*main_shop, *station_shop]);
+        assert_eq!(conflicts[0].message, "田中 太郎 が 2026-06-01 午前 に 本店・駅前店 で重なっています");
+
+        // 人を消すとまとめが外れ、重なりではなくなる
+        services.rule.delete_person(tanaka).await.unwrap();
+        assert!(compute_cross_plan_conflicts(&services, "2026-06-01", "2026-06-30").await.unwrap().is_empty());
+        let team = compute_team_schedule(&services, None, "2026-06-01", "2026-06-07").await.unwrap();
+        assert_eq!(team[0].name, "田中");
+        assert_eq!(team[0].person_id, None);
+    }
+}
@@ -2,7 +2,10 @@
 mod rule_repo_tests {
     use sqlx::sqlite::SqlitePoolOptions;
     use sqlx::SqlitePool;
+    use shift_manager_tauri_lib::domain::repository::{not_found, PlanRepository, RuleRepository, REVISION_CONFLICT};
+    use shift_manager_tauri_lib::domain::rule_model::{MemberConstraints, OverrideEntry, PlanItem, ShiftTime, Weekday};
     use shift_manager_tauri_lib::infrastructure::rule_repo::*;
+    use shift_timeline::{OverrideAction, Rotation};
 
     // 1. テスト用DBセットアップ (最新スキーマ反映)
     async fn setup_test_db() -> SqlitePool {
@@ -23,7 +26,7 @@ mod rule_repo_tests {
     #[tokio::test]
     async fn test_create_and_fetch_full_config() {
         let pool = setup_test_db().await;
-        let repo = RuleRepository::new(pool);
+        let repo = SqliteRuleRepository::new(pool);
 
         // A. Plan作成
         let plan_id = repo.create_plan("Test Plan 2026").await.unwrap();
@@ -59,11 +62,305 @@ mod rule_repo_tests {
         assert_eq!(config.rules[0].assignments.len(), 2);
     }
 
+    // 人員チェックの条件の保存と解除
+    #[tokio::test]
+    async fn test_coverage_limits() {
+        let pool = setup_test_db().await;
+        let repo = SqliteRuleRepository::new(pool);
+
+        let plan_id = repo.create_plan("Plan").await.unwrap();
+        let group_id = repo.add_staff_group(plan_id, "Kitchen").await.unwrap();
+
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert_eq!(config.plan.max_days_per_week, None);
+        assert_eq!(config.groups[0].group.min_per_period, None);
+
+        repo.update_plan_max_days(plan_id, Some(5)).await.unwrap();
+        repo.update_group_min_staff(group_id, Some(2)).await.unwrap();
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert_eq!(config.plan.max_days_per_week, Some(5));
+        assert_eq!(config.groups[0].group.min_per_period, Some(2));
+        assert_eq!(repo.list_plans().await.unwrap()[0].max_days_per_week, Some(5));
+
+        repo.update_group_min_staff(group_id, None).await.unwrap();
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert_eq!(config.groups[0].group.min_per_period, None);
+    }
+
+    #[tokio::test]
+    async fn test_member_constraints() {
+        let pool = setup_test_db().await;
+        let repo = SqliteRuleRepository::new(pool.clone());
+
+        let plan_id = repo.create_plan("Plan").await.unwrap();
+        let group_id = repo.add_staff_group(plan_id, "Kitchen").await.unwrap();
+        let member_id = repo.add_staff_member(group_id, "Tanaka").await.unwrap();
+
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert_eq!(config.groups[0].members[0].constraints, None);
+
+        let constraints = MemberConstraints {
+            max_shifts_per_week: Some(8),
+            max_shifts_per_month: None,
+            days_off: vec![Weekday::Sunday, Weekday::Wednesday],
+            no_morning_after_afternoon: true,
+            employment_start: Some(String::from("2026-04-01")),
+            employment_end: None,
+        };
+        repo.set_member_constraints(member_id, &constraints).await.unwrap();
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        let saved = config.groups[0].members[0].constraints.clone().unwrap();
+        assert_eq!(saved.days_off, vec![Weekday::Wednesday, Weekday::Sunday]);
+        assert_eq!(saved, MemberConstraints { days_off: saved.days_off.clone(), ..constraints.clone() });
+
+        // 上書き
+        let constraints = MemberConstraints { max_shifts_per_month: Some(20), ..Default::default() };
+        repo.set_member_constraints(member_id, &constraints).await.unwrap();
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert_eq!(config.groups[0].members[0].constraints, Some(constraints));
+
+        // 読めない日付・逆転した期間・存在しないメンバー
+        let bad_date = MemberConstraints { employment_start: Some(String::from("2026/04/01")), ..Default::default() };
+        assert!(repo.set_member_constraints(member_id, &bad_date).await.is_err());
+        let reversed = MemberConstraints {
+            employment_start: Some(String::from("2026-04-01")),
+            employment_end: Some(String::from("2026-03-31")),
+            ..Default::default()
+        };
+        assert!(repo.set_member_constraints(member_id, &reversed).await.is_err());
+        assert!(repo.set_member_constraints(member_id + 100, &MemberConstraints::default()).await.is_err());
+
+        repo.clear_member_constraints(member_id).await.unwrap();
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert_eq!(config.groups[0].members[0].constraints, None);
+
+        // メンバー削除で休みの曜日も消える
+        repo.set_member_constraints(member_id, &MemberConstraints { days_off: vec![Weekday::Monday], ..Default::default() })
+            .await
+            .unwrap();
+        repo.delete_staff_member(member_id).await.unwrap();
+        let days_off: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM staff_member_days_off")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(days_off, 0);
+    }
+
+    #[tokio::test]
+    async fn test_skills() {
+        let pool = setup_test_db().await;
+        let repo = SqliteRuleRepository::new(pool.clone());
+
+        let plan_id = repo.create_plan("Plan").await.unwrap();
+        let group_id = repo.add_staff_group(plan_id, "Kitchen").await.unwrap();
+        let member_id = repo.add_staff_member(group_id, "Tanaka").await.unwrap();
+        let rule_id = repo.add_weekly_rule(plan_id, "Rule").await.unwrap();
+        let assignment_id = repo.add_rule_assignment(rule_id, 0, 0, group_id, 0).await.unwrap();
+
+        let skills = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // 空白・空・重複は除いて名前順
+        repo.set_member_skills(member_id, &skills(&["免許", " 開店 ", "", "免許"])).await.unwrap();
+        repo.set_assignment_skills(assignment_id, &skills(&["開店"])).await.unwrap();
+        repo.update_plan_auto_swap(plan_id, true).await.unwrap();
+
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert_eq!(config.groups[0].members[0].skills, skills(&["免許", "開店"]));
+        assert_eq!(config.rules[0].assignments[0].required_skills, skills(&["開店"]));
+        assert!(config.plan.auto_swap_skills);
+
+        // 差し替え
+        repo.set_member_skills(member_id, &[]).await.unwrap();
+        let config = repo.get_plan_config(plan_id).await.unwrap();
+        assert!(config.groups[0].members[0].skills.is_empty());
+
+        // 存在しないメンバー・穴
+        assert!(repo.set_member_skills(member_id + 100, &skills(&["開店"])).await.is_err());
+        assert!(repo.set_assignment_skills(assignment_id + 100, &skills(&["開店"])).await.is_err());
+
+        // 穴を消すと必要スキルも消える
+        repo.delete_assignment(assignment_id).await.unwrap();
+        let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rule_assignment_skills")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rows, 0);
+    }
+
+    #[tokio::test]
+    async fn test_group_rotation() {
+        let pool = setup_test_db().await;
+        let repo = SqliteRuleRepository::new(pool);
+
+        let plan_id = repo.create_plan("Plan").await.unwrap();
+        let group_id = repo.add_staff_group(plan_id, "Kitchen").await.unwrap();
+
+        let rotation_of = || async { repo.get_plan_config(plan_id).await.unwrap().groups[0].group.rotation };
+        assert_eq!(rotation_of().await, None);
+
+        for rotation in [
+            Rotation::Fixed,
+            Rotation::ByWeek,
+            Rotation::ByDay,
+            Rotation::EveryNWeeks { weeks: 3 },
+            Rotation::Shuffle { seed: u64::MAX - 1 }, // i64 に収まらない seed
+        ] {
+            repo.update_group_rotation(group_id, Some(rotation)).await.unwrap();
+            assert_eq!(rotation_of().await, Some(rotation));
+        }
+
+        assert!(repo.update_group_rotation(group_id, Some(Rotation::EveryNWeeks { weeks: 0 })).await.is_err());
+
+        repo.update_group_rotation(group_id, None).await.unwrap();
+        assert_eq!(rotation_of().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_member_period() {
+        let pool = setup_test_db().await;
+        let repo = SqliteRuleRepository::new(pool);
+
+        let plan_id = repo.create_plan("Plan").await.unwrap();
+        let group_id = repo.add_staff_group(plan_id, "Kitchen").await.unwrap();
+        let member_id = repo.add_staff_member(group_id, "Alice").await.unwrap();
+
+        let period_of = || async {
+            let member = repo.get_plan_config(plan_id).await.unwrap().groups[0].members[0].clone();
+            (member.joined_on, member.left_on)
+        };
+        assert_eq!(period_of().await, (None, None));
+
+        repo.update_member_period(member_id, Some("2026-04-01"), Some("2026-09-30")).await.unwrap();
+        assert_eq!(period_of().await, (Some(String::from("2026-04-01")), Some(String::from("2026-09-30"))));
+
+        // 形式・前後がおかしければエラーで、保存済みの期間は変わらない
+        assert!(repo.update_member_period(member_id, Some("2026/04/01"), None).await.is_err());
+        assert!(repo.update_member_period(member_id, Some("2026-10-01"), Some("2026-09-30")).await.is_err());
+        assert_eq!(period_of().await.0.as_deref(), Some("2026-04-01"));
+
+        repo.update_member_period(member_id, None, None).await.unwrap();
+        assert_eq!(period_of().await, (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_people() {
+        let pool = setup_test_db().await;
+        let repo = SqliteRuleRepository::new(pool);
+
+        let shop_a = repo.create_plan("本店").await.unwrap();
+        let shop_b = repo.create_plan("駅前店").await.unwrap();
+        let group_a = repo.add_staff_group(shop_a, "Kitchen").await.unwrap();
+        let group_b = repo.add_staff_group(shop_b, "Kitchen").await.unwrap();
+        let alice_a = repo.add_staff_member(group_a, "Alice").await.unwrap();
+        let alice_b = repo.add_staff_member(group_b, "Alice (B)").await.unwrap();
+
+        let person_id = repo.add_person("Alice").await.unwrap();
+        repo.update_person_name(person_id, "Alice Smith").await.unwrap();
+        let people = repo.list_people().await.unwrap();
+        assert_eq!(people.len(), 1);
+        assert_eq!(people[0].name, "Alice Smith");
+
+        let person_of = |plan_id| {
+            let repo = &repo;
+            async move { repo.get_plan_config(plan_id).await.unwrap().groups[0].members[0].person_id }
+        };
+        assert_eq!(person_of(shop_a).await, None);
+        repo.link_member_person(alice_a, Some(person_id)).await.unwrap();
+        repo.link_member_person(alice_b, Some(person_id)).await.unwrap();
+        assert_eq!(person_of(shop_a).await, Some(person_id));
+        assert_eq!(person_of(shop_b).await, Some(person_id));
+
+        // 存在しない人にはまとめられない
+        assert!(repo.link_member_person(alice_a, Some(9999)).await.is_err());
+        repo.link_member_person(alice_b, None).await.unwrap();
+        assert_eq!(person_of(shop_b).await, None);
+
+        // 人を消してもメンバーは残り、まとめだけが外れる
+        repo.delete_person(person_id).await.unwrap();
+        assert!(repo.list_people().await.unwrap().is_empty());
+        let config = repo.get_plan_config(shop_a).await.unwrap();
+        assert_eq!(config.groups[0].members.len(), 1);
+        assert_eq!(config.groups[0].members[0].person_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_plan_revision() {
+        let pool = setup_test_db().await;
+        let repo = SqliteRuleRepository::new(pool);
+
+        let plan_id = repo.create_plan("Revision").await.unwrap();
+        let group_id = repo.add_staff_group(plan_id, "Group").await.unwrap();
+        let revision_of = |plan_id| {
+            let repo = &repo;
+            async move { repo.get_plan_config(plan_id).await.unwrap().plan.revision }
+        };
+        assert_eq!(revision_of(plan_id).await, 0);
+
+        // 下の物からでもプランの revision を進める
+        assert_eq!(repo.claim_revision(PlanItem::Group(group_id), Some(0)).await, Ok(plan_id));
+        assert_eq!(revision_of(plan_id).await, 1);
+        assert_eq!(repo.claim_revision(PlanItem::Plan(plan_id), None).await, Ok(plan_id));
+        assert_eq!(revision_of(plan_id).await, 2);
+
+        // 古い revision での書き換えは断り、revision も進めない
+        let err = repo.claim_revision(PlanItem::Plan(plan_id), Some(1)).await.unwrap_err();
+        assert!(err.starts_with(REVISION_CONFLICT), "{}", err);
+        assert_eq!(revision_of(plan_id).await, 2);
+
+        // 無い物
+        assert_eq!(
+            repo.claim_revision(PlanItem::Member(9999), Some(2)).await,
+            Err(not_found(PlanItem::Member(9999)))
+        );
+
+        // 他のプランの revision は変わらない
+        let other_id = repo.create_plan("Other").await.unwrap();
+        repo.claim_revision(PlanItem::Plan(plan_id), Some(2)).await.unwrap();
+        assert_eq!(revision_of(other_id).await, 0);
+    }
+
+    // 週・日単位の調整は追加した順に get_plan_config で返り、メンバーを消すと一緒に消える
+    #[tokio::test]
+    async fn test_shift_overrides() {
+        let pool = setup_test_db().await;
+        let repo = SqliteRuleRepository::new(pool);
+
+        let plan_id = repo.create_plan("Plan").await.unwrap();
+        let group_id = repo.add_staff_group(plan_id, "Group").await.unwrap();
+        let tanaka = repo.add_staff_member(group_id, "Tanaka").await.unwrap();
+        let suzuki = repo.add_staff_member(group_id, "Suzuki").await.unwrap();
+
+        let entry = |member_id, action| OverrideEntry {
+            abs_week: 2900,
+            weekday: Weekday::Friday,
+            shift_time: ShiftTime::Afternoon,
+            member_id,
+            action,
+        };
+        let first = repo.add_shift_override(plan_id, &entry(suzuki, OverrideAction::Remove)).await.unwrap();
+        repo.add_shift_override(plan_id, &entry(tanaka, OverrideAction::Add)).await.unwrap();
+        assert_eq!(repo.claim_revision(PlanItem::Override(first), None).await.unwrap(), plan_id);
+
+        let overrides = repo.get_plan_config(plan_id).await.unwrap().overrides;
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides[0].id, first);
+        assert_eq!(overrides[0].entry, entry(suzuki, OverrideAction::Remove));
+        assert_eq!(overrides[1].entry, entry(tanaka, OverrideAction::Add));
+
+        // 他のプランのメンバーは指定できない
+        let other_plan = repo.create_plan("Other").await.unwrap();
+        assert!(repo.add_shift_override(other_plan, &entry(tanaka, OverrideAction::Add)).await.is_err());
+
+        repo.delete_shift_override(first).await.unwrap();
+        repo.delete_staff_member(tanaka).await.unwrap();
+        assert!(repo.get_plan_config(plan_id).await.unwrap().overrides.is_empty());
+    }
+
     // 3. テスト: Cascade Deleteの確認
     #[tokio::test]
     async fn test_cascade_delete() {
         let pool = setup_test_db().await;
-        let repo = RuleRepository::new(pool.clone());
+        let repo = SqliteRuleRepository::new(pool.clone());
 
         // データ作成
         let plan_id = repo.create_plan("Delete Me").await.unwrap();
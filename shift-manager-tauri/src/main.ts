@@ -1,8 +1,11 @@
 import "./styles.css";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import type { 
     Plan, PlanConfig, StaffGroupWithMembers, WeeklyRuleWithAssignments, 
-    ShiftCalendarManager, WeekStatus, RuleAssignment, MonthlyShiftResult
+    ShiftCalendarManager, WeekStatus, RuleAssignment, MonthlyShiftResult, MemberConstraints, Rotation,
+    RuleEdit, RuleEditImpactDto, RuleDeletion, RulePattern, RulePatternKind, SkipRule, SkipRuleKind, HolidayDto,
+    Person, CrossPlanConflictDto, ChangeNotice
 } from "./types";
 
 /* ==========================================================================
@@ -51,9 +54,47 @@ function getGroupPrefix(index: number): string {
    ========================================================================== */
 window.addEventListener('DOMContentLoaded', async () => {
     setupEventListeners();
+    await listenBackendChanges();
+    // try/catch していない操作でも、競合は知らせる
+    window.addEventListener('unhandledrejection', (event) => {
+        if (isRevisionConflict(event.reason)) alert(String(event.reason));
+    });
     await loadPlanList();
 });
 
+// 他のウィンドウ (と自分) が変えた内容を、表示している所だけ読み直す
+async function listenBackendChanges() {
+    const isCurrentPlan = (planId: number) => planId === currentPlanId;
+
+    await listen<ChangeNotice>("plan-changed", async ({ payload }) => {
+        await loadPlanList();
+        const select = document.getElementById('plan-select') as HTMLSelectElement;
+        if (payload.planId === currentPlanId && payload.action === "deleted") {
+            currentPlanId = null;
+            currentConfig = null;
+            renderGroups([]);
+            renderRules([]);
+        } else if (currentPlanId !== null) {
+            select.value = currentPlanId.toString();
+            if (payload.planId === currentPlanId) await reloadConfig();
+        }
+    });
+    for (const name of ["staff-changed", "rule-changed"]) {
+        await listen<ChangeNotice>(name, async ({ payload }) => {
+            if (!("planId" in payload) || !isCurrentPlan(payload.planId)) return;
+            await reloadConfig();
+            await renderCalendarView();
+        });
+    }
+    await listen<ChangeNotice>("timeline-changed", async ({ payload }) => {
+        if (isCurrentPlan(payload.planId)) await renderCalendarView();
+    });
+    await listen<ChangeNotice>("people-changed", async () => {
+        // メンバーの Person の印 (*) が変わる
+        await reloadConfig();
+    });
+}
+
 async function loadPlanList() {
     try {
         const plans = await invoke<Plan[]>("list_all_plans");
@@ -105,6 +146,34 @@ async function reloadConfig() {
     }
 }
 
+// 書き換えたときに返る競合のエラー (src-tauri の REVISION_CONFLICT)
+const REVISION_CONFLICT = "プランが他の画面で変更されています";
+
+function isRevisionConflict(e: unknown): boolean {
+    return String(e).startsWith(REVISION_CONFLICT);
+}
+
+// プランの中身を書き換えるコマンドは、読み込んだときの revision (expectedRevision) を付けて呼ぶ
+// 競合したときだけ読み込み直してから投げ直す (他のエラーでは何も書き換わらず、revision も進まない)
+async function invokeEdit<T = void>(cmd: string, args: Record<string, unknown>): Promise<T> {
+    const plan = currentConfig?.plan;
+    const expectedRevision = plan && plan.id === currentPlanId ? plan.revision : null;
+    try {
+        const result = await invoke<T>(cmd, { ...args, expectedRevision });
+        // 自分の書き換えで1つ進む (通知で先に読み込み直していれば、そちらの方が新しい)
+        if (plan && expectedRevision !== null && currentConfig?.plan.id === plan.id) {
+            currentConfig.plan.revision = Math.max(currentConfig.plan.revision, expectedRevision + 1);
+        }
+        return result;
+    } catch (e) {
+        if (isRevisionConflict(e)) {
+            await reloadConfig();
+            await renderCalendarView();
+        }
+        throw e;
+    }
+}
+
 function renderConfigUI(config: PlanConfig) {
     renderGroups(config.groups);
     renderRules(config.rules);
@@ -141,8 +210,12 @@ function renderGroups(groups: StaffGroupWithMembers[]) {
                         ${prefix}
                     </span>
                     <strong>${g.group.name}</strong>
+                    <span style="color:#888; font-size:0.8em;">min ${g.group.min_per_period ?? '-'}</span>
+                    <span style="color:#888; font-size:0.8em;">${g.group.rotation ? g.group.rotation.kind : ''}</span>
                 </div>
                 <div>
+                    <button class="btn-sm btn-outline" onclick="window.updateGroupMinStaff(${g.group.id})">Min</button>
+                    <button class="btn-sm btn-outline" onclick="window.updateGroupRotation(${g.group.id})">Rotation</button>
                     <button class="btn-sm btn-outline" onclick="window.updateGroupName(${g.group.id})">Rename</button>
                     <button class="btn-sm btn-danger" onclick="window.removeGroup(${g.group.id})">Del</button>
                 </div>
@@ -171,6 +244,10 @@ function renderGroups(groups: StaffGroupWithMembers[]) {
                 </div>
                 <div>
                     <button class="btn-sm btn-outline" style="font-size:0.7em; margin-right:5px;" onclick="window.updateMemberName(${m.id})">Edit</button>
+                    <button class="btn-sm btn-outline" style="font-size:0.7em; margin-right:5px;" title="${m.skills.join(', ')}" onclick="window.updateMemberSkills(${m.id})">Skills${m.skills.length > 0 ? ` (${m.skills.length})` : ''}</button>
+                    <button class="btn-sm btn-outline" style="font-size:0.7em; margin-right:5px;" onclick="window.updateMemberConstraints(${m.id})">Limits${m.constraints ? ' *' : ''}</button>
+                    <button class="btn-sm btn-outline" style="font-size:0.7em; margin-right:5px;" title="${m.joined_on ?? ''} - ${m.left_on ?? ''}" onclick="window.updateMemberPeriod(${m.id})">Period${m.joined_on || m.left_on ? ' *' : ''}</button>
+                    <button class="btn-sm btn-outline" style="font-size:0.7em; margin-right:5px;" title="Same person in other plans" onclick="window.linkMemberPerson(${m.id})">Person${m.person_id !== null ? ' *' : ''}</button>
                     <button class="btn-sm btn-outline-danger" style="font-size:0.7em;" onclick="window.removeMember(${m.id})">x</button>
                 </div>
             `;
@@ -291,9 +368,18 @@ function renderRules(rules: WeeklyRuleWithAssignments[]) {
                     
                     // ★ 表示内容: "A-0" のような形式
                     chip.textContent = `${prefix}-${a.target_member_index}`;
+                    if (a.required_skills.length > 0) {
+                        chip.textContent += ` [${a.required_skills.join(', ')}]`;
+                    }
                     
                     // ホバー時に詳細（グループ名: 名前）を表示
-                    chip.title = `${groupData?.group.name || 'Unknown'}: ${memberName}`;
+                    chip.title = `${groupData?.group.name || 'Unknown'}: ${memberName}\n(right click: required skills)`;
+
+                    // 右クリックで必要スキルを編集
+                    chip.oncontextmenu = (e) => {
+                        e.preventDefault();
+                        updateAssignmentSkills(a.id, a.required_skills);
+                    };
                     
                     // クリックで削除
                     chip.onclick = (e) => {
@@ -414,58 +500,187 @@ function closeModal() {
 // Actions
 async function addNewGroup() {
     if (!currentPlanId) return;
-    await invoke("add_staff_group", { planId: currentPlanId, name: "New Group" });
+    await invokeEdit("add_staff_group", { planId: currentPlanId, name: "New Group" });
     reloadConfig();
 }
 
 async function removeGroup(groupId: number) {
     if(!confirm("Delete group?")) return;
-    await invoke("delete_staff_group", { groupId });
+    await invokeEdit("delete_staff_group", { groupId });
     reloadConfig();
 }
 
 async function updateGroupName(groupId: number) {
     const name = prompt("New name:");
-    if(name) { await invoke("update_group_name", { groupId, name }); reloadConfig(); }
+    if(name) { await invokeEdit("update_group_name", { groupId, name }); reloadConfig(); }
+}
+
+// 空欄で解除
+async function updateGroupMinStaff(groupId: number) {
+    const text = prompt("Minimum staff per period (empty = none):");
+    if (text === null) return;
+    const minPerPeriod = text.trim() === "" ? null : parseInt(text);
+    if (minPerPeriod !== null && (isNaN(minPerPeriod) || minPerPeriod < 0)) return;
+    await invokeEdit("update_group_min_staff", { groupId, minPerPeriod });
+    reloadConfig();
+}
+
+// スキルはカンマ区切り (空欄で全部外す)
+function parseSkills(text: string): string[] {
+    return text.split(',').map(s => s.trim()).filter(s => s !== '');
+}
+
+async function updateMemberSkills(memberId: number) {
+    const member = currentConfig?.groups.flatMap(g => g.members).find(m => m.id === memberId);
+    const text = prompt("Skills (comma separated):", member?.skills.join(', ') ?? '');
+    if (text === null) return;
+    try {
+        await invokeEdit("set_member_skills", { memberId, skills: parseSkills(text) });
+        await reloadConfig();
+    } catch (e) {
+        alert(`Failed to update skills: ${e}`);
+    }
+}
+
+async function updateAssignmentSkills(assignmentId: number, current: string[]) {
+    const text = prompt("Required skills (comma separated):", current.join(', '));
+    if (text === null) return;
+    try {
+        await invokeEdit("set_assignment_skills", { assignmentId, skills: parseSkills(text) });
+        await reloadConfig();
+    } catch (e) {
+        alert(`Failed to update required skills: ${e}`);
+    }
+}
+
+async function toggleAutoSwap() {
+    if (!currentPlanId || !currentConfig) return;
+    const autoSwapSkills = !currentConfig.plan.auto_swap_skills;
+    if (!confirm(`Swap unqualified staff within the group: ${autoSwapSkills ? 'ON' : 'OFF'}?`)) return;
+    await invokeEdit("update_plan_auto_swap", { planId: currentPlanId, autoSwapSkills });
+    await reloadConfig();
+    await renderCalendarView();
+}
+
+// fixed / byWeek / byDay / everyNWeeks:N / shuffle:SEED (空欄で既定の並べ方)
+async function updateGroupRotation(groupId: number) {
+    const text = prompt("Rotation (fixed, byWeek, byDay, everyNWeeks:N, shuffle:SEED, empty = default):");
+    if (text === null) return;
+    const [kind, param] = text.trim().split(':');
+    let rotation: Rotation | null;
+    switch (kind) {
+        case '': rotation = null; break;
+        case 'fixed': case 'byWeek': case 'byDay': rotation = { kind }; break;
+        case 'everyNWeeks': rotation = { kind, weeks: parseInt(param) }; break;
+        case 'shuffle': rotation = { kind, seed: parseInt(param) }; break;
+        default: alert(`Unknown rotation: ${kind}`); return;
+    }
+    if (rotation && 'weeks' in rotation && isNaN(rotation.weeks)) return;
+    if (rotation && 'seed' in rotation && isNaN(rotation.seed)) return;
+    try {
+        await invokeEdit("update_group_rotation", { groupId, rotation });
+        await reloadConfig();
+    } catch (e) {
+        alert(`Failed to update rotation: ${e}`);
+    }
+}
+
+async function updatePlanMaxDays() {
+    if (!currentPlanId) return;
+    const text = prompt("Max working days per week (empty = none):");
+    if (text === null) return;
+    const maxDaysPerWeek = text.trim() === "" ? null : parseInt(text);
+    if (maxDaysPerWeek !== null && (isNaN(maxDaysPerWeek) || maxDaysPerWeek < 0)) return;
+    await invokeEdit("update_plan_max_days", { planId: currentPlanId, maxDaysPerWeek });
+    await renderCalendarView();
 }
 
 async function addMember(groupId: number) {
-    await invoke("add_staff_member", { groupId, name: "New Member" });
+    await invokeEdit("add_staff_member", { groupId, name: "New Member" });
     reloadConfig();
 }
 
 async function removeMember(memberId: number) {
-    await invoke("delete_staff_member", { memberId });
+    await invokeEdit("delete_staff_member", { memberId });
     reloadConfig();
 }
 
 async function addNewRule() {
     if (!currentPlanId) return;
-    await invoke("add_weekly_rule", { planId: currentPlanId, name: "New Rule" });
+    await invokeEdit("add_weekly_rule", { planId: currentPlanId, name: "New Rule" });
     reloadConfig();
 }
 
+// ルールを変える前に、今日の週から先で入っている人が変わるコマを見せて確認する
+// 固定した週で使われているルールは、確認したときだけ変える
+// 変えなかったときは false
+async function applyRuleEdit(edit: RuleEdit, question: string): Promise<boolean> {
+    if (!currentPlanId) return false;
+    const today = new Date();
+    const fromDate = `${today.getFullYear()}-${String(today.getMonth() + 1).padStart(2, '0')}-${String(today.getDate()).padStart(2, '0')}`;
+    const impact = await invoke<RuleEditImpactDto>("preview_rule_edit", { planId: currentPlanId, edit, fromDate });
+
+    const lines = [question];
+    if (impact.changes.length > 0) {
+        lines.push(``, `${impact.changes.length} 件のコマが変わります:`);
+        lines.push(...impact.changes.slice(0, 10).map(c => `  ${c.locked ? '🔒 ' : ''}${c.message}`));
+        if (impact.changes.length > 10) lines.push(`  ...`);
+    }
+    if (impact.lockedWeeks.length > 0) {
+        lines.push(``, `このルールは固定した週 (${impact.lockedWeeks.join(', ')} の週) で使われています。`);
+        lines.push(`固定した週の表示は変わりませんが、固定を外すと変わります。`);
+    }
+    if (!confirm(lines.join('\n'))) return false;
+
+    await invokeEdit("apply_rule_edit", { planId: currentPlanId, edit, allowLocked: true });
+    return true;
+}
+
+// タイムラインで使われているルールは、付け替えるかSkippedにするかを選んでから消す
 async function removeRule(ruleId: number) {
-    if(!confirm("Delete rule?")) return;
-    await invoke("delete_weekly_rule", { ruleId });
+    if (!currentPlanId || !currentConfig) return;
+    if (!confirm("Delete rule?")) return;
+    const planId = currentPlanId;
+    try {
+        await invokeEdit("delete_weekly_rule_with", { planId, ruleId, mode: { kind: "refuse" } });
+    } catch (e) {
+        const others = currentConfig.rules.filter(r => r.rule.id !== ruleId);
+        const answer = prompt(`${e}\n\n付け替え先のルール名を入力してください (空欄のままだとその週をSkippedにします):\n${others.map(r => r.rule.name).join(', ')}`);
+        if (answer === null) return;
+        const target = others.find(r => r.rule.name === answer.trim());
+        if (answer.trim() !== "" && !target) {
+            alert(`ルール「${answer.trim()}」はありません`);
+            return;
+        }
+        const mode: RuleDeletion = target ? { kind: "reassign", toRuleId: target.rule.id } : { kind: "skip" };
+        try {
+            const changed = await invokeEdit<string[]>("delete_weekly_rule_with", { planId, ruleId, mode });
+            alert(`${changed.length} 週を書き換えました: ${changed.join(', ')}`);
+        } catch (e) {
+            alert(`Failed to delete rule: ${e}`);
+            return;
+        }
+    }
     reloadConfig();
+    await renderCalendarView();
 }
 
 async function addAssignment(ruleId: number, weekday: number, shiftTime: number, groupId: number, memberIndex: number) {
-    await invoke("add_rule_assignment", { ruleId, weekday, shiftTime, groupId, memberIndex });
-    reloadConfig();
+    try {
+        const edit: RuleEdit = { kind: "addAssignment", ruleId, weekday, shiftTime, groupId, memberIndex };
+        if (await applyRuleEdit(edit, "Add this assignment?")) reloadConfig();
+    } catch (e) {
+        alert(`Failed to add assignment: ${e}`);
+    }
 }
 
 async function removeAssignment(assignmentId: number) {
-    // 誤操作防止の確認
-    if (!confirm("Remove this assignment?")) return; // 確認が煩わしい場合はコメントアウトしてください
-
     try {
-        // Rustコマンド呼び出し
-        await invoke("delete_assignment", { assignmentId });
-        
-        // 画面更新
-        await reloadConfig();
+        // 変わるコマを見せて確認してから消す
+        if (await applyRuleEdit({ kind: "deleteAssignment", assignmentId }, "Remove this assignment?")) {
+            // 画面更新
+            await reloadConfig();
+        }
     } catch (e) {
         console.error("Failed to remove assignment:", e);
         alert(`Failed to remove assignment: ${e}`);
@@ -480,7 +695,7 @@ async function updateMemberName(memberId: number) {
     const newName = prompt("Enter new name:");
     if (newName && newName.trim() !== "") {
         try {
-            await invoke("update_member_name", { memberId, name: newName.trim() });
+            await invokeEdit("update_member_name", { memberId, name: newName.trim() });
             await reloadConfig();
         } catch (e) {
             alert(`Failed to update member name: ${e}`);
@@ -488,7 +703,238 @@ async function updateMemberName(memberId: number) {
     }
 }
 
+// JSONで編集する (空欄で解除)
+async function updateMemberConstraints(memberId: number) {
+    const member = currentConfig?.groups.flatMap(g => g.members).find(m => m.id === memberId);
+    const template: MemberConstraints = member?.constraints ?? {
+        max_shifts_per_week: null,
+        max_shifts_per_month: null,
+        days_off: [],
+        no_morning_after_afternoon: false,
+        employment_start: null,
+        employment_end: null,
+    };
+    const text = prompt("Constraints (JSON, empty = none):", JSON.stringify(template));
+    if (text === null) return;
+    try {
+        if (text.trim() === "") {
+            await invokeEdit("clear_member_constraints", { memberId });
+        } else {
+            await invokeEdit("set_member_constraints", { memberId, constraints: JSON.parse(text) });
+        }
+        await reloadConfig();
+    } catch (e) {
+        alert(`Failed to update constraints: ${e}`);
+    }
+}
+
+// 在籍期間 (空欄で指定なし)。辞めた人は削除せずに退職日を入れると、過去のシフトが変わらない
+async function updateMemberPeriod(memberId: number) {
+    const member = currentConfig?.groups.flatMap(g => g.members).find(m => m.id === memberId);
+    const joined = prompt("Joined on (YYYY-MM-DD, empty = none):", member?.joined_on ?? '');
+    if (joined === null) return;
+    const left = prompt("Left on (YYYY-MM-DD, empty = none):", member?.left_on ?? '');
+    if (left === null) return;
+    try {
+        await invokeEdit("update_member_period", {
+            memberId,
+            joinedOn: joined.trim() === "" ? null : joined.trim(),
+            leftOn: left.trim() === "" ? null : left.trim(),
+        });
+        await reloadConfig();
+    } catch (e) {
+        alert(`Failed to update period: ${e}`);
+    }
+}
+
+// 他のプランの同じ人とまとめる (IDで選ぶ・「+名前」で新しく作る・空欄で外す)
+async function linkMemberPerson(memberId: number) {
+    const member = currentConfig?.groups.flatMap(g => g.members).find(m => m.id === memberId);
+    try {
+        const people = await invoke<Person[]>("list_people");
+        const answer = prompt([
+            `Person for ${member?.name ?? memberId}:`,
+            ...people.map(p => `  [${p.id}] ${p.name}`),
+            ``,
+            `Enter an ID, "+Name" to add a new person, or leave empty to unlink`,
+        ].join('\n'), member?.person_id?.toString() ?? '');
+        if (answer === null) return;
+
+        const text = answer.trim();
+        let personId: number | null = null;
+        if (text.startsWith('+')) {
+            personId = await invoke<number>("add_person", { name: text.slice(1).trim() || member?.name });
+        } else if (text !== '') {
+            personId = parseInt(text);
+        }
+        await invokeEdit("link_member_person", { memberId, personId });
+        await reloadConfig();
+    } catch (e) {
+        alert(`Failed to link person: ${e}`);
+    }
+}
+
+// 表示している月に、同じ人が複数のプランで同じコマに入っていないかを調べる
+async function showCrossPlanConflicts() {
+    const first = new Date(currentYear, currentMonth, 1);
+    const last = new Date(currentYear, currentMonth + 1, 0);
+    const format = (d: Date) => `${d.getFullYear()}-${String(d.getMonth() + 1).padStart(2, '0')}-${String(d.getDate()).padStart(2, '0')}`;
+    try {
+        const conflicts = await invoke<CrossPlanConflictDto[]>("get_cross_plan_conflicts", {
+            startDate: format(first),
+            endDate: format(last),
+        });
+        alert(conflicts.length === 0
+            ? `${currentYear}年${currentMonth + 1}月にプランをまたいだ重なりはありません`
+            : conflicts.map(c => c.message).join('\n'));
+    } catch (e) {
+        alert(`重なりを調べられませんでした: ${e}`);
+    }
+}
+
 // Generateボタン: UI上のスキップ設定を集めてバックエンドへ送る
+// 表示している月を今の内容で固定する (公開)
+async function lockMonth() {
+    if (!currentPlanId) return;
+    try {
+        await invokeEdit("lock_month", { planId: currentPlanId, targetYear: currentYear, targetMonth: currentMonth });
+        await renderCalendarView();
+    } catch (e) {
+        alert(`固定に失敗しました: ${e}`);
+    }
+}
+
+// 表示している月の固定を外す (ルールから導出し直す)
+async function unlockMonth() {
+    if (!currentPlanId) return;
+    if (!confirm('この月の固定を外すと、今のルールから導出し直します。続けますか？')) return;
+    try {
+        await invokeEdit("unlock_month", { planId: currentPlanId, targetYear: currentYear, targetMonth: currentMonth });
+        await renderCalendarView();
+    } catch (e) {
+        alert(`固定の解除に失敗しました: ${e}`);
+    }
+}
+
+// タイムラインの末尾からルールのパターンで延ばす
+// 無いパターン名のときは「ルール名:週数, ...」か「odd=ルール名, even=ルール名」として作る (ルール名が "-" の週は Skipped)
+async function extendByPattern() {
+    if (!currentPlanId || !currentConfig) return;
+    const planId = currentPlanId;
+    const rules = currentConfig.rules;
+    try {
+        const calendar = await invoke<ShiftCalendarManager | null>("get_calendar_state", { planId });
+        if (!calendar) {
+            alert('カレンダーがありません');
+            return;
+        }
+        const patterns = await invoke<RulePattern[]>("list_rule_patterns", { planId });
+        const answer = prompt(`使うパターン名を入力してください:\n${patterns.map(p => p.name).join(', ')}\n\n新しく作るときは「A:3, B:1」(Aを3週、Bを1週) や「odd=A, even=B」(ISO週の奇数/偶数) と入力します`);
+        if (!answer || !answer.trim()) return;
+
+        let patternId = patterns.find(p => p.name === answer.trim())?.id;
+        if (patternId === undefined) {
+            const ruleIdOf = (name: string): number | null => {
+                if (name.trim() === '-') return null;
+                const rule = rules.find(r => r.rule.name === name.trim());
+                if (!rule) throw `ルール「${name.trim()}」はありません`;
+                return rule.rule.id;
+            };
+            const parts = answer.split(',').map(part => part.trim());
+            let kind: RulePatternKind;
+            if (parts.every(part => part.startsWith('odd=') || part.startsWith('even='))) {
+                const side = (key: string) => {
+                    const part = parts.find(p => p.startsWith(`${key}=`));
+                    return part ? ruleIdOf(part.slice(key.length + 1)) : null;
+                };
+                kind = { kind: "isoWeekParity", odd: side('odd'), even: side('even') };
+            } else {
+                const steps = parts.map(part => {
+                    const [name, weeks] = part.split(':');
+                    return { ruleId: ruleIdOf(name), weeks: weeks ? parseInt(weeks) : 1 };
+                });
+                kind = { kind: "sequence", anchorAbsWeek: calendar.baseAbsWeek + calendar.timeline.length, steps };
+            }
+            patternId = await invokeEdit<number>("add_rule_pattern", { planId, name: answer.trim(), kind });
+        }
+
+        const weeks = parseInt(prompt('何週延ばしますか?', '4') ?? '');
+        if (isNaN(weeks) || weeks <= 0) return;
+        await invokeEdit<number>("extend_timeline_by_pattern", { planId, patternId, weeks });
+        await renderCalendarView();
+    } catch (e) {
+        alert(`パターンで延ばせませんでした: ${e}`);
+    }
+}
+
+// 休業ルールの一覧を見せて、追加・削除する (追加した後に延ばす週から当たる)
+// 表示中の月のシフト表を、壁に貼る用の HTML に書き出す
+async function exportMonthSchedule() {
+    if (!currentPlanId || !currentConfig) return;
+    const groups = currentConfig.groups.map(g => `  [${g.group.id}] ${g.group.name}`);
+    const outputPath = prompt(`書き出すファイルのパス (.html。PDF はブラウザの印刷から保存してください):`, `shift-${currentYear}-${String(currentMonth + 1).padStart(2, '0')}.html`);
+    if (!outputPath || !outputPath.trim()) return;
+    const groupAnswer = prompt([`1グループだけにするときはIDを入力します (空欄で全員):`, ...groups].join('\n'));
+    if (groupAnswer === null) return;
+    const groupId = groupAnswer.trim() ? parseInt(groupAnswer.trim()) : null;
+    try {
+        await invoke("export_month_schedule", {
+            planId: currentPlanId,
+            targetYear: currentYear,
+            targetMonth: currentMonth,
+            groupId,
+            outputPath: outputPath.trim(),
+        });
+        alert(`${outputPath.trim()} に書き出しました`);
+    } catch (e) {
+        alert(`シフト表を書き出せませんでした: ${e}`);
+    }
+}
+
+async function manageSkipRules() {
+    if (!currentPlanId) return;
+    const planId = currentPlanId;
+    const describe = (rule: SkipRule) => {
+        switch (rule.kind) {
+            case "annualClosure": return `毎年 ${rule.from} 〜 ${rule.to}`;
+            case "lastWeekOfMonth": return `毎月の最後の週`;
+            case "dates": return rule.dates.join(', ');
+            case "nationalHolidays": return `祝日・休日が ${rule.minDays} 日以上ある週`;
+        }
+    };
+    try {
+        const rules = await invoke<SkipRule[]>("list_skip_rules", { planId });
+        const answer = prompt([
+            `休業ルール:`,
+            ...rules.map(r => `  [${r.id}] ${describe(r)}`),
+            ``,
+            `追加するときは「12-29~01-03」(毎年)、「last」(毎月の最後の週)、「2027-02-11, 2027-03-21」(日付)、「holidays 3」(祝日が3日以上の週) と入力します`,
+            `消すときは「-ID」と入力します`,
+        ].join('\n'));
+        if (!answer || !answer.trim()) return;
+
+        const text = answer.trim();
+        if (text.startsWith('-')) {
+            await invokeEdit("delete_skip_rule", { skipRuleId: parseInt(text.slice(1)) });
+            return;
+        }
+        let kind: SkipRuleKind;
+        if (text === 'last') {
+            kind = { kind: "lastWeekOfMonth" };
+        } else if (text.startsWith('holidays')) {
+            kind = { kind: "nationalHolidays", minDays: parseInt(text.slice('holidays'.length).trim() || '1') };
+        } else if (text.includes('~')) {
+            const [from, to] = text.split('~').map(part => part.trim());
+            kind = { kind: "annualClosure", from, to };
+        } else {
+            kind = { kind: "dates", dates: text.split(',').map(part => part.trim()) };
+        }
+        await invokeEdit<number>("add_skip_rule", { planId, kind });
+    } catch (e) {
+        alert(`休業ルールを変更できませんでした: ${e}`);
+    }
+}
+
 async function handleGenerate() {
     if (!currentPlanId) {
         alert("Please select a plan first.");
@@ -547,6 +993,11 @@ async function handleGenerate() {
    CALENDAR VIEW
    ========================================================================== */
 
+// "YYYY-MM-DD" (ローカル日付。toISOString は UTC になるので使わない)
+function formatDateKey(day: Date): string {
+    return `${day.getFullYear()}-${String(day.getMonth() + 1).padStart(2, '0')}-${String(day.getDate()).padStart(2, '0')}`;
+}
+
 async function renderCalendarView() {
     if (!currentPlanId) return;
 
@@ -564,7 +1015,7 @@ async function renderCalendarView() {
     const weeksData = calculateCalendarDates(currentYear, currentMonth);
 
     // 1. Rustから「確定シフトデータ」を取得
-    let shiftData: MonthlyShiftResult = { weeks: [] };
+    let shiftData: MonthlyShiftResult = { weeks: [], violations: [] };
     try {
         shiftData = await invoke<MonthlyShiftResult>("derive_monthly_shift", {
             planId: currentPlanId,
@@ -576,6 +1027,29 @@ async function renderCalendarView() {
         console.error("Failed to derive shifts:", e);
     }
 
+    // 表示している範囲の祝日・休日 (Skippedの週にも出す)
+    const holidays = new Map<string, string>();
+    try {
+        const firstDay = weeksData[0].days[0];
+        const lastWeek = weeksData[weeksData.length - 1].days;
+        const list = await invoke<HolidayDto[]>("list_holidays", {
+            startDate: formatDateKey(firstDay),
+            endDate: formatDateKey(lastWeek[lastWeek.length - 1]),
+        });
+        list.forEach(h => holidays.set(h.date, h.name));
+    } catch (e) {
+        console.error("Failed to load holidays:", e);
+    }
+
+    // 月のコマ数の上限の違反は月の見出しに出す
+    const monthViolations = shiftData.violations.filter(v => v.date === null);
+    if (label && monthViolations.length > 0) {
+        label.textContent += ` ⚠ ${monthViolations.length}`;
+        label.title = monthViolations.map(v => v.message).join('\n');
+    } else if (label) {
+        label.title = "";
+    }
+
     // 2. DBから保存済みの状態を取得 (Fixed判定用)
     let savedTimeline: WeekStatus[] = [];
     try {
@@ -706,6 +1180,31 @@ async function renderCalendarView() {
         statusText.style.marginTop = "4px";
 
         controlCell.appendChild(statusText);
+
+        // 人員チェックの警告・スキルのチェック結果・週のコマ数の上限の違反 (件数とホバーで内容)
+        const mondayKey = formatDateKey(week.days[0]);
+        const warnings = [
+            ...(shiftData.weeks[i]?.warnings ?? []),
+            ...(shiftData.weeks[i]?.skillIssues ?? []),
+            ...shiftData.violations.filter(v => v.date === mondayKey && v.kind === 'tooManyShiftsInWeek'),
+        ];
+        if (shiftData.weeks[i]?.locked) {
+            const lockBadge = document.createElement('span');
+            lockBadge.style.fontSize = "0.7em";
+            lockBadge.style.marginTop = "2px";
+            lockBadge.textContent = '🔒';
+            lockBadge.title = '固定した週です (ルールやメンバーを変えても変わりません)';
+            controlCell.appendChild(lockBadge);
+        }
+        if (state === 'fixed_active' && warnings.length > 0) {
+            const warnBadge = document.createElement('span');
+            warnBadge.style.fontSize = "0.7em";
+            warnBadge.style.color = "#e65100";
+            warnBadge.style.marginTop = "2px";
+            warnBadge.textContent = `⚠ ${warnings.length}`;
+            warnBadge.title = warnings.map(w => w.message).join('\n');
+            controlCell.appendChild(warnBadge);
+        }
         row.appendChild(controlCell);
 
         // [右カラム] 日付セル
@@ -714,6 +1213,16 @@ async function renderCalendarView() {
             cell.className = 'cal-cell-day'; // CSS: .cal-cell-day
             cell.textContent = day.getDate().toString();
 
+            // 祝日・休日は赤字で名前を添える
+            const holiday = holidays.get(formatDateKey(day));
+            if (holiday) {
+                cell.style.color = '#c62828';
+                const hBadge = document.createElement('div');
+                hBadge.style.fontSize = '0.7em';
+                hBadge.textContent = holiday;
+                cell.appendChild(hBadge);
+            }
+
             // 今月以外の日付は薄く
             if (day.getMonth() !== currentMonth) {
                 cell.style.opacity = '0.3';
@@ -760,6 +1269,13 @@ async function renderCalendarView() {
                 }
             }
 
+            // メンバーの制約の違反 (その日のもの)
+            const violations = shiftData.violations.filter(v => v.date === formatDateKey(day) && v.kind !== 'tooManyShiftsInWeek');
+            if (state === 'fixed_active' && violations.length > 0) {
+                cell.style.outline = '2px solid #e65100';
+                cell.title = violations.map(v => v.message).join('\n');
+            }
+
             row.appendChild(cell);
         });
 
@@ -809,6 +1325,11 @@ function setupEventListeners() {
         createPlanBtn.addEventListener('click', createNewPlan);
     }
 
+    // 週の出勤日数の上限
+    document.getElementById('max-days-btn')?.addEventListener('click', updatePlanMaxDays);
+    // スキルを持っていない人の入れ替え
+    document.getElementById('auto-swap-btn')?.addEventListener('click', toggleAutoSwap);
+
     // 3. 画面切り替え (View Switching)
     document.getElementById('switch-viewer')?.addEventListener('click', () => {
         document.getElementById('view-calendar')!.style.display = 'block';
@@ -861,6 +1382,13 @@ function setupEventListeners() {
         renderCalendarView();
     });
 
+    document.getElementById('lock-btn')?.addEventListener('click', lockMonth);
+    document.getElementById('unlock-btn')?.addEventListener('click', unlockMonth);
+    document.getElementById('pattern-btn')?.addEventListener('click', extendByPattern);
+    document.getElementById('skip-rules-btn')?.addEventListener('click', manageSkipRules);
+    document.getElementById('print-btn')?.addEventListener('click', exportMonthSchedule);
+    document.getElementById('conflicts-btn')?.addEventListener('click', showCrossPlanConflicts);
+
     // Generate Button
     // document.getElementById('generate-btn')?.addEventListener('click', handleGenerate);
     //
@@ -873,6 +1401,12 @@ function setupEventListeners() {
 // Global Exports for onclick
 (window as any).removeGroup = removeGroup;
 (window as any).updateGroupName = updateGroupName;
+(window as any).updateGroupMinStaff = updateGroupMinStaff;
+(window as any).updateGroupRotation = updateGroupRotation;
+(window as any).updateMemberConstraints = updateMemberConstraints;
+(window as any).updateMemberSkills = updateMemberSkills;
+(window as any).updateMemberPeriod = updateMemberPeriod;
+(window as any).linkMemberPerson = linkMemberPerson;
 (window as any).removeMember = removeMember;
 (window as any).removeRule = removeRule;
 
@@ -1,6 +1,9 @@
 export interface Plan {
   id: number;
   name: string;
+  max_days_per_week: number | null;
+  auto_swap_skills: boolean;
+  revision: number; // プランの中身を書き換えるたびに1増える (書き換えるコマンドの expectedRevision)
 }
 
 export interface StaffGroup {
@@ -8,15 +11,50 @@ export interface StaffGroup {
   plan_id: number;
   name: string;
   sort_order: number;
+  min_per_period: number | null;
+  rotation: Rotation | null; // null = 既定の並べ方
 }
 
+// ローテーションの方式
+export type Rotation =
+  | { kind: "fixed" }
+  | { kind: "byWeek" }
+  | { kind: "byDay" }
+  | { kind: "everyNWeeks"; weeks: number }
+  | { kind: "shuffle"; seed: number };
+
 export interface StaffMember {
   id: number;
   group_id: number;
   name: string;
   sort_order: number;
+  // 在籍期間 ("YYYY-MM-DD", 両端を含む。null = 指定なし)
+  joined_on: string | null;
+  left_on: string | null;
+  // プランをまたいだ同じ人 (null = まとめていない)
+  person_id: number | null;
+  constraints: MemberConstraints | null;
+  skills: string[];
+}
+
+// プランをまたいだ同じ人 (list_people)
+export interface Person {
+  id: number;
+  name: string;
 }
 
+// メンバーの制約 (null / 空 = 指定なし)
+export interface MemberConstraints {
+  max_shifts_per_week: number | null;
+  max_shifts_per_month: number | null;
+  days_off: Weekday[];
+  no_morning_after_afternoon: boolean;
+  employment_start: string | null; // "YYYY-MM-DD"
+  employment_end: string | null;
+}
+
+export type Weekday = "Monday" | "Tuesday" | "Wednesday" | "Thursday" | "Friday" | "Saturday" | "Sunday";
+
 export interface StaffGroupWithMembers {
   group: StaffGroup;
   members: StaffMember[];
@@ -36,6 +74,7 @@ export interface RuleAssignment {
   shift_time_type: number;
   target_group_id: number;
   target_member_index: number;
+  required_skills: string[];
 }
 
 export interface WeeklyRuleWithAssignments {
@@ -43,10 +82,22 @@ export interface WeeklyRuleWithAssignments {
   assignments: RuleAssignment[];
 }
 
+// 週・日単位の調整 (add_shift_override / PlanConfig.overrides)
+export interface OverrideEntry {
+  absWeek: number;
+  weekday: "Monday" | "Tuesday" | "Wednesday" | "Thursday" | "Friday" | "Saturday" | "Sunday";
+  shiftTime: "Morning" | "Afternoon";
+  memberId: number;
+  action: "add" | "remove";
+}
+
+export type PlanOverride = { id: number; planId: number } & OverrideEntry;
+
 export interface PlanConfig {
   plan: Plan;
   groups: StaffGroupWithMembers[];
   rules: WeeklyRuleWithAssignments[];
+  overrides: PlanOverride[]; // 追加した順に当てる
 }
 
 // カレンダー状態 (Rust Enum -> TS Tagged Union)
@@ -66,12 +117,155 @@ export interface ShiftCalendarManager {
 export interface DailyShiftDto {
     morning: string[];
     afternoon: string[];
+    holiday: string | null; // 祝日・休日の名前
+}
+
+// 祝日・休日 (list_holidays)
+export interface HolidayDto {
+    date: string; // "YYYY-MM-DD"
+    name: string;
+}
+
+// 複数のプランをまとめたシフトの1コマ (get_team_schedule)
+export interface TeamShiftDto {
+    date: string; // "YYYY-MM-DD"
+    shiftTime: "Morning" | "Afternoon";
+    personId: number | null;
+    name: string; // まとめた人の名前 (まとめていなければメンバーの名前)
+    planId: number;
+    planName: string;
+    memberId: number;
+    groupName: string;
+    holiday: string | null;
+}
+
+// 同じ人が同じ日・同じ時間帯に複数のプランに入っている重なり (get_cross_plan_conflicts)
+export interface CrossPlanConflictDto {
+    personId: number;
+    name: string;
+    date: string;
+    shiftTime: "Morning" | "Afternoon";
+    shifts: TeamShiftDto[];
+    message: string;
+}
+
+// 人員チェックの警告 (kind ごとに項目が違う)
+export interface CoverageWarningDto {
+    kind: "emptyPeriod" | "belowMinimum" | "doubleBooked" | "tooManyDays";
+    weekday?: number;
+    period?: "Morning" | "Afternoon";
+    message: string;
+}
+
+export interface SkillIssueDto {
+    kind: "unqualified" | "swapped";
+    weekday: number;
+    period: "Morning" | "Afternoon";
+    message: string;
 }
 
 export interface WeeklyShiftDto {
     days: DailyShiftDto[];
+    warnings: CoverageWarningDto[];
+    skillIssues: SkillIssueDto[];
+    locked: boolean; // 固定した週 (ルールを変えても変わらない)
+}
+
+export interface StaffViolationDto {
+    kind: "tooManyShiftsInWeek" | "tooManyShiftsInMonth" | "dayOff" | "noRest" | "outsideEmployment";
+    memberId: number;
+    date: string | null; // 週の上限はその週の月曜、月の上限は null
+    message: string;
 }
 
 export interface MonthlyShiftResult {
     weeks: (WeeklyShiftDto | null)[];
+    violations: StaffViolationDto[];
+}
+
+// ルールの変更 (preview_rule_edit / apply_rule_edit の edit)
+export type RuleEdit =
+    | { kind: "addAssignment"; ruleId: number; weekday: number; shiftTime: number; groupId: number; memberIndex: number }
+    | { kind: "deleteAssignment"; assignmentId: number }
+    | { kind: "deleteRule"; ruleId: number };
+
+// ルールを変えると入っている人が変わるコマ
+export interface CellChangeDto {
+    absWeek: number;
+    weekday: number;
+    period: "Morning" | "Afternoon";
+    before: string[];
+    after: string[];
+    date: string; // "YYYY-MM-DD"
+    locked: boolean; // 固定した週 (固定を外すと変わる)
+    message: string;
 }
+
+export interface RuleEditImpactDto {
+    changes: CellChangeDto[];
+    lockedWeeks: string[]; // 変えるルールを使っている固定した週の月曜
+}
+
+// タイムラインで使われているルールを消すときの扱い (delete_weekly_rule_with の mode)
+export type RuleDeletion =
+    | { kind: "refuse" }
+    | { kind: "reassign"; toRuleId: number }
+    | { kind: "skip" };
+
+// ルールの繰り返し方 (rule_id が null の区切り・週は Skipped)
+export interface PatternStep {
+    ruleId: number | null;
+    weeks: number;
+}
+
+export type RulePatternKind =
+    | { kind: "sequence"; anchorAbsWeek: number; steps: PatternStep[] } // anchorAbsWeek から steps を順に繰り返す
+    | { kind: "isoWeekParity"; odd: number | null; even: number | null }; // ISO週番号の奇数/偶数
+
+export type RulePattern = {
+    id: number;
+    planId: number;
+    name: string;
+} & RulePatternKind;
+
+// パターンから追加した週
+export interface PatternWeek {
+    absWeek: number;
+    patternId: number;
+}
+
+// 週を追加するときに自動でSkippedにする週 (休業ルール)
+export type SkipRuleKind =
+    | { kind: "annualClosure"; from: string; to: string } // "MM-DD" (年をまたいでもよい)
+    | { kind: "lastWeekOfMonth" } // 月曜がその月の最後の月曜の週
+    | { kind: "dates"; dates: string[] } // "YYYY-MM-DD" の入っている週
+    | { kind: "nationalHolidays"; minDays: number }; // 祝日・休日が minDays 日以上ある週
+
+export type SkipRule = {
+    id: number;
+    planId: number;
+} & SkipRuleKind;
+
+// バックエンドの変更の通知 (イベント名は plan-changed / staff-changed / rule-changed / timeline-changed / people-changed)
+export type ChangeAction = "created" | "updated" | "deleted";
+
+export interface ChangeTarget {
+    type: "group" | "member" | "rule" | "assignment" | "pattern" | "skipRule" | "person";
+    id: number;
+}
+
+export type ChangeEvent =
+    | { kind: "planChanged"; planId: number; action: ChangeAction }
+    | { kind: "staffChanged"; planId: number; target: ChangeTarget; action: ChangeAction }
+    | { kind: "ruleChanged"; planId: number; target: ChangeTarget; action: ChangeAction }
+    | {
+        kind: "timelineChanged";
+        planId: number;
+        change: "created" | "extended" | "truncated" | "rewritten" | "locked" | "unlocked";
+        fromAbsWeek: number;
+        count: number | null; // null = 最後まで
+    }
+    | { kind: "peopleChanged"; target: ChangeTarget; action: ChangeAction };
+
+// 変更の番号 (get_change_version と同じ) + 変更
+export type ChangeNotice = { version: number } & ChangeEvent;
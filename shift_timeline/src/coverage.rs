@@ -0,0 +1,310 @@
+//! 導出したシフトの人員チェック
+//!
+//! ルールの組み方によっては、誰もいないコマや、同じ人が同じコマに2回入るコマができてしまう
+//! 導出した1週間分 (`WeekDecidedShift`) を見て、次のものを警告として返す
+//! - 誰もいないコマ
+//! - グループごとの最低人数を下回るコマ
+//! - 同じ人が同じコマに2回以上入っている (同じ名前で別のグループにいる場合も含む)
+//! - 週の出勤日数の上限を超えている人
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+use shift_calendar::shift_gen::{Staff, WeekDecidedShift};
+
+use crate::staff_schedule::ShiftPeriod;
+
+pub(crate) const WEEKDAY_NAMES: [&str; 7] = ["月", "火", "水", "木", "金", "土", "日"];
+
+/// グループごとの条件 (`StaffGroupList` と同じ並び)
+#[derive(Debug, Clone, Default)]
+pub struct GroupCoverage {
+    /// 警告に出す名前
+    pub name: String,
+    /// 1コマあたりの最低人数 (`None` = 指定なし)
+    pub min_per_period: Option<usize>,
+}
+
+/// チェックの条件
+#[derive(Debug, Clone, Default)]
+pub struct CoverageLimits {
+    pub groups: Vec<GroupCoverage>,
+    /// 1人あたりの週の出勤日数の上限 (午前・午後のどちらかに入っていれば1日)
+    pub max_days_per_week: Option<usize>,
+}
+
+impl CoverageLimits {
+    fn group_name(&self, group_index: usize) -> String {
+        self.groups
+            .get(group_index)
+            .map(|g| g.name.clone())
+            .unwrap_or_else(|| format!("グループ{}", group_index))
+    }
+
+    /// 警告に出す名前 (名前が空のスロットは `グループ名[位置]`)
+    fn staff_label(&self, staff: &Staff) -> String {
+        if staff.name.is_empty() {
+            format!("{}[{}]", self.group_name(staff.group_id), staff.id)
+        } else {
+            staff.name.clone()
+        }
+    }
+}
+
+/// 見つかった問題
+/// `weekday` は 0 (月) 〜 6 (日)
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum CoverageWarning {
+    EmptyPeriod {
+        weekday: usize,
+        period: ShiftPeriod,
+    },
+    BelowMinimum {
+        weekday: usize,
+        period: ShiftPeriod,
+        group: String,
+        required: usize,
+        assigned: usize,
+    },
+    DoubleBooked {
+        weekday: usize,
+        period: ShiftPeriod,
+        staff: String,
+    },
+    TooManyDays {
+        staff: String,
+        days: usize,
+        max: usize,
+    },
+}
+
+impl CoverageWarning {
+    /// 曜日に関係する警告ならその曜日
+    pub fn weekday(&self) -> Option<usize> {
+        match self {
+            CoverageWarning::EmptyPeriod { weekday, .. }
+            | CoverageWarning::BelowMinimum { weekday, .. }
+            | CoverageWarning::DoubleBooked { weekday, .. } => Some(*weekday),
+            CoverageWarning::TooManyDays { .. } => None,
+        }
+    }
+
+    /// コマに関係する警告ならその時間帯
+    pub fn period(&self) -> Option<ShiftPeriod> {
+        match self {
+            CoverageWarning::EmptyPeriod { period, .. }
+            | CoverageWarning::BelowMinimum { period, .. }
+            | CoverageWarning::DoubleBooked { period, .. } => Some(*period),
+            CoverageWarning::TooManyDays { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for ShiftPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShiftPeriod::Morning => write!(f, "午前"),
+            ShiftPeriod::Afternoon => write!(f, "午後"),
+        }
+    }
+}
+
+impl fmt::Display for CoverageWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoverageWarning::EmptyPeriod { weekday, period } =>
+                write!(f, "{}曜 {}: 誰も入っていません", WEEKDAY_NAMES[*weekday], period),
+            CoverageWarning::BelowMinimum { weekday, period, group, required, assigned } =>
+                write!(
+                    f, "{}曜 {}: {} が {} 人です (最低 {} 人)",
+                    WEEKDAY_NAMES[*weekday], period, group, assigned, required
+                ),
+            CoverageWarning::DoubleBooked { weekday, period, staff } =>
+                write!(f, "{}曜 {}: {} が2回以上入っています", WEEKDAY_NAMES[*weekday], period, staff),
+            CoverageWarning::TooManyDays { staff, days, max } =>
+                write!(f, "{} の出勤が週 {} 日です (上限 {} 日)", staff, days, max),
+        }
+    }
+}
+
+/// 同じ人かどうかの判定に使うキー
+/// 名前があれば名前 (別のグループに同じ人がいる場合)、無ければグループ内の位置
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+enum StaffKey {
+    Name(String),
+    Slot(usize, usize),
+}
+
+fn staff_key(staff: &Staff) -> StaffKey {
+    if staff.name.is_empty() {
+        StaffKey::Slot(staff.group_id, staff.id)
+    } else {
+        StaffKey::Name(staff.name.clone())
+    }
+}
+
+/// 1週間分のチェック (曜日・午前午後の順、最後に出勤日数)
+pub fn check_week(week: &WeekDecidedShift, limits: &CoverageLimits) -> Vec<CoverageWarning> {
+    let mut warnings = Vec::new();
+    // 人ごとの (出勤した曜日の集合, 表示名)
+    let mut worked_days: BTreeMap<StaffKey, ([bool; 7], String)> = BTreeMap::new();
+
+    for (weekday, day) in week.0.iter().enumerate() {
+        for (period, staff_list) in [
+            (ShiftPeriod::Morning, &day.shift_morning),
+            (ShiftPeriod::Afternoon, &day.shift_afternoon),
+        ] {
+            if staff_list.is_empty() {
+                warnings.push(CoverageWarning::EmptyPeriod { weekday, period });
+                continue;
+            }
+
+            for (group_index, group) in limits.groups.iter().enumerate() {
+                let Some(required) = group.min_per_period else { continue };
+                let assigned = staff_list.iter().filter(|s| s.group_id == group_index).count();
+                if assigned < required {
+                    warnings.push(CoverageWarning::BelowMinimum {
+                        weekday,
+                        period,
+                        group: group.name.clone(),
+                        required,
+                        assigned,
+                    });
+                }
+            }
+
+            let mut seen: BTreeMap<StaffKey, usize> = BTreeMap::new();
+            for staff in staff_list.iter() {
+                let key = staff_key(staff);
+                let count = seen.entry(key.clone()).or_insert(0);
+                *count += 1;
+                if *count == 2 {
+                    warnings.push(CoverageWarning::DoubleBooked {
+                        weekday,
+                        period,
+                        staff: limits.staff_label(staff),
+                    });
+                }
+
+                worked_days
+                    .entry(key)
+                    .or_insert_with(|| ([false; 7], limits.staff_label(staff)))
+                    .0[weekday] = true;
+            }
+        }
+    }
+
+    if let Some(max) = limits.max_days_per_week {
+        for (days, staff) in worked_days.into_values() {
+            let days = days.iter().filter(|d| **d).count();
+            if days > max {
+                warnings.push(CoverageWarning::TooManyDays { staff, days, max });
+            }
+        }
+    }
+    warnings
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod coverage_test {
+    use shift_calendar::shift_gen::{DayDecidedShift, Staff, WeekDecidedShift};
+
+    use super::{CoverageLimits, CoverageWarning, GroupCoverage, check_week};
+    use crate::staff_schedule::ShiftPeriod;
+
+    /// [正社員: 田中, 佐藤], [パート: 田中 (正社員と同じ人), 名前なし]
+    fn staff() -> Vec<Vec<Staff>> {
+        [["田中", "佐藤"], ["田中", ""]]
+            .iter()
+            .enumerate()
+            .map(|(group_id, names)| names
+                .iter()
+                .enumerate()
+                .map(|(id, name)| Staff { name: name.to_string(), group_id, id })
+                .collect())
+            .collect()
+    }
+
+    fn limits(min: [Option<usize>; 2], max_days_per_week: Option<usize>) -> CoverageLimits {
+        CoverageLimits {
+            groups: ["正社員", "パート"]
+                .iter()
+                .zip(min)
+                .map(|(name, min_per_period)| GroupCoverage { name: name.to_string(), min_per_period })
+                .collect(),
+            max_days_per_week,
+        }
+    }
+
+    type Slots<'s> = &'s [(usize, usize)];
+
+    /// `days[曜日] = (午前, 午後)` の (グループ, 位置) から1週間分を作る
+    fn week<'a>(staff: &'a [Vec<Staff>], days: [(Slots, Slots); 7]) -> WeekDecidedShift<'a> {
+        let pick = |slots: Slots| -> Vec<&'a Staff> {
+            slots.iter().map(|(g, i)| &staff[*g][*i]).collect()
+        };
+        WeekDecidedShift(days.map(|(m, a)| DayDecidedShift {
+            shift_morning: pick(m),
+            shift_afternoon: pick(a),
+        }))
+    }
+
+    /// 問題の無い週・空いているコマ
+    #[test]
+    fn test00() {
+        let staff = staff();
+        let full: [(Slots, Slots); 7] = [(&[(0, 0)], &[(0, 1)]); 7];
+        assert!(check_week(&week(&staff, full), &limits([None, None], None)).is_empty());
+
+        let mut days = full;
+        days[6] = (&[], &[]);
+        let warnings = check_week(&week(&staff, days), &limits([None, None], None));
+        assert_eq!(warnings, vec![
+            CoverageWarning::EmptyPeriod { weekday: 6, period: ShiftPeriod::Morning },
+            CoverageWarning::EmptyPeriod { weekday: 6, period: ShiftPeriod::Afternoon },
+        ]);
+        assert_eq!(warnings[0].to_string(), "日曜 午前: 誰も入っていません");
+    }
+
+    /// 最低人数・二重に入っている人
+    #[test]
+    fn test01() {
+        let staff = staff();
+        let mut days: [(Slots, Slots); 7] = [(&[(0, 0)], &[(0, 1)]); 7];
+        // 月曜午前: 正社員の田中とパートの田中 (同じ人)
+        days[0].0 = &[(0, 0), (1, 0)];
+        // 火曜午後: 名前の無いスロットが2回
+        days[1].1 = &[(1, 1), (1, 1)];
+
+        let warnings = check_week(&week(&staff, days), &limits([Some(1), None], None));
+        assert_eq!(warnings, vec![
+            CoverageWarning::DoubleBooked { weekday: 0, period: ShiftPeriod::Morning, staff: String::from("田中") },
+            CoverageWarning::BelowMinimum {
+                weekday: 1,
+                period: ShiftPeriod::Afternoon,
+                group: String::from("正社員"),
+                required: 1,
+                assigned: 0,
+            },
+            CoverageWarning::DoubleBooked { weekday: 1, period: ShiftPeriod::Afternoon, staff: String::from("パート[1]") },
+        ]);
+        assert_eq!(warnings[1].to_string(), "火曜 午後: 正社員 が 0 人です (最低 1 人)");
+    }
+
+    /// 週の出勤日数
+    #[test]
+    fn test02() {
+        let staff = staff();
+        let days: [(Slots, Slots); 7] = [(&[(0, 0)], &[(0, 1)]); 7];
+
+        let warnings = check_week(&week(&staff, days), &limits([None, None], Some(5)));
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[1].to_string(), "田中 の出勤が週 7 日です (上限 5 日)");
+        assert_eq!(warnings[1].weekday(), None);
+
+        assert!(check_week(&week(&staff, days), &limits([None, None], Some(7))).is_empty());
+    }
+}
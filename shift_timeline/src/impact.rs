@@ -0,0 +1,122 @@
+//! 設定を変えたときに変わるコマ
+//!
+//! ルールの穴1つを足したり消したりするだけでも、ローテーションの位置がずれて何か月分ものシフトが変わることがある
+//! 変える前と変えた後の導出結果 (`WeekDecidedShift`) を比べて、入っている人が変わるコマを返す
+//! コマの中の並び順だけが変わった場合は、変わっていないものとして扱う
+
+use serde::Serialize;
+use shift_calendar::shift_gen::{Staff, WeekDecidedShift};
+
+use crate::shift_calendar_manager::AbsWeek;
+use crate::staff_schedule::ShiftPeriod;
+
+/// 入っている人が変わるコマ
+/// `weekday` は 0 (月) 〜 6 (日)。`before` / `after` はコマに入っている人の名前 (導出した並び)
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CellChange {
+    pub abs_week: AbsWeek,
+    pub weekday: usize,
+    pub period: ShiftPeriod,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// 同じ人かどうかはグループ内の位置で判定する (名前は空のことがある)
+fn members(staff_list: &[&Staff]) -> Vec<(usize, usize)> {
+    let mut keys: Vec<(usize, usize)> = staff_list.iter().map(|s| (s.group_id, s.id)).collect();
+    keys.sort();
+    keys
+}
+
+fn names(staff_list: &[&Staff]) -> Vec<String> {
+    staff_list.iter().map(|s| s.name.clone()).collect()
+}
+
+/// `before` と `after` (どちらも先頭が `first_abs_week`) で入っている人が変わるコマを、週・曜日・午前午後の順に返す
+/// 導出しない週 (`None`) は誰も入っていないものとして比べる
+pub fn diff_weeks(
+    first_abs_week: AbsWeek,
+    before: &[Option<WeekDecidedShift>],
+    after: &[Option<WeekDecidedShift>],
+) -> Vec<CellChange> {
+    let mut changes = Vec::new();
+    for offset in 0..before.len().max(after.len()) {
+        let week_before = before.get(offset).and_then(Option::as_ref);
+        let week_after = after.get(offset).and_then(Option::as_ref);
+
+        for weekday in 0..7 {
+            for period in [ShiftPeriod::Morning, ShiftPeriod::Afternoon] {
+                let cell = |week: Option<&WeekDecidedShift<'_>>| -> (Vec<(usize, usize)>, Vec<String>) {
+                    let Some(week) = week else { return (vec![], vec![]) };
+                    let day = &week.0[weekday];
+                    let staff_list = match period {
+                        ShiftPeriod::Morning => &day.shift_morning,
+                        ShiftPeriod::Afternoon => &day.shift_afternoon,
+                    };
+                    (members(staff_list), names(staff_list))
+                };
+                let (members_before, names_before) = cell(week_before);
+                let (members_after, names_after) = cell(week_after);
+                if members_before != members_after {
+                    changes.push(CellChange {
+                        abs_week: first_abs_week + offset,
+                        weekday,
+                        period,
+                        before: names_before,
+                        after: names_after,
+                    });
+                }
+            }
+        }
+    }
+    changes
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod impact_test {
+    use shift_calendar::shift_gen::{DayDecidedShift, Staff, WeekDecidedShift};
+
+    use super::{CellChange, diff_weeks};
+    use crate::staff_schedule::ShiftPeriod;
+
+    fn week<'a>(monday_morning: Vec<&'a Staff>) -> WeekDecidedShift<'a> {
+        let mut week = WeekDecidedShift(core::array::from_fn(|_| DayDecidedShift {
+            shift_morning: vec![],
+            shift_afternoon: vec![],
+        }));
+        week.0[0].shift_morning = monday_morning;
+        week
+    }
+
+    /// 入っている人が変わったコマだけが返る (並び順だけの違いは含まない)
+    #[test]
+    fn test00() {
+        let tanaka = Staff { name: String::from("田中"), group_id: 0, id: 0 };
+        let sato = Staff { name: String::from("佐藤"), group_id: 0, id: 1 };
+
+        let before = vec![Some(week(vec![&tanaka, &sato])), Some(week(vec![&tanaka])), None];
+        let after = vec![Some(week(vec![&sato, &tanaka])), Some(week(vec![&sato])), Some(week(vec![&tanaka]))];
+
+        let changes = diff_weeks(100, &before, &after);
+        assert_eq!(changes, vec![
+            CellChange {
+                abs_week: 101,
+                weekday: 0,
+                period: ShiftPeriod::Morning,
+                before: vec![String::from("田中")],
+                after: vec![String::from("佐藤")],
+            },
+            CellChange {
+                abs_week: 102,
+                weekday: 0,
+                period: ShiftPeriod::Morning,
+                before: vec![],
+                after: vec![String::from("田中")],
+            },
+        ]);
+
+        assert!(diff_weeks(100, &before, &before).is_empty());
+    }
+}
@@ -0,0 +1,61 @@
+//! WASM版 (component-features) とデスクトップ版 (shift-manager-tauri) で共有する
+//! シフトカレンダー（タイムライン）のドメインロジック
+//!
+//! 週ごとの付加情報（ルールIDなど）は型パラメータ `P` として持たせる
+//! - WASM版: `P = ()`
+//! - デスクトップ版: `P = RuleRef`
+
+pub mod coverage;
+pub mod impact;
+pub mod locked_weeks;
+pub mod overrides;
+pub mod rotation;
+pub mod schema;
+pub mod shift_calendar_manager;
+pub mod skills;
+pub mod staff_constraints;
+pub mod staff_removal;
+pub mod staff_schedule;
+
+pub use shift_calendar_manager::{
+    AbsWeek,
+    AppendWeekErrorKind,
+    CALENDAR_SCHEMA,
+    LogicalDelta,
+    NotDerivedWeek,
+    ShiftCalendarManager,
+    WeekStatus,
+    calculate_partial_shift,
+    calculate_partial_shift_by_week,
+};
+pub use coverage::{CoverageLimits, CoverageWarning, GroupCoverage, check_week};
+pub use impact::{CellChange, diff_weeks};
+pub use locked_weeks::{DETACHED_GROUP, LockedDay, LockedOverlay, LockedStaff, LockedWeek, rebind_members};
+pub use overrides::{OverrideAction, ShiftOverride, apply_overrides};
+pub use rotation::{
+    DaySlots,
+    Rotation,
+    RotationSlot,
+    RotationStrategy,
+    RuleSlot,
+    WeekSlots,
+    apply_rotation,
+};
+pub use skills::{
+    DaySkillRule,
+    SkillIssue,
+    SkilledStaff,
+    SlotRequirement,
+    WeekSkillRule,
+    check_skills,
+};
+pub use staff_constraints::{
+    AbsDay,
+    ConstrainedStaff,
+    StaffConstraints,
+    StaffViolation,
+    abs_day,
+    check_staff_constraints,
+};
+pub use staff_removal::{StaffRemoval, remove_from_overrides};
+pub use staff_schedule::{ShiftPeriod, StaffShift, staff_shifts_of};
@@ -0,0 +1,211 @@
+//! 確定 (公開) した週の固定
+//!
+//! シフトは毎回ルールから導出し直すので、ルールやメンバーを変えると公開済みの週まで変わってしまう
+//! 公開した週は、解決済みのスタッフ (曜日・午前午後ごと) を `LockedWeek` として保存しておき、
+//! 導出結果をその内容で置き換える (`LockedOverlay`)
+//!
+//! スタッフは名前とグループ内の位置をそのまま持つので、
+//! 後でメンバーの名前を変えたり削除したりしても、固定した週の表示は変わらない
+//! メンバーをIDで管理している場合 (デスクトップ版) は `member_id` も持たせ、
+//! 使う前に `rebind_members` で今の位置に付け替える (削除・並び替えで別の人と取り違えないように)
+
+use serde::{Deserialize, Serialize};
+use shift_calendar::shift_gen::{DayDecidedShift, Staff, WeekDecidedShift};
+
+use crate::shift_calendar_manager::AbsWeek;
+
+/// 固定したコマに入っている1人
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedStaff {
+    pub name: String,
+    pub group_index: usize,
+    pub staff_index: usize,
+    /// 固定したときのメンバーID (メンバーをIDで管理していない WASM版では None)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member_id: Option<i64>,
+}
+
+/// 1日分 (並びは導出結果と同じ)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedDay {
+    pub morning: Vec<LockedStaff>,
+    pub afternoon: Vec<LockedStaff>,
+}
+
+/// 固定した1週間分 (`days` は月曜から)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedWeek {
+    pub abs_week: AbsWeek,
+    pub days: [LockedDay; 7],
+}
+
+impl LockedWeek {
+    /// 導出した1週間分をそのまま固定する
+    pub fn from_decided(abs_week: AbsWeek, week: &WeekDecidedShift) -> Self {
+        let lock = |list: &Vec<&Staff>| -> Vec<LockedStaff> {
+            list.iter()
+                .map(|s| LockedStaff { name: s.name.clone(), group_index: s.group_id, staff_index: s.id, member_id: None })
+                .collect()
+        };
+        LockedWeek {
+            abs_week,
+            days: core::array::from_fn(|weekday| LockedDay {
+                morning: lock(&week.0[weekday].shift_morning),
+                afternoon: lock(&week.0[weekday].shift_afternoon),
+            }),
+        }
+    }
+    /// 固定したコマに入っている人 (曜日・午前午後・コマの中の順)
+    pub fn staff_mut(&mut self) -> impl Iterator<Item = &mut LockedStaff> {
+        self.days.iter_mut().flat_map(|day| day.morning.iter_mut().chain(day.afternoon.iter_mut()))
+    }
+}
+
+/// 固定した週の `member_id` を持つ人を、`position_of` が返す今の (グループの位置, グループ内の位置) に付け替える
+/// 位置が見つからない人 (削除したメンバー) は、どのグループにも入らない位置 (`DETACHED_GROUP`) にする。名前はそのまま
+/// `member_id` の無い人は、固定したときの位置のまま
+pub fn rebind_members(
+    locked_weeks: &[LockedWeek],
+    position_of: impl Fn(i64) -> Option<(usize, usize)>,
+) -> Vec<LockedWeek> {
+    let mut weeks = locked_weeks.to_vec();
+    for staff in weeks.iter_mut().flat_map(LockedWeek::staff_mut) {
+        let Some(member_id) = staff.member_id else { continue };
+        (staff.group_index, staff.staff_index) = position_of(member_id)
+            // 削除したメンバーどうしが同じ人に見えないよう、グループ内の位置にはIDを使う
+            .unwrap_or((DETACHED_GROUP, member_id as usize));
+    }
+    weeks
+}
+
+/// 削除したメンバーを入れるグループの位置 (どのグループとも一致しない)
+pub const DETACHED_GROUP: usize = usize::MAX;
+
+/// 1日分の (午前, 午後)
+type DayStaff = (Vec<Staff>, Vec<Staff>);
+
+/// 固定した週を `Staff` にしたもの (導出結果の中から参照されるので、導出結果より長く持つ)
+pub struct LockedOverlay {
+    first_abs_week: AbsWeek,
+    /// (`first_abs_week` からの位置, 曜日ごとの (午前, 午後))
+    weeks: Vec<(usize, [DayStaff; 7])>,
+}
+
+impl LockedOverlay {
+    /// `first_abs_week` から `range` 週分に入る固定した週
+    pub fn new(locked_weeks: &[LockedWeek], first_abs_week: AbsWeek, range: usize) -> Self {
+        let to_staff = |list: &Vec<LockedStaff>| -> Vec<Staff> {
+            list.iter()
+                .map(|s| Staff { name: s.name.clone(), group_id: s.group_index, id: s.staff_index })
+                .collect()
+        };
+        let weeks = locked_weeks
+            .iter()
+            .filter(|w| first_abs_week <= w.abs_week && w.abs_week < first_abs_week + range)
+            .map(|w| (
+                w.abs_week - first_abs_week,
+                core::array::from_fn(|weekday| (to_staff(&w.days[weekday].morning), to_staff(&w.days[weekday].afternoon))),
+            ))
+            .collect();
+        LockedOverlay { first_abs_week, weeks }
+    }
+
+    /// `first_abs_week` から `offset` 週目が固定されているか
+    pub fn is_locked(&self, offset: usize) -> bool {
+        self.weeks.iter().any(|(o, _)| *o == offset)
+    }
+
+    /// 固定した週の絶対週
+    pub fn abs_weeks(&self) -> impl Iterator<Item = AbsWeek> + '_ {
+        self.weeks.iter().map(|(offset, _)| self.first_abs_week + offset)
+    }
+
+    /// `decided` (先頭が `first_abs_week`) のうち、固定した週を固定した内容で置き換える
+    pub fn apply<'a>(&'a self, decided: &mut [Option<WeekDecidedShift<'a>>]) {
+        for (offset, days) in &self.weeks {
+            let Some(week) = decided.get_mut(*offset) else { continue };
+            *week = Some(WeekDecidedShift(core::array::from_fn(|weekday| DayDecidedShift {
+                shift_morning: days[weekday].0.iter().collect(),
+                shift_afternoon: days[weekday].1.iter().collect(),
+            })));
+        }
+    }
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod locked_weeks_test {
+    use shift_calendar::shift_gen::{DayDecidedShift, Staff, WeekDecidedShift};
+
+    use super::{DETACHED_GROUP, LockedOverlay, LockedWeek, rebind_members};
+
+    fn empty_week<'a>() -> WeekDecidedShift<'a> {
+        WeekDecidedShift(core::array::from_fn(|_| DayDecidedShift {
+            shift_morning: vec![],
+            shift_afternoon: vec![],
+        }))
+    }
+
+    /// 固定した内容で置き換わり、範囲外・固定していない週はそのまま
+    #[test]
+    fn test00() {
+        let tanaka = Staff { name: String::from("田中"), group_id: 0, id: 1 };
+        let sato = Staff { name: String::from("佐藤"), group_id: 0, id: 0 };
+
+        let mut published = empty_week();
+        published.0[2].shift_afternoon = vec![&tanaka];
+        let locked = vec![LockedWeek::from_decided(101, &published), LockedWeek::from_decided(110, &published)];
+
+        // 固定した後にルールが変わり、佐藤 が入るようになった
+        let current = || {
+            let mut week = empty_week();
+            week.0[2].shift_afternoon = vec![&sato];
+            week
+        };
+        let mut decided = vec![Some(current()), Some(current()), None];
+
+        let overlay = LockedOverlay::new(&locked, 100, 3);
+        assert!(!overlay.is_locked(0) && overlay.is_locked(1));
+        assert_eq!(overlay.abs_weeks().collect::<Vec<_>>(), vec![101]);
+        overlay.apply(&mut decided);
+
+        assert_eq!(decided[0].as_ref().unwrap().0[2].shift_afternoon[0].name, "佐藤");
+        let week = decided[1].as_ref().unwrap();
+        assert_eq!(week.0[2].shift_afternoon[0].name, "田中");
+        assert_eq!((week.0[2].shift_afternoon[0].group_id, week.0[2].shift_afternoon[0].id), (0, 1));
+        assert!(decided[2].is_none());
+
+        // 保存する形
+        let json = serde_json::to_value(&locked[0]).unwrap();
+        assert_eq!(json["absWeek"], 101);
+        assert_eq!(json["days"][2]["afternoon"][0], serde_json::json!({ "name": "田中", "groupIndex": 0, "staffIndex": 1 }));
+    }
+
+    /// member_id を持つ人は今の位置に付け替え、見つからない人はどのグループにも入らない
+    #[test]
+    fn test01() {
+        let tanaka = Staff { name: String::from("田中"), group_id: 0, id: 0 };
+        let sato = Staff { name: String::from("佐藤"), group_id: 0, id: 1 };
+        let suzuki = Staff { name: String::from("鈴木"), group_id: 1, id: 0 };
+
+        let mut published = empty_week();
+        published.0[0].shift_morning = vec![&tanaka, &sato, &suzuki];
+        let mut locked = LockedWeek::from_decided(100, &published);
+        // 田中 (ID 10) と 佐藤 (ID 11) はIDを持ち、鈴木 は持たない
+        locked.days[0].morning[0].member_id = Some(10);
+        locked.days[0].morning[1].member_id = Some(11);
+
+        // 田中 を削除したので 佐藤 が先頭になった
+        let rebound = rebind_members(&[locked], |member_id| (member_id == 11).then_some((0, 0)));
+        let positions: Vec<_> = rebound[0].days[0].morning
+            .iter()
+            .map(|s| (s.name.as_str(), s.group_index, s.staff_index))
+            .collect();
+        assert_eq!(positions, vec![("田中", DETACHED_GROUP, 10), ("佐藤", 0, 0), ("鈴木", 1, 0)]);
+
+        let json = serde_json::to_value(&rebound[0].days[0].morning[1]).unwrap();
+        assert_eq!(json, serde_json::json!({ "name": "佐藤", "groupIndex": 0, "staffIndex": 0, "memberId": 11 }));
+    }
+}
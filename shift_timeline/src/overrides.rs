@@ -0,0 +1,151 @@
+//! 週・日単位の手動調整
+//!
+//! ルールから導出した結果に、1コマずつスタッフを足したり外したりする
+//! (急な休み・ヘルプなど、ルールを変えるほどではない変更)
+//!
+//! 固定した週には当てない。固定した内容にはその時点の調整がもう入っているので、
+//! 導出 → `apply_overrides` → `LockedOverlay::apply` の順に呼ぶ
+
+use serde::{Deserialize, Serialize};
+use shift_calendar::shift_gen::{Staff, WeekDecidedShift};
+
+use crate::shift_calendar_manager::AbsWeek;
+use crate::staff_schedule::ShiftPeriod;
+
+/// 足すか外すか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverrideAction {
+    Add,
+    Remove,
+}
+
+/// 1コマの調整 (`group_index` の `staff_index` 番目のスタッフを足す・外す)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftOverride {
+    pub abs_week: AbsWeek,
+    /// 0 (月) 〜 6 (日)
+    pub weekday: usize,
+    pub period: ShiftPeriod,
+    pub group_index: usize,
+    pub staff_index: usize,
+    pub action: OverrideAction,
+}
+
+impl ShiftOverride {
+    /// 曜日・スタッフが範囲外なら理由 (`group_sizes` はグループごとの人数)
+    pub fn problem(&self, group_sizes: &[usize]) -> Option<String> {
+        if self.weekday > 6 {
+            return Some(format!("曜日 {} は 0-6 の範囲外です", self.weekday));
+        }
+        match group_sizes.get(self.group_index) {
+            None => Some(format!("グループ {} が存在しません", self.group_index)),
+            Some(size) if self.staff_index >= *size => Some(format!(
+                "グループ {} にスタッフ {} がいません",
+                self.group_index, self.staff_index
+            )),
+            _ => None,
+        }
+    }
+
+    fn is_target(&self, staff: &Staff) -> bool {
+        staff.group_id == self.group_index && staff.id == self.staff_index
+    }
+}
+
+/// `decided` (先頭が `first_abs_week`) に調整を並びの順に当てる
+/// `staff` は足すときに使う全スタッフ (`Staff::group_id` / `id` で探す)
+/// 導出していない週 (Skipped・範囲外) と、見つからないスタッフを足す調整は無視する
+/// 既に入っている人は足さない (同じコマに2回入れない)
+pub fn apply_overrides<'a>(
+    decided: &mut [Option<WeekDecidedShift<'a>>],
+    first_abs_week: AbsWeek,
+    overrides: &[ShiftOverride],
+    staff: &'a [Staff],
+) {
+    for o in overrides {
+        let Some(offset) = o.abs_week.checked_sub(first_abs_week) else { continue };
+        let Some(Some(week)) = decided.get_mut(offset) else { continue };
+        let Some(day) = week.0.get_mut(o.weekday) else { continue };
+        let staff_list = match o.period {
+            ShiftPeriod::Morning => &mut day.shift_morning,
+            ShiftPeriod::Afternoon => &mut day.shift_afternoon,
+        };
+        match o.action {
+            OverrideAction::Add => {
+                if staff_list.iter().any(|s| o.is_target(s)) {
+                    continue;
+                }
+                if let Some(target) = staff.iter().find(|s| o.is_target(s)) {
+                    staff_list.push(target);
+                }
+            }
+            OverrideAction::Remove => staff_list.retain(|s| !o.is_target(s)),
+        }
+    }
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod overrides_test {
+    use shift_calendar::shift_gen::{DayDecidedShift, Staff, WeekDecidedShift};
+
+    use super::{OverrideAction, ShiftOverride, apply_overrides};
+    use crate::staff_schedule::ShiftPeriod;
+
+    fn staff() -> Vec<Staff> {
+        ["a0", "a1"]
+            .iter()
+            .enumerate()
+            .map(|(id, name)| Staff { name: name.to_string(), group_id: 0, id })
+            .collect()
+    }
+
+    fn over(abs_week: usize, weekday: usize, staff_index: usize, action: OverrideAction) -> ShiftOverride {
+        ShiftOverride { abs_week, weekday, period: ShiftPeriod::Morning, group_index: 0, staff_index, action }
+    }
+
+    /// 月曜午前に a0 が入っている週
+    fn week(staff: &[Staff]) -> WeekDecidedShift<'_> {
+        let mut week = WeekDecidedShift(core::array::from_fn(|_| DayDecidedShift {
+            shift_morning: vec![],
+            shift_afternoon: vec![],
+        }));
+        week.0[0].shift_morning.push(&staff[0]);
+        week
+    }
+
+    /// 足す・外すを並びの順に当てる
+    #[test]
+    fn test00() {
+        let staff = staff();
+        let mut decided = vec![Some(week(&staff)), None, Some(week(&staff))];
+        apply_overrides(&mut decided, 100, &[
+            over(100, 0, 0, OverrideAction::Remove),
+            over(100, 0, 1, OverrideAction::Add),
+            over(100, 0, 1, OverrideAction::Add),
+            // Skippedの週・範囲外の週・いないスタッフは無視する
+            over(101, 0, 1, OverrideAction::Add),
+            over(99, 0, 1, OverrideAction::Add),
+            over(102, 3, 5, OverrideAction::Add),
+        ], &staff);
+
+        let names = |week: &Option<WeekDecidedShift>, weekday: usize| -> Vec<String> {
+            week.as_ref().unwrap().0[weekday].shift_morning.iter().map(|s| s.name.clone()).collect()
+        };
+        assert_eq!(names(&decided[0], 0), vec!["a1"]);
+        assert!(decided[1].is_none());
+        assert_eq!(names(&decided[2], 0), vec!["a0"]);
+        assert!(names(&decided[2], 3).is_empty());
+    }
+
+    /// 範囲外の曜日・スタッフ
+    #[test]
+    fn test01() {
+        assert_eq!(over(100, 0, 1, OverrideAction::Add).problem(&[2]), None);
+        assert_eq!(over(100, 7, 0, OverrideAction::Add).problem(&[2]), Some(String::from("曜日 7 は 0-6 の範囲外です")));
+        assert_eq!(over(100, 0, 2, OverrideAction::Add).problem(&[2]), Some(String::from("グループ 0 にスタッフ 2 がいません")));
+        assert_eq!(over(100, 0, 0, OverrideAction::Add).problem(&[]), Some(String::from("グループ 0 が存在しません")));
+    }
+}
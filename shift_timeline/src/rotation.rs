@@ -0,0 +1,290 @@
+//! ローテーションの方式
+//!
+//! `gen_one_week_shift` の並べ方は1通りしかないので、グループごとに別の方式を選べるようにする
+//! 方式を指定したグループの穴だけ、導出した1週間分 (`WeekDecidedShift`) の人を置き換える
+//! (指定の無いグループは `gen_one_week_shift` の結果のまま)
+//!
+//! 穴と導出結果の対応は `skills` と同じく、同じ日・同じ時間帯・同じグループの中での並び順でとる
+
+use serde::{Deserialize, Serialize};
+use shift_calendar::shift_gen::{Staff, WeekDecidedShift};
+
+use crate::shift_calendar_manager::LogicalDelta;
+
+/// 方式に渡す、穴1つ分の情報
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotationSlot {
+    /// ルールに書かれたグループ内の位置
+    pub member_index: usize,
+    /// グループの人数 (1以上)
+    pub group_size: usize,
+    pub logical_delta: LogicalDelta,
+    /// 0 (月) 〜 6 (日)
+    pub weekday: usize,
+}
+
+/// ローテーションの方式
+pub trait RotationStrategy {
+    /// 穴に入る人のグループ内の位置 (`0..group_size`)
+    fn pick(&self, slot: &RotationSlot) -> usize;
+}
+
+/// ローテーションしない (ルールに書かれた位置の人)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fixed;
+
+impl RotationStrategy for Fixed {
+    fn pick(&self, slot: &RotationSlot) -> usize {
+        slot.member_index % slot.group_size
+    }
+}
+
+/// 週ごとに1人ずつずらす
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoundRobinByWeek;
+
+impl RotationStrategy for RoundRobinByWeek {
+    fn pick(&self, slot: &RotationSlot) -> usize {
+        (slot.member_index + slot.logical_delta) % slot.group_size
+    }
+}
+
+/// 日ごとに1人ずつずらす (週をまたいでも続けてずれる)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoundRobinByDay;
+
+impl RotationStrategy for RoundRobinByDay {
+    fn pick(&self, slot: &RotationSlot) -> usize {
+        (slot.member_index + slot.logical_delta * 7 + slot.weekday) % slot.group_size
+    }
+}
+
+/// `weeks` 週ごとに1人ずつずらす
+#[derive(Debug, Clone, Copy)]
+pub struct EveryNWeeks {
+    /// 1以上 (0 は 1 として扱う)
+    pub weeks: usize,
+}
+
+impl RotationStrategy for EveryNWeeks {
+    fn pick(&self, slot: &RotationSlot) -> usize {
+        (slot.member_index + slot.logical_delta / self.weeks.max(1)) % slot.group_size
+    }
+}
+
+/// 週ごとに `seed` から決まる順番に並べ替える (同じ `seed`・同じ週なら毎回同じ)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SeededShuffle {
+    pub seed: u64,
+}
+
+impl SeededShuffle {
+    /// その週の並び (`order[member_index]` がグループ内の位置)
+    pub fn order(&self, logical_delta: LogicalDelta, group_size: usize) -> Vec<usize> {
+        let mut state = self.seed ^ (logical_delta as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let mut next = move || {
+            // splitmix64
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+
+        // Fisher-Yates
+        let mut order: Vec<usize> = (0..group_size).collect();
+        for i in (1..group_size).rev() {
+            let j = (next() % (i as u64 + 1)) as usize;
+            order.swap(i, j);
+        }
+        order
+    }
+}
+
+impl RotationStrategy for SeededShuffle {
+    fn pick(&self, slot: &RotationSlot) -> usize {
+        self.order(slot.logical_delta, slot.group_size)[slot.member_index % slot.group_size]
+    }
+}
+
+/// 保存する方式の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Rotation {
+    Fixed,
+    ByWeek,
+    ByDay,
+    EveryNWeeks { weeks: usize },
+    Shuffle { seed: u64 },
+}
+
+impl RotationStrategy for Rotation {
+    fn pick(&self, slot: &RotationSlot) -> usize {
+        match *self {
+            Rotation::Fixed => Fixed.pick(slot),
+            Rotation::ByWeek => RoundRobinByWeek.pick(slot),
+            Rotation::ByDay => RoundRobinByDay.pick(slot),
+            Rotation::EveryNWeeks { weeks } => EveryNWeeks { weeks }.pick(slot),
+            Rotation::Shuffle { seed } => SeededShuffle { seed }.pick(slot),
+        }
+    }
+}
+
+/// ルールの穴1つ分 (`ShiftHoll` と同じ中身)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuleSlot {
+    pub group_index: usize,
+    pub member_index: usize,
+}
+
+/// 1日分 (`DayRule` と同じ形)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DaySlots {
+    pub shift_morning: Vec<RuleSlot>,
+    pub shift_afternoon: Vec<RuleSlot>,
+}
+
+/// 1週間分 (`WeekRule` と同じ形)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeekSlots(pub [DaySlots; 7]);
+
+/// 方式を指定したグループ (`rotations[グループ]` が `Some`) の穴の人を置き換える
+/// `staff` はグループの全員 (グループ内の位置は `Staff::id`)
+pub fn apply_rotation<'a, S: RotationStrategy>(
+    week: &mut WeekDecidedShift<'a>,
+    slots: &WeekSlots,
+    staff: &'a [Staff],
+    rotations: &[Option<S>],
+    logical_delta: LogicalDelta,
+) {
+    if rotations.iter().all(Option::is_none) {
+        return;
+    }
+
+    let members_of = |group_index: usize| -> Vec<&'a Staff> {
+        let mut members: Vec<&'a Staff> = staff.iter().filter(|s| s.group_id == group_index).collect();
+        members.sort_by_key(|s| s.id);
+        members
+    };
+    let groups: Vec<Vec<&'a Staff>> = (0..rotations.len()).map(members_of).collect();
+
+    for (weekday, (day, day_slots)) in week.0.iter_mut().zip(&slots.0).enumerate() {
+        for (assigned, slots) in [
+            (&mut day.shift_morning, &day_slots.shift_morning),
+            (&mut day.shift_afternoon, &day_slots.shift_afternoon),
+        ] {
+            let mut used = vec![false; assigned.len()];
+            for slot in slots {
+                let Some(position) = (0..assigned.len())
+                    .find(|&i| !used[i] && assigned[i].group_id == slot.group_index)
+                else {
+                    continue; // グループが空で穴が埋まらなかった
+                };
+                used[position] = true;
+
+                let (Some(Some(strategy)), Some(members)) = (rotations.get(slot.group_index), groups.get(slot.group_index))
+                else {
+                    continue;
+                };
+                if members.is_empty() {
+                    continue;
+                }
+                let index = strategy.pick(&RotationSlot {
+                    member_index: slot.member_index,
+                    group_size: members.len(),
+                    logical_delta,
+                    weekday,
+                });
+                assigned[position] = members[index % members.len()];
+            }
+        }
+    }
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod rotation_test {
+    use shift_calendar::shift_gen::{DayDecidedShift, Staff, WeekDecidedShift};
+
+    use super::{
+        DaySlots, EveryNWeeks, Fixed, Rotation, RotationSlot, RotationStrategy, RoundRobinByDay,
+        RoundRobinByWeek, RuleSlot, SeededShuffle, WeekSlots, apply_rotation,
+    };
+
+    fn slot(member_index: usize, logical_delta: usize, weekday: usize) -> RotationSlot {
+        RotationSlot { member_index, group_size: 3, logical_delta, weekday }
+    }
+
+    /// 方式ごとの位置
+    #[test]
+    fn test00() {
+        assert_eq!(Fixed.pick(&slot(1, 5, 3)), 1);
+        assert_eq!(RoundRobinByWeek.pick(&slot(1, 5, 3)), 0);
+        assert_eq!(RoundRobinByDay.pick(&slot(0, 0, 4)), 1);
+        assert_eq!(RoundRobinByDay.pick(&slot(0, 1, 0)), 1); // 7日後
+        let every_two = EveryNWeeks { weeks: 2 };
+        let weeks: Vec<_> = (0..5).map(|delta| every_two.pick(&slot(0, delta, 0))).collect();
+        assert_eq!(weeks, vec![0, 0, 1, 1, 2]);
+        assert_eq!(EveryNWeeks { weeks: 0 }.pick(&slot(0, 2, 0)), 2);
+    }
+
+    /// シャッフルは同じ seed・同じ週なら同じ並びで、全員が1回ずつ出てくる
+    #[test]
+    fn test01() {
+        let shuffle = SeededShuffle { seed: 42 };
+        for delta in 0..10 {
+            let order = shuffle.order(delta, 5);
+            assert_eq!(order, shuffle.order(delta, 5));
+            let mut sorted = order.clone();
+            sorted.sort();
+            assert_eq!(sorted, vec![0, 1, 2, 3, 4]);
+        }
+        let orders: Vec<_> = (0..10).map(|delta| shuffle.order(delta, 5)).collect();
+        assert!(orders.iter().any(|o| *o != orders[0]), "週によって並びが変わる");
+        assert_ne!(
+            (0..10).map(|delta| SeededShuffle { seed: 7 }.order(delta, 5)).collect::<Vec<_>>(),
+            orders,
+        );
+    }
+
+    /// 方式を指定したグループの穴だけ置き換わる
+    #[test]
+    fn test02() {
+        // グループ0: a0, a1, a2 / グループ1: b0, b1
+        let staff: Vec<Staff> = [(0, 3), (1, 2)]
+            .iter()
+            .flat_map(|&(group_id, size)| (0..size).map(move |id| Staff {
+                name: format!("{}{}", ["a", "b"][group_id], id),
+                group_id,
+                id,
+            }))
+            .collect();
+
+        // 月曜午前: グループ1の0番, グループ0の0番 (導出結果は b1, a2 とする)
+        let mut week = WeekDecidedShift(core::array::from_fn(|_| DayDecidedShift {
+            shift_morning: vec![],
+            shift_afternoon: vec![],
+        }));
+        week.0[0].shift_morning = vec![&staff[4], &staff[2]];
+        let mut slots = WeekSlots::default();
+        slots.0[0] = DaySlots {
+            shift_morning: vec![
+                RuleSlot { group_index: 1, member_index: 0 },
+                RuleSlot { group_index: 0, member_index: 0 },
+            ],
+            shift_afternoon: vec![],
+        };
+
+        apply_rotation(&mut week, &slots, &staff, &[Some(Rotation::Fixed), None], 4);
+        let names: Vec<_> = week.0[0].shift_morning.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["b1", "a0"]);
+
+        apply_rotation(&mut week, &slots, &staff, &[None, Some(Rotation::ByWeek)], 3);
+        let names: Vec<_> = week.0[0].shift_morning.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["b1", "a0"]);
+
+        // 保存する形
+        let json = serde_json::to_value(Rotation::EveryNWeeks { weeks: 2 }).unwrap();
+        assert_eq!(json, serde_json::json!({ "kind": "everyNWeeks", "weeks": 2 }));
+    }
+}
@@ -0,0 +1,278 @@
+//! 保存するJSONのバージョン管理
+//!
+//! ファイルに保存するJSONは、次のエンベロープで包む
+//! ```json
+//! { "format": "shift-calendar", "version": 1, "data": { ...本体... } }
+//! ```
+//!
+//! エンベロープの無いJSON (バージョン管理を入れる前に保存したもの) は version 0 として読み、
+//! マイグレーションを順に適用して最新の形にしてから本体の型に変換する
+//! 知らない (新しい) バージョンは読まずにエラーにする
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use std::fmt;
+
+pub type SchemaVersion = u32;
+
+/// version n の本体を version n+1 の本体に変換する
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+/// 保存形式のエンベロープ
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Envelope<T> {
+    pub format: String,
+    pub version: SchemaVersion,
+    pub data: T,
+}
+
+/// 読み込むときの検査で見つかった問題
+///
+/// `path` は本体 (`data`) の中の位置 (例: `rules[0].schedule.mon.m[1]`)
+/// ファイル全体に関わるもの (パースの失敗・バージョン違いなど) は空文字列
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationError {
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { path: path.into(), message: message.into() }
+    }
+
+    /// ファイル全体に関わる問題
+    pub fn document(message: impl Into<String>) -> Self {
+        Self::new("", message)
+    }
+
+    /// 外側のドキュメントに埋め込まれたときの位置にする
+    /// (例: `timeline[2]` → `calendar.timeline[2]`)
+    pub fn within(self, prefix: &str) -> Self {
+        let path = if self.path.is_empty() {
+            prefix.to_string()
+        } else {
+            format!("{}.{}", prefix, self.path)
+        };
+        Self { path, ..self }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// ドキュメントの種類ごとの定義
+pub struct DocumentSchema {
+    /// エンベロープの `format`
+    pub format: &'static str,
+    /// `migrations[n]` は version n → n+1 の変換
+    /// (最新のバージョンは `migrations.len()`)
+    pub migrations: &'static [Migration],
+}
+
+impl DocumentSchema {
+    pub fn current_version(&self) -> SchemaVersion {
+        self.migrations.len() as SchemaVersion
+    }
+
+    /// 最新バージョンのエンベロープで包む
+    pub fn wrap<'a, T: Serialize>(&self, data: &'a T) -> Envelope<&'a T> {
+        Envelope {
+            format: self.format.to_string(),
+            version: self.current_version(),
+            data,
+        }
+    }
+
+    /// 最新バージョンのJSON文字列にする
+    pub fn to_json<T: Serialize>(&self, data: &T) -> Result<String, String> {
+        serde_json::to_string(&self.wrap(data))
+            .map_err(|_e| String::from("FailedToStringify"))
+    }
+
+    /// JSON文字列を読み、古いバージョンなら最新にしてから `T` に変換する
+    pub fn from_json<T: DeserializeOwned>(&self, json_str: &str) -> Result<T, String> {
+        let value: Value = serde_json::from_str(json_str)
+            .map_err(|e| format!("JSON parse error: {}", e))?;
+        self.from_value(value)
+    }
+
+    /// JSON以外 (TOMLなど) から読んだ値を、`from_json` と同じ手順で `T` に変換する
+    pub fn from_value<T: DeserializeOwned>(&self, value: Value) -> Result<T, String> {
+        let (version, mut data) = self.unwrap_envelope(value)?;
+
+        for migration in &self.migrations[version as usize..] {
+            data = migration(data)?;
+        }
+
+        serde_json::from_value(data)
+            .map_err(|e| format!("JSON parse error: {}", e))
+    }
+
+    /// `from_value` のあと `validate` で本体を検査する
+    /// 問題があればすべてまとめて返し、`T` は返さない
+    /// (呼び出し側は Ok のときだけ状態を差し替えればよい)
+    pub fn load_value<T: DeserializeOwned>(
+        &self,
+        value: Value,
+        validate: impl FnOnce(&T) -> Vec<ValidationError>,
+    ) -> Result<T, Vec<ValidationError>> {
+        let data: T = self
+            .from_value(value)
+            .map_err(|e| vec![ValidationError::document(e)])?;
+
+        let errors = validate(&data);
+        if errors.is_empty() {
+            Ok(data)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// JSON文字列を読み、`load_value` と同じく検査まで行う
+    pub fn load_json<T: DeserializeOwned>(
+        &self,
+        json_str: &str,
+        validate: impl FnOnce(&T) -> Vec<ValidationError>,
+    ) -> Result<T, Vec<ValidationError>> {
+        let value: Value = serde_json::from_str(json_str)
+            .map_err(|e| vec![ValidationError::document(format!("JSON parse error: {}", e))])?;
+        self.load_value(value, validate)
+    }
+
+    /// エンベロープを外して (バージョン, 本体) を返す
+    fn unwrap_envelope(&self, value: Value) -> Result<(SchemaVersion, Value), String> {
+        let is_envelope = value
+            .as_object()
+            .is_some_and(|o| o.contains_key("format") && o.contains_key("version") && o.contains_key("data"));
+        if !is_envelope {
+            // バージョン管理を入れる前の形式
+            return Ok((0, value));
+        }
+
+        let envelope: Envelope<Value> = serde_json::from_value(value)
+            .map_err(|e| format!("JSON parse error: {}", e))?;
+
+        if envelope.format != self.format {
+            return Err(format!(
+                "{} のデータではありません (format: {})",
+                self.format, envelope.format
+            ));
+        }
+        if envelope.version > self.current_version() {
+            return Err(format!(
+                "{} のバージョン {} には対応していません (読めるのはバージョン {} まで)。アプリを更新してください",
+                self.format, envelope.version, self.current_version()
+            ));
+        }
+        Ok((envelope.version, envelope.data))
+    }
+}
+
+/// 本体の形が変わらないバージョンアップ用
+pub fn unchanged(data: Value) -> Result<Value, String> {
+    Ok(data)
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod schema_test {
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+
+    use super::{DocumentSchema, ValidationError, unchanged};
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Doc {
+        name: String,
+        count: u32,
+    }
+
+    /// v1 で `title` を `name` に改名した想定
+    fn rename_title(mut data: Value) -> Result<Value, String> {
+        if let Some(obj) = data.as_object_mut()
+            && let Some(title) = obj.remove("title")
+        {
+            obj.insert(String::from("name"), title);
+        }
+        Ok(data)
+    }
+
+    const SCHEMA: DocumentSchema = DocumentSchema {
+        format: "test-doc",
+        migrations: &[rename_title, unchanged],
+    };
+
+    /// 書き出したものがそのまま読める
+    #[test]
+    fn test00() {
+        let doc = Doc { name: String::from("a"), count: 3 };
+        let json = SCHEMA.to_json(&doc).unwrap();
+        assert_eq!(json, r#"{"format":"test-doc","version":2,"data":{"name":"a","count":3}}"#);
+        assert_eq!(SCHEMA.from_json::<Doc>(&json).unwrap(), doc);
+    }
+
+    /// エンベロープ無し (version 0) と古いバージョンはマイグレーションされる
+    #[test]
+    fn test01() {
+        let expected = Doc { name: String::from("a"), count: 3 };
+        assert_eq!(SCHEMA.from_json::<Doc>(r#"{"title":"a","count":3}"#).unwrap(), expected);
+        assert_eq!(
+            SCHEMA.from_json::<Doc>(r#"{"format":"test-doc","version":1,"data":{"name":"a","count":3}}"#).unwrap(),
+            expected
+        );
+    }
+
+    /// 新しいバージョン・別の種類のデータはエラー
+    #[test]
+    fn test02() {
+        let err = SCHEMA
+            .from_json::<Doc>(r#"{"format":"test-doc","version":3,"data":{"name":"a","count":3}}"#)
+            .unwrap_err();
+        assert!(err.contains("バージョン 3"));
+
+        let err = SCHEMA
+            .from_json::<Doc>(r#"{"format":"other","version":1,"data":{}}"#)
+            .unwrap_err();
+        assert!(err.contains("format: other"));
+    }
+
+    /// 検査で見つかった問題はまとめて返る
+    #[test]
+    fn test03() {
+        let validate = |doc: &Doc| {
+            let mut errors = vec![];
+            if doc.name.is_empty() {
+                errors.push(ValidationError::new("name", "空です"));
+            }
+            if doc.count == 0 {
+                errors.push(ValidationError::new("count", "0 です"));
+            }
+            errors
+        };
+
+        let doc: Doc = SCHEMA.load_json(r#"{"name":"a","count":3}"#, validate).unwrap();
+        assert_eq!(doc.count, 3);
+
+        let errors = SCHEMA.load_json(r#"{"name":"","count":0}"#, validate).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![ValidationError::new("name", "空です"), ValidationError::new("count", "0 です")]
+        );
+        assert_eq!(errors[0].clone().within("doc").to_string(), "doc.name: 空です");
+
+        // パースの失敗はファイル全体の問題
+        let errors = SCHEMA.load_json(r#"{"name":"#, validate).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "");
+        assert!(errors[0].message.starts_with("JSON parse error"));
+    }
+}
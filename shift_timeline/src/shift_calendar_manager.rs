@@ -0,0 +1,880 @@
+// --- カレンダー管理層の実装 ---
+use shift_calendar::{
+    self,
+    shift_gen::{
+        Incomplete,
+        StaffGroupList,
+        WeekRuleTable,
+        WeekDecidedShift,
+        gen_one_week_shift,
+    }
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use std::fmt;
+
+use crate::locked_weeks::LockedWeek;
+use crate::schema::{DocumentSchema, ValidationError, unchanged};
+
+/// 保存するカレンダーJSONの形式
+/// - v0: `ShiftCalendarManager` そのもの (エンベロープなし)
+/// - v1: エンベロープを導入 (本体は v0 と同じ)
+/// - v2: `lockedWeeks` を追加 (無ければ空。古いアプリで読んで固定が外れないようにバージョンを上げる)
+pub const CALENDAR_SCHEMA: DocumentSchema = DocumentSchema {
+    format: "shift-calendar",
+    migrations: &[unchanged, unchanged],
+};
+
+/// 週ごとの状態
+///
+/// `P` はActiveな週に付随する情報
+/// - WASM版では `()`（ルールは1つのテーブルを使い回す）
+/// - デスクトップ版では適用するルールのID
+///
+/// `payload` はflattenされるので、JSON上は
+/// `{"Active":{"logical_delta":0}}` / `{"Active":{"logical_delta":0,"rule_id":1}}`
+/// のように従来と同じ形になる
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub enum WeekStatus<P> {
+    Active {
+        logical_delta: LogicalDelta,
+        #[serde(flatten)]
+        payload: P,
+    },
+    Skipped,
+}
+
+impl<P> WeekStatus<P> {
+    pub fn is_skipped(&self) -> bool {
+        matches!(self, WeekStatus::Skipped)
+    }
+
+    /// 追加しようとしている週 (`None` = skip) と同じ内容かどうか
+    fn same_as(&self, week: &Option<P>) -> bool
+    where
+        P: PartialEq,
+    {
+        match (self, week) {
+            (WeekStatus::Skipped, None) => true,
+            (WeekStatus::Active { payload, .. }, Some(new_payload)) => payload == new_payload,
+            _ => false,
+        }
+    }
+}
+
+// 1970年以前のシフトには対応しません（必要ない故）
+// 絶対週番号を型エイリアスとして定義（わかりやすくするため）
+pub type AbsWeek = usize;
+pub type LogicalDelta = usize;
+
+/// 確定した予定を入れます
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftCalendarManager<P> {
+    pub base_abs_week: AbsWeek,
+    pub initial_delta: LogicalDelta,
+
+    pub timeline: Vec<WeekStatus<P>>, // 実週番号 -> 状態
+
+    /// 固定 (公開) した週 (絶対週の順)。導出結果の代わりにこの内容を使う
+    #[serde(default)]
+    pub locked_weeks: Vec<LockedWeek>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AppendWeekErrorKind {
+    /// 予定の上書きエラー
+    AttemptedToOverwrite,
+    /// 連続しない予定エラー
+    NotConsecutiveShifts,
+    /// 加減突破
+    UnderFlow,
+}
+
+/// 固定しようとした週が、シフトを導出する週 (タイムライン上のActiveな週) ではない
+#[derive(Debug, PartialEq)]
+pub struct NotDerivedWeek(pub AbsWeek);
+
+impl fmt::Display for NotDerivedWeek {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "絶対週 {} はシフトを導出する週ではありません", self.0)
+    }
+}
+
+impl fmt::Display for AppendWeekErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppendWeekErrorKind::AttemptedToOverwrite =>
+                write!(f, "確定済みの予定を上書きしようとしました"),
+            AppendWeekErrorKind::NotConsecutiveShifts =>
+                write!(f, "タイムラインに空きがあります。データを連続させてください。"),
+            AppendWeekErrorKind::UnderFlow =>
+                write!(f, "カレンダーの開始週より前の週が指定されました"),
+        }
+    }
+}
+
+impl<P> ShiftCalendarManager<P> {
+
+    pub fn new(
+        base_abs_week: AbsWeek,
+        initial_delta: LogicalDelta
+    ) -> Self {
+        // コンストラクターがエラーを判定
+        Self { base_abs_week, initial_delta, timeline: Vec::new(), locked_weeks: Vec::new() }
+    }
+
+    fn abs_to_index(
+        &self,
+        abs_week: AbsWeek
+    ) -> Result<usize, AppendWeekErrorKind> {
+        if abs_week < self.base_abs_week {
+            Err(AppendWeekErrorKind::UnderFlow)
+        } else {
+            Ok(abs_week - self.base_abs_week)
+        }
+    }
+
+    /// タイムラインの末尾の次にあたる絶対週
+    pub fn end_abs_week(&self) -> AbsWeek {
+        self.base_abs_week + self.timeline.len()
+    }
+
+    /// すでに作成したリストのなかにtarget_abs_weekを含み
+    /// かつそれが変更される場合はエラーを返却する.
+    ///
+    /// `weeks` の各要素は `Some(payload)` = Active, `None` = Skipped
+    /// 重なる部分はskipかどうかに加えてpayloadも一致している必要がある
+    ///
+    /// [A | S]
+    ///
+    /// Ok
+    /// ```text
+    ///  0  1  2  3  4  5
+    /// [A, S, A, A]       self
+    ///     I  I  I
+    ///    [S, A, A, N, N] shift you want to append
+    /// target_abs_week: 1
+    /// ```
+    ///
+    /// Err(AttemptedToOverwrite)
+    /// ```text
+    ///  0  1  2  3  4  5
+    /// [A, S, A, A] E  E  self
+    ///     I  I  N
+    ///    [S, A, S, N, N] shift you want to append
+    /// target_abs_week: 1
+    /// ```
+    ///
+    /// Ok
+    /// ```text
+    ///  0  1  2  3  4
+    /// [A, S, A, A] E              :self
+    ///              I
+    ///             [S, A, S, N, N] :shift you want to append
+    /// target_abs_week: 4
+    /// ```
+    ///
+    /// Err(NotConsecutiveShifts)
+    /// ```text
+    ///  0  1  2  3  4  5
+    /// [A, S, A, A] E  E              :self
+    ///              E [S, A, S, N, N] :shift you want to append
+    /// target_abs_week: 5
+    /// ```
+    /// if self.timeline.len() < target_abs_week -> Error
+    pub fn append_check(
+        &self,
+        target_abs_week: AbsWeek,
+        weeks: &[Option<P>]
+    ) -> Result<(), AppendWeekErrorKind>
+    where
+        P: PartialEq,
+    {
+        if self.end_abs_week() < target_abs_week {
+            return Err(AppendWeekErrorKind::NotConsecutiveShifts);
+        }
+
+        if self
+            .timeline[self.abs_to_index(target_abs_week)?..]
+            .iter()
+            .zip(weeks)
+            .all(|(week_status, week)| week_status.same_as(week))
+        {
+            Ok(())
+        } else {
+            Err(AppendWeekErrorKind::AttemptedToOverwrite)
+        }
+    }
+
+    /// checkをしてOkであればtimelineに適用する
+    /// self.append_check関数のチェックが入る
+    pub fn apply_weeks(
+        &mut self,
+        target_abs_week: AbsWeek,
+        weeks: &[Option<P>]
+    ) -> Result<(), AppendWeekErrorKind>
+    where
+        P: PartialEq + Clone,
+    {
+        self.append_check(target_abs_week, weeks)?; // チェックをする
+        // target_abs_week: 2,
+        //  0, 1, 2  3  4  5  <- index
+        // [T, F, F]          <- timeline     .len() -> 3
+        //       [F, T, F, F] <- weeks
+        //         [ T, F, F] <- append_weeks
+        //
+        //  let after_timeline_len = target_abs_week (2) + weeks.len() (4);
+        //  let append_len = after_timeline_len (6) - self.timeline.len() (3); (3)
+        //  let append_start_index = weeks.len() (4) - append_len (3); (1)     // append start index
+        //  let append_weeks = [append_start_index..]
+        //
+        let append_start_index = self.timeline.len() - self.abs_to_index(target_abs_week)?;
+
+        // 重要: indexを超えている場合
+        // 何もしない
+        if weeks.len() <= append_start_index {
+            return Ok(());
+        }
+
+        for week in &weeks[append_start_index..] {
+            self.append_week(week.clone());
+        }
+        Ok(())
+    }
+
+    /// タイムラインの末尾に1週追加する
+    /// `None` を渡すとSkipped
+    pub fn append_week(
+        &mut self,
+        week: Option<P>,
+    ) {
+        let slot = match week {
+            Some(payload) => WeekStatus::Active {
+                logical_delta: self.next_delta(),
+                payload,
+            },
+            None => WeekStatus::Skipped,
+        };
+
+        self.timeline.push(slot);
+    }
+
+    /// 次にActiveな週を追加した場合のdelta
+    pub fn next_delta(&self) -> LogicalDelta {
+        self.find_last_active_delta()
+            .map(|d| d + 1)
+            .unwrap_or(self.initial_delta) // 初回
+    }
+
+    /// abs_weekからrange週分のtimelineを返す
+    /// timelineの範囲外の部分は切り詰められる
+    pub fn slice_by_abs(
+        &self,
+        abs_week: AbsWeek,
+        range: usize,
+    ) -> &[WeekStatus<P>] {
+        match self.abs_to_index(abs_week) {
+            Ok(index) => {
+                let len = self.timeline.len();
+                &self.timeline[index.min(len)..(index + range).min(len)]
+            }
+            Err(_) => &[],
+        }
+    }
+
+    /// シフトの導出
+    /// base_abs_weekを下回る場合でも返せる場合があるが
+    /// skipを使うことで週の途中からルールを開始することは可能なので対応しない
+    ///
+    /// `resolve_rule` はpayloadから使用するルールテーブルを引く関数
+    /// 固定した週もルールから導出し直す (固定した内容にするには `LockedOverlay` を使う)
+    pub fn derive_shift<'a, 'r, F>(
+        &self,
+        resolve_rule: F,
+        staff_group_list: &'a StaffGroupList,
+        gen_week_abs: AbsWeek,  // 生成の始点となる絶対週
+        gen_range: usize,       // 何週間分のシフトを作成するか
+    ) -> Vec<Option<WeekDecidedShift<'a>>>
+    where
+        'a: 'r,
+        F: Fn(&P) -> Option<&'r WeekRuleTable<'a, Incomplete>>,
+    {
+        calculate_partial_shift(
+            self.slice_by_abs(gen_week_abs, gen_range),
+            resolve_rule,
+            staff_group_list
+        )
+    }
+
+    /// 直近の有効なDeltaを探すヘルパー
+    pub fn find_last_active_delta(&self) -> Option<LogicalDelta> {
+        self.timeline.iter().rev().find_map(|slot| match slot {
+            WeekStatus::Active {
+                logical_delta, ..
+            } => Some(*logical_delta),
+            WeekStatus::Skipped => None,
+        })
+    }
+
+    /// logical_delta の採番が崩れている週を探す
+    /// Activeな週は initial_delta から1ずつ増えていくはずなので、
+    /// そうなっていない週の (timelineのindex, 期待値, 実際の値) を返す
+    pub fn delta_mismatches(&self) -> Vec<(usize, LogicalDelta, LogicalDelta)> {
+        let mut expected = self.initial_delta;
+        let mut mismatches = Vec::new();
+        for (index, status) in self.timeline.iter().enumerate() {
+            if let WeekStatus::Active { logical_delta, .. } = status {
+                if *logical_delta != expected {
+                    mismatches.push((index, expected, *logical_delta));
+                }
+                expected = logical_delta + 1;
+            }
+        }
+        mismatches
+    }
+
+    /// 読み込む前の検査 (`path` は `timeline[index]` / `lockedWeeks[index]`)
+    pub fn validation_errors(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = self.delta_mismatches()
+            .into_iter()
+            .map(|(index, expected, actual)| ValidationError::new(
+                format!("timeline[{}]", index),
+                format!("logical_delta が {} になっています (期待値 {})", actual, expected),
+            ))
+            .collect();
+
+        for (index, locked) in self.locked_weeks.iter().enumerate() {
+            let path = format!("lockedWeeks[{}]", index);
+            if !self.is_active(locked.abs_week) {
+                errors.push(ValidationError::new(path, NotDerivedWeek(locked.abs_week).to_string()));
+            } else if index > 0 && self.locked_weeks[index - 1].abs_week >= locked.abs_week {
+                errors.push(ValidationError::new(path, "絶対週の順に並んでいません"));
+            }
+        }
+        errors
+    }
+
+    /// タイムライン上でActiveな週か
+    fn is_active(&self, abs_week: AbsWeek) -> bool {
+        self.slice_by_abs(abs_week, 1).first().is_some_and(|status| !status.is_skipped())
+    }
+
+    /// 週を固定する (同じ週を固定済みなら置き換える)
+    /// タイムライン上でActiveな週でなければエラーで、何も変えない
+    pub fn lock_week(&mut self, locked: LockedWeek) -> Result<(), NotDerivedWeek> {
+        if !self.is_active(locked.abs_week) {
+            return Err(NotDerivedWeek(locked.abs_week));
+        }
+        match self.locked_weeks.binary_search_by_key(&locked.abs_week, |w| w.abs_week) {
+            Ok(index) => self.locked_weeks[index] = locked,
+            Err(index) => self.locked_weeks.insert(index, locked),
+        }
+        Ok(())
+    }
+
+    /// `abs_week` から `range` 週分の固定を外す。外した週があれば true
+    pub fn unlock_weeks(&mut self, abs_week: AbsWeek, range: usize) -> bool {
+        let locked_len = self.locked_weeks.len();
+        self.locked_weeks.retain(|w| !(abs_week <= w.abs_week && w.abs_week < abs_week + range));
+        self.locked_weeks.len() != locked_len
+    }
+
+    pub fn is_locked(&self, abs_week: AbsWeek) -> bool {
+        self.locked_weeks.iter().any(|w| w.abs_week == abs_week)
+    }
+
+    /// `pred` に当てはまるActiveな週のpayloadを `to` に付け替える
+    /// logical_delta も固定もそのまま。付け替えた絶対週を返す
+    pub fn reassign_weeks(&mut self, pred: impl Fn(&P) -> bool, to: P) -> Vec<AbsWeek>
+    where
+        P: Clone,
+    {
+        let mut reassigned = Vec::new();
+        for (index, status) in self.timeline.iter_mut().enumerate() {
+            if let WeekStatus::Active { payload, .. } = status
+                && pred(payload)
+            {
+                *payload = to.clone();
+                reassigned.push(self.base_abs_week + index);
+            }
+        }
+        reassigned
+    }
+
+    /// `pred` に当てはまるActiveな週をSkippedにする
+    /// Skippedの週ではローテーションが進まないので、後ろのActiveな週の logical_delta をその分だけ詰める
+    /// (後ろの週の導出結果も変わる)。Skippedにした週の固定は外れる。Skippedにした絶対週を返す
+    pub fn skip_weeks(&mut self, pred: impl Fn(&P) -> bool) -> Vec<AbsWeek> {
+        let mut skipped = Vec::new();
+        for (index, status) in self.timeline.iter_mut().enumerate() {
+            let hit = matches!(status, WeekStatus::Active { payload, .. } if pred(payload));
+            if hit {
+                *status = WeekStatus::Skipped;
+                skipped.push(self.base_abs_week + index);
+            } else if let WeekStatus::Active { logical_delta, .. } = status {
+                *logical_delta -= skipped.len();
+            }
+        }
+        self.locked_weeks.retain(|w| !skipped.contains(&w.abs_week));
+        skipped
+    }
+
+    /// 【重要】指定した絶対週以降をすべて削除する（Truncate）
+    /// 配列を短くするだけなので極めて高速かつ安全
+    /// 削除した週の固定も外れる。何か消したら true
+    pub fn truncate_from(&mut self, target_abs_week: AbsWeek) -> bool {
+        let locked_len = self.locked_weeks.len();
+        let timeline_len = self.timeline.len();
+        self.locked_weeks.retain(|w| w.abs_week < target_abs_week);
+        if target_abs_week < self.base_abs_week {
+            // 開始地点より前を指定されたら全消し
+            self.timeline.clear();
+            // 必要なら start_abs_week 自体を書き換えるロジックも検討
+        } else {
+            let keep_len = target_abs_week - self.base_abs_week;
+            if keep_len < self.timeline.len() {
+                self.timeline.truncate(keep_len);
+            }
+        }
+        self.locked_weeks.len() != locked_len || self.timeline.len() != timeline_len
+    }
+
+    /// base_abs_week を付け替える
+    ///
+    /// - 後ろへ動かす場合: 手前の週を捨て、捨てた分を initial_delta に繰り込む (捨てた週の固定も外れる)
+    ///   (残った週の logical_delta はそのままなので、以降の導出結果は変わらない)
+    /// - 前へ動かす場合: 増えた週をSkippedで埋める
+    ///
+    /// タイムラインの末尾より後ろへは動かせない
+    pub fn rebase(&mut self, new_base_abs_week: AbsWeek) -> Result<(), AppendWeekErrorKind> {
+        if self.end_abs_week() < new_base_abs_week {
+            return Err(AppendWeekErrorKind::NotConsecutiveShifts);
+        }
+
+        if self.base_abs_week <= new_base_abs_week {
+            let rest = self.timeline.split_off(new_base_abs_week - self.base_abs_week);
+            self.locked_weeks.retain(|w| new_base_abs_week <= w.abs_week);
+            // 捨てる週だけが残っている状態での「次のdelta」が新しい初期値になる
+            self.initial_delta = self.next_delta();
+            self.timeline = rest;
+        } else {
+            let padding = self.base_abs_week - new_base_abs_week;
+            self.timeline.splice(0..0, std::iter::repeat_with(|| WeekStatus::Skipped).take(padding));
+        }
+
+        self.base_abs_week = new_base_abs_week;
+        Ok(())
+    }
+
+    pub fn get_skip_list_by_abs(
+        &self,
+        abs_week: AbsWeek,
+        range: usize
+    ) -> Vec<bool> {
+        self
+            .slice_by_abs(abs_week, range)
+            .iter()
+            .map(|a| a.is_skipped())
+            .collect()
+    }
+
+    pub fn get_skip_list(&self) -> Vec<bool> {
+        self
+            .timeline
+            .iter()
+            .map(|a| a.is_skipped())
+            .collect()
+    }
+
+    pub fn get_timeline(&self) -> &Vec<WeekStatus<P>> {
+        &self.timeline
+    }
+
+    /// json化した内部状態を返却します
+    /// (最新バージョンの `CALENDAR_SCHEMA` のエンベロープで包む)
+    pub fn output_inner_data(&self) -> Result<String, String>
+    where
+        P: Serialize,
+    {
+        CALENDAR_SCHEMA.to_json(self)
+    }
+
+    /// エンベロープの無い古い形式も読める
+    /// 全体を検査してから差し替えるので、エラーのときは状態を変えない
+    pub fn load_calendar_from_json(&mut self, json_str: String) -> Result<(), Vec<ValidationError>>
+    where
+        P: DeserializeOwned,
+    {
+        let config: ShiftCalendarManager<P> =
+            CALENDAR_SCHEMA.load_json(&json_str, ShiftCalendarManager::validation_errors)?;
+
+        *self = config;
+        Ok(())
+    }
+}
+
+/// 指定された期間のシフトのみを計算する純粋関数
+///
+/// - `timeline_slice`: 計算対象の週のステータス（例: 4週間分だけ）
+/// - `resolve_rule`: payload から 実際のWeekRuleTable を引く関数
+///   (ルールが見つからない週は `None` になる)
+/// - `staff_group_list`: スタッフリスト
+pub fn calculate_partial_shift<'a, 'r, P, F>(
+    timeline_slice: &[WeekStatus<P>],
+    resolve_rule: F,
+    staff_group_list: &'a StaffGroupList,
+) -> Vec<Option<WeekDecidedShift<'a>>>
+where
+    'a: 'r,
+    F: Fn(&P) -> Option<&'r WeekRuleTable<'a, Incomplete>>,
+{
+    calculate_partial_shift_by_week(timeline_slice, resolve_rule, |_| staff_group_list)
+}
+
+/// `calculate_partial_shift` の、週ごとに別のスタッフリストを使う版
+/// (在籍期間があり、週によって在籍しているスタッフが違う場合)
+///
+/// - `staff_group_list_of`: `timeline_slice` の中の位置 (0始まり) から、その週のスタッフリストを引く関数
+pub fn calculate_partial_shift_by_week<'a, 'r, P, F, G>(
+    timeline_slice: &[WeekStatus<P>],
+    resolve_rule: F,
+    staff_group_list_of: G,
+) -> Vec<Option<WeekDecidedShift<'a>>>
+where
+    'a: 'r,
+    F: Fn(&P) -> Option<&'r WeekRuleTable<'a, Incomplete>>,
+    G: Fn(usize) -> &'a StaffGroupList,
+{
+    timeline_slice
+        .iter()
+        .enumerate()
+        .map(|(offset, i)| {
+            if let WeekStatus::Active { logical_delta, payload } = i {
+                resolve_rule(payload)
+                    .map(|week_rule_table|
+                        gen_one_week_shift(
+                            week_rule_table,
+                            staff_group_list_of(offset),
+                            *logical_delta
+                        )
+                    )
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod shift_calendar_manager_test {
+    use shift_calendar::shift_gen::{DayRule, ShiftHoll, StaffGroup, StaffGroupList, WeekRule, WeekRuleTable};
+
+    use crate::locked_weeks::LockedWeek;
+    use crate::shift_calendar_manager::{
+        AppendWeekErrorKind, NotDerivedWeek, ShiftCalendarManager, WeekStatus, calculate_partial_shift,
+        calculate_partial_shift_by_week,
+    };
+
+    /// 正しくシフトカレンダーに設定できるか？
+    #[test]
+    fn test00() {
+        let mut shift_calendar_manager: ShiftCalendarManager<()>
+            = ShiftCalendarManager::new(2000, 0);
+
+        let r = shift_calendar_manager
+            .append_check(2000, &[
+                Some(()), // 2000
+                Some(()), // 2001
+                None,     // 2002 skip!
+                Some(())  // 2003
+            ]);
+
+        // 何もないリストへ追加可能をチェック
+        // ここではOkと変えるはず
+        assert!(matches!(r, Ok(())));
+
+        shift_calendar_manager.append_week(Some(()));
+        shift_calendar_manager.append_week(Some(()));
+        shift_calendar_manager.append_week(None);
+        shift_calendar_manager.append_week(Some(()));  // 2003
+
+        let r = shift_calendar_manager
+            .append_check(2001, &[
+                Some(()), // 2001
+                None,     // 2002
+                Some(()), // 2003
+                Some(())  // 2004 (new)
+            ]);
+
+        shift_calendar_manager.append_week(Some(()));  // 2004
+
+        assert!(matches!(r, Ok(())));
+
+        let r = shift_calendar_manager
+            .append_check(2005, &[
+                Some(()), // 2005
+            ]);
+
+        shift_calendar_manager.append_week(Some(()));  // 2005
+        assert!(matches!(r, Ok(())));
+
+        let r = shift_calendar_manager
+            .append_check(2007, &[
+                Some(()), // 2005
+            ]);
+
+        assert!(matches!(r, Err(AppendWeekErrorKind::NotConsecutiveShifts)));
+
+        let r = shift_calendar_manager
+            .append_check(2001, &[
+                None,     // 2001
+                None,     // 2002
+                Some(()), // 2003
+                Some(())  // 2004 (new)
+            ]);
+
+        assert!(matches!(r, Err(AppendWeekErrorKind::AttemptedToOverwrite)));
+    }
+
+    /// payloadが異なる週の上書きは拒否され、deltaはskipを飛ばして連番になるか？
+    #[test]
+    fn test01() {
+        let mut shift_calendar_manager: ShiftCalendarManager<i64>
+            = ShiftCalendarManager::new(100, 5);
+
+        shift_calendar_manager
+            .apply_weeks(100, &[Some(1), None, Some(2)])
+            .unwrap();
+
+        let r = shift_calendar_manager.apply_weeks(102, &[Some(1), Some(1)]);
+        assert_eq!(r, Err(AppendWeekErrorKind::AttemptedToOverwrite));
+        assert_eq!(shift_calendar_manager.timeline.len(), 3);
+
+        // 重複部分が一致していれば、はみ出た分だけ追加される
+        shift_calendar_manager
+            .apply_weeks(102, &[Some(2), Some(1)])
+            .unwrap();
+
+        assert_eq!(
+            shift_calendar_manager.timeline,
+            vec![
+                WeekStatus::Active { logical_delta: 5, payload: 1 },
+                WeekStatus::Skipped,
+                WeekStatus::Active { logical_delta: 6, payload: 2 },
+                WeekStatus::Active { logical_delta: 7, payload: 1 },
+            ]
+        );
+
+        assert_eq!(
+            shift_calendar_manager.apply_weeks(99, &[None]),
+            Err(AppendWeekErrorKind::UnderFlow)
+        );
+    }
+
+    /// 範囲外を含む切り出し
+    #[test]
+    fn test02() {
+        let mut shift_calendar_manager: ShiftCalendarManager<()>
+            = ShiftCalendarManager::new(10, 0);
+        shift_calendar_manager.apply_weeks(10, &[Some(()), None, Some(())]).unwrap();
+
+        assert_eq!(shift_calendar_manager.get_skip_list_by_abs(11, 5), vec![true, false]);
+        assert!(shift_calendar_manager.get_skip_list_by_abs(20, 5).is_empty());
+        assert!(shift_calendar_manager.get_skip_list_by_abs(9, 5).is_empty());
+
+        shift_calendar_manager.truncate_from(11);
+        assert_eq!(shift_calendar_manager.get_skip_list(), vec![false]);
+    }
+
+    /// 手で編集されたタイムラインの採番チェック
+    #[test]
+    fn test03() {
+        let mut shift_calendar_manager: ShiftCalendarManager<()>
+            = ShiftCalendarManager::new(10, 3);
+        shift_calendar_manager.apply_weeks(10, &[Some(()), None, Some(())]).unwrap();
+        assert!(shift_calendar_manager.delta_mismatches().is_empty());
+
+        shift_calendar_manager.timeline[2] = WeekStatus::Active { logical_delta: 7, payload: () };
+        assert_eq!(shift_calendar_manager.delta_mismatches(), vec![(2, 4, 7)]);
+    }
+
+    /// base_abs_week の付け替え
+    #[test]
+    fn test04() {
+        let mut shift_calendar_manager: ShiftCalendarManager<()>
+            = ShiftCalendarManager::new(10, 2);
+        shift_calendar_manager.apply_weeks(10, &[Some(()), None, Some(()), Some(())]).unwrap();
+        let before = shift_calendar_manager.clone();
+
+        // 後ろへ: 捨てた2週 (Active 1週) が initial_delta に繰り込まれる
+        shift_calendar_manager.rebase(12).unwrap();
+        assert_eq!(shift_calendar_manager.initial_delta, 3);
+        assert_eq!(shift_calendar_manager.slice_by_abs(12, 2), before.slice_by_abs(12, 2));
+        assert!(shift_calendar_manager.delta_mismatches().is_empty());
+        assert_eq!(shift_calendar_manager.next_delta(), before.next_delta());
+
+        // 前へ: Skippedで埋まり、既存の週はそのまま
+        shift_calendar_manager.rebase(9).unwrap();
+        assert_eq!(shift_calendar_manager.get_skip_list(), vec![true, true, true, false, false]);
+        assert_eq!(shift_calendar_manager.slice_by_abs(12, 2), before.slice_by_abs(12, 2));
+        assert!(shift_calendar_manager.delta_mismatches().is_empty());
+
+        // 末尾ちょうどまでは動かせる (空のタイムラインになり、次のdeltaは保たれる)
+        shift_calendar_manager.rebase(14).unwrap();
+        assert!(shift_calendar_manager.timeline.is_empty());
+        assert_eq!(shift_calendar_manager.next_delta(), 5);
+
+        assert_eq!(
+            shift_calendar_manager.rebase(15),
+            Err(AppendWeekErrorKind::NotConsecutiveShifts)
+        );
+    }
+
+    /// 週ごとに別のスタッフリストで導出できる
+    #[test]
+    fn test06() {
+        let mut before = StaffGroupList::new();
+        let mut after = StaffGroupList::new();
+        for (list, names) in [(&mut before, &["a0"][..]), (&mut after, &["a0", "a1"])] {
+            let mut group = StaffGroup::new("A");
+            for name in names {
+                group.add_staff(name);
+            }
+            list.add_staff_group(group);
+        }
+
+        let mut days: [DayRule<'_, _>; 7] = core::array::from_fn(|_| DayRule {
+            shift_morning: vec![],
+            shift_afternoon: vec![],
+        });
+        days[0].shift_morning.push(ShiftHoll::new(0, 0));
+        let mut week_rule_table = WeekRuleTable::new();
+        week_rule_table.add_week_rule(WeekRule(days));
+
+        let timeline = [
+            WeekStatus::Active { logical_delta: 0, payload: () },
+            WeekStatus::Active { logical_delta: 1, payload: () },
+        ];
+        let lists = [&before, &after];
+        let decided = calculate_partial_shift_by_week(&timeline, |_| Some(&week_rule_table), |offset| lists[offset]);
+
+        // 1週目は a0 しかいない。2週目のリストに a1 が加わっても1週目は変わらない
+        assert_eq!(decided[0].as_ref().unwrap().0[0].shift_morning[0].name, "a0");
+        let only_before = calculate_partial_shift(&timeline[..1], |_| Some(&week_rule_table), &before);
+        assert_eq!(
+            only_before[0].as_ref().unwrap().0[0].shift_morning[0].name,
+            decided[0].as_ref().unwrap().0[0].shift_morning[0].name,
+        );
+        assert_eq!(decided[1].as_ref().unwrap().0[0].shift_morning.len(), 1);
+    }
+
+    /// 週の固定
+    #[test]
+    fn test07() {
+        let mut calendar: ShiftCalendarManager<()> = ShiftCalendarManager::new(10, 0);
+        calendar.apply_weeks(10, &[Some(()), None, Some(()), Some(())]).unwrap();
+        let locked = |abs_week| LockedWeek { abs_week, days: Default::default() };
+
+        // Skipped・タイムラインの外は固定できない
+        assert_eq!(calendar.lock_week(locked(11)), Err(NotDerivedWeek(11)));
+        assert_eq!(calendar.lock_week(locked(14)).unwrap_err().to_string(), "絶対週 14 はシフトを導出する週ではありません");
+        for abs_week in [13, 10, 12, 13] {
+            calendar.lock_week(locked(abs_week)).unwrap();
+        }
+        let locked_weeks = |c: &ShiftCalendarManager<()>| c.locked_weeks.iter().map(|w| w.abs_week).collect::<Vec<_>>();
+        assert_eq!(locked_weeks(&calendar), vec![10, 12, 13]);
+
+        // 保存して読み直しても同じ
+        let mut loaded: ShiftCalendarManager<()> = ShiftCalendarManager::new(0, 0);
+        loaded.load_calendar_from_json(calendar.output_inner_data().unwrap()).unwrap();
+        assert_eq!(loaded.locked_weeks, calendar.locked_weeks);
+
+        assert!(calendar.unlock_weeks(12, 1));
+        assert!(!calendar.is_locked(12) && calendar.is_locked(13));
+        // 固定していない週を外しても何も変わらない
+        assert!(!calendar.unlock_weeks(11, 1));
+
+        // 削除した週・基準週より前にした週の固定は外れる
+        assert!(calendar.truncate_from(13));
+        assert_eq!(locked_weeks(&calendar), vec![10]);
+        assert!(!calendar.truncate_from(20));
+        calendar.lock_week(locked(12)).unwrap();
+        calendar.rebase(11).unwrap();
+        assert_eq!(locked_weeks(&calendar), vec![12]);
+
+        // Activeでない週の固定は読まない
+        let err = loaded.load_calendar_from_json(String::from(
+            r#"{"baseAbsWeek":30,"initialDelta":0,"timeline":["Skipped"],"lockedWeeks":[{"absWeek":30,"days":[{"morning":[],"afternoon":[]},{"morning":[],"afternoon":[]},{"morning":[],"afternoon":[]},{"morning":[],"afternoon":[]},{"morning":[],"afternoon":[]},{"morning":[],"afternoon":[]},{"morning":[],"afternoon":[]}]}]}"#
+        )).unwrap_err();
+        assert_eq!(err[0].path, "lockedWeeks[0]");
+        assert_eq!(loaded.base_abs_week, 10);
+    }
+
+    /// 使っている週の付け替えとSkippedへの変更
+    #[test]
+    fn test08() {
+        let mut calendar: ShiftCalendarManager<u8> = ShiftCalendarManager::new(10, 3);
+        calendar.apply_weeks(10, &[Some(1), Some(2), None, Some(1), Some(2)]).unwrap();
+        calendar.lock_week(LockedWeek { abs_week: 13, days: Default::default() }).unwrap();
+        calendar.lock_week(LockedWeek { abs_week: 14, days: Default::default() }).unwrap();
+
+        let mut reassigned = calendar.clone();
+        assert_eq!(reassigned.reassign_weeks(|p| *p == 1, 9), vec![10, 13]);
+        assert_eq!(reassigned.timeline[3], WeekStatus::Active { logical_delta: 5, payload: 9 });
+        assert!(reassigned.is_locked(13));
+
+        // 後ろの週の logical_delta は詰まり、採番は崩れない
+        assert_eq!(calendar.skip_weeks(|p| *p == 1), vec![10, 13]);
+        assert_eq!(calendar.timeline, vec![
+            WeekStatus::Skipped,
+            WeekStatus::Active { logical_delta: 3, payload: 2 },
+            WeekStatus::Skipped,
+            WeekStatus::Skipped,
+            WeekStatus::Active { logical_delta: 4, payload: 2 },
+        ]);
+        assert!(calendar.delta_mismatches().is_empty());
+        assert!(!calendar.is_locked(13) && calendar.is_locked(14));
+        assert!(calendar.skip_weeks(|p| *p == 1).is_empty());
+    }
+
+    /// 保存形式のバージョン
+    #[test]
+    fn test05() {
+        let mut shift_calendar_manager: ShiftCalendarManager<()>
+            = ShiftCalendarManager::new(10, 0);
+        shift_calendar_manager.apply_weeks(10, &[Some(()), None]).unwrap();
+
+        let json = shift_calendar_manager.output_inner_data().unwrap();
+        assert!(json.starts_with(r#"{"format":"shift-calendar","version":2,"data":"#));
+
+        let mut loaded: ShiftCalendarManager<()> = ShiftCalendarManager::new(0, 0);
+        loaded.load_calendar_from_json(json).unwrap();
+        assert_eq!(loaded.get_skip_list(), vec![false, true]);
+
+        // エンベロープの無い古いファイル
+        loaded.load_calendar_from_json(String::from(
+            r#"{"baseAbsWeek":20,"initialDelta":3,"timeline":[{"Active":{"logical_delta":3}}]}"#
+        )).unwrap();
+        assert_eq!(loaded.base_abs_week, 20);
+        assert_eq!(loaded.next_delta(), 4);
+
+        // 新しいバージョンは読まずに、状態も変えない
+        let err = loaded.load_calendar_from_json(String::from(
+            r#"{"format":"shift-calendar","version":99,"data":{}}"#
+        )).unwrap_err();
+        assert!(err[0].message.contains("バージョン 99"));
+        assert_eq!(loaded.base_abs_week, 20);
+
+        // 採番の崩れたファイルも読まない
+        let err = loaded.load_calendar_from_json(String::from(
+            r#"{"baseAbsWeek":30,"initialDelta":0,"timeline":[{"Active":{"logical_delta":0}},{"Active":{"logical_delta":5}}]}"#
+        )).unwrap_err();
+        assert_eq!(err[0].path, "timeline[1]");
+        assert_eq!(err[0].message, "logical_delta が 5 になっています (期待値 1)");
+        assert_eq!(loaded.base_abs_week, 20);
+    }
+}
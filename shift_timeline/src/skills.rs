@@ -0,0 +1,264 @@
+//! スキル (資格) のチェック
+//!
+//! ルールの穴 (`ShiftHoll`) はグループとローテーションの位置しか持たないので、
+//! 「開店できる人」「免許を持っている人」が必要なコマに、持っていない人が入ることがある
+//! 導出した1週間分 (`WeekDecidedShift`) を見て、必要なスキルを持っていない人を返す
+//! `auto_swap` のときは、同じグループの中でスキルを持っている人と入れ替える
+//!
+//! 穴と導出結果の対応は、同じ日・同じ時間帯・同じグループの中での並び順でとる
+//! (`gen_one_week_shift` は穴の順にスタッフを並べる)
+
+use std::fmt;
+
+use serde::Serialize;
+use shift_calendar::shift_gen::{Staff, WeekDecidedShift};
+
+use crate::coverage::WEEKDAY_NAMES;
+use crate::staff_schedule::ShiftPeriod;
+
+/// 穴1つ分の必要スキル (`DayRule` の穴と同じ並び)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SlotRequirement {
+    pub group_index: usize,
+    /// すべて持っている必要がある (空 = 誰でもよい)
+    pub skills: Vec<String>,
+}
+
+/// 1日分 (`DayRule` と同じ形)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DaySkillRule {
+    pub shift_morning: Vec<SlotRequirement>,
+    pub shift_afternoon: Vec<SlotRequirement>,
+}
+
+/// 1週間分 (`WeekRule` と同じ形)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeekSkillRule(pub [DaySkillRule; 7]);
+
+/// スタッフと持っているスキル
+/// 入れ替えの候補にもなるので、グループの全員を並べる
+#[derive(Debug, Clone)]
+pub struct SkilledStaff {
+    pub staff: Staff,
+    pub skills: Vec<String>,
+}
+
+/// 見つかった問題
+/// `weekday` は 0 (月) 〜 6 (日)
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SkillIssue {
+    /// スキルを持っていない人が入っている (入れ替えられなかった)
+    Unqualified {
+        weekday: usize,
+        period: ShiftPeriod,
+        staff: String,
+        missing: Vec<String>,
+    },
+    /// 入れ替えた
+    Swapped {
+        weekday: usize,
+        period: ShiftPeriod,
+        from: String,
+        to: String,
+    },
+}
+
+impl SkillIssue {
+    pub fn weekday(&self) -> usize {
+        match self {
+            SkillIssue::Unqualified { weekday, .. } | SkillIssue::Swapped { weekday, .. } => *weekday,
+        }
+    }
+}
+
+impl fmt::Display for SkillIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkillIssue::Unqualified { weekday, period, staff, missing } =>
+                write!(f, "{}曜 {}: {} は {} を持っていません", WEEKDAY_NAMES[*weekday], period, staff, missing.join("・")),
+            SkillIssue::Swapped { weekday, period, from, to } =>
+                write!(f, "{}曜 {}: {} の代わりに {} を入れました", WEEKDAY_NAMES[*weekday], period, from, to),
+        }
+    }
+}
+
+fn same_staff(a: &Staff, b: &Staff) -> bool {
+    a.group_id == b.group_id && a.id == b.id
+}
+
+fn label(staff: &Staff) -> String {
+    if staff.name.is_empty() {
+        format!("{}-{}", staff.group_id, staff.id)
+    } else {
+        staff.name.clone()
+    }
+}
+
+/// 1週間分のチェック (曜日・午前午後・穴の順)
+/// `auto_swap` のときは `week` を書き換える。入れ替え先は、同じグループで
+/// 必要なスキルをすべて持ち、そのコマにまだ入っていない人 (ローテーションの次の人から順に探す)
+pub fn check_skills<'a>(
+    week: &mut WeekDecidedShift<'a>,
+    rule: &WeekSkillRule,
+    staff_list: &'a [SkilledStaff],
+    auto_swap: bool,
+) -> Vec<SkillIssue> {
+    let skills_of = |staff: &Staff| -> &[String] {
+        staff_list
+            .iter()
+            .find(|s| same_staff(&s.staff, staff))
+            .map(|s| s.skills.as_slice())
+            .unwrap_or(&[])
+    };
+
+    let mut issues = Vec::new();
+    for (weekday, (day, day_rule)) in week.0.iter_mut().zip(&rule.0).enumerate() {
+        for (period, assigned, slots) in [
+            (ShiftPeriod::Morning, &mut day.shift_morning, &day_rule.shift_morning),
+            (ShiftPeriod::Afternoon, &mut day.shift_afternoon, &day_rule.shift_afternoon),
+        ] {
+            // 穴ごとに、同じグループの次のスタッフを対応させる
+            let mut used = vec![false; assigned.len()];
+            for slot in slots {
+                let Some(position) = (0..assigned.len())
+                    .find(|&i| !used[i] && assigned[i].group_id == slot.group_index)
+                else {
+                    continue; // グループが空で穴が埋まらなかった
+                };
+                used[position] = true;
+
+                let current = assigned[position];
+                let has = skills_of(current);
+                let missing: Vec<String> = slot.skills.iter().filter(|s| !has.contains(s)).cloned().collect();
+                if missing.is_empty() {
+                    continue;
+                }
+
+                let substitute = auto_swap.then(|| {
+                    let mut group: Vec<&'a SkilledStaff> = staff_list
+                        .iter()
+                        .filter(|s| s.staff.group_id == slot.group_index)
+                        .collect();
+                    group.sort_by_key(|s| s.staff.id);
+                    // ローテーションの次の人から
+                    let start = group.iter().position(|s| s.staff.id > current.id).unwrap_or(0);
+                    group.rotate_left(start);
+                    group.into_iter().find(|candidate| {
+                        slot.skills.iter().all(|s| candidate.skills.contains(s))
+                            && !assigned.iter().any(|a| same_staff(a, &candidate.staff))
+                    })
+                }).flatten();
+
+                match substitute {
+                    Some(candidate) => {
+                        issues.push(SkillIssue::Swapped {
+                            weekday,
+                            period,
+                            from: label(current),
+                            to: label(&candidate.staff),
+                        });
+                        assigned[position] = &candidate.staff;
+                    }
+                    None => issues.push(SkillIssue::Unqualified {
+                        weekday,
+                        period,
+                        staff: label(current),
+                        missing,
+                    }),
+                }
+            }
+        }
+    }
+    issues
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod skills_test {
+    use shift_calendar::shift_gen::{DayDecidedShift, Staff, WeekDecidedShift};
+
+    use super::{DaySkillRule, SkillIssue, SkilledStaff, SlotRequirement, WeekSkillRule, check_skills};
+    use crate::staff_schedule::ShiftPeriod;
+
+    /// グループ0: 田中 (開店), 佐藤, 鈴木 (開店・免許) / グループ1: 高橋
+    fn staff_list() -> Vec<SkilledStaff> {
+        [(0, "田中", &["開店"][..]), (0, "佐藤", &[]), (0, "鈴木", &["開店", "免許"]), (1, "高橋", &[])]
+            .iter()
+            .scan([0usize; 2], |next_id, (group_id, name, skills)| {
+                let id = next_id[*group_id];
+                next_id[*group_id] += 1;
+                Some(SkilledStaff {
+                    staff: Staff { name: name.to_string(), group_id: *group_id, id },
+                    skills: skills.iter().map(|s| s.to_string()).collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// 月曜午前だけ `morning` が入っていて、穴は `slots`
+    fn monday<'a>(
+        staff_list: &'a [SkilledStaff],
+        morning: &[usize],
+        slots: Vec<SlotRequirement>,
+    ) -> (WeekDecidedShift<'a>, WeekSkillRule) {
+        let mut week = WeekDecidedShift(core::array::from_fn(|_| DayDecidedShift {
+            shift_morning: vec![],
+            shift_afternoon: vec![],
+        }));
+        week.0[0].shift_morning = morning.iter().map(|i| &staff_list[*i].staff).collect();
+
+        let mut rule = WeekSkillRule::default();
+        rule.0[0] = DaySkillRule { shift_morning: slots, shift_afternoon: vec![] };
+        (week, rule)
+    }
+
+    fn slot(group_index: usize, skills: &[&str]) -> SlotRequirement {
+        SlotRequirement { group_index, skills: skills.iter().map(|s| s.to_string()).collect() }
+    }
+
+    /// スキルを持っていない人 (入れ替えなし)
+    #[test]
+    fn test00() {
+        let staff_list = staff_list();
+        // 高橋 (グループ1) と佐藤 (グループ0)。穴はグループ0が先でも、グループごとに対応をとる
+        let (mut week, rule) = monday(&staff_list, &[3, 1], vec![slot(0, &["開店", "免許"]), slot(1, &[])]);
+
+        let issues = check_skills(&mut week, &rule, &staff_list, false);
+        assert_eq!(issues, vec![SkillIssue::Unqualified {
+            weekday: 0,
+            period: ShiftPeriod::Morning,
+            staff: String::from("佐藤"),
+            missing: vec![String::from("開店"), String::from("免許")],
+        }]);
+        assert_eq!(issues[0].to_string(), "月曜 午前: 佐藤 は 開店・免許 を持っていません");
+        assert_eq!(week.0[0].shift_morning[1].name, "佐藤");
+
+        // スキルを持っていれば問題なし
+        let (mut week, rule) = monday(&staff_list, &[0], vec![slot(0, &["開店"])]);
+        assert!(check_skills(&mut week, &rule, &staff_list, false).is_empty());
+    }
+
+    /// 同じグループの中で入れ替える
+    #[test]
+    fn test01() {
+        let staff_list = staff_list();
+        // 佐藤の次から探すので鈴木
+        let (mut week, rule) = monday(&staff_list, &[1], vec![slot(0, &["開店"])]);
+        let issues = check_skills(&mut week, &rule, &staff_list, true);
+        assert_eq!(issues[0].to_string(), "月曜 午前: 佐藤 の代わりに 鈴木 を入れました");
+        assert_eq!(week.0[0].shift_morning[0].name, "鈴木");
+
+        // 鈴木は同じコマに入っているので、田中と入れ替える
+        let (mut week, rule) = monday(&staff_list, &[2, 1], vec![slot(0, &[]), slot(0, &["開店"])]);
+        check_skills(&mut week, &rule, &staff_list, true);
+        let names: Vec<_> = week.0[0].shift_morning.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["鈴木", "田中"]);
+
+        // 候補がいなければ入れ替えずに報告する
+        let (mut week, rule) = monday(&staff_list, &[3], vec![slot(1, &["免許"])]);
+        let issues = check_skills(&mut week, &rule, &staff_list, true);
+        assert!(matches!(issues[0], SkillIssue::Unqualified { .. }));
+        assert_eq!(week.0[0].shift_morning[0].name, "高橋");
+    }
+}
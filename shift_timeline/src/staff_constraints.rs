@@ -0,0 +1,294 @@
+//! スタッフごとの制約のチェック
+//!
+//! 導出したシフト (`WeekDecidedShift`) を見て、スタッフごとの制約に反するコマを返す
+//! - 週・月のコマ数の上限 (午前・午後をそれぞれ1コマと数える)
+//! - 固定の休みの曜日
+//! - 午後に入った翌日の午前 (休息が取れない)
+//! - 雇用期間の外
+//!
+//! 日付は `AbsDay` (絶対週 * 7 + 曜日) で扱い、日付への変換は呼び出し側で行う
+
+use std::fmt;
+use std::ops::Range;
+
+use serde::Serialize;
+use shift_calendar::shift_gen::{Staff, WeekDecidedShift};
+
+use crate::coverage::WEEKDAY_NAMES;
+use crate::shift_calendar_manager::AbsWeek;
+use crate::staff_schedule::ShiftPeriod;
+
+/// 1969/12/29 (月) を 0 とした日数
+pub type AbsDay = usize;
+
+pub fn abs_day(abs_week: AbsWeek, weekday: usize) -> AbsDay {
+    abs_week * 7 + weekday
+}
+
+/// 1人分の制約 (`None` / 空 = 指定なし)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StaffConstraints {
+    pub max_shifts_per_week: Option<usize>,
+    pub max_shifts_per_month: Option<usize>,
+    /// 0 (月) 〜 6 (日)
+    pub days_off: Vec<usize>,
+    /// 午後に入った翌日の午前には入らない
+    pub no_morning_after_afternoon: bool,
+    /// 雇用期間 (両端を含む)
+    pub employed_from: Option<AbsDay>,
+    pub employed_until: Option<AbsDay>,
+}
+
+/// 制約を持つスタッフ (`StaffGroupList` の中の位置で指す)
+#[derive(Debug, Clone)]
+pub struct ConstrainedStaff {
+    pub group_index: usize,
+    pub staff_index: usize,
+    /// 違反に出す名前
+    pub name: String,
+    pub constraints: StaffConstraints,
+}
+
+/// 見つかった違反
+/// `weekday` は 0 (月) 〜 6 (日)
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum StaffViolation {
+    TooManyShiftsInWeek {
+        staff: String,
+        abs_week: AbsWeek,
+        shifts: usize,
+        max: usize,
+    },
+    TooManyShiftsInMonth {
+        staff: String,
+        shifts: usize,
+        max: usize,
+    },
+    DayOff {
+        staff: String,
+        abs_week: AbsWeek,
+        weekday: usize,
+        period: ShiftPeriod,
+    },
+    /// `weekday` は午前に入っている日 (前日の午後にも入っている)
+    NoRest {
+        staff: String,
+        abs_week: AbsWeek,
+        weekday: usize,
+    },
+    OutsideEmployment {
+        staff: String,
+        abs_week: AbsWeek,
+        weekday: usize,
+        period: ShiftPeriod,
+    },
+}
+
+impl StaffViolation {
+    /// 週に関係する違反ならその週
+    pub fn abs_week(&self) -> Option<AbsWeek> {
+        match self {
+            StaffViolation::TooManyShiftsInWeek { abs_week, .. }
+            | StaffViolation::DayOff { abs_week, .. }
+            | StaffViolation::NoRest { abs_week, .. }
+            | StaffViolation::OutsideEmployment { abs_week, .. } => Some(*abs_week),
+            StaffViolation::TooManyShiftsInMonth { .. } => None,
+        }
+    }
+
+    /// 日に関係する違反ならその曜日
+    pub fn weekday(&self) -> Option<usize> {
+        match self {
+            StaffViolation::DayOff { weekday, .. }
+            | StaffViolation::NoRest { weekday, .. }
+            | StaffViolation::OutsideEmployment { weekday, .. } => Some(*weekday),
+            StaffViolation::TooManyShiftsInWeek { .. }
+            | StaffViolation::TooManyShiftsInMonth { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for StaffViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaffViolation::TooManyShiftsInWeek { staff, shifts, max, .. } =>
+                write!(f, "{} のコマ数が週 {} です (上限 {})", staff, shifts, max),
+            StaffViolation::TooManyShiftsInMonth { staff, shifts, max } =>
+                write!(f, "{} のコマ数が月 {} です (上限 {})", staff, shifts, max),
+            StaffViolation::DayOff { staff, weekday, period, .. } =>
+                write!(f, "{}曜 {}: {} は休みの曜日です", WEEKDAY_NAMES[*weekday], period, staff),
+            StaffViolation::NoRest { staff, weekday, .. } =>
+                write!(f, "{}曜 午前: {} は前日の午後にも入っています", WEEKDAY_NAMES[*weekday], staff),
+            StaffViolation::OutsideEmployment { staff, weekday, period, .. } =>
+                write!(f, "{}曜 {}: {} は雇用期間外です", WEEKDAY_NAMES[*weekday], period, staff),
+        }
+    }
+}
+
+/// 連続した週 (先頭が `first_abs_week`、`None` = 導出されなかった週) のチェック
+/// 月のコマ数は `month` に含まれる日だけを数える
+/// 結果はスタッフごとに、日付の順 (週の上限はその週の後、月の上限は最後)
+pub fn check_staff_constraints(
+    first_abs_week: AbsWeek,
+    weeks: &[Option<WeekDecidedShift>],
+    month: Range<AbsDay>,
+    staff_list: &[ConstrainedStaff],
+) -> Vec<StaffViolation> {
+    let mut violations = Vec::new();
+
+    for target in staff_list {
+        let constraints = &target.constraints;
+        let is_target = |s: &&Staff| s.group_id == target.group_index && s.id == target.staff_index;
+        let staff = || target.name.clone();
+
+        let mut month_shifts = 0;
+        // 最後に午後に入った日
+        let mut last_afternoon: Option<AbsDay> = None;
+
+        for (offset, week) in weeks.iter().enumerate() {
+            let Some(week) = week else { continue };
+            let abs_week = first_abs_week + offset;
+            let mut week_shifts = 0;
+
+            for (weekday, day) in week.0.iter().enumerate() {
+                let today = abs_day(abs_week, weekday);
+                for (period, list) in [
+                    (ShiftPeriod::Morning, &day.shift_morning),
+                    (ShiftPeriod::Afternoon, &day.shift_afternoon),
+                ] {
+                    if !list.iter().any(is_target) {
+                        continue;
+                    }
+                    week_shifts += 1;
+                    if month.contains(&today) {
+                        month_shifts += 1;
+                    }
+
+                    if constraints.days_off.contains(&weekday) {
+                        violations.push(StaffViolation::DayOff { staff: staff(), abs_week, weekday, period });
+                    }
+                    if period == ShiftPeriod::Morning
+                        && constraints.no_morning_after_afternoon
+                        && last_afternoon.is_some_and(|d| d + 1 == today)
+                    {
+                        violations.push(StaffViolation::NoRest { staff: staff(), abs_week, weekday });
+                    }
+                    let employed = constraints.employed_from.is_none_or(|from| from <= today)
+                        && constraints.employed_until.is_none_or(|until| today <= until);
+                    if !employed {
+                        violations.push(StaffViolation::OutsideEmployment { staff: staff(), abs_week, weekday, period });
+                    }
+
+                    if period == ShiftPeriod::Afternoon {
+                        last_afternoon = Some(today);
+                    }
+                }
+            }
+
+            if let Some(max) = constraints.max_shifts_per_week
+                && week_shifts > max
+            {
+                violations.push(StaffViolation::TooManyShiftsInWeek { staff: staff(), abs_week, shifts: week_shifts, max });
+            }
+        }
+
+        if let Some(max) = constraints.max_shifts_per_month
+            && month_shifts > max
+        {
+            violations.push(StaffViolation::TooManyShiftsInMonth { staff: staff(), shifts: month_shifts, max });
+        }
+    }
+    violations
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod staff_constraints_test {
+    use shift_calendar::shift_gen::{DayDecidedShift, Staff, WeekDecidedShift};
+
+    use super::{ConstrainedStaff, StaffConstraints, StaffViolation, abs_day, check_staff_constraints};
+    use crate::staff_schedule::ShiftPeriod;
+
+    fn tanaka() -> Staff {
+        Staff { name: String::from("田中"), group_id: 0, id: 0 }
+    }
+
+    /// `days[曜日] = (午前, 午後)` に田中が入っている週
+    fn week(staff: &Staff, days: [(bool, bool); 7]) -> WeekDecidedShift<'_> {
+        let pick = |on: bool| if on { vec![staff] } else { vec![] };
+        WeekDecidedShift(days.map(|(m, a)| DayDecidedShift {
+            shift_morning: pick(m),
+            shift_afternoon: pick(a),
+        }))
+    }
+
+    fn target(constraints: StaffConstraints) -> Vec<ConstrainedStaff> {
+        vec![ConstrainedStaff { group_index: 0, staff_index: 0, name: String::from("田中"), constraints }]
+    }
+
+    /// 休みの曜日・休息・雇用期間
+    #[test]
+    fn test00() {
+        let staff = tanaka();
+        let mut days = [(false, false); 7];
+        days[0] = (true, false); // 月曜午前
+        days[2] = (false, true); // 水曜午後
+        days[3] = (true, false); // 木曜午前 (前日の午後に入っている)
+        days[6] = (false, true); // 日曜午後 (次の週の月曜午前に続く)
+        let weeks = vec![Some(week(&staff, days)), Some(week(&staff, days))];
+
+        let constraints = StaffConstraints {
+            days_off: vec![0],
+            no_morning_after_afternoon: true,
+            employed_until: Some(abs_day(101, 2)),
+            ..Default::default()
+        };
+        let violations = check_staff_constraints(100, &weeks, 0..usize::MAX, &target(constraints));
+        assert_eq!(violations, vec![
+            StaffViolation::DayOff { staff: String::from("田中"), abs_week: 100, weekday: 0, period: ShiftPeriod::Morning },
+            StaffViolation::NoRest { staff: String::from("田中"), abs_week: 100, weekday: 3 },
+            StaffViolation::DayOff { staff: String::from("田中"), abs_week: 101, weekday: 0, period: ShiftPeriod::Morning },
+            StaffViolation::NoRest { staff: String::from("田中"), abs_week: 101, weekday: 0 },
+            StaffViolation::NoRest { staff: String::from("田中"), abs_week: 101, weekday: 3 },
+            StaffViolation::OutsideEmployment {
+                staff: String::from("田中"), abs_week: 101, weekday: 3, period: ShiftPeriod::Morning,
+            },
+            StaffViolation::OutsideEmployment {
+                staff: String::from("田中"), abs_week: 101, weekday: 6, period: ShiftPeriod::Afternoon,
+            },
+        ]);
+        assert_eq!(violations[1].to_string(), "木曜 午前: 田中 は前日の午後にも入っています");
+
+        // 間に導出されない週があれば休息の違反にならない
+        let weeks = vec![Some(week(&staff, days)), None, Some(week(&staff, days))];
+        let constraints = StaffConstraints { no_morning_after_afternoon: true, ..Default::default() };
+        let violations = check_staff_constraints(100, &weeks, 0..usize::MAX, &target(constraints));
+        assert!(violations.iter().all(|v| v.weekday() == Some(3)));
+    }
+
+    /// 週・月のコマ数
+    #[test]
+    fn test01() {
+        let staff = tanaka();
+        let weeks = vec![Some(week(&staff, [(true, true); 7])), Some(week(&staff, [(true, false); 7]))];
+
+        let constraints = StaffConstraints {
+            max_shifts_per_week: Some(10),
+            max_shifts_per_month: Some(12),
+            ..Default::default()
+        };
+        // 月は2週目の水曜から
+        let month = abs_day(101, 2)..abs_day(102, 0);
+        let violations = check_staff_constraints(100, &weeks, month, &target(constraints.clone()));
+        assert_eq!(violations, vec![
+            StaffViolation::TooManyShiftsInWeek { staff: String::from("田中"), abs_week: 100, shifts: 14, max: 10 },
+        ]);
+        assert_eq!(violations[0].to_string(), "田中 のコマ数が週 14 です (上限 10)");
+
+        let month = abs_day(100, 0)..abs_day(102, 0);
+        let violations = check_staff_constraints(100, &weeks, month, &target(constraints));
+        assert_eq!(violations.last().unwrap().to_string(), "田中 のコマ数が月 21 です (上限 12)");
+        assert_eq!(violations.last().unwrap().abs_week(), None);
+    }
+}
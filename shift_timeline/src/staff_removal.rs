@@ -0,0 +1,182 @@
+//! グループ・スロットを消したときの位置の付け替え
+//!
+//! WASM版はスタッフを (グループの位置, グループ内の位置) だけで指す
+//! 手動調整 (`ShiftOverride`) や固定した週 (`LockedStaff`) も位置で持つので、消した後ろの位置を詰めないと、
+//! 後ろにいた別の人を指してしまう
+
+use crate::locked_weeks::{DETACHED_GROUP, LockedWeek};
+use crate::overrides::ShiftOverride;
+use crate::shift_calendar_manager::ShiftCalendarManager;
+
+/// 消したもの
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaffRemoval {
+    /// グループ `group_index` の `staff_index` 番目のスロット
+    Slot { group_index: usize, staff_index: usize },
+    /// グループ `group_index` (中のスロットごと)
+    Group { group_index: usize },
+}
+
+impl StaffRemoval {
+    /// 消した後の (グループの位置, グループ内の位置)。消した人なら None
+    /// どのグループにも入らない位置 (`DETACHED_GROUP`) はそのまま
+    pub fn position_after(&self, (group_index, staff_index): (usize, usize)) -> Option<(usize, usize)> {
+        if group_index == DETACHED_GROUP {
+            return Some((group_index, staff_index));
+        }
+        match *self {
+            StaffRemoval::Slot { group_index: removed_group, staff_index: removed } => {
+                if group_index != removed_group || staff_index < removed {
+                    Some((group_index, staff_index))
+                } else if staff_index == removed {
+                    None
+                } else {
+                    Some((group_index, staff_index - 1))
+                }
+            }
+            StaffRemoval::Group { group_index: removed_group } => {
+                if group_index < removed_group {
+                    Some((group_index, staff_index))
+                } else if group_index == removed_group {
+                    None
+                } else {
+                    Some((group_index - 1, staff_index))
+                }
+            }
+        }
+    }
+}
+
+/// 手動調整を消した後の位置に付け替え、消した人の調整は取り除く。変わったら true
+pub fn remove_from_overrides(overrides: &mut Vec<ShiftOverride>, removal: StaffRemoval) -> bool {
+    let before = overrides.clone();
+    overrides.retain_mut(|o| match removal.position_after((o.group_index, o.staff_index)) {
+        Some(position) => {
+            (o.group_index, o.staff_index) = position;
+            true
+        }
+        None => false,
+    });
+    *overrides != before
+}
+
+impl<P> ShiftCalendarManager<P> {
+    /// 固定した週の人を、グループ・スロットを消した後の位置に付け替える。変わったら true
+    /// 消した人は、どのグループにも入らない位置 (`DETACHED_GROUP`) にする (名前はそのままなので表示は変わらない)
+    /// (デスクトップ版の `rebind_members` で削除したメンバーを外すのと同じ)
+    pub fn remove_locked_staff(&mut self, removal: StaffRemoval) -> bool {
+        let before = self.locked_weeks.clone();
+        // 前に外した人と同じ人に見えないよう、グループ内の位置には空いている番号を使う
+        let detached_from = self.locked_weeks
+            .iter_mut()
+            .flat_map(LockedWeek::staff_mut)
+            .filter(|s| s.group_index == DETACHED_GROUP)
+            .map(|s| s.staff_index + 1)
+            .max()
+            .unwrap_or(0);
+        for staff in self.locked_weeks.iter_mut().flat_map(LockedWeek::staff_mut) {
+            (staff.group_index, staff.staff_index) = removal
+                .position_after((staff.group_index, staff.staff_index))
+                .unwrap_or((DETACHED_GROUP, detached_from + staff.staff_index));
+        }
+        self.locked_weeks != before
+    }
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod staff_removal_test {
+    use shift_calendar::shift_gen::{DayRule, ShiftHoll, StaffGroup, StaffGroupList, WeekRule, WeekRuleTable};
+
+    use super::{StaffRemoval, remove_from_overrides};
+    use crate::locked_weeks::{DETACHED_GROUP, LockedOverlay, LockedWeek};
+    use crate::overrides::{OverrideAction, ShiftOverride};
+    use crate::shift_calendar_manager::ShiftCalendarManager;
+    use crate::staff_schedule::ShiftPeriod;
+
+    fn over(group_index: usize, staff_index: usize) -> ShiftOverride {
+        ShiftOverride { abs_week: 100, weekday: 0, period: ShiftPeriod::Morning, group_index, staff_index, action: OverrideAction::Add }
+    }
+
+    fn positions(overrides: &[ShiftOverride]) -> Vec<(usize, usize)> {
+        overrides.iter().map(|o| (o.group_index, o.staff_index)).collect()
+    }
+
+    /// 消した後ろの位置だけ詰める
+    #[test]
+    fn test00() {
+        let slot = StaffRemoval::Slot { group_index: 1, staff_index: 1 };
+        assert_eq!(slot.position_after((0, 2)), Some((0, 2)));
+        assert_eq!(slot.position_after((1, 0)), Some((1, 0)));
+        assert_eq!(slot.position_after((1, 1)), None);
+        assert_eq!(slot.position_after((1, 2)), Some((1, 1)));
+
+        let group = StaffRemoval::Group { group_index: 1 };
+        assert_eq!(group.position_after((0, 2)), Some((0, 2)));
+        assert_eq!(group.position_after((1, 0)), None);
+        assert_eq!(group.position_after((2, 3)), Some((1, 3)));
+        assert_eq!(group.position_after((DETACHED_GROUP, 3)), Some((DETACHED_GROUP, 3)));
+    }
+
+    /// 消した人の調整は取り除き、後ろの人の調整は同じ人を指したまま
+    #[test]
+    fn test01() {
+        let mut overrides = vec![over(0, 0), over(0, 1), over(0, 2), over(1, 0)];
+
+        assert!(remove_from_overrides(&mut overrides, StaffRemoval::Slot { group_index: 0, staff_index: 1 }));
+        assert_eq!(positions(&overrides), vec![(0, 0), (0, 1), (1, 0)]);
+
+        assert!(remove_from_overrides(&mut overrides, StaffRemoval::Group { group_index: 0 }));
+        assert_eq!(positions(&overrides), vec![(0, 0)]);
+
+        // 調整の無いスロットを消しても変わらない
+        assert!(!remove_from_overrides(&mut overrides, StaffRemoval::Slot { group_index: 0, staff_index: 3 }));
+    }
+
+    /// 固定した後にスロットを消しても、固定したコマは消した人のまま (後ろの人に移らない)
+    #[test]
+    fn test02() {
+        let staff_group_list = |names: &[&str]| {
+            let mut group = StaffGroup::new("A");
+            for name in names {
+                group.add_staff(name);
+            }
+            let mut list = StaffGroupList::new();
+            list.add_staff_group(group);
+            list
+        };
+        // 月曜午前に a0、火曜午前に a1
+        let mut days: [DayRule<'_, _>; 7] = core::array::from_fn(|_| DayRule { shift_morning: vec![], shift_afternoon: vec![] });
+        days[0].shift_morning.push(ShiftHoll::new(0, 0));
+        days[1].shift_morning.push(ShiftHoll::new(0, 1));
+        let mut week_rule_table = WeekRuleTable::new();
+        week_rule_table.add_week_rule(WeekRule(days));
+
+        let mut calendar: ShiftCalendarManager<()> = ShiftCalendarManager::new(100, 0);
+        calendar.apply_weeks(100, &[Some(())]).unwrap();
+        let before = staff_group_list(&["田中", "佐藤"]);
+        let week = calendar.derive_shift(|_| Some(&week_rule_table), &before, 100, 1).pop().unwrap().unwrap();
+        calendar.lock_week(LockedWeek::from_decided(100, &week)).unwrap();
+
+        // 田中 (0番) を消したので 佐藤 が0番になった
+        assert!(calendar.remove_locked_staff(StaffRemoval::Slot { group_index: 0, staff_index: 0 }));
+        let after = staff_group_list(&["佐藤"]);
+
+        let shifts = calendar.staff_shifts(|_| Some(&week_rule_table), &after, (0, 0), &[], 100, 1);
+        let found: Vec<_> = shifts.iter().map(|s| (s.weekday, s.period)).collect();
+        assert_eq!(found, vec![(1, ShiftPeriod::Morning)]);
+
+        // 表示は固定したまま。田中 はどのグループにも入らない
+        let overlay = LockedOverlay::new(&calendar.locked_weeks, 100, 1);
+        let mut decided = calendar.derive_shift(|_| Some(&week_rule_table), &after, 100, 1);
+        overlay.apply(&mut decided);
+        let monday = &decided[0].as_ref().unwrap().0[0].shift_morning;
+        assert_eq!((monday[0].name.as_str(), monday[0].group_id), ("田中", DETACHED_GROUP));
+
+        // 続けて消しても、前に外した人と同じ位置にならない
+        assert!(calendar.remove_locked_staff(StaffRemoval::Group { group_index: 0 }));
+        let staff: Vec<_> = calendar.locked_weeks[0].staff_mut().map(|s| (s.name.clone(), s.group_index, s.staff_index)).collect();
+        assert_eq!(staff, vec![(String::from("田中"), DETACHED_GROUP, 0), (String::from("佐藤"), DETACHED_GROUP, 1)]);
+        assert!(!calendar.remove_locked_staff(StaffRemoval::Group { group_index: 0 }));
+    }
+}
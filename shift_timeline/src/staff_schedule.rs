@@ -0,0 +1,256 @@
+//! スタッフ1人分のシフト (「来月はいつ出勤？」への答え)
+//!
+//! 週ごとの導出は `calculate_partial_shift` と同じで、
+//! その結果から指定したスタッフが入っている日・時間帯だけを取り出す
+//! 日付やグループ名・ルール名への変換は呼び出し側 (WASM版・デスクトップ版) で行う
+
+use shift_calendar::shift_gen::{Incomplete, Staff, StaffGroupList, WeekDecidedShift, WeekRuleTable};
+
+use serde::{Deserialize, Serialize};
+
+use crate::locked_weeks::LockedOverlay;
+use crate::overrides::{ShiftOverride, apply_overrides};
+use crate::shift_calendar_manager::{
+    AbsWeek,
+    LogicalDelta,
+    ShiftCalendarManager,
+    WeekStatus,
+    calculate_partial_shift,
+};
+
+/// 午前・午後
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub enum ShiftPeriod {
+    Morning,
+    Afternoon,
+}
+
+/// スタッフが入っている1コマ
+#[derive(Debug, PartialEq)]
+pub struct StaffShift<'c, P> {
+    pub abs_week: AbsWeek,
+    /// 0 (月) 〜 6 (日)
+    pub weekday: usize,
+    pub period: ShiftPeriod,
+    /// その週の logical_delta (WASM版はこれでルール表のどのルールを使ったかが決まる)
+    pub logical_delta: LogicalDelta,
+    /// その週のpayload (デスクトップ版ではルールID)
+    pub payload: &'c P,
+}
+
+/// `timeline_slice` (先頭が `first_abs_week`) の中で、
+/// グループ `group_index` の `staff_index` 番目のスタッフが入っているコマを週・曜日・午前午後の順に返す
+pub fn staff_shifts_in<'c, 'a, 'r, P, F>(
+    timeline_slice: &'c [WeekStatus<P>],
+    first_abs_week: AbsWeek,
+    resolve_rule: F,
+    staff_group_list: &'a StaffGroupList,
+    group_index: usize,
+    staff_index: usize,
+) -> Vec<StaffShift<'c, P>>
+where
+    'a: 'r,
+    F: Fn(&P) -> Option<&'r WeekRuleTable<'a, Incomplete>>,
+{
+    let decided = calculate_partial_shift(timeline_slice, resolve_rule, staff_group_list);
+    staff_shifts_of(timeline_slice, first_abs_week, &decided, group_index, staff_index)
+}
+
+/// `staff_shifts_in` の、導出済みの週 (`decided` は `timeline_slice` と同じ並び) を受け取る版
+/// 導出した後に人を入れ替える場合 (ローテーションの方式・スキル) はこちらを使う
+pub fn staff_shifts_of<'c, P>(
+    timeline_slice: &'c [WeekStatus<P>],
+    first_abs_week: AbsWeek,
+    decided: &[Option<WeekDecidedShift>],
+    group_index: usize,
+    staff_index: usize,
+) -> Vec<StaffShift<'c, P>> {
+    let mut shifts = Vec::new();
+    for (offset, (status, week)) in timeline_slice.iter().zip(decided).enumerate() {
+        let (WeekStatus::Active { logical_delta, payload }, Some(week)) = (status, week) else {
+            continue;
+        };
+
+        for (weekday, day) in week.0.iter().enumerate() {
+            for (period, staff_list) in [
+                (ShiftPeriod::Morning, &day.shift_morning),
+                (ShiftPeriod::Afternoon, &day.shift_afternoon),
+            ] {
+                if staff_list.iter().any(|s| s.group_id == group_index && s.id == staff_index) {
+                    shifts.push(StaffShift {
+                        abs_week: first_abs_week + offset,
+                        weekday,
+                        period,
+                        logical_delta: *logical_delta,
+                        payload,
+                    });
+                }
+            }
+        }
+    }
+    shifts
+}
+
+impl<P> ShiftCalendarManager<P> {
+    /// `gen_week_abs` から `gen_range` 週分の、1人分のシフト
+    /// 基準週より前・タイムラインより後ろの週は含まれない
+    /// 固定した週は、固定した内容から取り出す
+    /// 固定していない週には `overrides` (週・日単位の調整) を当ててから取り出す
+    pub fn staff_shifts<'a, 'r, F>(
+        &self,
+        resolve_rule: F,
+        staff_group_list: &'a StaffGroupList,
+        (group_index, staff_index): (usize, usize),
+        overrides: &[ShiftOverride],
+        gen_week_abs: AbsWeek,
+        gen_range: usize,
+    ) -> Vec<StaffShift<'_, P>>
+    where
+        'a: 'r,
+        F: Fn(&P) -> Option<&'r WeekRuleTable<'a, Incomplete>>,
+    {
+        // slice_by_abs は基準週より前を指定すると空になるので、基準週から始める
+        let start = gen_week_abs.max(self.base_abs_week);
+        let range = (gen_week_abs + gen_range).saturating_sub(start);
+
+        let timeline_slice = self.slice_by_abs(start, range);
+        let overlay = LockedOverlay::new(&self.locked_weeks, start, timeline_slice.len());
+        // 取り出すのはこの人のコマだけなので、足す調整もこの人の分だけ当たれば足りる
+        // (名前は取り出した後に使わない)
+        let target = [Staff { name: String::new(), group_id: group_index, id: staff_index }];
+        let mut decided = calculate_partial_shift(timeline_slice, resolve_rule, staff_group_list);
+        apply_overrides(&mut decided, start, overrides, &target);
+        overlay.apply(&mut decided);
+        staff_shifts_of(timeline_slice, start, &decided, group_index, staff_index)
+    }
+}
+
+// ==================================== test ====================================
+#[cfg(test)]
+mod staff_schedule_test {
+    use shift_calendar::shift_gen::{
+        DayRule, ShiftHoll, StaffGroup, StaffGroupList, WeekRule, WeekRuleTable,
+    };
+
+    use super::ShiftPeriod;
+    use crate::locked_weeks::LockedWeek;
+    use crate::overrides::{OverrideAction, ShiftOverride};
+    use crate::shift_calendar_manager::ShiftCalendarManager;
+
+    /// グループ0: 1人 (ローテーションしても同じ人), グループ1: 1人
+    /// 月曜午前にグループ0、水曜午後にグループ0とグループ1
+    fn sample<'a>() -> (StaffGroupList, WeekRuleTable<'a, shift_calendar::shift_gen::Incomplete>) {
+        let mut staff_group_list = StaffGroupList::new();
+        for (name, staff) in [("A", "a0"), ("B", "b0")] {
+            let mut group = StaffGroup::new(name);
+            group.add_staff(staff);
+            staff_group_list.add_staff_group(group);
+        }
+
+        let mut days: [DayRule<'_, _>; 7] = core::array::from_fn(|_| DayRule {
+            shift_morning: vec![],
+            shift_afternoon: vec![],
+        });
+        days[0].shift_morning.push(ShiftHoll::new(0, 0));
+        days[2].shift_afternoon.push(ShiftHoll::new(0, 0));
+        days[2].shift_afternoon.push(ShiftHoll::new(1, 0));
+
+        let mut week_rule_table = WeekRuleTable::new();
+        week_rule_table.add_week_rule(WeekRule(days));
+        (staff_group_list, week_rule_table)
+    }
+
+    /// Skippedの週は飛ばし、指定したスタッフのコマだけが返る
+    #[test]
+    fn test00() {
+        let (staff_group_list, week_rule_table) = sample();
+        let mut calendar: ShiftCalendarManager<()> = ShiftCalendarManager::new(100, 0);
+        calendar.apply_weeks(100, &[Some(()), None, Some(())]).unwrap();
+
+        let shifts = calendar.staff_shifts(|_| Some(&week_rule_table), &staff_group_list, (0, 0), &[], 100, 3);
+        let found: Vec<_> = shifts.iter().map(|s| (s.abs_week, s.weekday, s.period)).collect();
+        assert_eq!(found, vec![
+            (100, 0, ShiftPeriod::Morning),
+            (100, 2, ShiftPeriod::Afternoon),
+            (102, 0, ShiftPeriod::Morning),
+            (102, 2, ShiftPeriod::Afternoon),
+        ]);
+
+        let shifts = calendar.staff_shifts(|_| Some(&week_rule_table), &staff_group_list, (1, 0), &[], 100, 3);
+        assert_eq!(shifts.len(), 2);
+        assert!(shifts.iter().all(|s| s.weekday == 2 && s.period == ShiftPeriod::Afternoon));
+    }
+
+    /// 基準週より前から指定しても週がずれない
+    #[test]
+    fn test01() {
+        let (staff_group_list, week_rule_table) = sample();
+        let mut calendar: ShiftCalendarManager<()> = ShiftCalendarManager::new(100, 0);
+        calendar.apply_weeks(100, &[Some(()), Some(())]).unwrap();
+
+        let shifts = calendar.staff_shifts(|_| Some(&week_rule_table), &staff_group_list, (0, 0), &[], 98, 3);
+        let weeks: Vec<_> = shifts.iter().map(|s| s.abs_week).collect();
+        assert_eq!(weeks, vec![100, 100]);
+
+        // 範囲がすべて基準週より前
+        assert!(calendar.staff_shifts(|_| Some(&week_rule_table), &staff_group_list, (0, 0), &[], 90, 5).is_empty());
+    }
+
+    /// 固定した週は固定した内容から取り出す
+    #[test]
+    fn test02() {
+        let (staff_group_list, week_rule_table) = sample();
+        let mut calendar: ShiftCalendarManager<()> = ShiftCalendarManager::new(100, 0);
+        calendar.apply_weeks(100, &[Some(()), Some(())]).unwrap();
+
+        // 101週は 月曜午前 の a0 を外した状態で固定する
+        let mut week = calendar.derive_shift(|_| Some(&week_rule_table), &staff_group_list, 101, 1).pop().unwrap().unwrap();
+        week.0[0].shift_morning.clear();
+        calendar.lock_week(LockedWeek::from_decided(101, &week)).unwrap();
+
+        let shifts = calendar.staff_shifts(|_| Some(&week_rule_table), &staff_group_list, (0, 0), &[], 100, 2);
+        let found: Vec<_> = shifts.iter().map(|s| (s.abs_week, s.weekday)).collect();
+        assert_eq!(found, vec![(100, 0), (100, 2), (101, 2)]);
+    }
+
+    /// 調整で足したコマ・外したコマが反映される (固定した週には当てない)
+    #[test]
+    fn test03() {
+        let (staff_group_list, week_rule_table) = sample();
+        let mut calendar: ShiftCalendarManager<()> = ShiftCalendarManager::new(100, 0);
+        calendar.apply_weeks(100, &[Some(()), Some(())]).unwrap();
+        let week = calendar.derive_shift(|_| Some(&week_rule_table), &staff_group_list, 101, 1).pop().unwrap().unwrap();
+        calendar.lock_week(LockedWeek::from_decided(101, &week)).unwrap();
+
+        let over = |abs_week, weekday, period, action| ShiftOverride {
+            abs_week, weekday, period, group_index: 1, staff_index: 0, action,
+        };
+        let overrides = [
+            // b0 を100週の金曜午前に足し、水曜午後から外す
+            over(100, 4, ShiftPeriod::Morning, OverrideAction::Add),
+            over(100, 2, ShiftPeriod::Afternoon, OverrideAction::Remove),
+            // 固定した週には当たらない
+            over(101, 2, ShiftPeriod::Afternoon, OverrideAction::Remove),
+        ];
+
+        let shifts = calendar.staff_shifts(|_| Some(&week_rule_table), &staff_group_list, (1, 0), &overrides, 100, 2);
+        let found: Vec<_> = shifts.iter().map(|s| (s.abs_week, s.weekday, s.period)).collect();
+        assert_eq!(found, vec![(100, 4, ShiftPeriod::Morning), (101, 2, ShiftPeriod::Afternoon)]);
+
+        // 他のスタッフのコマは変わらない
+        let shifts = calendar.staff_shifts(|_| Some(&week_rule_table), &staff_group_list, (0, 0), &overrides, 100, 1);
+        assert_eq!(shifts.len(), 2);
+    }
+
+    /// 週ごとの logical_delta が付く (Skippedの週の分は進まない)
+    #[test]
+    fn test04() {
+        let (staff_group_list, week_rule_table) = sample();
+        let mut calendar: ShiftCalendarManager<()> = ShiftCalendarManager::new(100, 3);
+        calendar.apply_weeks(100, &[Some(()), None, Some(())]).unwrap();
+
+        let shifts = calendar.staff_shifts(|_| Some(&week_rule_table), &staff_group_list, (1, 0), &[], 100, 3);
+        let found: Vec<_> = shifts.iter().map(|s| (s.abs_week, s.logical_delta)).collect();
+        assert_eq!(found, vec![(100, 3), (102, 4)]);
+    }
+}
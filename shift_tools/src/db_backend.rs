@@ -0,0 +1,670 @@
+// デスクトップ版 (shift-manager-tauri) の app.db を操作するバックエンド
+//
+// グループ・ルールはDBのID、メンバーはグループ内の位置 (sort_order順) で指定する
+// 読み書きはアプリと同じリポジトリ実装を通すので、DBの制約もそのまま効く
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use tokio::runtime::Runtime;
+
+use component_features::load_rules::{
+    JsonAssignment, JsonConfig, JsonRule, JsonSlot, JsonStaffGroup, JsonWeeklySchedule,
+};
+use shift_manager_tauri_lib::{
+    AppServices,
+    application::{
+        commands::{
+            compute_add_rule_assignment, compute_apply_rule_edit, compute_delete_weekly_rule_with, compute_monthly_shift,
+            compute_printable_month,
+        },
+        revision::Transactional,
+        schedule_print::{check_output_path, render_html},
+    },
+    domain::{
+        repository::{CalendarRepository, PlanRepository, RuleRepository},
+        rule_model::{PlanConfig, PlanItem, RuleAssignment, RuleDeletion, RuleEdit, ShiftTime},
+        shift_calendar_model::{PlanCalendar, RuleRef},
+    },
+    open_database,
+};
+use shift_timeline::{ShiftCalendarManager, WeekStatus};
+
+use crate::error::{CliError, CliResult};
+use crate::exchange::{ExchangeCalendar, RuleIndex, check_config, check_timeline};
+use crate::render::{self, DayView, WeekView};
+use crate::week::{YearMonth, parse_week};
+use crate::{
+    AssignCommand, Backend, GroupCommand, MemberCommand, PlanCommand, RuleCommand, ShiftTimeArg,
+    TimelineCommand,
+};
+
+pub struct DbBackend {
+    runtime: Runtime,
+    services: AppServices,
+    plan: Option<i64>,
+}
+
+impl DbBackend {
+    /// DBを開く (無ければアプリと同じくマイグレーション済みの空のDBを作る)
+    pub fn open(db_path: &Path, plan: Option<i64>) -> CliResult<Self> {
+        let runtime = Runtime::new().map_err(|e| CliError::Failed(e.to_string()))?;
+        let pool = runtime
+            .block_on(open_database(db_path))
+            .map_err(CliError::Data)?;
+
+        Ok(Self { runtime, services: AppServices::new(pool), plan })
+    }
+
+    /// 操作するプラン
+    /// --plan が無い場合は、プランが1つだけのときに限りそれを使う
+    fn plan_id(&self) -> CliResult<i64> {
+        if let Some(plan_id) = self.plan {
+            let plans = self.runtime.block_on(self.services.rule.list_plans())?;
+            return if plans.iter().any(|p| p.id == plan_id) {
+                Ok(plan_id)
+            } else {
+                Err(CliError::Failed(format!("Plan ID: {} は存在しません", plan_id)))
+            };
+        }
+
+        let plans = self.runtime.block_on(self.services.rule.list_plans())?;
+        match plans.as_slice() {
+            [plan] => Ok(plan.id),
+            [] => Err(CliError::Failed(String::from(
+                "プランがありません。`plan create` で作成してください"
+            ))),
+            _ => Err(CliError::Usage(String::from(
+                "プランが複数あります。--plan で指定してください (`plan list` で一覧)"
+            ))),
+        }
+    }
+
+    /// アプリと同じく、`item` の入っているプランの revision を進めて書き換える
+    /// (開いているアプリが古い内容のまま上書きしないように。失敗したら revision も進まない)
+    fn write<T>(&self, item: PlanItem, write: impl AsyncFnOnce(&AppServices) -> Result<T, String>) -> CliResult<T> {
+        let (_, value) = self.runtime.block_on(self.services.with_revision(item, None, write))?;
+        Ok(value)
+    }
+
+    fn plan_config(&self) -> CliResult<PlanConfig> {
+        let plan_id = self.plan_id()?;
+        Ok(self.runtime.block_on(self.services.rule.get_plan_config(plan_id))?)
+    }
+
+    fn calendar(&self) -> CliResult<Option<PlanCalendar>> {
+        let plan_id = self.plan_id()?;
+        Ok(self.runtime.block_on(self.services.calendar.find_by_plan_id(plan_id))?)
+    }
+
+    /// 月のシフト表を印刷用の HTML に書き出す
+    /// group を指定すると、そのグループの人だけの表になる
+    pub fn print(&self, year_month: YearMonth, group: Option<i64>, out: &Path) -> CliResult<()> {
+        check_output_path(out).map_err(CliError::Usage)?;
+        let plan_id = self.plan_id()?;
+        let month = self.runtime.block_on(compute_printable_month(
+            &self.services, plan_id, year_month.year, year_month.month, group
+        ))?;
+        fs::write(out, render_html(&month))
+            .map_err(|e| CliError::Data(format!("'{}' に書き込めませんでした: {}", out.display(), e)))
+    }
+
+    pub fn plan(&self, command: PlanCommand) -> CliResult<()> {
+        let repo = &self.services.rule;
+        match command {
+            PlanCommand::List => {
+                for plan in self.runtime.block_on(repo.list_plans())? {
+                    println!("{}\t{}", plan.id, plan.name);
+                }
+            }
+            PlanCommand::Create { name } => {
+                println!("{}", self.runtime.block_on(repo.create_plan(&name))?);
+            }
+            PlanCommand::Rename { plan, name } => {
+                self.write(PlanItem::Plan(plan), async |tx| tx.rule.update_plan_name(plan, &name).await)?;
+            }
+            PlanCommand::Delete { plan } => {
+                self.write(PlanItem::Plan(plan), async |tx| tx.rule.delete_plan(plan).await)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 指定したルールのアサインを show と同じ並び (曜日・時間帯・ID順) で返す
+    fn sorted_assignments(config: &PlanConfig, rule_id: i64) -> CliResult<Vec<&RuleAssignment>> {
+        let rule = config
+            .rules
+            .iter()
+            .find(|r| r.rule.id == rule_id)
+            .ok_or_else(|| not_in_plan("ルール", rule_id))?;
+
+        let mut assignments: Vec<&RuleAssignment> = rule.assignments.iter().collect();
+        assignments.sort_by_key(|a| (a.weekday as i64, a.shift_time_type as i64, a.id));
+        Ok(assignments)
+    }
+
+    /// ルールID → 名前
+    fn rule_names(config: &PlanConfig) -> HashMap<i64, String> {
+        config.rules.iter().map(|r| (r.rule.id, r.rule.name.clone())).collect()
+    }
+
+    /// 週の指定 (`skip` / `-` / ルールID) を解釈する
+    fn parse_status(config: &PlanConfig, token: &str) -> CliResult<Option<RuleRef>> {
+        if token == "skip" || token == "-" {
+            return Ok(None);
+        }
+        let rule_id: i64 = token.parse().map_err(|_| CliError::Usage(format!(
+            "週の指定はルールIDか skip です: {}", token
+        )))?;
+        if !config.rules.iter().any(|r| r.rule.id == rule_id) {
+            return Err(not_in_plan("ルール", rule_id));
+        }
+        Ok(Some(RuleRef { rule_id }))
+    }
+}
+
+fn not_in_plan(kind: &str, id: i64) -> CliError {
+    CliError::Failed(format!("{} ID: {} はこのプランにありません", kind, id))
+}
+
+fn shift_time_value(time: ShiftTimeArg) -> i64 {
+    match time {
+        ShiftTimeArg::Morning => ShiftTime::Morning as i64,
+        ShiftTimeArg::Afternoon => ShiftTime::Afternoon as i64,
+    }
+}
+
+impl Backend for DbBackend {
+    fn show(&self) -> CliResult<()> {
+        let config = self.plan_config()?;
+        println!("プラン {}: {}", config.plan.id, config.plan.name);
+
+        println!();
+        println!("グループ");
+        for group in &config.groups {
+            println!("  {}: {}", group.group.id, group.group.name);
+            for (index, member) in group.members.iter().enumerate() {
+                println!("    [{}] {}", index, member.name);
+            }
+        }
+
+        let group_names: HashMap<i64, &str> = config
+            .groups
+            .iter()
+            .map(|g| (g.group.id, g.group.name.as_str()))
+            .collect();
+
+        println!();
+        println!("ルール");
+        for rule in &config.rules {
+            println!("  {}: {}", rule.rule.id, rule.rule.name);
+            let assignments = Self::sorted_assignments(&config, rule.rule.id)?;
+            let mut position = 0;
+            let mut previous = None;
+            for assign in assignments {
+                let key = (assign.weekday as i64, assign.shift_time_type as i64);
+                if previous != Some(key) {
+                    position = 0;
+                    previous = Some(key);
+                }
+                println!(
+                    "    {} {} [{}] グループ {} ({}) の [{}]",
+                    render::weekday_label(assign.weekday as usize),
+                    render::shift_time_label(assign.shift_time_type == ShiftTime::Afternoon),
+                    position,
+                    assign.target_group_id,
+                    group_names.get(&assign.target_group_id).unwrap_or(&"?"),
+                    assign.target_member_index,
+                );
+                position += 1;
+            }
+        }
+
+        println!();
+        match self.calendar()? {
+            Some(calendar) => println!(
+                "カレンダー: base_abs_week {}  initial_delta {}  {} 週",
+                calendar.calendar.base_abs_week,
+                calendar.calendar.initial_delta,
+                calendar.calendar.timeline.len(),
+            ),
+            None => println!("カレンダー: (未作成)"),
+        }
+        Ok(())
+    }
+
+    fn group(&mut self, command: GroupCommand) -> CliResult<()> {
+        let plan_id = self.plan_id()?;
+        match command {
+            GroupCommand::Add { name } => {
+                println!("{}", self.write(PlanItem::Plan(plan_id), async |tx| tx.rule.add_staff_group(plan_id, &name).await)?);
+            }
+            GroupCommand::Rename { group, name } => {
+                let config = self.plan_config()?;
+                if !config.groups.iter().any(|g| g.group.id == group) {
+                    return Err(not_in_plan("グループ", group));
+                }
+                self.write(PlanItem::Group(group), async |tx| tx.rule.update_group_name(group, &name).await)?;
+            }
+            GroupCommand::Delete { group } => {
+                let config = self.plan_config()?;
+                if !config.groups.iter().any(|g| g.group.id == group) {
+                    return Err(not_in_plan("グループ", group));
+                }
+                // DBの外部キーでも止まるが、どのルールが使っているかを出す
+                let used_by: Vec<&str> = config
+                    .rules
+                    .iter()
+                    .filter(|r| r.assignments.iter().any(|a| a.target_group_id == group))
+                    .map(|r| r.rule.name.as_str())
+                    .collect();
+                if !used_by.is_empty() {
+                    return Err(CliError::Failed(format!(
+                        "グループ {} はルール ({}) から参照されているため削除できません",
+                        group, used_by.join(", ")
+                    )));
+                }
+                self.write(PlanItem::Group(group), async |tx| tx.rule.delete_staff_group(group).await)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn member(&mut self, command: MemberCommand) -> CliResult<()> {
+        let config = self.plan_config()?;
+        let find_group = |group: i64| config
+            .groups
+            .iter()
+            .find(|g| g.group.id == group)
+            .ok_or_else(|| not_in_plan("グループ", group));
+        let find_member = |group: i64, index: usize| find_group(group)?
+            .members
+            .get(index)
+            .map(|m| m.id)
+            .ok_or_else(|| CliError::Failed(format!(
+                "グループ {} に [{}] のメンバーはいません", group, index
+            )));
+
+        match command {
+            MemberCommand::Add { group, name } => {
+                find_group(group)?;
+                self.write(PlanItem::Group(group), async |tx| tx.rule.add_staff_member(group, &name).await)?;
+            }
+            MemberCommand::Rename { group, index, name } => {
+                let member_id = find_member(group, index)?;
+                self.write(PlanItem::Member(member_id), async |tx| tx.rule.update_member_name(member_id, &name).await)?;
+            }
+            MemberCommand::Delete { group, index } => {
+                let member_id = find_member(group, index)?;
+                self.write(PlanItem::Member(member_id), async |tx| tx.rule.delete_staff_member(member_id).await)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn rule(&mut self, command: RuleCommand) -> CliResult<()> {
+        let plan_id = self.plan_id()?;
+        match command {
+            RuleCommand::Add { name } => {
+                println!("{}", self.write(PlanItem::Plan(plan_id), async |tx| tx.rule.add_weekly_rule(plan_id, &name).await)?);
+            }
+            RuleCommand::Rename { rule, name } => {
+                Self::sorted_assignments(&self.plan_config()?, rule)?;
+                self.write(PlanItem::Rule(rule), async |tx| tx.rule.update_rule_name(rule, &name).await)?;
+            }
+            RuleCommand::Delete { rule, reassign, skip } => {
+                Self::sorted_assignments(&self.plan_config()?, rule)?;
+                // アプリの「ルールを削除」と同じ。使っている週があれば、モードを指定しないと断る
+                let mode = match (reassign, skip) {
+                    (Some(to_rule_id), _) => RuleDeletion::Reassign { to_rule_id },
+                    (None, true) => RuleDeletion::Skip,
+                    (None, false) => RuleDeletion::Refuse,
+                };
+                let changed = self.write(PlanItem::Plan(plan_id), async |tx| {
+                    compute_delete_weekly_rule_with(tx, plan_id, rule, &mode).await
+                })?;
+                for monday in changed {
+                    println!("{}", monday);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn assign(&mut self, command: AssignCommand) -> CliResult<()> {
+        let config = self.plan_config()?;
+        let plan_id = config.plan.id;
+        // アプリのルール編集と同じく、固定した週が使っているルールは変えない
+        match command {
+            AssignCommand::Add { rule, weekday, time, group, index } => {
+                Self::sorted_assignments(&config, rule)?;
+                let members = config
+                    .groups
+                    .iter()
+                    .find(|g| g.group.id == group)
+                    .ok_or_else(|| not_in_plan("グループ", group))?
+                    .members
+                    .len();
+                if index >= members {
+                    return Err(CliError::Failed(format!(
+                        "グループ {} のメンバーは {} 人です (index: {})", group, members, index
+                    )));
+                }
+                let edit = RuleEdit::AddAssignment {
+                    rule_id: rule,
+                    weekday: weekday.index() as i64,
+                    shift_time: shift_time_value(time),
+                    group_id: group,
+                    member_index: index as i64,
+                };
+                self.write(PlanItem::Plan(plan_id), async |tx| compute_add_rule_assignment(tx, plan_id, edit).await)?;
+            }
+            AssignCommand::Delete { rule, weekday, time, position } => {
+                let assignment_id = Self::sorted_assignments(&config, rule)?
+                    .into_iter()
+                    .filter(|a| a.weekday as usize == weekday.index()
+                        && a.shift_time_type as i64 == shift_time_value(time))
+                    .nth(position)
+                    .map(|a| a.id)
+                    .ok_or_else(|| CliError::Failed(format!(
+                        "ルール {} の {} {} に [{}] のアサインはありません",
+                        rule,
+                        render::weekday_label(weekday.index()),
+                        render::shift_time_label(matches!(time, ShiftTimeArg::Afternoon)),
+                        position
+                    )))?;
+                let edit = RuleEdit::DeleteAssignment { assignment_id };
+                self.write(PlanItem::Plan(plan_id), async |tx| compute_apply_rule_edit(tx, plan_id, edit, false).await)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn timeline(&mut self, command: TimelineCommand) -> CliResult<()> {
+        let plan_id = self.plan_id()?;
+        let plan = PlanItem::Plan(plan_id);
+        match command {
+            TimelineCommand::Show => {
+                let calendar = self
+                    .calendar()?
+                    .ok_or_else(|| CliError::Failed(String::from("カレンダーがありません")))?
+                    .calendar;
+                let rule_names = Self::rule_names(&self.plan_config()?);
+                let labels: Vec<String> = calendar
+                    .timeline
+                    .iter()
+                    .map(|week| render::status_label(Some(week), |r: &RuleRef| Some(
+                        rule_names.get(&r.rule_id).cloned().unwrap_or_else(|| format!("ルール {}", r.rule_id))
+                    )))
+                    .collect();
+                render::print_timeline(calendar.base_abs_week, calendar.initial_delta, &labels);
+            }
+            TimelineCommand::Create { base, initial_delta } => {
+                let base_abs_week = parse_week(&base)?;
+                self.write(plan, async |tx| tx.calendar.create_calendar(plan_id, base_abs_week, initial_delta).await)?;
+            }
+            TimelineCommand::Append { start, statuses } => {
+                let start_abs_week = parse_week(&start)?;
+                let config = self.plan_config()?;
+                let statuses = statuses
+                    .iter()
+                    .map(|token| Self::parse_status(&config, token).map(|s| s.map(|r| r.rule_id)))
+                    .collect::<CliResult<Vec<_>>>()?;
+                self.write(plan, async |tx| tx.calendar.try_to_append_timeline(plan_id, start_abs_week, statuses).await)?;
+            }
+            TimelineCommand::Truncate { from } => {
+                let from_abs_week = parse_week(&from)?;
+                self.write(plan, async |tx| tx.calendar.truncate_timeline(plan_id, from_abs_week).await)?;
+            }
+            TimelineCommand::Rebase { base } => {
+                let base_abs_week = parse_week(&base)?;
+                self.write(plan, async |tx| tx.calendar.rebase_calendar(plan_id, base_abs_week).await)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn month(&self, year_month: YearMonth) -> CliResult<Vec<WeekView>> {
+        let plan_id = self.plan_id()?;
+        let result = self.runtime.block_on(compute_monthly_shift(
+            &self.services, plan_id, year_month.year, year_month.month
+        ))?;
+
+        let first_abs_week = year_month.first_abs_week()?;
+        let calendar = self.calendar()?;
+        let rule_names = Self::rule_names(&self.plan_config()?);
+
+        Ok(result
+            .weeks
+            .into_iter()
+            .enumerate()
+            .map(|(offset, week)| {
+                let abs_week = first_abs_week + offset;
+                let status = calendar
+                    .as_ref()
+                    .and_then(|c| c.calendar.slice_by_abs(abs_week, 1).first());
+                let (days, warnings) = match week {
+                    Some(w) => (
+                        Some(w.days.into_iter().map(|d| DayView { morning: d.morning, afternoon: d.afternoon }).collect()),
+                        w.warnings.into_iter().map(|warning| warning.message).collect(),
+                    ),
+                    None => (None, Vec::new()),
+                };
+                WeekView {
+                    abs_week,
+                    label: render::status_label(status, |r: &RuleRef| rule_names.get(&r.rule_id).cloned()),
+                    days,
+                    warnings,
+                }
+            })
+            .collect())
+    }
+
+    fn validate(&self) -> CliResult<Vec<String>> {
+        let (config, _) = self.export()?;
+        let mut issues = check_config(&config);
+
+        if let Some(calendar) = self.calendar()? {
+            issues.extend(check_timeline(&calendar.calendar));
+
+            let rule_names = Self::rule_names(&self.plan_config()?);
+            for (offset, week) in calendar.calendar.timeline.iter().enumerate() {
+                if let WeekStatus::Active { payload, .. } = week
+                    && !rule_names.contains_key(&payload.rule_id)
+                {
+                    issues.push(format!(
+                        "絶対週 {}: ルール {} はこのプランにありません",
+                        calendar.calendar.base_abs_week + offset, payload.rule_id
+                    ));
+                }
+            }
+        }
+        Ok(issues)
+    }
+
+    /// PlanConfig を web版のJSONに変換する
+    /// グループ・ルールはDBの並び順 (sort_order) の位置に置き換わる
+    fn export(&self) -> CliResult<(JsonConfig, Option<ExchangeCalendar>)> {
+        let config = self.plan_config()?;
+
+        let group_index: HashMap<i64, usize> = config
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(index, g)| (g.group.id, index))
+            .collect();
+        let rule_index: HashMap<i64, usize> = config
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, r)| (r.rule.id, index))
+            .collect();
+
+        let staff_groups = config
+            .groups
+            .iter()
+            .map(|g| JsonStaffGroup {
+                name: g.group.name.clone(),
+                slots: g.members.iter().map(|m| JsonSlot { name: m.name.clone() }).collect(),
+                min_per_period: g.group.min_per_period.and_then(|n| u32::try_from(n).ok()),
+            })
+            .collect();
+
+        let mut rules = Vec::new();
+        for rule in &config.rules {
+            let mut schedule = JsonWeeklySchedule::default();
+            for assign in Self::sorted_assignments(&config, rule.rule.id)? {
+                let staff_group_id = *group_index.get(&assign.target_group_id).ok_or_else(|| {
+                    CliError::Failed(format!(
+                        "ルール {} がプラン外のグループ {} を参照しています",
+                        rule.rule.id, assign.target_group_id
+                    ))
+                })?;
+                let day = &mut schedule.days_mut()[assign.weekday as usize];
+                let holls = match assign.shift_time_type {
+                    ShiftTime::Morning => &mut day.m,
+                    ShiftTime::Afternoon => &mut day.a,
+                };
+                holls.push(JsonAssignment {
+                    staff_group_id: staff_group_id as u32,
+                    shift_staff_index: assign.target_member_index as u32,
+                });
+            }
+            rules.push(JsonRule { name: rule.rule.name.clone(), schedule });
+        }
+
+        let calendar = self.calendar()?.map(|c| ShiftCalendarManager {
+            base_abs_week: c.calendar.base_abs_week,
+            initial_delta: c.calendar.initial_delta,
+            timeline: c
+                .calendar
+                .timeline
+                .into_iter()
+                .map(|week| match week {
+                    WeekStatus::Active { logical_delta, payload } => WeekStatus::Active {
+                        logical_delta,
+                        payload: rule_index
+                            .get(&payload.rule_id)
+                            .map(|&rule_index| RuleIndex { rule_index }),
+                    },
+                    WeekStatus::Skipped => WeekStatus::Skipped,
+                })
+                .collect(),
+            locked_weeks: c.calendar.locked_weeks,
+        });
+
+        let max_days_per_week = config.plan.max_days_per_week.and_then(|n| u32::try_from(n).ok());
+        Ok((JsonConfig { staff_groups, rules, max_days_per_week }, calendar))
+    }
+
+    /// 新しいプランとして取り込む
+    /// プランの作成からまとめて1つのトランザクションで行うので、途中で失敗したら何も残らない
+    fn import(
+        &mut self,
+        config: JsonConfig,
+        calendar: Option<ExchangeCalendar>,
+        name: String,
+        rule: Option<usize>,
+    ) -> CliResult<()> {
+        let mut issues = check_config(&config);
+        if let Some(calendar) = &calendar {
+            issues.extend(check_timeline(calendar));
+        }
+        if !issues.is_empty() {
+            return Err(CliError::Invalid(issues));
+        }
+
+        // rule_index を持たない週に使うルール
+        let fallback_rule = match (rule, config.rules.len()) {
+            (Some(index), len) if index < len => Some(index),
+            (Some(index), len) => return Err(CliError::Usage(format!(
+                "--rule {} はルールの数 ({}) を超えています", index, len
+            ))),
+            (None, 1) => Some(0),
+            (None, _) => None,
+        };
+
+        let weeks: Option<Vec<Option<usize>>> = calendar
+            .as_ref()
+            .map(|c| c
+                .timeline
+                .iter()
+                .enumerate()
+                .map(|(offset, week)| match week {
+                    WeekStatus::Active { payload, .. } => payload
+                        .map(|p| p.rule_index)
+                        .or(fallback_rule)
+                        .filter(|&index| index < config.rules.len())
+                        .map(Some)
+                        .ok_or_else(|| CliError::Usage(format!(
+                            "絶対週 {} に適用するルールが決まりません (--rule で指定してください)",
+                            c.base_abs_week + offset
+                        ))),
+                    WeekStatus::Skipped => Ok(None),
+                })
+                .collect::<CliResult<_>>())
+            .transpose()?;
+
+        let plan_id = self.runtime.block_on(async {
+            let repo = self.services.begin().await?;
+            let plan_id = repo.rule.create_plan(&name).await?;
+            if config.max_days_per_week.is_some() {
+                repo.rule.update_plan_max_days(plan_id, config.max_days_per_week).await?;
+            }
+
+            let mut group_ids = Vec::new();
+            for group in &config.staff_groups {
+                let group_id = repo.rule.add_staff_group(plan_id, &group.name).await?;
+                if group.min_per_period.is_some() {
+                    repo.rule.update_group_min_staff(group_id, group.min_per_period).await?;
+                }
+                for slot in &group.slots {
+                    repo.rule.add_staff_member(group_id, &slot.name).await?;
+                }
+                group_ids.push(group_id);
+            }
+
+            let mut rule_ids = Vec::new();
+            for json_rule in &config.rules {
+                let rule_id = repo.rule.add_weekly_rule(plan_id, &json_rule.name).await?;
+                for (weekday, day) in json_rule.schedule.days().iter().enumerate() {
+                    for (shift_time, holls) in [(ShiftTime::Morning, &day.m), (ShiftTime::Afternoon, &day.a)] {
+                        for holl in holls {
+                            repo.rule.add_rule_assignment(
+                                rule_id,
+                                weekday as i64,
+                                shift_time as i64,
+                                group_ids[holl.staff_group_id as usize],
+                                holl.shift_staff_index as i64,
+                            ).await?;
+                        }
+                    }
+                }
+                rule_ids.push(rule_id);
+            }
+
+            if let (Some(calendar), Some(weeks)) = (&calendar, weeks) {
+                repo.calendar
+                    .create_calendar(plan_id, calendar.base_abs_week, calendar.initial_delta)
+                    .await?;
+                if !weeks.is_empty() {
+                    repo.calendar
+                        .try_to_append_timeline(
+                            plan_id,
+                            calendar.base_abs_week,
+                            weeks.into_iter().map(|w| w.map(|index| rule_ids[index])).collect(),
+                        )
+                        .await?;
+                }
+                if !calendar.locked_weeks.is_empty() {
+                    repo.calendar.lock_weeks(plan_id, calendar.locked_weeks.clone()).await?;
+                }
+            }
+            repo.commit().await?;
+            Ok::<_, String>(plan_id)
+        }).map_err(CliError::Failed)?;
+
+        println!("{}", plan_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt;
+use std::process::ExitCode;
+
+/// shift_tools のエラー
+/// 種類ごとに終了コードを分けて、スクリプトから判別できるようにする
+///
+/// | 終了コード | 意味                                             |
+/// |------------|--------------------------------------------------|
+/// | 0          | 成功                                             |
+/// | 1          | 操作の失敗 (上書き・歯抜け・存在しないIDなど)    |
+/// | 2          | 引数の誤り (clap のエラーも 2)                   |
+/// | 3          | ファイル・DBの読み書きの失敗、形式の誤り         |
+/// | 4          | validate で問題が見つかった                      |
+#[derive(Debug)]
+pub enum CliError {
+    Failed(String),
+    Usage(String),
+    Data(String),
+    Invalid(Vec<String>),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            CliError::Failed(_) => ExitCode::from(1),
+            CliError::Usage(_) => ExitCode::from(2),
+            CliError::Data(_) => ExitCode::from(3),
+            CliError::Invalid(_) => ExitCode::from(4),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Failed(message) => write!(f, "エラー: {}", message),
+            CliError::Usage(message) => write!(f, "引数エラー: {}", message),
+            CliError::Data(message) => write!(f, "データエラー: {}", message),
+            CliError::Invalid(issues) => {
+                writeln!(f, "{} 件の問題が見つかりました", issues.len())?;
+                for issue in issues {
+                    writeln!(f, "  - {}", issue)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// リポジトリなどが返す `Result<_, String>` は操作の失敗として扱う
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Failed(message)
+    }
+}
+
+pub type CliResult<T> = Result<T, CliError>;
@@ -0,0 +1,72 @@
+// export / import で読み書きするファイル形式と、共通のチェック
+//
+// 形式は web版 (component-features) のJSONそのもの
+// - ルール設定: JsonConfig (`load-config-from-json` と同じ、CONFIG_SCHEMA)
+// - カレンダー: ShiftCalendarManager (`output-calendar-manager-data` と同じ、CALENDAR_SCHEMA)
+//
+// 書き出しは常に最新バージョンのエンベロープで包み、読み込みは古いバージョンも受け付ける
+//
+// デスクトップ版から書き出したカレンダーには、週ごとに適用するルールの位置
+// (`rule_index`) が追加で入る。web版はこのフィールドを無視して読み込める
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use component_features::load_rules::JsonConfig;
+use shift_timeline::{ShiftCalendarManager, schema::DocumentSchema};
+
+use crate::error::{CliError, CliResult};
+
+/// Activeな週に適用するルール (JsonConfig.rules の位置)
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy)]
+pub struct RuleIndex {
+    pub rule_index: usize,
+}
+
+/// export / import するカレンダー
+/// `rule_index` が無い (web版で作った) 週は `None`
+pub type ExchangeCalendar = ShiftCalendarManager<Option<RuleIndex>>;
+
+/// バージョン付きのJSONを読む (古いバージョンは最新の形にして返す)
+pub fn read_json<T: DeserializeOwned>(path: &Path, schema: &DocumentSchema) -> CliResult<T> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| CliError::Data(format!("'{}' を読めませんでした: {}", path.display(), e)))?;
+    schema
+        .from_json(&text)
+        .map_err(|e| CliError::Data(format!("'{}' の形式が正しくありません: {}", path.display(), e)))
+}
+
+/// 最新バージョンのエンベロープで包んだJSONにする
+pub fn to_pretty_json<T: Serialize>(schema: &DocumentSchema, value: &T) -> CliResult<String> {
+    serde_json::to_string_pretty(&schema.wrap(value))
+        .map_err(|e| CliError::Data(e.to_string()))
+}
+
+pub fn write_json<T: Serialize>(path: &Path, schema: &DocumentSchema, value: &T) -> CliResult<()> {
+    let text = to_pretty_json(schema, value)?;
+    fs::write(path, text)
+        .map_err(|e| CliError::Data(format!("'{}' に書き込めませんでした: {}", path.display(), e)))
+}
+
+/// ルール設定の参照チェック
+/// (存在しないグループ・スロットを指しているアサインを探す)
+pub fn check_config(config: &JsonConfig) -> Vec<String> {
+    config
+        .validation_errors()
+        .iter()
+        .map(|e| e.to_string())
+        .collect()
+}
+
+/// タイムラインの採番チェック
+pub fn check_timeline<P>(calendar: &ShiftCalendarManager<P>) -> Vec<String> {
+    calendar
+        .delta_mismatches()
+        .into_iter()
+        .map(|(index, expected, actual)| format!(
+            "絶対週 {}: logical_delta が {} になっています (期待値 {})",
+            calendar.base_abs_week + index, actual, expected
+        ))
+        .collect()
+}
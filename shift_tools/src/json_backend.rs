@@ -0,0 +1,421 @@
+// web版 (wasm-shift-manager) のJSONファイルを操作するバックエンド
+//
+// グループ・ルールは先頭からの位置で指定する (web版のUIと同じ)
+// 変更のあるコマンドは、成功したときだけファイルを書き戻す
+use std::path::{Path, PathBuf};
+
+use component_features::load_rules::{
+    CONFIG_SCHEMA, JsonAssignment, JsonConfig, JsonRule, JsonSlot, JsonStaffGroup,
+};
+use shift_timeline::{CALENDAR_SCHEMA, ShiftCalendarManager, WeekStatus, check_week, schema::DocumentSchema};
+
+use crate::error::{CliError, CliResult};
+use crate::exchange::{ExchangeCalendar, check_config, check_timeline, read_json, write_json};
+use crate::render::{self, DayView, WeekView};
+use crate::week::{YearMonth, parse_week};
+use crate::{
+    AssignCommand, Backend, GroupCommand, MemberCommand, RuleCommand, ShiftTimeArg, TimelineCommand,
+};
+
+/// web版のカレンダー (payloadなし)
+type WebCalendar = ShiftCalendarManager<()>;
+
+pub struct JsonBackend {
+    config_path: Option<PathBuf>,
+    calendar_path: Option<PathBuf>,
+    config: JsonConfig,
+    calendar: Option<WebCalendar>,
+}
+
+/// 指定されたファイルを読む (まだ無いファイルは None)
+fn read_if_exists<T: serde::de::DeserializeOwned>(path: Option<&Path>, schema: &DocumentSchema) -> CliResult<Option<T>> {
+    match path {
+        Some(path) if path.exists() => read_json(path, schema).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn to_index(kind: &str, id: i64, len: usize) -> CliResult<usize> {
+    usize::try_from(id)
+        .ok()
+        .filter(|&index| index < len)
+        .ok_or_else(|| CliError::Failed(format!("{} [{}] はありません ({} 件)", kind, id, len)))
+}
+
+impl JsonBackend {
+    pub fn open(config_path: Option<PathBuf>, calendar_path: Option<PathBuf>) -> CliResult<Self> {
+        let config = read_if_exists(config_path.as_deref(), &CONFIG_SCHEMA)?
+            .unwrap_or(JsonConfig { staff_groups: Vec::new(), rules: Vec::new(), max_days_per_week: None });
+        let calendar = read_if_exists(calendar_path.as_deref(), &CALENDAR_SCHEMA)?;
+
+        Ok(Self { config_path, calendar_path, config, calendar })
+    }
+
+    fn save_config(&self) -> CliResult<()> {
+        let path = self.config_path.as_deref().ok_or_else(|| CliError::Usage(
+            String::from("--config でルール設定のファイルを指定してください")
+        ))?;
+        write_json(path, &CONFIG_SCHEMA, &self.config)
+    }
+
+    fn save_calendar(&self) -> CliResult<()> {
+        let path = self.calendar_path.as_deref().ok_or_else(|| CliError::Usage(
+            String::from("--calendar でカレンダーのファイルを指定してください")
+        ))?;
+        match &self.calendar {
+            Some(calendar) => write_json(path, &CALENDAR_SCHEMA, calendar),
+            None => Ok(()),
+        }
+    }
+
+    fn calendar_ref(&self) -> CliResult<&WebCalendar> {
+        if self.calendar_path.is_none() {
+            return Err(CliError::Usage(String::from(
+                "--calendar でカレンダーのファイルを指定してください"
+            )));
+        }
+        self.calendar
+            .as_ref()
+            .ok_or_else(|| CliError::Failed(String::from("カレンダーがありません")))
+    }
+
+    fn holls_mut(&mut self, rule: i64, weekday: usize, time: ShiftTimeArg) -> CliResult<&mut Vec<JsonAssignment>> {
+        let rule = to_index("ルール", rule, self.config.rules.len())?;
+        let day = self.config.rules[rule].schedule.days_mut().into_iter().nth(weekday)
+            .ok_or_else(|| CliError::Usage(format!("曜日の指定が正しくありません: {}", weekday)))?;
+        Ok(match time {
+            ShiftTimeArg::Morning => &mut day.m,
+            ShiftTimeArg::Afternoon => &mut day.a,
+        })
+    }
+}
+
+impl Backend for JsonBackend {
+    fn show(&self) -> CliResult<()> {
+        println!("グループ");
+        for (group_index, group) in self.config.staff_groups.iter().enumerate() {
+            println!("  {}: {}", group_index, group.name);
+            for (index, slot) in group.slots.iter().enumerate() {
+                println!("    [{}] {}", index, slot.name);
+            }
+        }
+
+        println!();
+        println!("ルール");
+        for (rule_index, rule) in self.config.rules.iter().enumerate() {
+            println!("  {}: {}", rule_index, rule.name);
+            for (weekday, day) in rule.schedule.days().iter().enumerate() {
+                for (afternoon, holls) in [(false, &day.m), (true, &day.a)] {
+                    for (position, holl) in holls.iter().enumerate() {
+                        let group_name = self
+                            .config
+                            .staff_groups
+                            .get(holl.staff_group_id as usize)
+                            .map_or("?", |g| g.name.as_str());
+                        println!(
+                            "    {} {} [{}] グループ {} ({}) の [{}]",
+                            render::weekday_label(weekday),
+                            render::shift_time_label(afternoon),
+                            position,
+                            holl.staff_group_id,
+                            group_name,
+                            holl.shift_staff_index,
+                        );
+                    }
+                }
+            }
+        }
+
+        if self.calendar_path.is_some() {
+            println!();
+            match &self.calendar {
+                Some(calendar) => println!(
+                    "カレンダー: base_abs_week {}  initial_delta {}  {} 週",
+                    calendar.base_abs_week,
+                    calendar.initial_delta,
+                    calendar.timeline.len(),
+                ),
+                None => println!("カレンダー: (未作成)"),
+            }
+        }
+        Ok(())
+    }
+
+    fn group(&mut self, command: GroupCommand) -> CliResult<()> {
+        let groups = &mut self.config.staff_groups;
+        match command {
+            GroupCommand::Add { name } => {
+                groups.push(JsonStaffGroup { name, slots: Vec::new(), min_per_period: None });
+                println!("{}", groups.len() - 1);
+            }
+            GroupCommand::Rename { group, name } => {
+                let index = to_index("グループ", group, groups.len())?;
+                groups[index].name = name;
+            }
+            GroupCommand::Delete { group } => {
+                let index = to_index("グループ", group, groups.len())?;
+                // デスクトップ版の外部キーと同じく、参照されているグループは消さない
+                let used_by: Vec<&str> = self
+                    .config
+                    .rules
+                    .iter()
+                    .filter(|r| r.schedule.days().iter().any(|d| d
+                        .m
+                        .iter()
+                        .chain(d.a.iter())
+                        .any(|h| h.staff_group_id as usize == index)))
+                    .map(|r| r.name.as_str())
+                    .collect();
+                if !used_by.is_empty() {
+                    return Err(CliError::Failed(format!(
+                        "グループ {} はルール ({}) から参照されているため削除できません",
+                        index, used_by.join(", ")
+                    )));
+                }
+
+                self.config.staff_groups.remove(index);
+                // 後ろのグループは位置が1つずれる
+                for rule in self.config.rules.iter_mut() {
+                    for day in rule.schedule.days_mut() {
+                        for holl in day.m.iter_mut().chain(day.a.iter_mut()) {
+                            if holl.staff_group_id as usize > index {
+                                holl.staff_group_id -= 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self.save_config()
+    }
+
+    fn member(&mut self, command: MemberCommand) -> CliResult<()> {
+        let groups = &mut self.config.staff_groups;
+        match command {
+            MemberCommand::Add { group, name } => {
+                let group = to_index("グループ", group, groups.len())?;
+                groups[group].slots.push(JsonSlot { name });
+            }
+            MemberCommand::Rename { group, index, name } => {
+                let group = to_index("グループ", group, groups.len())?;
+                let slots = &mut groups[group].slots;
+                let index = to_index("メンバー", index as i64, slots.len())?;
+                slots[index].name = name;
+            }
+            MemberCommand::Delete { group, index } => {
+                let group = to_index("グループ", group, groups.len())?;
+                let slots = &mut groups[group].slots;
+                let index = to_index("メンバー", index as i64, slots.len())?;
+                slots.remove(index);
+            }
+        }
+        self.save_config()
+    }
+
+    fn rule(&mut self, command: RuleCommand) -> CliResult<()> {
+        let rules = &mut self.config.rules;
+        match command {
+            RuleCommand::Add { name } => {
+                rules.push(JsonRule { name, schedule: Default::default() });
+                println!("{}", rules.len() - 1);
+            }
+            RuleCommand::Rename { rule, name } => {
+                let index = to_index("ルール", rule, rules.len())?;
+                rules[index].name = name;
+            }
+            RuleCommand::Delete { rule, reassign, skip } => {
+                if reassign.is_some() || skip {
+                    return Err(CliError::Usage(String::from(
+                        "--reassign / --skip は --db を指定したときだけ使えます"
+                    )));
+                }
+                let index = to_index("ルール", rule, rules.len())?;
+                rules.remove(index);
+            }
+        }
+        self.save_config()
+    }
+
+    fn assign(&mut self, command: AssignCommand) -> CliResult<()> {
+        match command {
+            AssignCommand::Add { rule, weekday, time, group, index } => {
+                let group = to_index("グループ", group, self.config.staff_groups.len())?;
+                let slots = self.config.staff_groups[group].slots.len();
+                let index = to_index("メンバー", index as i64, slots)?;
+                self.holls_mut(rule, weekday.index(), time)?.push(JsonAssignment {
+                    staff_group_id: group as u32,
+                    shift_staff_index: index as u32,
+                });
+            }
+            AssignCommand::Delete { rule, weekday, time, position } => {
+                let holls = self.holls_mut(rule, weekday.index(), time)?;
+                let position = to_index("アサイン", position as i64, holls.len())?;
+                holls.remove(position);
+            }
+        }
+        self.save_config()
+    }
+
+    fn timeline(&mut self, command: TimelineCommand) -> CliResult<()> {
+        match command {
+            TimelineCommand::Show => {
+                let calendar = self.calendar_ref()?;
+                let labels: Vec<String> = calendar
+                    .timeline
+                    .iter()
+                    .map(|week| render::status_label(Some(week), |_| None))
+                    .collect();
+                render::print_timeline(calendar.base_abs_week, calendar.initial_delta, &labels);
+                return Ok(());
+            }
+            TimelineCommand::Create { base, initial_delta } => {
+                if self.calendar.is_some() {
+                    return Err(CliError::Failed(String::from("カレンダーはすでに存在します")));
+                }
+                self.calendar = Some(ShiftCalendarManager::new(parse_week(&base)?, initial_delta));
+            }
+            TimelineCommand::Append { start, statuses } => {
+                let start_abs_week = parse_week(&start)?;
+                let weeks = statuses
+                    .iter()
+                    .map(|token| match token.as_str() {
+                        "active" | "a" => Ok(Some(())),
+                        "skip" | "-" => Ok(None),
+                        other => Err(CliError::Usage(format!(
+                            "週の指定は active か skip です: {}", other
+                        ))),
+                    })
+                    .collect::<CliResult<Vec<_>>>()?;
+                self.calendar_ref()?;
+                if let Some(calendar) = self.calendar.as_mut() {
+                    calendar
+                        .apply_weeks(start_abs_week, &weeks)
+                        .map_err(|e| CliError::Failed(e.to_string()))?;
+                }
+            }
+            TimelineCommand::Truncate { from } => {
+                let from_abs_week = parse_week(&from)?;
+                self.calendar_ref()?;
+                if let Some(calendar) = self.calendar.as_mut() {
+                    calendar.truncate_from(from_abs_week);
+                }
+            }
+            TimelineCommand::Rebase { base } => {
+                let base_abs_week = parse_week(&base)?;
+                self.calendar_ref()?;
+                if let Some(calendar) = self.calendar.as_mut() {
+                    calendar
+                        .rebase(base_abs_week)
+                        .map_err(|e| CliError::Failed(e.to_string()))?;
+                }
+            }
+        }
+        self.save_calendar()
+    }
+
+    /// web版と同じく、全ルールを1つのテーブルとして導出する
+    fn month(&self, year_month: YearMonth) -> CliResult<Vec<WeekView>> {
+        let calendar = self.calendar_ref()?;
+        let first_abs_week = year_month.first_abs_week()?;
+        let range = shift_manager_tauri_lib::application::time::calculate_weeks_in_month(
+            year_month.year, year_month.month
+        ) as usize;
+
+        let staff_group_list = self.config.to_staff_group_list();
+        let week_rule_table = self.config.to_week_rule_table();
+        let has_rules = !self.config.rules.is_empty();
+        let limits = self.config.coverage_limits();
+
+        Ok((0..range)
+            .map(|offset| {
+                let abs_week = first_abs_week + offset;
+                let status = calendar.slice_by_abs(abs_week, 1).first();
+                let week = calendar
+                    .derive_shift(|_| has_rules.then_some(&week_rule_table), &staff_group_list, abs_week, 1)
+                    .into_iter()
+                    .next()
+                    .flatten();
+                let warnings = week
+                    .as_ref()
+                    .map(|w| check_week(w, &limits).iter().map(|warning| warning.to_string()).collect())
+                    .unwrap_or_default();
+                let days = week.map(|week| week
+                    .0
+                    .iter()
+                    .map(|day| DayView {
+                        morning: day.shift_morning.iter().map(|s| s.name.clone()).collect(),
+                        afternoon: day.shift_afternoon.iter().map(|s| s.name.clone()).collect(),
+                    })
+                    .collect());
+                WeekView { abs_week, label: render::status_label(status, |_| None), days, warnings }
+            })
+            .collect())
+    }
+
+    fn validate(&self) -> CliResult<Vec<String>> {
+        let mut issues = check_config(&self.config);
+        if let Some(calendar) = &self.calendar {
+            issues.extend(check_timeline(calendar));
+        }
+        Ok(issues)
+    }
+
+    fn export(&self) -> CliResult<(JsonConfig, Option<ExchangeCalendar>)> {
+        let calendar = self.calendar.as_ref().map(|c| ShiftCalendarManager {
+            base_abs_week: c.base_abs_week,
+            initial_delta: c.initial_delta,
+            timeline: c
+                .timeline
+                .iter()
+                .map(|week| match week {
+                    WeekStatus::Active { logical_delta, .. } =>
+                        WeekStatus::Active { logical_delta: *logical_delta, payload: None },
+                    WeekStatus::Skipped => WeekStatus::Skipped,
+                })
+                .collect(),
+            locked_weeks: c.locked_weeks.clone(),
+        });
+        Ok((self.config.clone(), calendar))
+    }
+
+    /// --config / --calendar の内容を置き換える
+    /// (rule_index はweb版では使わないので落とす)
+    fn import(
+        &mut self,
+        config: JsonConfig,
+        calendar: Option<ExchangeCalendar>,
+        _name: String,
+        _rule: Option<usize>,
+    ) -> CliResult<()> {
+        let mut issues = check_config(&config);
+        if let Some(calendar) = &calendar {
+            issues.extend(check_timeline(calendar));
+        }
+        if !issues.is_empty() {
+            return Err(CliError::Invalid(issues));
+        }
+
+        if self.config_path.is_some() {
+            self.config = config;
+            self.save_config()?;
+        }
+        if let Some(calendar) = calendar {
+            self.calendar = Some(ShiftCalendarManager {
+                base_abs_week: calendar.base_abs_week,
+                initial_delta: calendar.initial_delta,
+                timeline: calendar
+                    .timeline
+                    .into_iter()
+                    .map(|week| match week {
+                        WeekStatus::Active { logical_delta, .. } =>
+                            WeekStatus::Active { logical_delta, payload: () },
+                        WeekStatus::Skipped => WeekStatus::Skipped,
+                    })
+                    .collect(),
+                locked_weeks: calendar.locked_weeks,
+            });
+            self.save_calendar()?;
+        }
+        Ok(())
+    }
+}
@@ -1,24 +1,162 @@
-use clap::{Parser, Subcommand};
-use std::fs;
+//! wasm-shift-manager / shift-manager-tauri のデータを操作する管理用CLI
+//!
+//! 操作対象は次のどちらか
+//! - `--db app.db [--plan ID]` : デスクトップ版のSQLite
+//! - `--config config.json --calendar calendar.json` : web版のJSON
+//!
+//! グループ・ルールは、SQLiteではID、JSONでは先頭からの位置 (0始まり) で指定する
+//! メンバーはどちらもグループ内の位置で指定する (アサインの member index と同じ)
+//!
+//! 終了コードは `error::CliError` を参照
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
+use std::process::ExitCode;
 
-use component_features::shift_calendar_manager::{
-    ShiftCalendarManager, 
+use component_features::load_rules::CONFIG_SCHEMA;
+use shift_timeline::{
+    CALENDAR_SCHEMA,
+    ShiftCalendarManager,
     WeekStatus
 };
 
+mod db_backend;
+mod error;
+mod exchange;
+mod json_backend;
+mod render;
+mod week;
+
+use db_backend::DbBackend;
+use error::{CliError, CliResult};
+use exchange::{ExchangeCalendar, read_json, to_pretty_json, write_json};
+use json_backend::JsonBackend;
+use render::WeekView;
+use week::{YearMonth, parse_month};
+
 // 引数を構造体として定義します
 #[derive(Parser)]
 #[command(name = "shift_tools")]
 #[command(version = "0.1.0")]
 #[command(about = "wasm-shift-managerに関わるデータの操作をします", long_about = None)]
 struct Cli {
+    #[command(flatten)]
+    source: SourceArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// 操作対象のデータ
+#[derive(Args)]
+struct SourceArgs {
+    /// デスクトップ版のSQLiteファイル (app.db)
+    #[arg(long, global = true, conflicts_with_all = ["config", "calendar"])]
+    db: Option<PathBuf>,
+
+    /// 操作するプランのID (--db のみ。プランが1つだけなら省略できる)
+    #[arg(long, global = true)]
+    plan: Option<i64>,
+
+    /// web版のルール設定JSON (load-config-from-json の形式)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// web版のカレンダーJSON (output-calendar-manager-data の形式)
+    #[arg(long, global = true)]
+    calendar: Option<PathBuf>,
+}
+
 #[derive(Subcommand)]
 enum Commands {
+    /// プランの一覧・作成・名前変更・削除 (--db のみ)
+    Plan {
+        #[command(subcommand)]
+        command: PlanCommand,
+    },
+
+    /// グループ・メンバー・ルール・アサイン・カレンダーの概要を表示する
+    Show,
+
+    /// スタッフグループの編集
+    Group {
+        #[command(subcommand)]
+        command: GroupCommand,
+    },
+
+    /// メンバー (スロット) の編集
+    Member {
+        #[command(subcommand)]
+        command: MemberCommand,
+    },
+
+    /// 週ルールの編集
+    Rule {
+        #[command(subcommand)]
+        command: RuleCommand,
+    },
+
+    /// 週ルールのアサイン (シフトホール) の編集
+    Assign {
+        #[command(subcommand)]
+        command: AssignCommand,
+    },
+
+    /// タイムラインの表示・作成・追記・切り詰め・基準週の付け替え
+    Timeline {
+        #[command(subcommand)]
+        command: TimelineCommand,
+    },
+
+    /// 1か月分のシフトを導出して表示する
+    Month {
+        /// YYYY-MM (月は 1-12)
+        month: String,
+    },
+
+    /// 1か月分のシフト表を印刷用の HTML に書き出す (--db のみ)
+    Print {
+        /// YYYY-MM (月は 1-12)
+        month: String,
+
+        /// 出力先 (拡張子は .html。拡張子なしでも HTML)
+        out: PathBuf,
+
+        /// このグループ (ID) の人だけの表にする
+        #[arg(long)]
+        group: Option<i64>,
+    },
+
+    /// 参照切れや logical_delta の採番崩れを検査する
+    Validate,
+
+    /// web版のJSON形式で書き出す
+    Export {
+        /// ルール設定の出力先
+        config_out: PathBuf,
+
+        /// カレンダーの出力先
+        calendar_out: Option<PathBuf>,
+    },
+
+    /// web版のJSON形式から読み込む
+    /// --db の場合は新しいプランを作成し、JSONの場合は --config/--calendar を置き換える
+    Import {
+        /// 読み込むルール設定
+        config_file: PathBuf,
+
+        /// 読み込むカレンダー
+        calendar_file: Option<PathBuf>,
+
+        /// 作成するプラン名 (--db のみ。省略時はファイル名)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// rule_index を持たない週に割り当てるルールの位置 (--db のみ)
+        #[arg(long)]
+        rule: Option<usize>,
+    },
+
     /// 指定したinit_deltaに変更します
     ChangeDelta {
         /// カレンダーデータファイル
@@ -33,55 +171,273 @@ enum Commands {
     }
 }
 
-fn change_delta(file: PathBuf, init_delta: usize, out: Option<PathBuf>) {
-    // 'count' コマンドが選ばれたときの処理
-    match fs::read_to_string(&file) {
-        Ok(text) => {
-            if let Ok(shift_calendar_manager) = serde_json::from_str::<ShiftCalendarManager>(&text) {
-                let mut timeline = Vec::new();
-                let mut counter = init_delta;
-                for week_status in shift_calendar_manager.timeline{
-                    if let WeekStatus::Active { logical_delta:_ } = week_status {
-                        timeline.push(WeekStatus::Active { logical_delta:counter });
-                        counter += 1;
+#[derive(Subcommand)]
+pub enum PlanCommand {
+    List,
+    Create { name: String },
+    Rename { plan: i64, name: String },
+    Delete { plan: i64 },
+}
+
+#[derive(Subcommand)]
+pub enum GroupCommand {
+    Add { name: String },
+    Rename { group: i64, name: String },
+    /// アサインから参照されているグループは削除できない
+    Delete { group: i64 },
+}
+
+#[derive(Subcommand)]
+pub enum MemberCommand {
+    Add { group: i64, name: String },
+    Rename { group: i64, index: usize, name: String },
+    Delete { group: i64, index: usize },
+}
+
+#[derive(Subcommand)]
+pub enum RuleCommand {
+    Add { name: String },
+    Rename { rule: i64, name: String },
+    /// タイムラインで使われているルールは、--reassign か --skip を指定しないと削除できない
+    /// (--db のみ。書き換えた週の月曜を表示する)
+    Delete {
+        rule: i64,
+
+        /// 使っている週をこのルールに付け替える (ローテーションの位置は変わらない)
+        #[arg(long, conflicts_with = "skip")]
+        reassign: Option<i64>,
+
+        /// 使っている週をSkippedにする (後ろの週のローテーションが詰まる)
+        #[arg(long)]
+        skip: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AssignCommand {
+    /// ルールの 曜日・時間帯 に グループ内 index 番目のスタッフ を割り当てる
+    Add {
+        rule: i64,
+        weekday: WeekdayArg,
+        time: ShiftTimeArg,
+        group: i64,
+        index: usize,
+    },
+    /// 曜日・時間帯 の中で position 番目のアサインを削除する (show の表示順)
+    Delete {
+        rule: i64,
+        weekday: WeekdayArg,
+        time: ShiftTimeArg,
+        position: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TimelineCommand {
+    Show,
+    /// カレンダーを新規作成する
+    Create {
+        /// 基準週 (絶対週 / YYYY-MM / YYYY-MM-DD)
+        base: String,
+
+        #[arg(long, default_value_t = 0)]
+        initial_delta: usize,
+    },
+    /// start の週から順に追記する
+    /// 週の指定は `skip` (または `-`) と、--db ではルールID、JSONでは `active`
+    Append {
+        /// 開始週 (絶対週 / YYYY-MM / YYYY-MM-DD)
+        start: String,
+
+        #[arg(required = true)]
+        statuses: Vec<String>,
+    },
+    /// 基準週 (base_abs_week) を付け替える
+    /// 後ろへ動かすと手前の週を捨てて initial_delta に繰り込み、前へ動かすとSkippedで埋める
+    Rebase {
+        /// 新しい基準週 (絶対週 / YYYY-MM / YYYY-MM-DD)
+        base: String,
+    },
+    /// 指定した週以降を削除する
+    Truncate {
+        /// 絶対週 / YYYY-MM / YYYY-MM-DD
+        from: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum WeekdayArg {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl WeekdayArg {
+    /// 月曜 = 0
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ShiftTimeArg {
+    #[value(alias = "m")]
+    Morning,
+    #[value(alias = "a")]
+    Afternoon,
+}
+
+/// --db と JSON で同じ操作を提供する
+pub trait Backend {
+    fn show(&self) -> CliResult<()>;
+    fn group(&mut self, command: GroupCommand) -> CliResult<()>;
+    fn member(&mut self, command: MemberCommand) -> CliResult<()>;
+    fn rule(&mut self, command: RuleCommand) -> CliResult<()>;
+    fn assign(&mut self, command: AssignCommand) -> CliResult<()>;
+    fn timeline(&mut self, command: TimelineCommand) -> CliResult<()>;
+    fn month(&self, year_month: YearMonth) -> CliResult<Vec<WeekView>>;
+    /// 見つかった問題の一覧 (空なら問題なし)
+    fn validate(&self) -> CliResult<Vec<String>>;
+    fn export(&self) -> CliResult<(component_features::load_rules::JsonConfig, Option<ExchangeCalendar>)>;
+    fn import(
+        &mut self,
+        config: component_features::load_rules::JsonConfig,
+        calendar: Option<ExchangeCalendar>,
+        name: String,
+        rule: Option<usize>,
+    ) -> CliResult<()>;
+}
+
+fn open_backend(source: SourceArgs) -> CliResult<Box<dyn Backend>> {
+    match source.db {
+        Some(db) => Ok(Box::new(DbBackend::open(&db, source.plan)?)),
+        None if source.config.is_some() || source.calendar.is_some() => {
+            if source.plan.is_some() {
+                return Err(CliError::Usage(String::from("--plan は --db と一緒に指定してください")));
+            }
+            Ok(Box::new(JsonBackend::open(source.config, source.calendar)?))
+        }
+        None => Err(CliError::Usage(String::from(
+            "--db または --config/--calendar で操作対象を指定してください"
+        ))),
+    }
+}
+
+fn change_delta(file: PathBuf, init_delta: usize, out: Option<PathBuf>) -> CliResult<()> {
+    // rule_index を持つカレンダーでもそのまま保つ
+    let shift_calendar_manager: ExchangeCalendar = read_json(&file, &CALENDAR_SCHEMA)?;
+
+    let mut timeline = Vec::new();
+    let mut counter = init_delta;
+    for week_status in shift_calendar_manager.timeline{
+        if let WeekStatus::Active { logical_delta:_, payload } = week_status {
+            timeline.push(WeekStatus::Active { logical_delta:counter, payload });
+            counter += 1;
+        } else {
+            timeline.push(WeekStatus::Skipped);
+        }
+    }
+
+    let return_shift_manager_data = ShiftCalendarManager {
+        base_abs_week: shift_calendar_manager.base_abs_week,
+        initial_delta: init_delta,
+        timeline,
+        locked_weeks: shift_calendar_manager.locked_weeks,
+    };
+
+    if let Some(path) = out {
+        write_json(&path, &CALENDAR_SCHEMA, &return_shift_manager_data)
+    } else {
+        println!("{}", to_pretty_json(&CALENDAR_SCHEMA, &return_shift_manager_data)?);
+        Ok(())
+    }
+}
+
+fn run(args: Cli) -> CliResult<()> {
+    // 3. パターンマッチで分岐処理
+    match args.command {
+        Commands::ChangeDelta { file, init_delta, out } => {
+            change_delta(file, init_delta, out)
+        }
+        Commands::Plan { command } => {
+            let db = args.source.db.ok_or_else(|| CliError::Usage(
+                String::from("plan は --db を指定したときだけ使えます")
+            ))?;
+            DbBackend::open(&db, None)?.plan(command)
+        }
+        Commands::Print { month, out, group } => {
+            let db = args.source.db.ok_or_else(|| CliError::Usage(
+                String::from("print は --db を指定したときだけ使えます")
+            ))?;
+            let year_month = parse_month(&month)?;
+            DbBackend::open(&db, args.source.plan)?.print(year_month, group, &out)
+        }
+        command => {
+            let mut backend = open_backend(args.source)?;
+            match command {
+                Commands::Show => backend.show(),
+                Commands::Group { command } => backend.group(command),
+                Commands::Member { command } => backend.member(command),
+                Commands::Rule { command } => backend.rule(command),
+                Commands::Assign { command } => backend.assign(command),
+                Commands::Timeline { command } => backend.timeline(command),
+                Commands::Month { month } => {
+                    let year_month = parse_month(&month)?;
+                    let weeks = backend.month(year_month)?;
+                    render::print_month(year_month, &weeks);
+                    Ok(())
+                }
+                Commands::Validate => {
+                    let issues = backend.validate()?;
+                    if issues.is_empty() {
+                        println!("問題は見つかりませんでした");
+                        Ok(())
                     } else {
-                        timeline.push(WeekStatus::Skipped);
+                        Err(CliError::Invalid(issues))
                     }
                 }
-
-                let return_shift_manager_data = ShiftCalendarManager {
-                    base_abs_week: shift_calendar_manager.base_abs_week,
-                    initial_delta: init_delta,
-                    timeline
-                };
-
-                if let Some(path) = out {
-                    if let Err(_e) = fs::write(
-                        path,
-                        serde_json::to_string(&return_shift_manager_data).unwrap()
-                    ) {
-                        eprintln!("ファイルの書き込みに失敗しました");
+                Commands::Export { config_out, calendar_out } => {
+                    let (config, calendar) = backend.export()?;
+                    write_json(&config_out, &CONFIG_SCHEMA, &config)?;
+                    match (calendar_out, calendar) {
+                        (Some(path), Some(calendar)) => write_json(&path, &CALENDAR_SCHEMA, &calendar),
+                        (Some(_), None) => Err(CliError::Failed(String::from("カレンダーがありません"))),
+                        (None, _) => Ok(()),
                     }
-                } else {
-                    println!("{:?}", return_shift_manager_data);
                 }
-            } else {
-                eprintln!("ファイルが形式に沿っていません");
+                Commands::Import { config_file, calendar_file, name, rule } => {
+                    let config = read_json(&config_file, &CONFIG_SCHEMA)?;
+                    let calendar = calendar_file
+                        .as_deref()
+                        .map(|path| read_json(path, &CALENDAR_SCHEMA))
+                        .transpose()?;
+                    let name = name.unwrap_or_else(|| config_file
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default());
+                    backend.import(config, calendar, name, rule)
+                }
+                Commands::ChangeDelta { .. } | Commands::Plan { .. } | Commands::Print { .. } => unreachable!(),
             }
         }
-        Err(e) => {
-            eprintln!("エラー: ファイル '{}' を読めませんでした: {}", file.display(), e);
-        }
     }
 }
 
-fn main() {
+fn main() -> ExitCode {
     let args = Cli::parse();
 
-    // 3. パターンマッチで分岐処理
-    match args.command {
-        Commands::ChangeDelta { file, init_delta, out } => {
-            change_delta(file, init_delta, out);
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprint!("{}", e);
+            if !matches!(e, CliError::Invalid(_)) {
+                eprintln!();
+            }
+            e.exit_code()
         }
     }
 }
@@ -0,0 +1,99 @@
+// 端末向けの整形出力
+use chrono::Duration;
+
+use shift_timeline::{AbsWeek, WeekStatus};
+
+use crate::week::{monday_of, YearMonth};
+
+const WEEKDAY_LABELS: [&str; 7] = ["月", "火", "水", "木", "金", "土", "日"];
+
+/// 1日分のシフト (名前だけ)
+pub struct DayView {
+    pub morning: Vec<String>,
+    pub afternoon: Vec<String>,
+}
+
+/// 1週分の表示データ
+pub struct WeekView {
+    pub abs_week: AbsWeek,
+    /// 例: `Active Δ3 (標準ルール)` / `Skipped` / `(未生成)`
+    pub label: String,
+    /// Activeな週だけ Some (月〜日の7要素)
+    pub days: Option<Vec<DayView>>,
+    /// 人員チェックの警告 (表示用の文字列)
+    pub warnings: Vec<String>,
+}
+
+/// タイムラインの1週分の状態を表示用の文字列にする
+/// `rule_label` は payload からルール名などを作る関数
+pub fn status_label<P>(status: Option<&WeekStatus<P>>, rule_label: impl Fn(&P) -> Option<String>) -> String {
+    match status {
+        Some(WeekStatus::Active { logical_delta, payload }) => match rule_label(payload) {
+            Some(rule) => format!("Active Δ{} ({})", logical_delta, rule),
+            None => format!("Active Δ{}", logical_delta),
+        },
+        Some(WeekStatus::Skipped) => String::from("Skipped"),
+        None => String::from("(未生成)"),
+    }
+}
+
+fn week_heading(abs_week: AbsWeek) -> String {
+    let monday = monday_of(abs_week);
+    let sunday = monday + Duration::days(6);
+    format!("[{}] {} 〜 {}", abs_week, monday.format("%Y-%m-%d"), sunday.format("%m-%d"))
+}
+
+fn names(list: &[String]) -> String {
+    if list.is_empty() {
+        String::from("-")
+    } else {
+        list.join(", ")
+    }
+}
+
+/// month サブコマンドの出力
+pub fn print_month(year_month: YearMonth, weeks: &[WeekView]) {
+    match (weeks.first(), weeks.last()) {
+        (Some(first), Some(last)) =>
+            println!("{}  (絶対週 {} 〜 {})", year_month, first.abs_week, last.abs_week),
+        _ => println!("{}", year_month),
+    }
+
+    for week in weeks {
+        println!();
+        println!("{}  {}", week_heading(week.abs_week), week.label);
+
+        if let Some(days) = &week.days {
+            let monday = monday_of(week.abs_week);
+            for (offset, day) in days.iter().enumerate() {
+                let date = monday + Duration::days(offset as i64);
+                println!(
+                    "  {} {}  午前: {:<16} 午後: {}",
+                    WEEKDAY_LABELS[offset],
+                    date.format("%m/%d"),
+                    names(&day.morning),
+                    names(&day.afternoon),
+                );
+            }
+        }
+        for warning in &week.warnings {
+            println!("  ! {}", warning);
+        }
+    }
+}
+
+/// timeline show の出力
+pub fn print_timeline(base_abs_week: AbsWeek, initial_delta: usize, labels: &[String]) {
+    println!("base_abs_week: {}  initial_delta: {}  週数: {}", base_abs_week, initial_delta, labels.len());
+    for (offset, label) in labels.iter().enumerate() {
+        println!("{}  {}", week_heading(base_abs_week + offset), label);
+    }
+}
+
+pub fn weekday_label(index: usize) -> &'static str {
+    WEEKDAY_LABELS[index]
+}
+
+pub fn shift_time_label(afternoon: bool) -> &'static str {
+    if afternoon { "午後" } else { "午前" }
+}
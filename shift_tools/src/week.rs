@@ -0,0 +1,70 @@
+// 絶対週・年月の指定を解釈するヘルパー
+use chrono::{Duration, NaiveDate};
+
+use shift_manager_tauri_lib::application::time::calculate_abs_week;
+use shift_timeline::AbsWeek;
+
+use crate::error::{CliError, CliResult};
+
+/// 年月 (month は 0-11)
+#[derive(Debug, Clone, Copy)]
+pub struct YearMonth {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl YearMonth {
+    /// その月の1日を含む絶対週
+    pub fn first_abs_week(&self) -> CliResult<AbsWeek> {
+        calculate_abs_week(self.year, self.month, 1)
+            .ok_or_else(|| CliError::Usage(format!("{} は扱えない年月です", self)))
+    }
+}
+
+impl std::fmt::Display for YearMonth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}年{}月", self.year, self.month + 1)
+    }
+}
+
+/// `YYYY-MM` (月は 1-12)
+pub fn parse_month(text: &str) -> CliResult<YearMonth> {
+    let (year, month) = text
+        .split_once('-')
+        .and_then(|(y, m)| Some((y.parse::<i32>().ok()?, m.parse::<u32>().ok()?)))
+        .filter(|(_, m)| (1..=12).contains(m))
+        .ok_or_else(|| CliError::Usage(format!("年月は YYYY-MM で指定してください: {}", text)))?;
+
+    Ok(YearMonth { year, month: month - 1 })
+}
+
+/// 週の指定を絶対週に変換する
+/// - `2944`       : 絶対週そのもの
+/// - `2026-06`    : その月の1日を含む週
+/// - `2026-06-10` : その日を含む週
+pub fn parse_week(text: &str) -> CliResult<AbsWeek> {
+    if let Ok(abs_week) = text.parse::<AbsWeek>() {
+        return Ok(abs_week);
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        // 1970年以前のシフトには対応しない
+        let days = (date - week_base()).num_days();
+        if days < 0 {
+            return Err(CliError::Usage(format!("{} は扱えない日付です", text)));
+        }
+        return Ok((days / 7) as AbsWeek);
+    }
+
+    parse_month(text)?.first_abs_week()
+}
+
+/// 絶対週0の月曜日 (1969/12/29)
+fn week_base() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1969, 12, 29).unwrap() /* safe unwrap */
+}
+
+/// 絶対週の月曜日
+pub fn monday_of(abs_week: AbsWeek) -> NaiveDate {
+    week_base() + Duration::weeks(abs_week as i64)
+}
@@ -0,0 +1,232 @@
+// shift_tools をコマンドとして実行し、出力と終了コードを確認する
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// テストごとの作業ディレクトリ
+fn work_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("shift_tools_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_shift_tools"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn run_ok(dir: &PathBuf, args: &[&str]) -> String {
+    let output = run(dir, args);
+    assert!(
+        output.status.success(),
+        "{:?} failed: {}", args, String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// JSONのルール設定とカレンダーを作る
+fn build_json(dir: &PathBuf) {
+    let json = ["--config", "config.json", "--calendar", "calendar.json"];
+    run_ok(dir, &[&json[..], &["group", "add", "Group A"]].concat());
+    run_ok(dir, &[&json[..], &["member", "add", "0", "Tanaka"]].concat());
+    run_ok(dir, &[&json[..], &["member", "add", "0", "Suzuki"]].concat());
+    run_ok(dir, &[&json[..], &["rule", "add", "Standard"]].concat());
+    run_ok(dir, &[&json[..], &["assign", "add", "0", "mon", "morning", "0", "1"]].concat());
+    run_ok(dir, &[&json[..], &["timeline", "create", "2026-06"]].concat());
+    run_ok(dir, &[&json[..], &["timeline", "append", "2026-06", "active", "skip", "active"]].concat());
+}
+
+#[test]
+fn test_json_edit_and_month() {
+    let dir = work_dir("json");
+    build_json(&dir);
+
+    let json = ["--config", "config.json", "--calendar", "calendar.json"];
+    let month = run_ok(&dir, &[&json[..], &["month", "2026-06"]].concat());
+    assert!(month.contains("[2944] 2026-06-01"));
+    assert!(month.contains("Skipped"));
+    assert!(month.contains("(未生成)"));
+    // 月曜午前以外は空いている
+    assert!(month.contains("  ! 月曜 午後: 誰も入っていません"));
+
+    // 参照されているグループは消せない
+    let output = run(&dir, &[&json[..], &["group", "delete", "0"]].concat());
+    assert_eq!(output.status.code(), Some(1));
+
+    // 2週目以降を切り詰める
+    run_ok(&dir, &[&json[..], &["timeline", "truncate", "2945"]].concat());
+    let timeline = run_ok(&dir, &[&json[..], &["timeline", "show"]].concat());
+    assert!(timeline.contains("週数: 1"));
+}
+
+#[test]
+fn test_json_rebase() {
+    let dir = work_dir("rebase");
+    build_json(&dir);
+
+    let json = ["--config", "config.json", "--calendar", "calendar.json"];
+    let before = run_ok(&dir, &[&json[..], &["month", "2026-06"]].concat());
+
+    // 先頭2週を捨てても、残った週の導出結果は変わらない
+    run_ok(&dir, &[&json[..], &["timeline", "rebase", "2946"]].concat());
+    let timeline = run_ok(&dir, &[&json[..], &["timeline", "show"]].concat());
+    assert!(timeline.contains("base_abs_week: 2946  initial_delta: 1  週数: 1"));
+    let after = run_ok(&dir, &[&json[..], &["month", "2026-06"]].concat());
+    let tail = |text: &str| text[text.find("[2946]").unwrap()..].to_string();
+    assert_eq!(tail(&before), tail(&after));
+
+    // 前へ戻すとSkippedで埋まる
+    run_ok(&dir, &[&json[..], &["timeline", "rebase", "2944"]].concat());
+    run_ok(&dir, &[&json[..], &["validate"]].concat());
+
+    // 末尾より後ろへは動かせない
+    let output = run(&dir, &[&json[..], &["timeline", "rebase", "2950"]].concat());
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_exit_codes() {
+    let dir = work_dir("exit_codes");
+
+    // 操作対象の指定なし
+    assert_eq!(run(&dir, &["show"]).status.code(), Some(2));
+    // 読めないファイル
+    assert_eq!(run(&dir, &["change-delta", "missing.json", "-i", "0"]).status.code(), Some(3));
+
+    // logical_delta の採番が崩れている
+    fs::write(
+        dir.join("calendar.json"),
+        r#"{"baseAbsWeek":10,"initialDelta":0,"timeline":[{"Active":{"logical_delta":0}},"Skipped",{"Active":{"logical_delta":5}}]}"#,
+    ).unwrap();
+    let output = run(&dir, &["--calendar", "calendar.json", "validate"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("絶対週 12"));
+
+    // change-delta で採番し直すと通る
+    run_ok(&dir, &["change-delta", "calendar.json", "-i", "0", "-o", "fixed.json"]);
+    run_ok(&dir, &["--calendar", "fixed.json", "validate"]);
+
+    // 知らないバージョンのファイルは読まない
+    fs::write(
+        dir.join("future.json"),
+        r#"{"format":"shift-calendar","version":99,"data":{}}"#,
+    ).unwrap();
+    let output = run(&dir, &["--calendar", "future.json", "validate"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("バージョン 99"));
+}
+
+#[test]
+fn test_db_import_export_round_trip() {
+    let dir = work_dir("db");
+    build_json(&dir);
+
+    let plan_id = run_ok(&dir, &["--db", "app.db", "import", "config.json", "calendar.json", "--name", "Imported"]);
+    assert_eq!(plan_id.trim(), "1");
+
+    let timeline = run_ok(&dir, &["--db", "app.db", "timeline", "show"]);
+    assert!(timeline.contains("Active Δ0 (Standard)"));
+
+    run_ok(&dir, &["--db", "app.db", "export", "config_out.json", "calendar_out.json"]);
+    assert_eq!(
+        fs::read_to_string(dir.join("config.json")).unwrap(),
+        fs::read_to_string(dir.join("config_out.json")).unwrap(),
+    );
+    let calendar = fs::read_to_string(dir.join("calendar_out.json")).unwrap();
+    assert!(calendar.contains("\"format\": \"shift-calendar\""));
+    assert!(calendar.contains("\"rule_index\": 0"));
+
+    // 2つ目のプランを作ると --plan が必要になる
+    run_ok(&dir, &["--db", "app.db", "plan", "create", "Second"]);
+    assert_eq!(run(&dir, &["--db", "app.db", "show"]).status.code(), Some(2));
+    run_ok(&dir, &["--db", "app.db", "--plan", "1", "show"]);
+}
+
+#[test]
+fn test_db_rule_delete() {
+    let dir = work_dir("db_rule_delete");
+    build_json(&dir);
+    run_ok(&dir, &["--db", "app.db", "import", "config.json", "calendar.json", "--name", "Imported"]);
+    let other = run_ok(&dir, &["--db", "app.db", "rule", "add", "Other"]);
+    let other = other.trim();
+
+    // タイムラインで使われているルールは、モードを指定しないと消さない
+    let output = run(&dir, &["--db", "app.db", "rule", "delete", "1"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2026-06-01, 2026-06-15 の週"));
+
+    // 付け替えた週の月曜を表示する
+    let changed = run_ok(&dir, &["--db", "app.db", "rule", "delete", "1", "--reassign", other]);
+    assert_eq!(changed, "2026-06-01\n2026-06-15\n");
+    let timeline = run_ok(&dir, &["--db", "app.db", "timeline", "show"]);
+    assert!(timeline.contains("Active Δ0 (Other)"));
+
+    run_ok(&dir, &["--db", "app.db", "rule", "delete", other, "--skip"]);
+    let timeline = run_ok(&dir, &["--db", "app.db", "timeline", "show"]);
+    assert!(!timeline.contains("Active"));
+
+    // JSONのルール設定には週ごとのルールが無い
+    let json = ["--config", "config.json", "--calendar", "calendar.json"];
+    assert_eq!(run(&dir, &[&json[..], &["rule", "delete", "0", "--skip"]].concat()).status.code(), Some(2));
+}
+
+/// --db の書き換えはアプリと同じくプランの revision を進める (開いているアプリが古い内容で上書きしないように)
+#[test]
+fn test_db_writes_advance_revision() {
+    use shift_manager_tauri_lib::{AppServices, domain::repository::PlanRepository, open_database};
+
+    let dir = work_dir("db_revision");
+    let revision = || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let services = AppServices::new(open_database(&dir.join("app.db")).await.unwrap());
+            services.rule.list_plans().await.unwrap()[0].revision
+        })
+    };
+
+    run_ok(&dir, &["--db", "app.db", "plan", "create", "Plan"]);
+    assert_eq!(revision(), 0);
+    for args in [
+        &["group", "add", "Group A"][..],
+        &["member", "add", "1", "Tanaka"],
+        &["rule", "add", "Standard"],
+        &["assign", "add", "1", "mon", "morning", "1", "0"],
+        &["timeline", "create", "2026-06"],
+        &["timeline", "append", "2026-06", "1"],
+    ] {
+        run_ok(&dir, &[&["--db", "app.db"][..], args].concat());
+    }
+    assert_eq!(revision(), 6);
+
+    // 失敗した書き換えでは進まない
+    assert_eq!(run(&dir, &["--db", "app.db", "member", "add", "9", "Suzuki"]).status.code(), Some(1));
+    assert_eq!(run(&dir, &["--db", "app.db", "rule", "delete", "1"]).status.code(), Some(1));
+    assert_eq!(revision(), 6);
+}
+
+#[test]
+fn test_db_print() {
+    let dir = work_dir("print");
+    build_json(&dir);
+    run_ok(&dir, &["--db", "app.db", "import", "config.json", "calendar.json", "--name", "Imported"]);
+
+    run_ok(&dir, &["--db", "app.db", "print", "2026-06", "june.html"]);
+    let html = fs::read_to_string(dir.join("june.html")).unwrap();
+    assert!(html.contains("<title>Imported 2026年6月</title>"));
+    assert!(html.contains(">Suzuki</span>"));
+
+    run_ok(&dir, &["--db", "app.db", "print", "2026-06", "group.html", "--group", "1"]);
+    let html = fs::read_to_string(dir.join("group.html")).unwrap();
+    assert!(html.contains("<title>Imported 2026年6月 (Group A)</title>"));
+
+    // HTML 以外の拡張子・存在しないグループ・JSON には使えない
+    assert_eq!(run(&dir, &["--db", "app.db", "print", "2026-06", "june.pdf"]).status.code(), Some(2));
+    assert!(!dir.join("june.pdf").exists());
+    assert_eq!(run(&dir, &["--db", "app.db", "print", "2026-06", "june.png"]).status.code(), Some(2));
+    assert_eq!(run(&dir, &["--db", "app.db", "print", "2026-06", "june.html", "--group", "9"]).status.code(), Some(1));
+    assert_eq!(run(&dir, &["--config", "config.json", "print", "2026-06", "june.html"]).status.code(), Some(2));
+}